@@ -0,0 +1,96 @@
+use solana_program::program_error::ProgramError;
+
+#[derive(Debug, Clone)]
+pub enum MyError {
+    SlotExpired,
+    // Pump 代币已迁移到 PumpAMM，但仍在使用内盘选择器
+    PumpTokenMigrated,
+    // Pump 代币仍在内盘 bonding curve，但使用了外盘选择器
+    PumpTokenOnCurve,
+    // 费率超过 MAX_FEE_BPS 上限
+    FeeRateTooHigh,
+    // 转发的目标程序账户缺失或不可执行
+    TargetProgramNotExecutable,
+    // 推荐码未登记
+    ReferralNotFound,
+    // 推荐码已被撤销
+    ReferralRevoked,
+    // 交易中缺少推荐码登记的推荐人账户（已改为回退到协议，不再返回）
+    ReferrerAccountMissing,
+    // 分成比例超过 10_000 基点
+    InvalidShareBps,
+    // 卖出时手续费支付者既不是代币账户所有者，也不是额度足够的委托人
+    FeePayerNotAuthorized,
+    // 托管中没有已到期可释放的收入
+    NothingToRelease,
+    // 签名者不是程序的升级权限（或程序已不可升级）
+    NotUpgradeAuthority,
+    // 预言机价格账户缺失、格式错误或报价状态异常
+    InvalidOraclePrice,
+    // 预言机报价超过允许的最大时长未更新
+    StaleOraclePrice,
+    // 选择器属于已知系列但当前版本不支持（宽松模式）
+    UnsupportedRoute,
+    // 中继交易中缺少用户的 ed25519 授权签名
+    MissingUserSignature,
+    // 转发的目标程序是本程序自身
+    SelfInvocation,
+    // 配置要求交易附带 Memo 指令
+    MemoRequired,
+    // 配置了回购分成但交易中缺少回购钱包账户
+    BuybackAccountMissing,
+    // nonce 在防重放窗口内已被使用
+    NonceReused,
+    // nonce 缓冲区中没有已过期的空位
+    NonceRingFull,
+    // 通过 CPI 调用的程序不在白名单中
+    CpiCallerNotAllowed,
+    // 手续费接收方是配置账户或非系统程序所有的账户
+    InvalidFeeReceiver,
+    // 转发的指令数据超过配置的长度上限
+    InnerDataTooLarge,
+    // 高风险操作的多签管理员签名数不足
+    MultisigThresholdNotMet,
+    // 距离该钱包上一笔交易未超过冷却时间
+    TradeCooldown,
+    // 该路由已被管理员单独暂停
+    RoutePaused,
+    // 单次提取金额超过 max_withdraw_per_tx
+    WithdrawLimitExceeded,
+    // 协议已被全局暂停
+    ProtocolPaused,
+    // 实际成交数量低于预期超过价格影响上限
+    PriceImpactExceeded,
+    // 交易的代币在禁止列表中
+    MintBlocked,
+    // 计算出的手续费超过交易金额，通常是费率配置异常
+    FeeExceedsAmount,
+    // PumpAMM 买入缺少全局或用户交易量累计账户，或位置不对
+    PumpVolumeAccumulatorMissing,
+    // 指定的目标代币账户收到的数量低于 min_tokens
+    MinTokensNotReceived,
+    // 配置账户按旧版较短的布局分配，容纳不下当前配置，需要先迁移扩容
+    ConfigAccountTooSmall,
+    // 手续费接收方归其他程序所有（如其他程序的 PDA），system_program::transfer 无法转入
+    FeeReceiverNotSystemOwned,
+    // 代付交易的手续费超过代付方授权的剩余额度
+    AllowanceExceeded,
+    // deadline 超过允许的最大提前时间
+    DeadlineTooFar,
+    // 转发的程序既不是路由的目标程序，也不是为该路由登记的程序
+    RouteProgramMismatch,
+    // 接收代币的目标代币账户已被冻结
+    DestinationFrozen,
+    // 转发账户与路由 AccountSpec 描述的顺序不一致
+    AccountOrderMismatch,
+    // 防重放窗口为 0（记录永不过期），nonce 缓冲区写满后钱包将永久无法交易，拒绝带 nonce 的交易
+    ReplayWindowNotSet,
+    // 以代币收取的手续费不支持挂载了 transfer hook 的 Token-2022 mint：手续费转账不会附带 hook 所需的额外账户
+    TransferHookNotSupported,
+}
+
+impl From<MyError> for ProgramError {
+    fn from(e: MyError) -> Self {
+        ProgramError::Custom(e as u32) // 自定义错误码更清晰
+    }
+}
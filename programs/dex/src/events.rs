@@ -0,0 +1,237 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    log::sol_log_data,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::utils::find_account;
+
+// 事件通过 sol_log_data 输出：[8 字节事件标识][borsh 序列化的事件体]
+pub const FEE_COLLECTED_EVENT: &[u8; 8] = b"fee_coll";
+pub const REFERRAL_FALLBACK_EVENT: &[u8; 8] = b"ref_fall";
+pub const ROUTE_PAUSE_EVENT: &[u8; 8] = b"rt_pause";
+pub const PROTOCOL_PAUSE_EVENT: &[u8; 8] = b"pr_pause";
+pub const FEE_SKIPPED_EVENT: &[u8; 8] = b"fee_skip";
+pub const FEE_WALLET_FAILOVER_EVENT: &[u8; 8] = b"fee_fovr";
+
+// Anchor emit_cpi! 格式：以事件 PDA 签名自调用，指令数据为
+// [EVENT_IX_TAG_LE][事件鉴别器 sha256("event:<事件名>")[..8]][borsh 序列化的事件体]
+pub const EVENT_IX_TAG_LE: &[u8; 8] = &[228, 69, 165, 46, 81, 203, 154, 29];
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"__event_authority";
+pub const FEE_COLLECTED_DISCRIMINATOR: &[u8; 8] = &[12, 28, 17, 248, 244, 36, 8, 73];
+
+pub fn event_authority_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], program_id)
+}
+
+// 事件 PDA 与本程序账户需追加在账户末尾
+fn emit_cpi<T: BorshSerialize>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    discriminator: &[u8; 8],
+    event: &T,
+) -> ProgramResult {
+    let (authority_key, bump) = event_authority_address(program_id);
+    let (Some(authority), Some(program)) = (
+        find_account(accounts, &authority_key),
+        find_account(accounts, program_id),
+    ) else {
+        msg!("event CPI 需要追加事件 PDA {} 与本程序账户", authority_key);
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let mut data = Vec::with_capacity(16);
+    data.extend_from_slice(EVENT_IX_TAG_LE);
+    data.extend_from_slice(discriminator);
+    event.serialize(&mut data)?;
+
+    invoke_signed(
+        &Instruction {
+            program_id: *program_id,
+            accounts: vec![AccountMeta::new_readonly(authority_key, true)],
+            data,
+        },
+        &[authority.clone(), program.clone()],
+        &[&[EVENT_AUTHORITY_SEED, &[bump]]],
+    )
+}
+
+// 自调用的事件指令本身不做任何处理，只接受事件 PDA 签名的调用，防止伪造事件
+pub fn process_event_cpi(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    match accounts.first() {
+        Some(authority) if authority.is_signer && authority.key == &event_authority_address(program_id).0 => Ok(()),
+        _ => Err(ProgramError::MissingRequiredSignature),
+    }
+}
+
+fn emit<T: BorshSerialize>(tag: &[u8; 8], event: &T) -> ProgramResult {
+    sol_log_data(&[tag, &borsh::to_vec(event)?]);
+    Ok(())
+}
+
+// 每次收取手续费时输出，seq 按配置账户单调递增，供索引器严格排序。
+// amount 与 fee_raw 以 fee_mint 的最小单位计（SOL 手续费的 fee_mint 为默认公钥、decimals 为 9）；
+// fee 与 referral_fee 只统计 lamports，以代币收费时 fee 为 0
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct FeeCollected {
+    pub seq: u64,
+    pub selector: [u8; 8],
+    pub payer: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub referral_fee: u64,
+    pub fee_raw: u64,
+    pub decimals: u8,
+    pub fee_mint: Pubkey,
+}
+
+impl FeeCollected {
+    pub fn emit(&self) -> ProgramResult {
+        emit(FEE_COLLECTED_EVENT, self)
+    }
+
+    pub fn emit_cpi(&self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        emit_cpi(program_id, accounts, FEE_COLLECTED_DISCRIMINATOR, self)
+    }
+}
+
+// 推荐人账户无效时输出，该笔推荐分成全部归协议
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct ReferralFallback {
+    pub code: u32,
+    pub referrer: Pubkey,
+    pub fee: u64,
+}
+
+impl ReferralFallback {
+    pub fn emit(&self) -> ProgramResult {
+        emit(REFERRAL_FALLBACK_EVENT, self)
+    }
+}
+
+// 精简收费事件，开启 compact_events 后代替 FeeCollected 输出，sol_log_data 只有一段数据（普通事件为两段）:
+// [route_id 4 = 路由选择器前 4 字节][fee: LEB128 变长 u64，1-10 字节][wallet_index u8]
+// wallet_index 为交易钱包（手续费支付者）在本指令账户列表中的位置，索引器按交易的账户表还原公钥；
+// 不含 seq，按交易在区块中的顺序排序
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactFeeCollected {
+    pub route_id: [u8; 4],
+    pub fee: u64,
+    pub wallet_index: u8,
+}
+
+impl CompactFeeCollected {
+    pub const MAX_LEN: usize = 4 + 10 + 1;
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(Self::MAX_LEN);
+        data.extend_from_slice(&self.route_id);
+        let mut fee = self.fee;
+        loop {
+            let byte = (fee & 0x7f) as u8;
+            fee >>= 7;
+            if fee == 0 {
+                data.push(byte);
+                break;
+            }
+            data.push(byte | 0x80);
+        }
+        data.push(self.wallet_index);
+        data
+    }
+
+    // 数据长度或变长编码不合法时返回 None
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let route_id = <[u8; 4]>::try_from(data.get(..4)?).ok()?;
+        let mut fee = 0u64;
+        let mut offset = 4;
+        for shift in (0..64).step_by(7) {
+            let byte = *data.get(offset)?;
+            offset += 1;
+            fee |= ((byte & 0x7f) as u64).checked_shl(shift)?;
+            if byte & 0x80 == 0 {
+                let wallet_index = *data.get(offset)?;
+                return (offset + 1 == data.len()).then_some(Self { route_id, fee, wallet_index });
+            }
+        }
+        None
+    }
+
+    pub fn emit(&self) -> ProgramResult {
+        sol_log_data(&[&self.encode()]);
+        Ok(())
+    }
+}
+
+// 手续费转账无法完成的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum FeeSkipReason {
+    PayerInsufficientFunds,
+    ReceiverCannotReceive,
+    BuybackAccountMissing,
+    TokenAccountFrozen,
+    TokenAccountMissing,
+}
+
+// on_fee_failure 为 Skip 且预检发现手续费转账会失败时输出，该笔交易不收费
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct FeeSkipped {
+    pub selector: [u8; 8],
+    pub payer: Pubkey,
+    pub fee: u64,
+    pub reason: FeeSkipReason,
+}
+
+impl FeeSkipped {
+    pub fn emit(&self) -> ProgramResult {
+        emit(FEE_SKIPPED_EVENT, self)
+    }
+}
+
+// 主手续费钱包不可用、本笔 SOL 手续费改投备用钱包时输出
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct FeeWalletFailover {
+    pub primary: Pubkey,
+    pub backup: Pubkey,
+}
+
+impl FeeWalletFailover {
+    pub fn emit(&self) -> ProgramResult {
+        emit(FEE_WALLET_FAILOVER_EVENT, self)
+    }
+}
+
+// 管理员或守护者暂停、恢复单个路由时输出，reason 为运营自定义的原因码
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct RoutePauseChanged {
+    pub selector: [u8; 8],
+    pub paused: bool,
+    pub reason: u16,
+    pub authority: Pubkey,
+}
+
+impl RoutePauseChanged {
+    pub fn emit(&self) -> ProgramResult {
+        emit(ROUTE_PAUSE_EVENT, self)
+    }
+}
+
+// 管理员或守护者全局暂停、恢复时输出
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct ProtocolPauseChanged {
+    pub paused: bool,
+    pub reason: u16,
+    pub authority: Pubkey,
+}
+
+impl ProtocolPauseChanged {
+    pub fn emit(&self) -> ProgramResult {
+        emit(PROTOCOL_PAUSE_EVENT, self)
+    }
+}
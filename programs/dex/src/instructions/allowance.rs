@@ -0,0 +1,140 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::MyError;
+use crate::state::AllowanceState;
+use crate::utils::{create_pda_account, find_account, transfer_lamports};
+
+// 代付方授权、撤销交易发起人的手续费代付额度: [交易发起人 32][lamports u64] / [交易发起人 32]
+pub const APPROVE_ALLOWANCE_SELECTOR: &[u8; 8] = b"allow_ap";
+pub const REVOKE_ALLOWANCE_SELECTOR: &[u8; 8] = b"allow_rv";
+
+pub const ALLOWANCE_SEED: &[u8] = b"allowance";
+
+pub fn allowance_address(program_id: &Pubkey, sponsor: &Pubkey, delegate: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ALLOWANCE_SEED, sponsor.as_ref(), delegate.as_ref()], program_id)
+}
+
+fn parse_delegate(data: &[u8]) -> Result<Pubkey, ProgramError> {
+    let bytes = data.get(0..32).ok_or(ProgramError::InvalidInstructionData)?;
+    Ok(Pubkey::new_from_array(bytes.try_into().unwrap()))
+}
+
+// 代付交易中支付者不签名，按 [代付方, 交易发起人] 查找追加在账户末尾的额度 PDA，必须可写
+pub fn load_allowance<'a, 'info>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'info>],
+    sponsor: &Pubkey,
+    delegate: &Pubkey,
+) -> Result<&'a AccountInfo<'info>, ProgramError> {
+    let account = find_account(accounts, &allowance_address(program_id, sponsor, delegate).0)
+        .filter(|account| account.owner == program_id && account.is_writable)
+        .ok_or_else(|| {
+            msg!("支付者 {} 未签名，且缺少其授权给 {} 的可写代付额度 PDA", sponsor, delegate);
+            ProgramError::MissingRequiredSignature
+        })?;
+    let state = AllowanceState::try_from_slice(&account.data.borrow())?;
+    if &state.sponsor != sponsor || &state.delegate != delegate {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(account)
+}
+
+// 从额度中扣除本次交易的 SOL 手续费，并把等额 lamports 从 PDA 报销给先行垫付的交易发起人
+pub fn spend_allowance(allowance_account: &AccountInfo, delegate: &AccountInfo, fee: u64) -> ProgramResult {
+    let mut state = AllowanceState::try_from_slice(&allowance_account.data.borrow())?;
+    if fee > state.remaining {
+        msg!("手续费 {} 超过代付剩余额度 {}", fee, state.remaining);
+        return Err(MyError::AllowanceExceeded.into());
+    }
+    state.remaining -= fee;
+    state.serialize(&mut &mut allowance_account.data.borrow_mut()[..])?;
+
+    **allowance_account.try_borrow_mut_lamports()? -= fee;
+    **delegate.try_borrow_mut_lamports()? += fee;
+    Ok(())
+}
+
+// 代付方预存 lamports 并增加额度，首次授权时同时支付 PDA 租金
+// 账户: [代付方(签名), 额度 PDA, 系统程序]
+pub fn process_approve_allowance(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let delegate = parse_delegate(instruction_data)?;
+    let lamports = instruction_data
+        .get(32..40)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidInstructionData)?;
+
+    let accounts_iter = &mut accounts.iter();
+    let sponsor = next_account_info(accounts_iter)?;
+    let allowance_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    if !sponsor.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (address, bump) = allowance_address(program_id, sponsor.key, &delegate);
+    if allowance_account.key != &address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let mut state = if allowance_account.owner == program_id {
+        AllowanceState::try_from_slice(&allowance_account.data.borrow())?
+    } else {
+        create_pda_account(
+            sponsor,
+            allowance_account,
+            system_program,
+            program_id,
+            AllowanceState::LEN,
+            &[ALLOWANCE_SEED, sponsor.key.as_ref(), delegate.as_ref(), &[bump]],
+        )?;
+        AllowanceState {
+            sponsor: *sponsor.key,
+            delegate,
+            remaining: 0,
+        }
+    };
+
+    transfer_lamports(sponsor, allowance_account, system_program, lamports)?;
+    state.remaining = state.remaining.checked_add(lamports).ok_or(ProgramError::ArithmeticOverflow)?;
+    state.serialize(&mut &mut allowance_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// 撤销额度并关闭 PDA，剩余额度与租金全部退还给代付方
+// 账户: [代付方(签名), 额度 PDA]
+pub fn process_revoke_allowance(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let delegate = parse_delegate(instruction_data)?;
+
+    let accounts_iter = &mut accounts.iter();
+    let sponsor = next_account_info(accounts_iter)?;
+    let allowance_account = next_account_info(accounts_iter)?;
+    if !sponsor.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if allowance_account.owner != program_id
+        || allowance_account.key != &allowance_address(program_id, sponsor.key, &delegate).0
+    {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // 清空数据并转出全部 lamports，交易结束后账户即被回收
+    allowance_account.data.borrow_mut().fill(0);
+    let lamports = allowance_account.lamports();
+    **allowance_account.try_borrow_mut_lamports()? -= lamports;
+    **sponsor.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
@@ -4,12 +4,12 @@ use solana_program::{
     entrypoint::ProgramResult,
     instruction::{AccountMeta, Instruction},
     program::invoke_unchecked,
-    pubkey::Pubkey,
 };
 
+use crate::token::ASSOCIATED_TOKEN_PROGRAM_ID;
+
 pub const ATA_SELECTOR: &[u8; 8] = &[22, 51, 53, 97, 247, 184, 54, 78];
 
-const ATA_PROGRAM: Pubkey = solana_program::pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
 pub fn process_create_associated_token_account(
     accounts: &[AccountInfo],
     instruction_data: &[u8],
@@ -21,7 +21,7 @@ pub fn process_create_associated_token_account(
 
     invoke_unchecked(
         &Instruction {
-            program_id: ATA_PROGRAM,
+            program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
             accounts: vec![
                 AccountMeta::new(funder_key, true),
                 AccountMeta::new(*associated_token_account_info.key, false),
@@ -0,0 +1,151 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    epoch_schedule::EpochSchedule,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+use crate::error::MyError;
+use crate::state::{EscrowState, TradeFeeState};
+use crate::utils::{check_upgrade_authority, create_pda_account};
+
+// 管理员创建协议收入托管账户：[release_per_epoch u64]
+pub const INIT_ESCROW_SELECTOR: &[u8; 8] = b"esc_init";
+// 管理员按释放计划提取托管收入：[amount u64]（可省略，省略或为 0 时提取全部可释放金额）
+pub const WITHDRAW_ESCROW_SELECTOR: &[u8; 8] = b"esc_wdrw";
+
+pub const ESCROW_SEED: &[u8] = b"escrow";
+
+pub fn escrow_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ESCROW_SEED], program_id)
+}
+
+// 手续费接收账户是否为本程序的托管 PDA
+pub fn is_escrow_account(program_id: &Pubkey, account: &AccountInfo) -> bool {
+    account.owner == program_id && account.key == &escrow_address(program_id).0
+}
+
+// 手续费转入托管后累加 total
+pub fn record_escrow_deposit(account: &AccountInfo, lamports: u64) -> ProgramResult {
+    let mut escrow = EscrowState::try_from_slice(&account.data.borrow())?;
+    escrow.total = escrow
+        .total
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    escrow.serialize(&mut &mut account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// 按上次释放以来经过的 epoch 数计算可释放金额，不超过尚未释放的累计收入
+pub fn releasable_amount(escrow: &EscrowState, schedule: &EpochSchedule, current_epoch: u64) -> u64 {
+    let last_epoch = schedule.get_epoch(escrow.last_release_slot);
+    let elapsed = current_epoch.saturating_sub(last_epoch);
+    let vested = elapsed.saturating_mul(escrow.release_per_epoch);
+    vested.min(escrow.total.saturating_sub(escrow.released))
+}
+
+// 账户: [配置账户, 管理员(签名并支付租金), 托管 PDA, 系统程序]
+pub fn process_init_escrow(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let release_per_epoch = instruction_data
+        .get(0..8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidInstructionData)?;
+
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    TradeFeeState::load_as_admin(program_id, config_account, admin)?;
+
+    let (address, bump) = escrow_address(program_id);
+    if escrow_account.key != &address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    create_pda_account(
+        admin,
+        escrow_account,
+        system_program,
+        program_id,
+        EscrowState::LEN,
+        &[ESCROW_SEED, &[bump]],
+    )?;
+
+    let escrow = EscrowState {
+        total: 0,
+        released: 0,
+        last_release_slot: Clock::get()?.slot,
+        release_per_epoch,
+    };
+    escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// 把已到期的收入转给协议费钱包，未到期部分继续留在托管中
+// 只提取部分已到期收入时，剩余部分从本次提取起重新按释放计划到期
+// 管理员必须同时是程序升级权限
+// 账户: [配置账户, 管理员(协议费钱包), 托管 PDA, 本程序的 ProgramData]
+pub fn process_withdraw_escrow(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let requested = instruction_data
+        .get(0..8)
+        .map_or(0, |bytes| u64::from_le_bytes(bytes.try_into().unwrap()));
+
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+    let program_data = next_account_info(accounts_iter)?;
+
+    let config = TradeFeeState::load_as_admin(program_id, config_account, admin)?;
+    check_upgrade_authority(program_id, program_data, admin)?;
+    config.check_multisig(accounts)?;
+    if !is_escrow_account(program_id, escrow_account) {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let clock = Clock::get()?;
+    let schedule = EpochSchedule::get()?;
+    let mut escrow = EscrowState::try_from_slice(&escrow_account.data.borrow())?;
+
+    let releasable = releasable_amount(&escrow, &schedule, clock.epoch);
+    if releasable == 0 {
+        msg!("托管收入尚未到释放时间");
+        return Err(MyError::NothingToRelease.into());
+    }
+    let amount = match requested {
+        0 => releasable,
+        requested if requested <= releasable => requested,
+        requested => {
+            msg!("提取金额 {} 超过可释放金额 {}", requested, releasable);
+            return Err(ProgramError::InsufficientFunds);
+        }
+    };
+    if config.max_withdraw_per_tx > 0 && amount > config.max_withdraw_per_tx {
+        msg!("提取金额 {} 超过单次上限 {}", amount, config.max_withdraw_per_tx);
+        return Err(MyError::WithdrawLimitExceeded.into());
+    }
+
+    escrow.released += amount;
+    escrow.last_release_slot = clock.slot;
+    escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+
+    // 托管 PDA 归本程序所有，可以直接划转 lamports
+    **escrow_account.try_borrow_mut_lamports()? -= amount;
+    **admin.try_borrow_mut_lamports()? += amount;
+    Ok(())
+}
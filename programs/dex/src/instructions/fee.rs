@@ -0,0 +1,1495 @@
+use core::cell::Cell;
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    compute_units::sol_remaining_compute_units,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    msg,
+    program::{invoke, set_return_data, MAX_RETURN_DATA},
+    program_error::ProgramError,
+    pubkey,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+    sysvar::Sysvar,
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::error::MyError;
+use crate::events::{CompactFeeCollected, FeeCollected, FeeSkipReason, FeeSkipped, FeeWalletFailover, ReferralFallback};
+use crate::instructions::allowance::{load_allowance, spend_allowance};
+use crate::instructions::escrow::{is_escrow_account, record_escrow_deposit};
+use crate::instructions::fee_exempt::fee_exempt_mint;
+use crate::instructions::impact::{check_min_tokens, check_price_impact};
+use crate::instructions::meteora::{DAMM_V2_BUY_ROUTE, DAMM_V2_SELL_ROUTE};
+use crate::instructions::mint_fee::mint_fee_rate;
+use crate::instructions::nonce::record_nonce;
+use crate::instructions::openbook::OPENBOOK_BUY_ROUTE;
+use crate::instructions::pump::{
+    PUMP_AMM_BUY_ROUTE, PUMP_AMM_SELL_ROUTE, PUMP_BUY_ROUTE, PUMP_SELL_ROUTE,
+};
+use crate::instructions::raydium::{RAYDIUM_BUY_BASE_OUT_ROUTE, RAYDIUM_CLMM_BUY_EXACT_OUT_ROUTE};
+use crate::instructions::referral::load_active_referral;
+use crate::instructions::rent_funder::{rent_funder_snapshot, repay_rent_funder, RentFunderSnapshot};
+use crate::instructions::rollup::record_epoch_fee;
+use crate::instructions::route_program::route_program_version;
+use crate::instructions::token_fee_floor::token_fee_floor;
+use crate::instructions::wallet::{record_wallet_trade, wallet_trade_is_free};
+use crate::oracle::{read_pyth_price, usd_micros_to_lamports};
+use crate::state::{FeeFailurePolicy, FeeSides, FeeTiming, ReferralState, TradeFeeState, BPS_DENOMINATOR, PREFLIGHT_FROZEN_DESTINATION, PREFLIGHT_PAYER_BALANCE, PREFLIGHT_TOKEN_FEE_FLOOR, FEE_RATE_DENOMINATOR, MAX_FEE_RATE_PIPS};
+use crate::token::{
+    associated_token_address, create_associated_token_account_idempotent, mint_decimals, transfer_checked, transfer_hook_program, TokenAccount,
+    ACCOUNT_STATE_FROZEN, ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID,
+};
+use crate::utils::{cpi_caller, find_account, invoked_via_cpi, transaction_has_instruction, transfer_lamports};
+
+// 无状态手续费预估：[amount u64][rate_bps u16]，不读取任何账户
+pub const FEE_PREVIEW_SELECTOR: &[u8; 8] = b"fee_prev";
+// 路由执行计划（只解析不执行）：[路由选择器 8 字节][该路由的指令数据]，账户与路由调用相同
+pub const EXPLAIN_SELECTOR: &[u8; 8] = b"explain\0";
+
+const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+const MEMO_V1_PROGRAM_ID: Pubkey = pubkey!("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+/// 转发账户顺序描述中的一项：账户名称，以及目标程序要求的可写、签名标志
+#[derive(Debug, Clone, Copy)]
+pub struct AccountSpec {
+    pub name: &'static str,
+    pub writable: bool,
+    pub signer: bool,
+}
+
+impl AccountSpec {
+    pub const fn readonly(name: &'static str) -> Self {
+        Self { name, writable: false, signer: false }
+    }
+
+    pub const fn writable(name: &'static str) -> Self {
+        Self { name, writable: true, signer: false }
+    }
+
+    pub const fn signer(name: &'static str) -> Self {
+        Self { name, writable: false, signer: true }
+    }
+
+    pub const fn writable_signer(name: &'static str) -> Self {
+        Self { name, writable: true, signer: true }
+    }
+}
+
+/// 带手续费的转发路由
+pub struct FeeRoute {
+    /// 路由名称，用于日志
+    pub name: &'static str,
+    /// 代理合约对外暴露的选择器
+    pub selector: &'static [u8; 8],
+    /// 目标 DEX 程序
+    pub program: Pubkey,
+    /// 目标 DEX 指令鉴别器（Anchor 为 8 字节，Raydium AMM v4 为 1 字节），金额参数紧随其后
+    pub inner_selector: &'static [u8],
+    /// 目标程序账户是否也作为内层指令账户（Anchor event CPI 需要），否则只用于 invoke
+    pub forward_program_account: bool,
+    /// 转发账户（含目标程序账户）的最少数量
+    pub min_accounts: usize,
+    /// 转发账户的顺序描述，按位置校验可写与签名标志；描述之后的账户（tick array、扩展账户等）不校验
+    pub accounts: &'static [AccountSpec],
+    /// 买入或卖出
+    pub side: TradeSide,
+    /// 用户代币账户在转发账户中的位置（买入为接收账户，卖出为来源账户）
+    pub token_account_index: usize,
+    /// 交易发起人（用户代币账户的 owner）在转发账户中的位置，CPI 前要求其已签名
+    pub authority_account_index: usize,
+    /// 兑换输出的目标账户在转发账户中的位置，None 表示 SOL 直接转入支付者钱包
+    pub output_account_index: Option<usize>,
+    /// 精确输出路由中用户来源代币账户的位置：手续费按最大输入预估，兑换后按该账户实际减少的数量收取
+    pub input_account_index: Option<usize>,
+    /// AMM 交易对中用户 quote 代币账户的位置，其 mint 为配置的 quote_fee_mint 时以 quote 代币收费
+    pub quote_account_index: Option<usize>,
+    /// 卖出时总是从兑换得到的 SOL 中收费，不改动代币输入数量（不受 fee_timing 配置影响）
+    pub fee_from_output: bool,
+    /// 买入时直接花费交易发起人钱包中的 lamports（而非 WSOL 代币账户），余额预检时计入输入金额
+    pub sol_input: bool,
+    /// 收费前的路由专属校验
+    pub check: fn(&[AccountInfo]) -> ProgramResult,
+    /// 把扣费后的金额写入内层指令数据（参数为转发账户、内层数据、剩余金额）
+    pub patch_amount: fn(&[AccountInfo], &mut [u8], u64) -> ProgramResult,
+}
+
+/// 包装选择器解析出的附加参数
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FeeOptions {
+    pub referral_code: Option<u32>,
+    /// 中继交易中经 ed25519 签名授权的用户，卖出权限按该用户校验
+    pub authorized_user: Option<Pubkey>,
+    /// 防重放 nonce，按授权用户（或手续费支付者）记录
+    pub nonce: Option<u64>,
+    /// 兑换后按实际收到的数量校验价格影响
+    pub price_impact: Option<PriceImpactGuard>,
+    /// 兑换后校验指定代币账户的余额增量
+    pub min_tokens: Option<MinTokensGuard>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PriceImpactGuard {
+    pub expected_out: u64,
+    pub max_impact_bps: u16,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MinTokensGuard {
+    pub destination: Pubkey,
+    pub min_tokens: u64,
+}
+
+pub const FEE_ROUTES: [&FeeRoute; 9] = [
+    &PUMP_BUY_ROUTE,
+    &PUMP_AMM_BUY_ROUTE,
+    &PUMP_SELL_ROUTE,
+    &PUMP_AMM_SELL_ROUTE,
+    &RAYDIUM_BUY_BASE_OUT_ROUTE,
+    &RAYDIUM_CLMM_BUY_EXACT_OUT_ROUTE,
+    &OPENBOOK_BUY_ROUTE,
+    &DAMM_V2_BUY_ROUTE,
+    &DAMM_V2_SELL_ROUTE,
+];
+
+pub fn find_fee_route(selector: &[u8]) -> Option<&'static FeeRoute> {
+    FEE_ROUTES
+        .iter()
+        .copied()
+        .find(|route| route.selector.as_slice() == selector)
+}
+
+// 按路由的账户顺序描述逐个校验转发账户，集成方把账户顺序弄错时给出具体位置、期望的账户与实际传入的地址，
+// 而不是等内层程序报出难以定位的约束错误
+pub fn check_account_order(route: &FeeRoute, forwarded: &[AccountInfo]) -> ProgramResult {
+    for (index, (spec, account)) in route.accounts.iter().zip(forwarded).enumerate() {
+        if spec.writable && !account.is_writable {
+            msg!("路由 {} 转发账户 {} 应为可写的 {}，实际为只读的 {}", route.name, index, spec.name, account.key);
+            return Err(MyError::AccountOrderMismatch.into());
+        }
+        if spec.signer && !account.is_signer {
+            msg!("路由 {} 转发账户 {} 应为已签名的 {}，实际为未签名的 {}", route.name, index, spec.name, account.key);
+            return Err(MyError::AccountOrderMismatch.into());
+        }
+    }
+    Ok(())
+}
+
+// 无需额外校验的路由
+pub fn no_route_check(_accounts: &[AccountInfo]) -> ProgramResult {
+    Ok(())
+}
+
+// 金额为鉴别器后的第一个参数时，直接覆盖为扣费后的金额
+pub fn patch_first_arg<const OFFSET: usize>(
+    _forwarded: &[AccountInfo],
+    data: &mut [u8],
+    remaining: u64,
+) -> ProgramResult {
+    data.get_mut(OFFSET..OFFSET + 8)
+        .ok_or(ProgramError::InvalidInstructionData)?
+        .copy_from_slice(&remaining.to_le_bytes());
+    Ok(())
+}
+
+// 使用 u128 中间值计算，避免大额交易时 amount * pips 溢出
+fn calculate_fee(amount: u64, fee_rate_pips: u32) -> Result<u64, ProgramError> {
+    let fee = amount as u128 * fee_rate_pips as u128 / FEE_RATE_DENOMINATOR as u128;
+    u64::try_from(fee).map_err(|_| ProgramError::ArithmeticOverflow)
+}
+
+// 按费率计算手续费，配置中累计的零头（单位 1/1_000_000 lamport）加上本笔舍去的零头满 1 lamport 时补收。
+// 只读取累计值，实际收取的手续费确定后由 commit_fee_carry 更新
+fn calculate_fee_with_carry(amount: u64, fee_rate_pips: u32, config: &TradeFeeState) -> Result<u64, ProgramError> {
+    let scaled = amount as u128 * fee_rate_pips as u128;
+    let fee = (config.fee_remainder_accumulator as u128 + scaled) / FEE_RATE_DENOMINATOR as u128;
+    u64::try_from(fee).map_err(|_| ProgramError::ArithmeticOverflow)
+}
+
+// 按最终收取的手续费结转零头：累计值加上按实际计费数量算出的应收部分，减去已收取的整数 lamports。
+// 只有按费率计费时结转，本笔舍入为 0 lamport 时零头同样累计；跳过收费、免收或按美元定额收费时不结转
+fn commit_fee_carry(ctx: &FeeContext, config: &mut TradeFeeState, amount: u64, fee: u64) -> ProgramResult {
+    if !ctx.fee_carry.get() {
+        return Ok(());
+    }
+    let denominator = FEE_RATE_DENOMINATOR as u128;
+    let rate = effective_rate(ctx, config, amount, true)?;
+    let owed = config.fee_remainder_accumulator as u128 + amount as u128 * rate as u128;
+    let carried = owed.saturating_sub(fee as u128 * denominator).min(denominator - 1);
+    config.fee_remainder_accumulator = carried as u64;
+    Ok(())
+}
+
+// 美元定额按预言机报价换算为 lamports，仍不超过 MAX_FEE_BPS 上限
+fn usd_fee(config: &TradeFeeState, accounts: &[AccountInfo], amount: u64) -> Result<u64, ProgramError> {
+    let oracle = find_account(accounts, &config.oracle).ok_or_else(|| {
+        msg!("缺少预言机价格账户 {}", config.oracle);
+        ProgramError::from(MyError::InvalidOraclePrice)
+    })?;
+    let price = read_pyth_price(oracle, &Clock::get()?, config.max_price_age_secs, config.max_data_age_slots)?;
+    let fee = usd_micros_to_lamports(config.fee_usd_micros, &price)?;
+    Ok(fee.min(calculate_fee(amount, MAX_FEE_RATE_PIPS)?))
+}
+
+// 手续费各去向的实际金额
+#[derive(Debug, Default, BorshSerialize, BorshDeserialize)]
+pub struct FeeBreakdown {
+    pub treasury: u64,
+    pub referrer: u64,
+    pub buyback: u64,
+}
+
+// 所有兑换路由统一写入的 return data：route_id 为代理合约选择器，amount_out 为目标账户余额的实际变化，
+// remaining 为扣费后的数量（兑换前收费时为转发的输入，兑换后收费时为用户实得的输出）
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct SwapResult {
+    pub route_id: [u8; 8],
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    pub remaining: u64,
+    pub breakdown: FeeBreakdown,
+    // 目标程序 CPI 前后剩余计算单元之差（含 CPI 调用本身的开销）
+    pub compute_units: u64,
+}
+
+// 目标账户余额：代币账户读取代币数量，其他账户（接收 SOL 的钱包）读取 lamports
+pub fn output_balance(account: &AccountInfo) -> Result<u64, ProgramError> {
+    if account.owner == &TOKEN_PROGRAM_ID || account.owner == &TOKEN_2022_PROGRAM_ID {
+        return Ok(TokenAccount::unpack(account)?.amount);
+    }
+    Ok(account.lamports())
+}
+
+// 手续费预估结果，通过 return data 返回
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct FeePreview {
+    pub fee: u64,
+    pub remaining: u64,
+}
+
+// 按调用方给出的费率计算手续费，供模拟交易时估算使用
+pub fn process_fee_preview(instruction_data: &[u8]) -> ProgramResult {
+    if instruction_data.len() < 10 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let amount = u64::from_le_bytes(<[u8; 8]>::try_from(&instruction_data[0..8]).unwrap());
+    let rate_bps = u16::from_le_bytes(<[u8; 2]>::try_from(&instruction_data[8..10]).unwrap());
+    if rate_bps as u64 > BPS_DENOMINATOR {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let fee = calculate_fee(amount, rate_bps as u32 * 100)?;
+    let preview = FeePreview {
+        fee,
+        remaining: amount - fee,
+    };
+    set_return_data(&borsh::to_vec(&preview)?);
+    Ok(())
+}
+
+// explain 返回的执行计划。兑换后收费（post_swap）时手续费取决于实际成交量，fee 为 0，
+// inner_data 中为完整数量；免费笔数与交易冷却需要写入钱包 PDA，不在计划中体现
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct RoutePlan {
+    pub route_id: [u8; 8],
+    pub program: Pubkey,
+    pub is_buy: bool,
+    pub post_swap: bool,
+    pub amount: u64,
+    pub fee_rate_pips: u32,
+    // 叠加会员折扣与各项返还后的有效费率，见 effective_rate
+    pub effective_rate_pips: u32,
+    pub fee: u64,
+    pub inner_amount: u64,
+    // 实际转发给目标程序的指令数据与账户
+    pub inner_data: Vec<u8>,
+    pub accounts: Vec<Pubkey>,
+}
+
+// 按路由调用的账户和数据解析出执行计划并通过 return data 返回，不转账、不调用目标程序、不修改任何账户
+pub fn process_explain(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (selector, route_data) = instruction_data.split_at(8);
+    let route = find_fee_route(selector).ok_or_else(|| {
+        msg!("选择器 {:?} 不是带手续费的路由", selector);
+        ProgramError::from(MyError::UnsupportedRoute)
+    })?;
+    (route.check)(accounts)?;
+    check_account_order(route, accounts.get(4..).unwrap_or(&[]))?;
+
+    let accounts_iter = &mut accounts.iter();
+    let fee_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let fee_payer = next_account_info(accounts_iter)?;
+    let fee_receiver = next_account_info(accounts_iter)?;
+    if fee_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let config = TradeFeeState::unpack(&fee_account.data.borrow())?;
+    let target_program = target_program(program_id, route, accounts)?;
+    if config.paused {
+        msg!("协议已全局暂停");
+        return Err(MyError::ProtocolPaused.into());
+    }
+    if config.is_route_paused(route.selector) {
+        msg!("路由 {} 已暂停", route.name);
+        return Err(MyError::RoutePaused.into());
+    }
+    if route_data.len() < 16 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let amount = u64::from_le_bytes(<[u8; 8]>::try_from(&route_data[..8]).unwrap());
+
+    let options = FeeOptions::default();
+    let ctx = FeeContext {
+        program_id,
+        route,
+        accounts,
+        options: &options,
+        config_account: fee_account,
+        system_program,
+        payer: fee_payer,
+        receiver: fee_receiver,
+        to_escrow: is_escrow_account(program_id, fee_receiver),
+        program: target_program,
+        rent_funder: None,
+        fee_carry: Cell::new(false),
+    };
+
+    let quote_fee = quote_fee_account(route, accounts, &config)?.is_some();
+    let post_swap = match route.side {
+        TradeSide::Sell => quote_fee || route.fee_from_output || config.fee_timing == FeeTiming::PostSwap,
+        TradeSide::Buy => !quote_fee && config.buy_fee_timing == FeeTiming::PostSwap,
+    };
+    let fee_rate_pips = trade_fee_rate(&ctx, &config);
+    let effective_rate_pips = effective_rate(&ctx, &config, amount, !quote_fee)?;
+    // 与执行时相同的免收判断与计费路径，只是不记录钱包交易
+    let free = wallet_trade_is_free(program_id, accounts, fee_payer, &config)? || fee_free(&ctx, &config)?;
+    let fee = match (post_swap, quote_fee_account(route, accounts, &config)?) {
+        _ if free => 0,
+        (true, _) => 0,
+        (false, Some(quote_account)) => token_rate_fee(&ctx, &config, quote_account, amount)?,
+        (false, None) => lamport_fee(&ctx, &config, amount)?,
+    };
+    let inner_amount = amount_after_fee(amount, fee)?;
+
+    let plan = RoutePlan {
+        route_id: *route.selector,
+        program: target_program,
+        is_buy: route.side == TradeSide::Buy,
+        post_swap,
+        amount,
+        fee_rate_pips,
+        effective_rate_pips,
+        fee,
+        inner_amount,
+        inner_data: build_inner_data(route, accounts, route_data, inner_amount)?,
+        accounts: accounts[4..]
+            .iter()
+            .filter(|acc| route.forward_program_account || acc.key != &target_program)
+            .map(|acc| *acc.key)
+            .collect(),
+    };
+    let data = borsh::to_vec(&plan)?;
+    if data.len() > MAX_RETURN_DATA {
+        msg!("执行计划 {} 字节超过 return data 上限", data.len());
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    set_return_data(&data);
+    Ok(())
+}
+
+// 账户可写、不可执行，且转入后不会处于低于免租金额的状态
+fn can_receive_lamports(account: &AccountInfo, lamports: u64) -> Result<bool, ProgramError> {
+    if !account.is_writable || account.executable {
+        return Ok(false);
+    }
+    if account.lamports() == 0 {
+        return Ok(lamports == 0 || lamports >= Rent::get()?.minimum_balance(0));
+    }
+    Ok(true)
+}
+
+// 最小到账校验的目标账户需在账户列表中，且是交易者（中继交易为授权用户）自己的代币账户，返回账户及兑换前余额
+fn min_tokens_destination<'a, 'info>(
+    ctx: &FeeContext<'a, 'info>,
+    guard: &MinTokensGuard,
+) -> Result<(&'a AccountInfo<'info>, u64), ProgramError> {
+    let destination = find_account(ctx.accounts, &guard.destination).ok_or_else(|| {
+        msg!("缺少最小到账校验的目标代币账户 {}", guard.destination);
+        ProgramError::NotEnoughAccountKeys
+    })?;
+    let token = TokenAccount::unpack(destination)?;
+    let trader = ctx.options.authorized_user.unwrap_or(*ctx.payer.key);
+    if token.owner != trader {
+        msg!("目标代币账户 {} 属于 {}，不是交易者 {}", destination.key, token.owner, trader);
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok((destination, token.amount))
+}
+
+// 验证转发账户中包含可执行的目标程序，避免 invoke 报出难以排查的错误。
+// 除路由内置地址外，也接受管理员为该路由登记的其他程序版本（登记 PDA 追加在账户末尾）；
+// 解析出的目标程序为本程序自身时拒绝，避免递归调用
+fn target_program(program_id: &Pubkey, route: &FeeRoute, accounts: &[AccountInfo]) -> Result<Pubkey, ProgramError> {
+    let forwarded = accounts.get(4..).unwrap_or(&[]);
+    let target = match forwarded.iter().find(|acc| acc.key == &route.program) {
+        Some(target) => Some(target),
+        None => forwarded.iter().filter(|acc| acc.executable).find_map(|acc| {
+            let version = route_program_version(program_id, accounts, route.selector, acc.key)?;
+            msg!("路由 {} 使用登记的目标程序 {}（版本 {}）", route.name, acc.key, version);
+            Some(acc)
+        }),
+    };
+    match target {
+        Some(target) if target.key == program_id => Err(MyError::SelfInvocation.into()),
+        Some(target) if target.executable => Ok(*target.key),
+        Some(target) => {
+            msg!("目标程序 {} 不可执行", target.key);
+            Err(MyError::TargetProgramNotExecutable.into())
+        }
+        None => {
+            // 客户端常见错误：选择器与转发的程序不属于同一家 DEX，例如用 Pump 选择器转发 Raydium 账户
+            let other = FEE_ROUTES
+                .iter()
+                .find(|other| other.program != route.program && forwarded.iter().any(|acc| acc.key == &other.program));
+            if let Some(other) = other {
+                msg!(
+                    "选择器对应路由 {}（程序 {}），但转发账户中只有路由 {} 的程序 {}",
+                    route.name,
+                    route.program,
+                    other.name,
+                    other.program
+                );
+                return Err(MyError::RouteProgramMismatch.into());
+            }
+            msg!("转发账户中缺少路由 {} 的目标程序 {}", route.name, route.program);
+            Err(MyError::TargetProgramNotExecutable.into())
+        }
+    }
+}
+
+// 备用钱包需追加在账户末尾，同样必须是可写的系统程序钱包；只用于 SOL 手续费，代币手续费仍转入主钱包的关联代币账户
+fn failover_fee_receiver<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    config: &TradeFeeState,
+    primary: &AccountInfo,
+) -> Result<&'a AccountInfo<'info>, ProgramError> {
+    let backup = match config.fee_wallet_backup() {
+        Some(backup) => find_account(accounts, &backup),
+        None => None,
+    };
+    match backup {
+        Some(backup) if backup.is_writable && backup.owner == &system_program::id() => {
+            msg!("主手续费钱包 {} 不可用，改用备用钱包 {}", primary.key, backup.key);
+            FeeWalletFailover {
+                primary: *primary.key,
+                backup: *backup.key,
+            }
+            .emit()?;
+            Ok(backup)
+        }
+        _ => {
+            msg!(
+                "手续费接收方 {} 归程序 {} 所有，SOL 手续费无法通过系统程序转入；请传入系统程序所有的手续费钱包、本程序的托管 PDA，或配置备用钱包",
+                primary.key,
+                primary.owner
+            );
+            Err(MyError::FeeReceiverNotSystemOwned.into())
+        }
+    }
+}
+
+// 卖出时交易发起人必须是来源代币账户的所有者，或是委托额度足够的委托人
+fn check_sell_authority(
+    authority: &Pubkey,
+    forwarded: &[AccountInfo],
+    token_account_index: usize,
+    amount: u64,
+) -> ProgramResult {
+    let source = forwarded
+        .get(token_account_index)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let token_account = TokenAccount::unpack(source)?;
+
+    if token_account.owner == *authority {
+        return Ok(());
+    }
+    if token_account.delegate == Some(*authority) && token_account.delegated_amount >= amount {
+        return Ok(());
+    }
+
+    msg!("{} 无权卖出代币账户 {}", authority, source.key);
+    Err(MyError::FeePayerNotAuthorized.into())
+}
+
+pub fn process_fee_route(
+    program_id: &Pubkey,
+    route: &FeeRoute,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+    options: &FeeOptions,
+) -> ProgramResult {
+    (route.check)(accounts)?;
+    process_with_fee(program_id, route, accounts, instruction_data, options)
+}
+
+fn process_with_fee(
+    program_id: &Pubkey,
+    route: &FeeRoute,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+    options: &FeeOptions,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    
+    // 安全获取账户
+    let fee_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let fee_payer = next_account_info(accounts_iter)?; // 支付手续费的SOL账户
+    let fee_receiver = next_account_info(accounts_iter)?; // 接收手续费的SOL账户
+    
+    // 代付交易：支付者未签名时，需附带其预先授权给交易发起人的额度 PDA。交易发起人作为实际支付者先行垫付
+    // SOL 手续费，兑换完成后按实际手续费从额度中扣减并报销
+    let (fee_payer, allowance) = if fee_payer.is_signer {
+        (fee_payer, None)
+    } else {
+        let authority = accounts
+            .get(4..)
+            .and_then(|forwarded| forwarded.get(route.authority_account_index))
+            .filter(|authority| authority.is_signer)
+            .ok_or(ProgramError::MissingRequiredSignature)?;
+        let allowance = load_allowance(program_id, accounts, fee_payer.key, authority.key)?;
+        msg!("由 {} 代付手续费，交易发起人 {} 垫付", fee_payer.key, authority.key);
+        (authority, Some(allowance))
+    };
+
+    // 同一钱包可以同时作为支付者、交易者和接收方重复出现，运行时按交易消息合并权限，
+    // 这里校验合并后收费所需的写权限仍然存在
+    if !fee_payer.is_writable || !fee_receiver.is_writable {
+        msg!("支付者和手续费接收方必须可写");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // 手续费转账依赖系统程序，位置传错时直接报错而不是在 CPI 中失败
+    if system_program.key != &system_program::id() {
+        msg!("第 2 个账户必须是系统程序，实际为 {}", system_program.key);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // 转发账户不足时内层程序只会报出难以定位的错误，这里按路由提前校验
+    if accounts.len() < 4 + route.min_accounts {
+        msg!(
+            "路由 {} 需要至少 {} 个转发账户，实际为 {}",
+            route.name,
+            route.min_accounts,
+            accounts.len().saturating_sub(4)
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    check_account_order(route, &accounts[4..])?;
+
+    // 反序列化配置，配置账户必须归本程序所有
+    if fee_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let mut trade_fee_config = TradeFeeState::unpack(&fee_account.data.borrow())?;
+
+    let target_program = target_program(program_id, route, accounts)?;
+
+    if trade_fee_config.paused {
+        msg!("协议已全局暂停");
+        return Err(MyError::ProtocolPaused.into());
+    }
+    if trade_fee_config.is_route_paused(route.selector) {
+        msg!("路由 {} 已暂停", route.name);
+        return Err(MyError::RoutePaused.into());
+    }
+    
+    // 本笔交易中创建的 PDA 可由共享租金账户垫付租金，交易结束前归还
+    let rent_funder = rent_funder_snapshot(program_id, accounts);
+
+    // 防重放：记录本次交易使用的 nonce
+    if let Some(nonce) = options.nonce {
+        let wallet = options.authorized_user.unwrap_or(*fee_payer.key);
+        record_nonce(program_id, accounts, &trade_fee_config, &wallet, fee_payer, system_program, nonce)?;
+    }
+
+    // CPI 调用方白名单：开启后需要在账户中传入 Instructions sysvar
+    if trade_fee_config.cpi_allowlist_enabled {
+        if let Some(caller) = cpi_caller(accounts)? {
+            if !trade_fee_config.cpi_callers.contains(&caller) || caller == Pubkey::default() {
+                msg!("调用方程序 {} 不在 CPI 白名单中", caller);
+                return Err(MyError::CpiCallerNotAllowed.into());
+            }
+        }
+    }
+
+    // 合规要求：交易中必须包含 Memo 指令
+    if trade_fee_config.require_memo
+        && !transaction_has_instruction(accounts, |ix| {
+            ix.program_id == MEMO_PROGRAM_ID || ix.program_id == MEMO_V1_PROGRAM_ID
+        })?
+    {
+        msg!("配置要求交易附带 Memo 指令");
+        return Err(MyError::MemoRequired.into());
+    }
+
+    // 验证接收方地址匹配配置，或为协议收入托管 PDA
+    let to_escrow = is_escrow_account(program_id, fee_receiver);
+    if !to_escrow && fee_receiver.key != &trade_fee_config.fee_wallet {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // 转入配置账户或其他程序所有的账户后无法按普通钱包取回，托管 PDA 除外；
+    // 主钱包已不归系统程序所有时改用配置的备用钱包
+    let fee_receiver = if !to_escrow && fee_receiver.owner != &system_program::id() {
+        failover_fee_receiver(accounts, &trade_fee_config, fee_receiver)?
+    } else {
+        fee_receiver
+    };
+    if fee_receiver.key == fee_account.key {
+        msg!("手续费接收方 {} 必须是系统程序所有的钱包或托管 PDA", fee_receiver.key);
+        return Err(MyError::InvalidFeeReceiver.into());
+    }
+    
+    // 解析金额。转发数据由内层鉴别器和 instruction_data[8..] 拼成，其中至少要有 8 字节的金额参数
+    // 供扣费后改写，只带 8 字节金额的指令在收费前直接拒绝
+    if instruction_data.len() < 16 {
+        msg!("指令数据长度 {} 不足，需要 [金额 u64][内层参数，至少 8 字节]", instruction_data.len());
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    // 构建 CPI 前限制转发数据长度
+    let inner_len = route.inner_selector.len() + instruction_data.len() - 8;
+    if inner_len > trade_fee_config.inner_data_limit() {
+        msg!("转发数据长度 {} 超过上限 {}", inner_len, trade_fee_config.inner_data_limit());
+        return Err(MyError::InnerDataTooLarge.into());
+    }
+    let amount = u64::from_le_bytes(
+        instruction_data[0..8]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+    
+    check_blocked_mints(route, accounts, &trade_fee_config)?;
+
+    // 卖出时校验交易发起人对来源代币账户的权限（本人卖出或委托卖出），中继交易按授权用户校验
+    if route.side == TradeSide::Sell {
+        let authority = options.authorized_user.unwrap_or(*fee_payer.key);
+        check_sell_authority(&authority, &accounts[4..], route.token_account_index, amount)?;
+    }
+
+    // 目标程序要求交易发起人签名，提前校验，避免未经用户同意代其发起交易。
+    // 中继交易中交易发起人为已通过 ed25519 验签授权的用户时，以该授权代替交易签名
+    let authority = accounts[4..]
+        .get(route.authority_account_index)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if !authority.is_signer && options.authorized_user != Some(*authority.key) {
+        msg!("路由 {} 的交易发起人 {} 未签名", route.name, authority.key);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let ctx = FeeContext {
+        program_id,
+        route,
+        accounts,
+        options,
+        config_account: fee_account,
+        system_program,
+        payer: fee_payer,
+        receiver: fee_receiver,
+        to_escrow,
+        program: target_program,
+        rent_funder,
+        fee_carry: Cell::new(false),
+    };
+
+    let output = match route.output_account_index {
+        Some(index) => accounts[4..].get(index).ok_or(ProgramError::NotEnoughAccountKeys)?,
+        None => fee_payer,
+    };
+    // 卖出所得 SOL 直接转入钱包时，手续费从支付者扣除，收款钱包必须是支付者本人；
+    // 中继交易由中继者为授权用户付费，收款钱包为授权用户
+    if route.side == TradeSide::Sell
+        && output.owner == &system_program::id()
+        && output.key != fee_payer.key
+        && options.authorized_user != Some(*output.key)
+    {
+        msg!("卖出所得转入 {}，与手续费支付者 {} 不一致", output.key, fee_payer.key);
+        return Err(MyError::FeePayerNotAuthorized.into());
+    }
+    if route.side == TradeSide::Buy && trade_fee_config.preflight(PREFLIGHT_FROZEN_DESTINATION) {
+        check_destination_not_frozen(output)?;
+    }
+    let output_before = output_balance(output)?;
+    let destination_before = match &options.min_tokens {
+        Some(guard) => Some(min_tokens_destination(&ctx, guard)?),
+        None => None,
+    };
+
+    // 卖出且配置为兑换后收费（或路由固定从输出收费）时，先用完整数量兑换，
+    // 再按实际收到的数量收费，避免先扣费导致内层兑换余额不足
+    let post_swap = route.side == TradeSide::Sell
+        && (route.fee_from_output || trade_fee_config.fee_timing == FeeTiming::PostSwap);
+    let quote_account = quote_fee_account(route, accounts, &trade_fee_config)?;
+    let result = if let Some(quote_account) = quote_account {
+        // quote 代币收费：买入从支付的 quote 中先行扣除，卖出从收到的 quote 中收取
+        match route.side {
+            TradeSide::Buy => {
+                let fee = compute_token_fee(&ctx, &trade_fee_config, quote_account, amount)?;
+                let fee = fee_or_skip(&ctx, &trade_fee_config, fee, Some(quote_account))?;
+                let remaining_amount = amount_after_fee(amount, fee)?;
+                let breakdown = collect_token_fee(&ctx, &mut trade_fee_config, quote_account, amount, fee)?;
+                let compute_units = invoke_route(&ctx, instruction_data, remaining_amount)?;
+                SwapResult {
+                    route_id: *route.selector,
+                    amount_in: amount,
+                    amount_out: output_balance(output)?.saturating_sub(output_before),
+                    fee,
+                    remaining: remaining_amount,
+                    breakdown,
+                    compute_units,
+                }
+            }
+            TradeSide::Sell => {
+                let quote_before = output_balance(quote_account)?;
+                let compute_units = invoke_route(&ctx, instruction_data, amount)?;
+                let received = output_balance(quote_account)?.saturating_sub(quote_before);
+
+                let fee = compute_token_fee(&ctx, &trade_fee_config, quote_account, received)?;
+                let fee = fee_or_skip(&ctx, &trade_fee_config, fee, Some(quote_account))?;
+                let breakdown = collect_token_fee(&ctx, &mut trade_fee_config, quote_account, received, fee)?;
+                SwapResult {
+                    route_id: *route.selector,
+                    amount_in: amount,
+                    amount_out: received,
+                    fee,
+                    remaining: amount_after_fee(received, fee)?,
+                    breakdown,
+                    compute_units,
+                }
+            }
+        }
+    } else if post_swap {
+        let compute_units = invoke_route(&ctx, instruction_data, amount)?;
+        let received = output_balance(output)?.saturating_sub(output_before);
+
+        let fee = compute_fee(&ctx, &trade_fee_config, received)?;
+        let fee = fee_or_skip(&ctx, &trade_fee_config, fee, None)?;
+        let breakdown = collect_fee(&ctx, &mut trade_fee_config, received, fee)?;
+        SwapResult {
+            route_id: *route.selector,
+            amount_in: amount,
+            amount_out: received,
+            fee,
+            remaining: received.saturating_sub(fee),
+            breakdown,
+            compute_units,
+        }
+    } else if route.side == TradeSide::Buy && trade_fee_config.buy_fee_timing == FeeTiming::PostSwap {
+        // 买入按输出代币收费：完整输入兑换后，从实际收到的代币中收取
+        check_payer_balance(&ctx, &trade_fee_config, 0, amount)?;
+        let compute_units = invoke_route(&ctx, instruction_data, amount)?;
+        let received = output_balance(output)?.saturating_sub(output_before);
+
+        let fee = compute_token_fee(&ctx, &trade_fee_config, output, received)?;
+        let fee = fee_or_skip(&ctx, &trade_fee_config, fee, Some(output))?;
+        let breakdown = collect_token_fee(&ctx, &mut trade_fee_config, output, received, fee)?;
+        SwapResult {
+            route_id: *route.selector,
+            amount_in: amount,
+            amount_out: received,
+            fee,
+            remaining: amount_after_fee(received, fee)?,
+            breakdown,
+            compute_units,
+        }
+    } else if let Some(index) = route.input_account_index {
+        // 精确输出：按最大输入预估手续费并压低内层最大输入，兑换后按实际消耗的输入收取，多估部分不再收取
+        let input = accounts[4..].get(index).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let estimated_fee = compute_fee(&ctx, &trade_fee_config, amount)?;
+        let estimated_fee = fee_or_skip(&ctx, &trade_fee_config, estimated_fee, None)?;
+        let remaining_amount = amount_after_fee(amount, estimated_fee)?;
+        check_payer_balance(&ctx, &trade_fee_config, estimated_fee, remaining_amount)?;
+
+        let input_before = output_balance(input)?;
+        let compute_units = invoke_route(&ctx, instruction_data, remaining_amount)?;
+        let consumed = input_before.saturating_sub(output_balance(input)?);
+
+        let fee = exact_out_fee(&trade_fee_config, estimated_fee, amount, consumed)?;
+        if fee < estimated_fee {
+            msg!("实际输入 {}，手续费由预估的 {} 调整为 {}", consumed, estimated_fee, fee);
+        }
+        let breakdown = collect_fee(&ctx, &mut trade_fee_config, consumed, fee)?;
+        SwapResult {
+            route_id: *route.selector,
+            amount_in: consumed,
+            amount_out: output_balance(output)?.saturating_sub(output_before),
+            fee,
+            remaining: remaining_amount,
+            breakdown,
+            compute_units,
+        }
+    } else {
+        let fee = compute_fee(&ctx, &trade_fee_config, amount)?;
+        let fee = fee_or_skip(&ctx, &trade_fee_config, fee, None)?;
+        let remaining_amount = amount_after_fee(amount, fee)?;
+        check_payer_balance(&ctx, &trade_fee_config, fee, remaining_amount)?;
+
+        let breakdown = collect_fee(&ctx, &mut trade_fee_config, amount, fee)?;
+        let compute_units = invoke_route(&ctx, instruction_data, remaining_amount)?;
+        // 输出为支付者钱包时，余额变化需加回兑换前已转出的手续费
+        let mut output_after = output_balance(output)?;
+        if output.key == fee_payer.key {
+            output_after = output_after.saturating_add(fee);
+        }
+        SwapResult {
+            route_id: *route.selector,
+            amount_in: amount,
+            amount_out: output_after.saturating_sub(output_before),
+            fee,
+            remaining: remaining_amount,
+            breakdown,
+            compute_units,
+        }
+    };
+
+    if let Some(guard) = &options.price_impact {
+        check_price_impact(guard, result.amount_out)?;
+    }
+    if let (Some(guard), Some((destination, before))) = (&options.min_tokens, destination_before) {
+        check_min_tokens(guard, before, TokenAccount::unpack(destination)?.amount)?;
+    }
+    // 代付额度只覆盖 SOL 手续费，以 quote 或输出代币收取的手续费由用户的代币账户承担
+    let sol_fee = quote_account.is_none()
+        && !(route.side == TradeSide::Buy && trade_fee_config.buy_fee_timing == FeeTiming::PostSwap);
+    // 手续费不足以归还共享租金账户垫付的租金时，由支付者补齐
+    repay_rent_funder(rent_funder, fee_payer, system_program, u64::MAX)?;
+    if let (Some(allowance), true) = (allowance, sol_fee) {
+        spend_allowance(allowance, fee_payer, result.fee)?;
+    }
+
+    // 在内层调用之后写入，避免被目标程序的 return data 覆盖
+    set_return_data(&borsh::to_vec(&result)?);
+    Ok(())
+}
+
+// 一次带手续费路由调用中各步骤共用的账户与参数
+struct FeeContext<'a, 'info> {
+    program_id: &'a Pubkey,
+    route: &'a FeeRoute,
+    accounts: &'a [AccountInfo<'info>],
+    options: &'a FeeOptions,
+    config_account: &'a AccountInfo<'info>,
+    system_program: &'a AccountInfo<'info>,
+    payer: &'a AccountInfo<'info>,
+    receiver: &'a AccountInfo<'info>,
+    to_escrow: bool,
+    // 实际调用的目标程序：路由内置地址，或配置中为该路由登记的其他版本
+    program: Pubkey,
+    rent_funder: RentFunderSnapshot<'a, 'info>,
+    // 本笔手续费是否按费率计算（含零头），由 lamport_fee 设置，跳过收费时清除
+    fee_carry: Cell<bool>,
+}
+
+// 按 fee_sides 配置，该方向的交易是否收费
+fn side_charged(config: &TradeFeeState, side: TradeSide) -> bool {
+    matches!(
+        (config.fee_sides, side),
+        (FeeSides::Both, _) | (FeeSides::BuyOnly, TradeSide::Buy) | (FeeSides::SellOnly, TradeSide::Sell)
+    )
+}
+
+// 交易代币登记为免手续费代币、开启 waive_cpi_fees 时其他程序通过 CPI 发起的交易、推广区间内的交易，
+// 以及协议闲置后的第一笔交易免收手续费
+fn fee_waived(ctx: &FeeContext, config: &TradeFeeState) -> Result<bool, ProgramError> {
+    let user_token_account = ctx.accounts[4..].get(ctx.route.token_account_index);
+    if let Some(mint) = fee_exempt_mint(ctx.program_id, ctx.accounts, user_token_account) {
+        msg!("代币 {} 为免手续费代币", mint);
+        return Ok(true);
+    }
+    if config.waive_cpi_fees && invoked_via_cpi() {
+        msg!("通过 CPI 调用，按配置免收手续费");
+        return Ok(true);
+    }
+    if config.promo_end_slot == 0 && config.dormant_waiver_slots == 0 {
+        return Ok(false);
+    }
+    let slot = Clock::get()?.slot;
+    if config.in_promo_window(slot) {
+        msg!("slot {} 处于推广区间 [{}, {})，免收手续费", slot, config.promo_start_slot, config.promo_end_slot);
+        return Ok(true);
+    }
+    if config.is_dormant(slot) {
+        msg!("上一笔交易在 slot {}，已闲置超过 {} 个 slot，本笔免收手续费", config.last_trade_slot, config.dormant_waiver_slots);
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+// 免收方向的交易与 fee_waived 中的各类免收
+fn fee_free(ctx: &FeeContext, config: &TradeFeeState) -> Result<bool, ProgramError> {
+    Ok(!side_charged(config, ctx.route.side) || fee_waived(ctx, config)?)
+}
+
+// 计算费用，新钱包前 N 笔交易、免收方向的交易、CPI 调用及推广区间内的交易不收费，同时记录钱包交易用于冷却校验
+fn compute_fee(ctx: &FeeContext, config: &TradeFeeState, amount: u64) -> Result<u64, ProgramError> {
+    if record_wallet_trade(ctx.program_id, ctx.accounts, ctx.payer, ctx.system_program, config)? || fee_free(ctx, config)? {
+        return Ok(0);
+    }
+    lamport_fee(ctx, config, amount)
+}
+
+// 以 lamports 计的手续费：按有效费率收取并累计零头；设置了美元定额时对定额依次应用同样的折扣
+fn lamport_fee(ctx: &FeeContext, config: &TradeFeeState, amount: u64) -> Result<u64, ProgramError> {
+    if config.fee_usd_micros == 0 {
+        let rate = effective_rate(ctx, config, amount, true)?;
+        ctx.fee_carry.set(true);
+        return calculate_fee_with_carry(amount, rate, config);
+    }
+    let fee = usd_fee(config, ctx.accounts, amount)?;
+    apply_discounts(ctx, config, amount, fee, true)
+}
+
+// CPI 失败会回滚整笔交易，无法事后跳过，只能在转账前按已知的失败条件预检。
+// 转账会失败且 on_fee_failure 为 Skip 时输出 FeeSkipped 并返回 0，按完整数量继续兑换；否则原样返回手续费
+fn fee_or_skip(
+    ctx: &FeeContext,
+    config: &TradeFeeState,
+    fee: u64,
+    token_source: Option<&AccountInfo>,
+) -> Result<u64, ProgramError> {
+    if fee == 0 || config.on_fee_failure == FeeFailurePolicy::Abort {
+        return Ok(fee);
+    }
+    let blocker = match token_source {
+        Some(source) => token_fee_blocker(ctx, config, source, fee)?,
+        None => sol_fee_blocker(ctx, config, fee)?,
+    };
+    let Some(reason) = blocker else {
+        return Ok(fee);
+    };
+
+    msg!("手续费 {} 无法转账（{:?}），按配置跳过收费", fee, reason);
+    ctx.fee_carry.set(false);
+    FeeSkipped {
+        selector: *ctx.route.selector,
+        payer: *ctx.payer.key,
+        fee,
+        reason,
+    }
+    .emit()?;
+    Ok(0)
+}
+
+fn sol_fee_blocker(ctx: &FeeContext, config: &TradeFeeState, fee: u64) -> Result<Option<FeeSkipReason>, ProgramError> {
+    if ctx.payer.lamports() < fee {
+        return Ok(Some(FeeSkipReason::PayerInsufficientFunds));
+    }
+    let buyback_fee = fee as u128 * config.buyback_share_bps as u128 / BPS_DENOMINATOR as u128;
+    if buyback_fee > 0 && find_account(ctx.accounts, &config.buyback_wallet).is_none() {
+        return Ok(Some(FeeSkipReason::BuybackAccountMissing));
+    }
+    if !can_receive_lamports(ctx.receiver, fee)? {
+        return Ok(Some(FeeSkipReason::ReceiverCannotReceive));
+    }
+    Ok(None)
+}
+
+// 代币手续费需要来源账户、mint、协议钱包及其关联代币账户，任一账户缺失或被冻结时转账会失败
+fn token_fee_blocker(
+    ctx: &FeeContext,
+    config: &TradeFeeState,
+    source: &AccountInfo,
+    fee: u64,
+) -> Result<Option<FeeSkipReason>, ProgramError> {
+    let Ok(source_account) = TokenAccount::unpack(source) else {
+        return Ok(Some(FeeSkipReason::TokenAccountMissing));
+    };
+    if source_account.state == ACCOUNT_STATE_FROZEN {
+        return Ok(Some(FeeSkipReason::TokenAccountFrozen));
+    }
+    if source_account.amount < fee {
+        return Ok(Some(FeeSkipReason::PayerInsufficientFunds));
+    }
+
+    let treasury_key = associated_token_address(&config.fee_wallet, &source_account.mint, source.owner);
+    let required = [source.owner, &source_account.mint, &config.fee_wallet, &treasury_key, &ASSOCIATED_TOKEN_PROGRAM_ID];
+    if required.iter().any(|key| find_account(ctx.accounts, key).is_none()) {
+        return Ok(Some(FeeSkipReason::TokenAccountMissing));
+    }
+    // 协议钱包的关联代币账户尚未创建时由收费流程创建，已存在则不能处于冻结状态
+    match find_account(ctx.accounts, &treasury_key) {
+        Some(treasury) if treasury.owner == source.owner && TokenAccount::unpack(treasury)?.state == ACCOUNT_STATE_FROZEN => {
+            Ok(Some(FeeSkipReason::TokenAccountFrozen))
+        }
+        _ => Ok(None),
+    }
+}
+
+// 开启 PREFLIGHT_PAYER_BALANCE 时在收费和 CPI 前预检余额，提前返回 InsufficientFunds，避免内层程序中途失败。
+// 支付者承担 SOL 手续费，路由直接花费 lamports 时交易发起人还需承担输入金额，两者转出后都要保留免租金额
+fn check_payer_balance(ctx: &FeeContext, config: &TradeFeeState, fee: u64, input: u64) -> ProgramResult {
+    if !config.preflight(PREFLIGHT_PAYER_BALANCE) {
+        return Ok(());
+    }
+    let authority = ctx.accounts[4..]
+        .get(ctx.route.authority_account_index)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let (payer_spend, authority_spend) = match (ctx.route.sol_input, authority.key == ctx.payer.key) {
+        (false, _) => (fee, 0),
+        (true, true) => (fee.saturating_add(input), 0),
+        (true, false) => (fee, input),
+    };
+
+    let rent = Rent::get()?.minimum_balance(0);
+    for (wallet, spend) in [(ctx.payer, payer_spend), (authority, authority_spend)] {
+        let required = spend.saturating_add(rent);
+        if spend > 0 && wallet.lamports() < required {
+            msg!("{} 余额 {} 不足，需要至少 {} lamports（含免租金额 {}）", wallet.key, wallet.lamports(), required, rent);
+            return Err(ProgramError::InsufficientFunds);
+        }
+    }
+    Ok(())
+}
+
+// 买入的输出代币账户被冻结时，买到的代币无法转出或卖出，收费和兑换前拒绝；输出为钱包时不检查
+fn check_destination_not_frozen(output: &AccountInfo) -> ProgramResult {
+    if output.owner != &TOKEN_PROGRAM_ID && output.owner != &TOKEN_2022_PROGRAM_ID {
+        return Ok(());
+    }
+    if TokenAccount::unpack(output)?.state == ACCOUNT_STATE_FROZEN {
+        msg!("输出代币账户 {} 已被冻结，拒绝买入", output.key);
+        return Err(MyError::DestinationFrozen.into());
+    }
+    Ok(())
+}
+
+// 费率配置异常（如超过 100%）时手续费可能大于金额，单独报错而不是笼统的余额不足
+fn amount_after_fee(amount: u64, fee: u64) -> Result<u64, ProgramError> {
+    amount.checked_sub(fee).ok_or_else(|| {
+        msg!("手续费 {} 超过交易金额 {}，请检查费率配置", fee, amount);
+        ProgramError::from(MyError::FeeExceedsAmount)
+    })
+}
+
+// 按最大输入预估的手续费按实际消耗比例缩减；美元定额为固定金额，不随成交量变化
+fn exact_out_fee(config: &TradeFeeState, estimated_fee: u64, max_amount_in: u64, consumed: u64) -> Result<u64, ProgramError> {
+    if config.fee_usd_micros != 0 || max_amount_in == 0 {
+        return Ok(estimated_fee);
+    }
+    let fee = estimated_fee as u128 * consumed.min(max_amount_in) as u128 / max_amount_in as u128;
+    u64::try_from(fee).map_err(|_| ProgramError::ArithmeticOverflow)
+}
+
+// 以代币计费时按费率计算，不低于按 mint 精度换算的代币手续费下限（同样应用折扣，且与美元定额一样不超过
+// MAX_FEE_BPS 上限）；美元定额、零头累计与大额返还阈值均以 lamports 计，不适用。
+// 0 位精度 mint 的小额交易按费率舍入为 0 时不收取，设置了下限时至少收取 1 个最小单位（仍受上限约束）
+fn compute_token_fee(
+    ctx: &FeeContext,
+    config: &TradeFeeState,
+    token_account: &AccountInfo,
+    received: u64,
+) -> Result<u64, ProgramError> {
+    if record_wallet_trade(ctx.program_id, ctx.accounts, ctx.payer, ctx.system_program, config)? || fee_free(ctx, config)? {
+        return Ok(0);
+    }
+    token_rate_fee(ctx, config, token_account, received)
+}
+
+// 不免收时以代币计的手续费：费率、下限、上限与金额校验
+fn token_rate_fee(
+    ctx: &FeeContext,
+    config: &TradeFeeState,
+    token_account: &AccountInfo,
+    received: u64,
+) -> Result<u64, ProgramError> {
+    let mut fee = calculate_fee(received, effective_rate(ctx, config, received, false)?)?;
+    if config.preflight(PREFLIGHT_TOKEN_FEE_FLOOR) {
+        let mint = required_account(ctx.accounts, &TokenAccount::unpack(token_account)?.mint)?;
+        let floor = token_fee_floor(ctx.program_id, ctx.accounts, config, mint)?;
+        let floor = apply_discounts(ctx, config, received, floor, false)?.min(calculate_fee(received, MAX_FEE_RATE_PIPS)?);
+        fee = fee.max(floor);
+    }
+    // 代币手续费在转账前确认不超过以代币计的金额
+    amount_after_fee(received, fee)?;
+    Ok(fee)
+}
+
+// 转发账户中出现被禁止的 mint（Pump 路由会传入 mint 账户），或用户代币账户属于被禁止的 mint 时拒绝
+fn check_blocked_mints(route: &FeeRoute, accounts: &[AccountInfo], config: &TradeFeeState) -> ProgramResult {
+    if config.blocked_mints.iter().all(|mint| mint == &Pubkey::default()) {
+        return Ok(());
+    }
+    let forwarded = &accounts[4..];
+    let user_token_mint = forwarded
+        .get(route.token_account_index)
+        .and_then(|account| TokenAccount::unpack(account).ok())
+        .map(|token_account| token_account.mint);
+
+    let blocked = forwarded
+        .iter()
+        .map(|account| *account.key)
+        .chain(user_token_mint)
+        .find(|key| config.is_mint_blocked(key));
+    if let Some(mint) = blocked {
+        msg!("代币 {} 在禁止列表中", mint);
+        return Err(MyError::MintBlocked.into());
+    }
+    Ok(())
+}
+
+// 路由支持 quote 代币收费且用户 quote 代币账户的 mint 与配置一致时返回该账户
+fn quote_fee_account<'a, 'info>(
+    route: &FeeRoute,
+    accounts: &'a [AccountInfo<'info>],
+    config: &TradeFeeState,
+) -> Result<Option<&'a AccountInfo<'info>>, ProgramError> {
+    let Some(index) = route.quote_account_index else {
+        return Ok(None);
+    };
+    if config.quote_fee_mint == Pubkey::default() {
+        return Ok(None);
+    }
+    let account = accounts[4..].get(index).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    Ok((TokenAccount::unpack(account)?.mint == config.quote_fee_mint).then_some(account))
+}
+
+// 费率优先级：代币费率 PDA > 路由覆盖 > 全局费率
+fn trade_fee_rate(ctx: &FeeContext, config: &TradeFeeState) -> u32 {
+    let user_token_account = ctx.accounts[4..].get(ctx.route.token_account_index);
+    mint_fee_rate(ctx.program_id, ctx.accounts, user_token_account)
+        .unwrap_or_else(|| config.fee_rate_for(ctx.route.selector))
+}
+
+// 一笔交易折扣后的有效费率（pips），只读取账户，不修改任何状态。各项机制的优先级：
+// 1. 免收：新钱包免费笔数、免收方向、CPI 免收、推广区间，由调用方在计算费率前判定，命中时整笔不收费；
+// 2. 基础费率：代币费率 PDA > 路由覆盖 > 全局费率，只取优先级最高的一项，不叠加；
+// 3. 折扣按会员折扣 → 全局返还 rebate_bps → 大额返还 whale_rebate_bps 的顺序依次叠乘，每一步向下取整；
+//    大额阈值以 lamports 计，只在 lamports_amount 为 true（按 SOL 计费）时适用。
+// 推荐人分成是从已收取的手续费中分出的份额，不影响有效费率
+fn effective_rate(ctx: &FeeContext, config: &TradeFeeState, amount: u64, lamports_amount: bool) -> Result<u32, ProgramError> {
+    let rate = apply_discounts(ctx, config, amount, trade_fee_rate(ctx, config) as u64, lamports_amount)?;
+    Ok(rate as u32)
+}
+
+// 对费率或手续费依次应用会员折扣、全局返还和大额返还，结果不会大于输入
+fn apply_discounts(
+    ctx: &FeeContext,
+    config: &TradeFeeState,
+    amount: u64,
+    value: u64,
+    lamports_amount: bool,
+) -> Result<u64, ProgramError> {
+    let value = config.fee_after_rebate(membership_discount(ctx, config, value)?);
+    Ok(match lamports_amount {
+        true => config.fee_after_whale_rebate(amount, value),
+        false => value,
+    })
+}
+
+// 会员折扣：交易者的会员代币关联账户余额大于 0 时按比例减免
+fn membership_discount(ctx: &FeeContext, config: &TradeFeeState, fee: u64) -> Result<u64, ProgramError> {
+    if config.membership_discount_bps > 0 && config.membership_mint != Pubkey::default() {
+        let trader = ctx.options.authorized_user.unwrap_or(*ctx.payer.key);
+        if holds_membership(ctx.accounts, &trader, &config.membership_mint)? {
+            let discount = fee as u128 * config.membership_discount_bps as u128 / BPS_DENOMINATOR as u128;
+            return Ok(fee - discount as u64);
+        }
+    }
+    Ok(fee)
+}
+
+// 在追加账户中查找交易者的会员代币关联账户（Token 或 Token-2022），未传入时视为非会员
+fn holds_membership(accounts: &[AccountInfo], trader: &Pubkey, mint: &Pubkey) -> Result<bool, ProgramError> {
+    for token_program in [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID] {
+        let address = associated_token_address(trader, mint, &token_program);
+        let Some(account) = find_account(accounts, &address) else {
+            continue;
+        };
+        if account.owner != &token_program {
+            continue;
+        }
+        let token_account = TokenAccount::unpack(account)?;
+        return Ok(token_account.mint == *mint && token_account.owner == *trader && token_account.amount > 0);
+    }
+    Ok(false)
+}
+
+// 按推荐人、回购、协议的顺序分配手续费，并递增事件序号、输出收费事件
+fn collect_fee(
+    ctx: &FeeContext,
+    config: &mut TradeFeeState,
+    amount: u64,
+    fee: u64,
+) -> Result<FeeBreakdown, ProgramError> {
+    let (fee_payer, system_program) = (ctx.payer, ctx.system_program);
+
+    // 验证支付者有足够余额
+    if fee_payer.lamports() < fee {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    // 推荐码分成：按登记的比例（不超过配置上限）把部分手续费转给推荐人，
+    // 未带推荐码的交易分成给配置的默认推荐人
+    let mut treasury_fee = fee;
+    let mut referral_fee = 0;
+    match ctx.options.referral_code {
+        Some(code) => match load_active_referral(ctx.program_id, ctx.accounts, code)? {
+            Some(referral) => referral_fee = pay_referrer(ctx, config, code, &referral, fee)?,
+            None => {
+                // 未知推荐码不中断交易，视为未带推荐码；没有默认推荐人时手续费全部归协议
+                let default_referral = config.default_referral();
+                ReferralFallback {
+                    code,
+                    referrer: default_referral.as_ref().map_or(Pubkey::default(), |r| r.wallet),
+                    fee,
+                }
+                .emit()?;
+                if let Some(referral) = default_referral {
+                    referral_fee = pay_referrer(ctx, config, referral.code, &referral, fee)?;
+                }
+            }
+        },
+        None => {
+            if let Some(referral) = config.default_referral() {
+                referral_fee = pay_referrer(ctx, config, referral.code, &referral, fee)?;
+            }
+        }
+    }
+    treasury_fee -= referral_fee;
+    
+    // 回购分成：按配置比例从剩余手续费中转给回购钱包
+    let buyback_fee = (fee as u128 * config.buyback_share_bps as u128 / BPS_DENOMINATOR as u128)
+        .min(treasury_fee as u128) as u64;
+    if buyback_fee > 0 {
+        let buyback = find_account(ctx.accounts, &config.buyback_wallet).ok_or_else(|| {
+            msg!("缺少回购钱包账户 {}", config.buyback_wallet);
+            ProgramError::from(MyError::BuybackAccountMissing)
+        })?;
+        transfer_lamports(fee_payer, buyback, system_program, buyback_fee)?;
+        treasury_fee -= buyback_fee;
+    }
+
+    // 共享租金账户为本笔交易垫付的租金先从协议所得中归还
+    treasury_fee -= repay_rent_funder(ctx.rent_funder, fee_payer, system_program, treasury_fee)?;
+
+    // 转账SOL手续费到协议钱包
+    transfer_lamports(fee_payer, ctx.receiver, system_program, treasury_fee)?;
+    if ctx.to_escrow {
+        record_escrow_deposit(ctx.receiver, treasury_fee)?;
+    }
+
+    record_epoch_fee(ctx.program_id, ctx.accounts, fee_payer, system_program, config, fee)?;
+    commit_fee_carry(ctx, config, amount, fee)?;
+    config.total_fees_collected = config.total_fees_collected.saturating_add(fee);
+    record_fee_event(ctx, config, amount, fee, referral_fee, FeeDenomination::LAMPORTS)?;
+    Ok(FeeBreakdown {
+        treasury: treasury_fee,
+        referrer: referral_fee,
+        buyback: buyback_fee,
+    })
+}
+
+// 按登记的比例（不超过配置上限）把部分手续费转给推荐人，返回实际转出的金额
+// 推荐人账户缺失或无法接收转账时，不中断交易，全部手续费归协议
+fn pay_referrer(
+    ctx: &FeeContext,
+    config: &TradeFeeState,
+    code: u32,
+    referral: &ReferralState,
+    fee: u64,
+) -> Result<u64, ProgramError> {
+    let share_bps = config.referral_share_for(referral.share_bps);
+    let referrer_fee = (fee as u128 * share_bps as u128 / BPS_DENOMINATOR as u128) as u64;
+
+    match find_account(ctx.accounts, &referral.wallet) {
+        Some(referrer) if can_receive_lamports(referrer, referrer_fee)? => {
+            transfer_lamports(ctx.payer, referrer, ctx.system_program, referrer_fee)?;
+            Ok(referrer_fee)
+        }
+        _ => {
+            msg!("推荐码 {} 的推荐人账户 {} 无效，手续费全部归协议", code, referral.wallet);
+            ReferralFallback {
+                code,
+                referrer: referral.wallet,
+                fee,
+            }
+            .emit()?;
+            Ok(0)
+        }
+    }
+}
+
+// 买入按输出代币收费：手续费从用户收到的代币中转入协议钱包的关联代币账户，不足时先创建，
+// 推荐与回购分成以 SOL 结算，不适用于代币手续费。TransferChecked 不附带 transfer hook 的额外账户，
+// 挂载了 hook 的 Token-2022 mint 在转账前拒绝。收费事件以 mint 最小单位记录，交易须附带 mint 账户
+fn collect_token_fee<'info>(
+    ctx: &FeeContext<'_, 'info>,
+    config: &mut TradeFeeState,
+    user_token: &AccountInfo<'info>,
+    received: u64,
+    fee: u64,
+) -> Result<FeeBreakdown, ProgramError> {
+    let mint_key = TokenAccount::unpack(user_token)?.mint;
+    let mint = required_account(ctx.accounts, &mint_key)?;
+    if fee > 0 {
+        let token_program = required_account(ctx.accounts, user_token.owner)?;
+        let fee_wallet = required_account(ctx.accounts, &config.fee_wallet)?;
+        let treasury_key = associated_token_address(&config.fee_wallet, &mint_key, token_program.key);
+        let treasury = required_account(ctx.accounts, &treasury_key)?;
+        if let Some(hook) = transfer_hook_program(mint)? {
+            msg!("代币 {} 挂载了 transfer hook 程序 {}，无法以该代币收取手续费", mint_key, hook);
+            return Err(MyError::TransferHookNotSupported.into());
+        }
+
+        create_associated_token_account_idempotent(
+            ctx.payer,
+            treasury,
+            fee_wallet,
+            mint,
+            ctx.system_program,
+            token_program,
+            required_account(ctx.accounts, &ASSOCIATED_TOKEN_PROGRAM_ID)?,
+        )?;
+        transfer_checked(token_program, user_token, mint, treasury, ctx.payer, fee)?;
+    }
+
+    let denomination = FeeDenomination { mint: mint_key, decimals: mint_decimals(mint)? };
+    record_fee_event(ctx, config, received, fee, 0, denomination)?;
+    Ok(FeeBreakdown {
+        treasury: fee,
+        ..FeeBreakdown::default()
+    })
+}
+
+fn required_account<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    key: &Pubkey,
+) -> Result<&'a AccountInfo<'info>, ProgramError> {
+    find_account(accounts, key).ok_or_else(|| {
+        msg!("缺少账户 {}", key);
+        ProgramError::NotEnoughAccountKeys
+    })
+}
+
+// 收费事件中 amount 与手续费的计价单位
+struct FeeDenomination {
+    mint: Pubkey,
+    decimals: u8,
+}
+
+impl FeeDenomination {
+    // 以 lamports 收取的 SOL 手续费，mint 记为默认公钥
+    const LAMPORTS: FeeDenomination = FeeDenomination { mint: Pubkey::new_from_array([0; 32]), decimals: 9 };
+}
+
+// 递增事件序号、记录最近交易 slot 并输出收费事件
+fn record_fee_event(
+    ctx: &FeeContext,
+    config: &mut TradeFeeState,
+    amount: u64,
+    fee: u64,
+    referral_fee: u64,
+    denomination: FeeDenomination,
+) -> ProgramResult {
+    config.seq = config
+        .seq
+        .checked_add(1)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    config.last_trade_slot = Clock::get()?.slot;
+    config.store(ctx.config_account)?;
+    let event = FeeCollected {
+        seq: config.seq,
+        selector: *ctx.route.selector,
+        payer: *ctx.payer.key,
+        amount,
+        // 代币手续费不计入 lamports 口径的 fee，只记在 fee_raw 中
+        fee: if denomination.mint == Pubkey::default() { fee } else { 0 },
+        referral_fee,
+        fee_raw: fee,
+        decimals: denomination.decimals,
+        fee_mint: denomination.mint,
+    };
+    if config.compact_events {
+        // 通常为第 3 个账户；代付交易中实际支付者是交易发起人，位于转发账户中
+        let wallet_index = ctx
+            .accounts
+            .iter()
+            .position(|account| account.key == ctx.payer.key)
+            .and_then(|index| u8::try_from(index).ok())
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        CompactFeeCollected {
+            route_id: <[u8; 4]>::try_from(&ctx.route.selector[..4]).unwrap(),
+            fee,
+            wallet_index,
+        }
+        .emit()?;
+    } else {
+        event.emit()?;
+    }
+    if config.event_cpi {
+        event.emit_cpi(ctx.program_id, ctx.accounts)?;
+    }
+    Ok(())
+}
+
+// 构建转发给目标 DEX 的指令数据：内层鉴别器 + instruction_data[8..]，再写入扣费后的金额
+fn build_inner_data(
+    route: &FeeRoute,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+    inner_amount: u64,
+) -> Result<Vec<u8>, ProgramError> {
+    let mut data = Vec::with_capacity(route.inner_selector.len() + instruction_data.len() - 8);
+    data.extend_from_slice(route.inner_selector);
+    data.extend_from_slice(&instruction_data[8..]);
+    (route.patch_amount)(&accounts[4..], &mut data, inner_amount)?;
+    Ok(data)
+}
+
+// 把内层金额写入指令数据后调用目标 DEX，返回 CPI 消耗的计算单元
+fn invoke_route(ctx: &FeeContext, instruction_data: &[u8], inner_amount: u64) -> Result<u64, ProgramError> {
+    let (route, accounts) = (ctx.route, ctx.accounts);
+
+    let data = build_inner_data(route, accounts, instruction_data, inner_amount)?;
+    
+    let compute_before = sol_remaining_compute_units();
+    // 执行原始交易（使用剩余账户）
+    // 内层程序执行失败会直接终止整笔交易，这里能捕获的是 CPI 发起前的错误（账户缺失、权限提升等）
+    invoke(
+        &Instruction {
+            program_id: ctx.program,
+            accounts: accounts[4..] // 跳过已处理的账户
+                .iter()
+                .filter(|acc| route.forward_program_account || acc.key != &ctx.program)
+                .map(|acc| AccountMeta {
+                    pubkey: *acc.key,
+                    is_signer: acc.is_signer,
+                    is_writable: acc.is_writable,
+                })
+                .collect(),
+            data,
+        },
+        &accounts[4..],
+    )
+    .map_err(|e| {
+        msg!("路由 {} 调用 {} 失败: {:?}", route.name, ctx.program, e);
+        e
+    })?;
+    Ok(compute_before.saturating_sub(sol_remaining_compute_units()))
+}
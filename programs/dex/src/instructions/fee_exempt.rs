@@ -0,0 +1,103 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::state::{FeeExemptMintState, TradeFeeState};
+use crate::token::TokenAccount;
+use crate::utils::{create_pda_account, find_account};
+
+// 管理员登记、移除免手续费的合作方代币: [mint 32]
+pub const ADD_FEE_EXEMPT_MINT_SELECTOR: &[u8; 8] = b"fexm_add";
+pub const REMOVE_FEE_EXEMPT_MINT_SELECTOR: &[u8; 8] = b"fexm_rm\0";
+
+pub const FEE_EXEMPT_MINT_SEED: &[u8] = b"fee_exempt";
+
+pub fn fee_exempt_mint_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FEE_EXEMPT_MINT_SEED, mint.as_ref()], program_id)
+}
+
+fn parse_mint(data: &[u8]) -> Result<Pubkey, ProgramError> {
+    let bytes = data.get(0..32).ok_or(ProgramError::InvalidInstructionData)?;
+    Ok(Pubkey::new_from_array(bytes.try_into().unwrap()))
+}
+
+// 用户代币账户的 mint 已登记为免手续费代币（登记 PDA 追加在账户末尾）时返回该 mint
+pub fn fee_exempt_mint(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    user_token_account: Option<&AccountInfo>,
+) -> Option<Pubkey> {
+    let mint = TokenAccount::unpack(user_token_account?).ok()?.mint;
+    let account = find_account(accounts, &fee_exempt_mint_address(program_id, &mint).0)?;
+    if account.owner != program_id {
+        return None;
+    }
+    let state = FeeExemptMintState::try_from_slice(&account.data.borrow()).ok()?;
+    (state.mint == mint).then_some(mint)
+}
+
+// 账户: [配置账户, 管理员(签名并支付租金), 免手续费登记 PDA, 系统程序]，免收手续费开启多签时需达到门限
+pub fn process_add_fee_exempt_mint(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let mint = parse_mint(instruction_data)?;
+
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let exempt_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    let config = TradeFeeState::load_as_admin(program_id, config_account, admin)?;
+    config.check_zero_fee_multisig(accounts, true)?;
+
+    let (address, bump) = fee_exempt_mint_address(program_id, &mint);
+    if exempt_account.key != &address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if exempt_account.owner != program_id {
+        create_pda_account(
+            admin,
+            exempt_account,
+            system_program,
+            program_id,
+            FeeExemptMintState::LEN,
+            &[FEE_EXEMPT_MINT_SEED, mint.as_ref(), &[bump]],
+        )?;
+    }
+
+    FeeExemptMintState { mint }.serialize(&mut &mut exempt_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// 移除免手续费代币并关闭 PDA，租金退还给管理员
+// 账户: [配置账户, 管理员, 免手续费登记 PDA]
+pub fn process_remove_fee_exempt_mint(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let mint = parse_mint(instruction_data)?;
+
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let exempt_account = next_account_info(accounts_iter)?;
+
+    TradeFeeState::load_as_admin(program_id, config_account, admin)?;
+    if exempt_account.owner != program_id || exempt_account.key != &fee_exempt_mint_address(program_id, &mint).0 {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    exempt_account.data.borrow_mut().fill(0);
+    let lamports = exempt_account.lamports();
+    **exempt_account.try_borrow_mut_lamports()? -= lamports;
+    **admin.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
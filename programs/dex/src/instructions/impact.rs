@@ -0,0 +1,95 @@
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::MyError;
+use crate::instructions::fee::{
+    find_fee_route, process_fee_route, FeeOptions, MinTokensGuard, PriceImpactGuard, TradeSide,
+};
+use crate::state::BPS_DENOMINATOR;
+
+// 带价格影响上限的买入：[expected_out u64][max_impact_bps u16][路由选择器 8][路由数据...]
+pub const IMPACT_SWAP_SELECTOR: &[u8; 8] = b"impactsw";
+// 指定目标代币账户的最小到账：[目标代币账户 32][min_tokens u64][路由选择器 8][路由数据...]
+pub const MIN_TOKENS_SWAP_SELECTOR: &[u8; 8] = b"min_tkns";
+
+// 实际收到的数量低于预期超过 max_impact_bps 时拒绝，恰好等于上限时放行
+pub fn check_price_impact(guard: &PriceImpactGuard, realized_out: u64) -> ProgramResult {
+    let shortfall = guard.expected_out.saturating_sub(realized_out) as u128;
+    if shortfall * BPS_DENOMINATOR as u128 > guard.expected_out as u128 * guard.max_impact_bps as u128 {
+        msg!(
+            "实际收到 {} 低于预期 {}，超过价格影响上限 {} 基点",
+            realized_out,
+            guard.expected_out,
+            guard.max_impact_bps
+        );
+        return Err(MyError::PriceImpactExceeded.into());
+    }
+    Ok(())
+}
+
+// 目标代币账户的余额增量低于 min_tokens 时拒绝
+pub fn check_min_tokens(guard: &MinTokensGuard, before: u64, after: u64) -> ProgramResult {
+    let received = after.saturating_sub(before);
+    if received < guard.min_tokens {
+        msg!("目标代币账户 {} 收到 {}，低于 min_tokens {}", guard.destination, received, guard.min_tokens);
+        return Err(MyError::MinTokensNotReceived.into());
+    }
+    Ok(())
+}
+
+// 账户与被包装的买入路由相同，兑换后按目标账户余额变化校验价格影响
+pub fn process_impact_swap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 18 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let expected_out = u64::from_le_bytes(<[u8; 8]>::try_from(&instruction_data[..8]).unwrap());
+    let max_impact_bps = u16::from_le_bytes(<[u8; 2]>::try_from(&instruction_data[8..10]).unwrap());
+    if expected_out == 0 || max_impact_bps as u64 > BPS_DENOMINATOR {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let route = find_fee_route(&instruction_data[10..18]).ok_or(ProgramError::InvalidInstructionData)?;
+    if route.side != TradeSide::Buy {
+        msg!("价格影响保护只支持买入路由，{} 为卖出路由", route.name);
+        return Err(MyError::UnsupportedRoute.into());
+    }
+
+    let options = FeeOptions {
+        price_impact: Some(PriceImpactGuard {
+            expected_out,
+            max_impact_bps,
+        }),
+        ..FeeOptions::default()
+    };
+    process_fee_route(program_id, route, accounts, &instruction_data[18..], &options)
+}
+
+// 账户与被包装的路由相同，目标代币账户不在转发账户中时需追加在末尾；
+// 兑换前后读取该账户余额，增量低于 min_tokens 时回滚，滑点保护落到具体账户而不依赖路由推断的输出账户
+pub fn process_min_tokens_swap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 48 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let destination = Pubkey::new_from_array(<[u8; 32]>::try_from(&instruction_data[..32]).unwrap());
+    let min_tokens = u64::from_le_bytes(<[u8; 8]>::try_from(&instruction_data[32..40]).unwrap());
+    if min_tokens == 0 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let route = find_fee_route(&instruction_data[40..48]).ok_or(ProgramError::InvalidInstructionData)?;
+    let options = FeeOptions {
+        min_tokens: Some(MinTokensGuard { destination, min_tokens }),
+        ..FeeOptions::default()
+    };
+    process_fee_route(program_id, route, accounts, &instruction_data[48..], &options)
+}
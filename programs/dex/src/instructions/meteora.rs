@@ -0,0 +1,104 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey,
+    pubkey::Pubkey,
+};
+
+use crate::instructions::fee::{patch_first_arg, process_fee_route, AccountSpec, FeeOptions, FeeRoute, TradeSide};
+
+// 带手续费的 Meteora DAMM v2 swap: [计费金额 u64][amount_in u64][minimum_amount_out u64]
+// 买入时输入为 quote（WSOL 等）代币账户，卖出时输出为 quote 代币账户
+pub const DAMM_V2_BUY_SELECTOR: &[u8; 8] = b"dmv2_buy";
+pub const DAMM_V2_SELL_SELECTOR: &[u8; 8] = b"dmv2_sel";
+
+const DAMM_V2_PROGRAM: Pubkey = pubkey!("cpamdpZCGKUy5JxQXB4dcpGPiikHawvSWAd6mEn1sGG");
+const DAMM_V2_SWAP: &[u8] = &[248, 198, 158, 145, 225, 117, 135, 200];
+
+// swap 账户布局: pool_authority、pool、input_token_account、output_token_account、token_a_vault、token_b_vault、
+// token_a_mint、token_b_mint、payer、token_a_program、token_b_program、referral_token_account、event_authority、program
+const POOL_INDEX: usize = 1;
+const INPUT_TOKEN_INDEX: usize = 2;
+const OUTPUT_TOKEN_INDEX: usize = 3;
+const PAYER_INDEX: usize = 8;
+// 14 个账户，末尾的程序账户同时用于 event CPI；未使用的 referral_token_account 传程序地址占位
+const MIN_ACCOUNTS: usize = 14;
+// referral_token_account 未使用时为只读的程序地址，不要求可写
+const SWAP_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("pool_authority"),
+    AccountSpec::writable("pool"),
+    AccountSpec::writable("input_token_account"),
+    AccountSpec::writable("output_token_account"),
+    AccountSpec::writable("token_a_vault"),
+    AccountSpec::writable("token_b_vault"),
+    AccountSpec::readonly("token_a_mint"),
+    AccountSpec::readonly("token_b_mint"),
+    AccountSpec::signer("payer"),
+    AccountSpec::readonly("token_a_program"),
+    AccountSpec::readonly("token_b_program"),
+    AccountSpec::readonly("referral_token_account"),
+    AccountSpec::readonly("event_authority"),
+    AccountSpec::readonly("program"),
+];
+
+fn check_damm_v2_pool(accounts: &[AccountInfo]) -> ProgramResult {
+    let pool = accounts
+        .get(4..)
+        .and_then(|forwarded| forwarded.get(POOL_INDEX))
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if pool.owner != &DAMM_V2_PROGRAM {
+        msg!("池子账户 {} 不属于 Meteora DAMM v2", pool.key);
+        return Err(ProgramError::IllegalOwner);
+    }
+    Ok(())
+}
+
+pub const DAMM_V2_BUY_ROUTE: FeeRoute = FeeRoute {
+    name: "meteora_damm_v2_buy",
+    selector: DAMM_V2_BUY_SELECTOR,
+    program: DAMM_V2_PROGRAM,
+    inner_selector: DAMM_V2_SWAP,
+    forward_program_account: true,
+    min_accounts: MIN_ACCOUNTS,
+    accounts: SWAP_ACCOUNTS,
+    side: TradeSide::Buy,
+    token_account_index: OUTPUT_TOKEN_INDEX,
+    quote_account_index: Some(INPUT_TOKEN_INDEX),
+    authority_account_index: PAYER_INDEX,
+    output_account_index: Some(OUTPUT_TOKEN_INDEX),
+    input_account_index: None,
+    fee_from_output: false,
+    sol_input: false,
+    check: check_damm_v2_pool,
+    patch_amount: patch_first_arg::<8>,
+};
+
+pub const DAMM_V2_SELL_ROUTE: FeeRoute = FeeRoute {
+    name: "meteora_damm_v2_sell",
+    selector: DAMM_V2_SELL_SELECTOR,
+    program: DAMM_V2_PROGRAM,
+    inner_selector: DAMM_V2_SWAP,
+    forward_program_account: true,
+    min_accounts: MIN_ACCOUNTS,
+    accounts: SWAP_ACCOUNTS,
+    side: TradeSide::Sell,
+    token_account_index: INPUT_TOKEN_INDEX,
+    quote_account_index: Some(OUTPUT_TOKEN_INDEX),
+    authority_account_index: PAYER_INDEX,
+    output_account_index: Some(OUTPUT_TOKEN_INDEX),
+    input_account_index: None,
+    fee_from_output: false,
+    sol_input: false,
+    check: check_damm_v2_pool,
+    patch_amount: patch_first_arg::<8>,
+};
+
+pub fn process_damm_v2_buy(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    process_fee_route(program_id, &DAMM_V2_BUY_ROUTE, accounts, instruction_data, &FeeOptions::default())
+}
+
+pub fn process_damm_v2_sell(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    process_fee_route(program_id, &DAMM_V2_SELL_ROUTE, accounts, instruction_data, &FeeOptions::default())
+}
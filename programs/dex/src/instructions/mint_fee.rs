@@ -0,0 +1,113 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::MyError;
+use crate::state::{MintFeeState, TradeFeeState, MAX_FEE_RATE_PIPS};
+use crate::token::TokenAccount;
+use crate::utils::{create_pda_account, find_account};
+
+// 管理员设置、清除单个代币的费率: [mint 32][rate_pips u32] / [mint 32]
+pub const SET_MINT_FEE_SELECTOR: &[u8; 8] = b"mfee_set";
+pub const CLEAR_MINT_FEE_SELECTOR: &[u8; 8] = b"mfee_clr";
+
+pub const MINT_FEE_SEED: &[u8] = b"mint_fee";
+
+pub fn mint_fee_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MINT_FEE_SEED, mint.as_ref()], program_id)
+}
+
+fn parse_mint(data: &[u8]) -> Result<Pubkey, ProgramError> {
+    let bytes = data.get(0..32).ok_or(ProgramError::InvalidInstructionData)?;
+    Ok(Pubkey::new_from_array(bytes.try_into().unwrap()))
+}
+
+// 交易代币为用户代币账户的 mint，按其费率 PDA（追加在账户末尾）查找覆盖费率，未登记时返回 None
+pub fn mint_fee_rate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    user_token_account: Option<&AccountInfo>,
+) -> Option<u32> {
+    let mint = TokenAccount::unpack(user_token_account?).ok()?.mint;
+    let account = find_account(accounts, &mint_fee_address(program_id, &mint).0)?;
+    if account.owner != program_id {
+        return None;
+    }
+    let state = MintFeeState::try_from_slice(&account.data.borrow()).ok()?;
+    (state.mint == mint).then_some(state.rate_pips)
+}
+
+// 账户: [配置账户, 管理员(签名并支付租金), 代币费率 PDA, 系统程序]，已存在时直接更新费率
+pub fn process_set_mint_fee(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let mint = parse_mint(instruction_data)?;
+    let rate_pips = instruction_data
+        .get(32..36)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    if rate_pips > MAX_FEE_RATE_PIPS {
+        return Err(MyError::FeeRateTooHigh.into());
+    }
+
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let mint_fee_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    let config = TradeFeeState::load_as_admin(program_id, config_account, admin)?;
+    config.check_zero_fee_multisig(accounts, rate_pips == 0)?;
+
+    let (address, bump) = mint_fee_address(program_id, &mint);
+    if mint_fee_account.key != &address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if mint_fee_account.owner != program_id {
+        create_pda_account(
+            admin,
+            mint_fee_account,
+            system_program,
+            program_id,
+            MintFeeState::LEN,
+            &[MINT_FEE_SEED, mint.as_ref(), &[bump]],
+        )?;
+    }
+
+    let state = MintFeeState { mint, rate_pips };
+    state.serialize(&mut &mut mint_fee_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// 清除代币费率并关闭 PDA，租金退还给管理员
+// 账户: [配置账户, 管理员, 代币费率 PDA]
+pub fn process_clear_mint_fee(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let mint = parse_mint(instruction_data)?;
+
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let mint_fee_account = next_account_info(accounts_iter)?;
+
+    TradeFeeState::load_as_admin(program_id, config_account, admin)?;
+    if mint_fee_account.owner != program_id || mint_fee_account.key != &mint_fee_address(program_id, &mint).0 {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // 清空数据并转出全部 lamports，交易结束后账户即被回收
+    mint_fee_account.data.borrow_mut().fill(0);
+    let lamports = mint_fee_account.lamports();
+    **mint_fee_account.try_borrow_mut_lamports()? -= lamports;
+    **admin.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
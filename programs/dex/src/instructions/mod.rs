@@ -1,4 +1,21 @@
+pub mod allowance;
 pub mod ata;
+pub mod escrow;
+pub mod fee;
+pub mod fee_exempt;
+pub mod impact;
+pub mod meteora;
+pub mod mint_fee;
+pub mod nonce;
+pub mod openbook;
 pub mod pump;
 pub mod raydium;
+pub mod referral;
+pub mod relay;
+pub mod rent_funder;
+pub mod route_program;
+pub mod rollup;
 pub mod slot;
+pub mod token_fee_floor;
+pub mod version;
+pub mod wallet;
@@ -0,0 +1,108 @@
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg,
+    program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
+};
+
+use crate::error::MyError;
+use crate::instructions::fee::{find_fee_route, process_fee_route, FeeOptions};
+use crate::state::TradeFeeState;
+use crate::instructions::rent_funder::create_trade_pda;
+use crate::utils::find_account;
+
+// 带防重放 nonce 的交易：[nonce u64][路由选择器 8][路由数据...]
+pub const NONCE_SWAP_SELECTOR: &[u8; 8] = b"nonce_sw";
+
+pub const NONCE_SEED: &[u8] = b"nonces";
+// 环形缓冲每项为 [nonce u64][记录时的 slot u64]，slot 为 0 表示空位
+const NONCE_ENTRY_LEN: usize = 16;
+pub const DEFAULT_NONCE_RING_SIZE: u16 = 32;
+pub const MAX_NONCE_RING_SIZE: u16 = 256;
+
+pub fn nonce_address(program_id: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[NONCE_SEED, wallet.as_ref()], program_id)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+// 记录 wallet 使用的 nonce：窗口内重复使用时拒绝，超出 replay_window_slots 的旧记录被淘汰。
+// 记录永不过期时缓冲区写满后钱包无法再交易，因此必须先配置非零窗口；
+// nonce PDA 追加在账户末尾，首次使用时由支付者按配置的环形缓冲大小创建
+pub fn record_nonce<'info>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'info>],
+    config: &TradeFeeState,
+    wallet: &Pubkey,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    nonce: u64,
+) -> ProgramResult {
+    if config.replay_window_slots == 0 {
+        msg!("未配置 nonce 防重放窗口，请先通过 set_rply 设置非零窗口");
+        return Err(MyError::ReplayWindowNotSet.into());
+    }
+    let (address, bump) = nonce_address(program_id, wallet);
+    let ring = find_account(accounts, &address).ok_or_else(|| {
+        msg!("缺少 nonce 账户 {}", address);
+        ProgramError::NotEnoughAccountKeys
+    })?;
+
+    if ring.owner != program_id {
+        let size = match config.nonce_ring_size {
+            0 => DEFAULT_NONCE_RING_SIZE,
+            size => size,
+        };
+        create_trade_pda(
+            program_id,
+            accounts,
+            payer,
+            ring,
+            system_program,
+            size as usize * NONCE_ENTRY_LEN,
+            &[NONCE_SEED, wallet.as_ref(), &[bump]],
+        )?;
+    }
+
+    let slot = Clock::get()?.slot;
+    let expired = |recorded: u64| recorded == 0 || slot.saturating_sub(recorded) > config.replay_window_slots;
+
+    let mut data = ring.try_borrow_mut_data()?;
+    let mut free = None;
+    for offset in (0..data.len() / NONCE_ENTRY_LEN).map(|i| i * NONCE_ENTRY_LEN) {
+        let recorded = read_u64(&data, offset + 8);
+        if expired(recorded) {
+            free.get_or_insert(offset);
+        } else if read_u64(&data, offset) == nonce {
+            msg!("nonce {} 已在 slot {} 使用过", nonce, recorded);
+            return Err(MyError::NonceReused.into());
+        }
+    }
+
+    let offset = free.ok_or_else(|| {
+        msg!("nonce 缓冲区已满，请等待旧记录过期");
+        ProgramError::from(MyError::NonceRingFull)
+    })?;
+    data[offset..offset + 8].copy_from_slice(&nonce.to_le_bytes());
+    data[offset + 8..offset + 16].copy_from_slice(&slot.to_le_bytes());
+    Ok(())
+}
+
+// 账户与被包装的路由相同，手续费支付者的 nonce PDA 追加在末尾
+pub fn process_nonce_swap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 16 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let nonce = read_u64(instruction_data, 0);
+    let route = find_fee_route(&instruction_data[8..16]).ok_or(ProgramError::InvalidInstructionData)?;
+
+    let options = FeeOptions {
+        nonce: Some(nonce),
+        ..FeeOptions::default()
+    };
+    process_fee_route(program_id, route, accounts, &instruction_data[16..], &options)
+}
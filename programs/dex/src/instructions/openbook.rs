@@ -0,0 +1,218 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey,
+    pubkey::Pubkey,
+};
+
+use crate::error::MyError;
+use crate::instructions::fee::{
+    find_fee_route, no_route_check, output_balance, process_fee_route, AccountSpec, FeeOptions, FeeRoute, TradeSide,
+};
+
+// 带手续费的 OpenBook v2 IOC 买单:
+// [quote 原生数量 u64][side u8][price_lots i64][max_base_lots i64][占位 i64][order_type u8][limit u8]
+pub const OPENBOOK_BUY_SELECTOR: &[u8; 8] = b"ob2_buy\0";
+
+pub const OPENBOOK_V2_PROGRAM: Pubkey = pubkey!("opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb");
+const PLACE_TAKE_ORDER_SELECTOR: &[u8] = &[3, 44, 71, 3, 26, 199, 203, 85];
+
+// 买入后挂出止盈卖单:
+// [买入路由账户数 u8][price_lots i64][client_order_id u64][expiry_timestamp u64][买入路由选择器 8][买入路由数据]
+// 账户为 [配置, 系统程序, 支付者, 手续费接收方][买入路由转发与扩展账户 n 个][place_order 账户 12 个][OpenBook 程序]
+pub const BUY_TAKE_PROFIT_SELECTOR: &[u8; 8] = b"buy_tp\0\0";
+
+const PLACE_ORDER_SELECTOR: &[u8] = &[51, 194, 155, 175, 109, 130, 96, 106];
+// place_order 账户: signer, open_orders_account, open_orders_admin, user_token_account, market,
+// bids, asks, event_heap, market_vault, oracle_a, oracle_b, token_program
+const PLACE_ORDER_ACCOUNTS: usize = 12;
+const ORDER_SIGNER_INDEX: usize = 0;
+const ORDER_TOKEN_ACCOUNT_INDEX: usize = 3;
+const ORDER_MARKET_INDEX: usize = 4;
+const SIDE_ASK: u8 = 1;
+// PlaceOrderType::Limit，目标价未触及时挂单等待成交
+const ORDER_TYPE_LIMIT: u8 = 0;
+// SelfTradeBehavior::DecrementTake
+const SELF_TRADE_DECREMENT_TAKE: u8 = 0;
+// 卖单与簿上订单撮合时最多遍历的订单数
+const ORDER_MATCH_LIMIT: u8 = 10;
+
+// place_take_order 账户中 signer、market 与 user_base_account 的位置
+const SIGNER_INDEX: usize = 0;
+const MARKET_INDEX: usize = 2;
+const USER_BASE_ACCOUNT_INDEX: usize = 9;
+// place_take_order 的 16 个账户（未使用的预言机传程序地址占位）加上目标程序账户
+const MIN_ACCOUNTS: usize = 17;
+// 可选账户用程序地址占位，只读即可
+const PLACE_TAKE_ORDER_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::writable_signer("signer"),
+    AccountSpec::writable("penalty_payer"),
+    AccountSpec::writable("market"),
+    AccountSpec::readonly("market_authority"),
+    AccountSpec::writable("bids"),
+    AccountSpec::writable("asks"),
+    AccountSpec::writable("market_base_vault"),
+    AccountSpec::writable("market_quote_vault"),
+    AccountSpec::writable("event_heap"),
+    AccountSpec::writable("user_base_account"),
+    AccountSpec::writable("user_quote_account"),
+    AccountSpec::readonly("oracle_a"),
+    AccountSpec::readonly("oracle_b"),
+    AccountSpec::readonly("token_program"),
+    AccountSpec::readonly("system_program"),
+    AccountSpec::readonly("open_orders_admin"),
+    AccountSpec::readonly("program"),
+];
+// Market 账户中 quote_lot_size 的偏移（8 字节鉴别器之后依次为固定字段、OracleConfig、StablePriceModel）
+const QUOTE_LOT_SIZE_OFFSET: usize = 736;
+const BASE_LOT_SIZE_OFFSET: usize = QUOTE_LOT_SIZE_OFFSET + 8;
+
+// 内层参数中 side 与 max_quote_lots_including_fees 的位置
+const SIDE_OFFSET: usize = 8;
+const MAX_QUOTE_LOTS_OFFSET: usize = 8 + 1 + 8 + 8;
+const SIDE_BID: u8 = 0;
+
+fn read_lot_size(market: &AccountInfo, offset: usize) -> Result<u64, ProgramError> {
+    if market.owner != &OPENBOOK_V2_PROGRAM {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let data = market.try_borrow_data()?;
+    let bytes = data
+        .get(offset..offset + 8)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    match i64::from_le_bytes(bytes.try_into().unwrap()) {
+        size if size > 0 => Ok(size as u64),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+fn read_quote_lot_size(market: &AccountInfo) -> Result<u64, ProgramError> {
+    read_lot_size(market, QUOTE_LOT_SIZE_OFFSET)
+}
+
+// 扣费后的 quote 原生数量按 market 的 quote_lot_size 换算为 lots，只允许买单
+fn patch_quote_lots(forwarded: &[AccountInfo], data: &mut [u8], remaining: u64) -> ProgramResult {
+    if data.get(SIDE_OFFSET) != Some(&SIDE_BID) || data.len() < MAX_QUOTE_LOTS_OFFSET + 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let market = forwarded
+        .get(MARKET_INDEX)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    let quote_lots = remaining / read_quote_lot_size(market)?;
+    data[MAX_QUOTE_LOTS_OFFSET..MAX_QUOTE_LOTS_OFFSET + 8].copy_from_slice(&quote_lots.to_le_bytes());
+    Ok(())
+}
+
+pub const OPENBOOK_BUY_ROUTE: FeeRoute = FeeRoute {
+    name: "openbook_v2_buy",
+    selector: OPENBOOK_BUY_SELECTOR,
+    program: OPENBOOK_V2_PROGRAM,
+    inner_selector: PLACE_TAKE_ORDER_SELECTOR,
+    forward_program_account: true,
+    min_accounts: MIN_ACCOUNTS,
+    accounts: PLACE_TAKE_ORDER_ACCOUNTS,
+    side: TradeSide::Buy,
+    token_account_index: USER_BASE_ACCOUNT_INDEX,
+    quote_account_index: None,
+    authority_account_index: SIGNER_INDEX,
+    output_account_index: Some(USER_BASE_ACCOUNT_INDEX),
+    input_account_index: None,
+    fee_from_output: false,
+    sol_input: false,
+    check: no_route_check,
+    patch_amount: patch_quote_lots,
+};
+
+pub fn process_openbook_buy(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    process_fee_route(program_id, &OPENBOOK_BUY_ROUTE, accounts, instruction_data, &FeeOptions::default())
+}
+
+// 先按买入路由收费并兑换，再把本次买到的全部 base 代币以 price_lots 挂出 OpenBook v2 限价卖单。
+// 卖单的 user_token_account 必须是买入路由的输出账户，签名者必须是买入路由的交易发起人；卖单不收手续费
+pub fn process_buy_take_profit(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    if instruction_data.len() < 33 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let buy_accounts = 4 + instruction_data[0] as usize;
+    let price_lots = i64::from_le_bytes(<[u8; 8]>::try_from(&instruction_data[1..9]).unwrap());
+    let client_order_id = u64::from_le_bytes(<[u8; 8]>::try_from(&instruction_data[9..17]).unwrap());
+    let expiry_timestamp = u64::from_le_bytes(<[u8; 8]>::try_from(&instruction_data[17..25]).unwrap());
+    if price_lots <= 0 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let route = find_fee_route(&instruction_data[25..33]).ok_or_else(|| {
+        msg!("选择器 {:?} 不是带手续费的路由", &instruction_data[25..33]);
+        ProgramError::from(MyError::UnsupportedRoute)
+    })?;
+    let output_index = match (route.side, route.output_account_index) {
+        (TradeSide::Buy, Some(index)) => index,
+        _ => {
+            msg!("止盈单只能跟在有输出账户的买入路由 {} 之后", route.name);
+            return Err(MyError::UnsupportedRoute.into());
+        }
+    };
+    if accounts.len() < buy_accounts + PLACE_ORDER_ACCOUNTS + 1 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let (buy, order) = accounts.split_at(buy_accounts);
+    let (order, openbook) = order.split_at(PLACE_ORDER_ACCOUNTS);
+    if openbook[0].key != &OPENBOOK_V2_PROGRAM {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let forwarded = &buy[4..];
+    let output = forwarded.get(output_index).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let authority = forwarded
+        .get(route.authority_account_index)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if order[ORDER_TOKEN_ACCOUNT_INDEX].key != output.key || order[ORDER_SIGNER_INDEX].key != authority.key {
+        msg!("止盈单的代币账户或签名者与买入路由不一致");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let before = output_balance(output)?;
+    process_fee_route(program_id, route, buy, &instruction_data[33..], &FeeOptions::default())?;
+    let bought = output_balance(output)?.saturating_sub(before);
+
+    let max_base_lots = bought / read_lot_size(&order[ORDER_MARKET_INDEX], BASE_LOT_SIZE_OFFSET)?;
+    if max_base_lots == 0 {
+        msg!("买入数量 {} 不足一个 base lot，无法挂止盈单", bought);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let mut data = Vec::with_capacity(8 + 44);
+    data.extend_from_slice(PLACE_ORDER_SELECTOR);
+    data.push(SIDE_ASK);
+    data.extend_from_slice(&price_lots.to_le_bytes());
+    data.extend_from_slice(&(max_base_lots as i64).to_le_bytes());
+    // 卖单不限制 quote 数量
+    data.extend_from_slice(&i64::MAX.to_le_bytes());
+    data.extend_from_slice(&client_order_id.to_le_bytes());
+    data.push(ORDER_TYPE_LIMIT);
+    data.extend_from_slice(&expiry_timestamp.to_le_bytes());
+    data.push(SELF_TRADE_DECREMENT_TAKE);
+    data.push(ORDER_MATCH_LIMIT);
+
+    msg!("挂出止盈卖单: {} lots @ {} price_lots", max_base_lots, price_lots);
+    invoke(
+        &Instruction {
+            program_id: OPENBOOK_V2_PROGRAM,
+            accounts: order
+                .iter()
+                .map(|acc| AccountMeta {
+                    pubkey: *acc.key,
+                    is_signer: acc.is_signer,
+                    is_writable: acc.is_writable,
+                })
+                .collect(),
+            data,
+        },
+        &accounts[buy_accounts..],
+    )
+}
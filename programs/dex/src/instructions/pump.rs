@@ -1,16 +1,17 @@
 use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    borsh::try_from_slice_unchecked,
+    account_info::AccountInfo,
     entrypoint::ProgramResult,
-    instruction::{AccountMeta, Instruction},
-    program::invoke,
+    msg,
     program_error::ProgramError,
     pubkey::Pubkey,
-    system_instruction,
     pubkey,
 };
 
-use crate::state::TradeFeeState;
+use crate::error::MyError;
+use crate::state::BPS_DENOMINATOR;
+use crate::instructions::fee::{patch_first_arg, process_fee_route, AccountSpec, FeeOptions, FeeRoute, TradeSide};
+use crate::token::{associated_token_address, create_associated_token_account_idempotent, ASSOCIATED_TOKEN_PROGRAM_ID};
+use crate::utils::find_account;
 
 const PUMPFUN_BUY_SELECTOR: &[u8; 8] = &[102, 6, 61, 18, 1, 218, 235, 234];
 const PUMPFUN_SELL_SELECTOR: &[u8; 8] = &[51, 230, 133, 164, 1, 127, 131, 173];
@@ -21,129 +22,399 @@ pub const PUMP_SELECTOR: &[u8; 8] = &[82, 225, 119, 231, 78, 29, 45, 70];
 pub const PUMP_AMM_SELECTOR: &[u8; 8] = &[129, 59, 179, 195, 110, 135, 61, 2];
 pub const PUMP_SELL_SELECTOR: &[u8; 8] = &[83, 225, 119, 231, 78, 29, 45, 70];
 pub const PUMP_AMM_SELL_SELECTOR: &[u8; 8] = &[130, 59, 179, 195, 110, 135, 61, 2];
+// 先幂等创建用户的代币 ATA 再执行带手续费的内盘买入，数据与 PUMP_SELECTOR 相同，
+// 账户为内盘买入账户并在末尾追加 ATA 程序
+pub const PUMP_BUY_WITH_ATA_SELECTOR: &[u8; 8] = b"pump_ata";
+// 按滑点自动计算 max_sol_cost 的内盘买入: [代币数量 u64][slippage_bps u16]，账户与 PUMP_SELECTOR 相同
+pub const PUMP_BUY_SLIPPAGE_SELECTOR: &[u8; 8] = b"pump_slp";
 
 const PUMP_PROGRAM: Pubkey = pubkey!("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P");
 const PUMP_AMM_PROGRAM_ID: Pubkey = pubkey!("pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA");
 
-const ARG_LEN: usize = 24;
-
-fn to_account_metas(accounts: &[AccountInfo]) -> Vec<AccountMeta> {
-    let mut metas = Vec::with_capacity(accounts.len());
-    metas.append(
-        &mut accounts
-            .iter()
-            .map(|acc| match acc.is_writable {
-                false => AccountMeta::new_readonly(*acc.key, acc.is_signer),
-                true => AccountMeta::new(*acc.key, acc.is_signer),
-            })
-            .collect(),
-    );
-    metas
+// 转发账户中内盘 bonding curve 与外盘 pool 的位置
+const BONDING_CURVE_INDEX: usize = 3;
+const AMM_POOL_INDEX: usize = 0;
+// 用户代币账户位置：内盘为 associated_user，外盘为 user_base_token_account
+const CURVE_USER_TOKEN_INDEX: usize = 5;
+const AMM_USER_BASE_TOKEN_INDEX: usize = 5;
+// 内盘 mint 与代币程序
+const CURVE_MINT_INDEX: usize = 2;
+const CURVE_TOKEN_PROGRAM_INDEX: usize = 8;
+// 签名的用户钱包（内盘与外盘的 user 账户）
+const CURVE_USER_INDEX: usize = 6;
+const AMM_USER_INDEX: usize = 1;
+// 转发账户最少数量（含 event_authority 与目标程序账户）
+const CURVE_MIN_ACCOUNTS: usize = 12;
+const AMM_MIN_ACCOUNTS: usize = 17;
+// 外盘买入在 coin_creator_vault_ata、coin_creator_vault_authority 之后追加全局与用户交易量累计账户
+const AMM_GLOBAL_VOLUME_ACCUMULATOR_INDEX: usize = 19;
+const AMM_USER_VOLUME_ACCUMULATOR_INDEX: usize = 20;
+const AMM_BUY_MIN_ACCOUNTS: usize = 21;
+const GLOBAL_VOLUME_ACCUMULATOR_SEED: &[u8] = b"global_volume_accumulator";
+const USER_VOLUME_ACCUMULATOR_SEED: &[u8] = b"user_volume_accumulator";
+// 外盘用户的 quote（WSOL、USDC 等）代币账户，卖出时接收兑换所得
+const AMM_USER_QUOTE_TOKEN_INDEX: usize = 6;
+// BondingCurve 账户中 complete 字段的偏移（8 字节鉴别器 + 5 个 u64）
+const CURVE_COMPLETE_OFFSET: usize = 48;
+// BondingCurve 账户中虚拟代币与虚拟 SOL 储备的偏移
+const CURVE_VIRTUAL_TOKEN_RESERVES_OFFSET: usize = 8;
+const CURVE_VIRTUAL_SOL_RESERVES_OFFSET: usize = 16;
+// 内盘 Global 账户位置，以及其中协议费率与创建者费率（基点）的偏移；旧版 Global 没有创建者费率
+const CURVE_GLOBAL_INDEX: usize = 0;
+const GLOBAL_FEE_BPS_OFFSET: usize = 8 + 1 + 32 + 32 + 8 * 4;
+const GLOBAL_CREATOR_FEE_BPS_OFFSET: usize = GLOBAL_FEE_BPS_OFFSET + 8 + 32 + 1 + 8;
+
+// 各路由转发账户的顺序描述
+const CURVE_BUY_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("global"),
+    AccountSpec::writable("fee_recipient"),
+    AccountSpec::readonly("mint"),
+    AccountSpec::writable("bonding_curve"),
+    AccountSpec::writable("associated_bonding_curve"),
+    AccountSpec::writable("associated_user"),
+    AccountSpec::writable_signer("user"),
+    AccountSpec::readonly("system_program"),
+    AccountSpec::readonly("token_program"),
+    AccountSpec::writable("creator_vault"),
+    AccountSpec::readonly("event_authority"),
+    AccountSpec::readonly("program"),
+];
+
+// 内盘卖出中 creator_vault 位于 token_program 之前，与买入不同
+const CURVE_SELL_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("global"),
+    AccountSpec::writable("fee_recipient"),
+    AccountSpec::readonly("mint"),
+    AccountSpec::writable("bonding_curve"),
+    AccountSpec::writable("associated_bonding_curve"),
+    AccountSpec::writable("associated_user"),
+    AccountSpec::writable_signer("user"),
+    AccountSpec::readonly("system_program"),
+    AccountSpec::writable("creator_vault"),
+    AccountSpec::readonly("token_program"),
+    AccountSpec::readonly("event_authority"),
+    AccountSpec::readonly("program"),
+];
+
+const AMM_BUY_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("pool"),
+    AccountSpec::writable_signer("user"),
+    AccountSpec::readonly("global_config"),
+    AccountSpec::readonly("base_mint"),
+    AccountSpec::readonly("quote_mint"),
+    AccountSpec::writable("user_base_token_account"),
+    AccountSpec::writable("user_quote_token_account"),
+    AccountSpec::writable("pool_base_token_account"),
+    AccountSpec::writable("pool_quote_token_account"),
+    AccountSpec::readonly("protocol_fee_recipient"),
+    AccountSpec::writable("protocol_fee_recipient_token_account"),
+    AccountSpec::readonly("base_token_program"),
+    AccountSpec::readonly("quote_token_program"),
+    AccountSpec::readonly("system_program"),
+    AccountSpec::readonly("associated_token_program"),
+    AccountSpec::readonly("event_authority"),
+    AccountSpec::readonly("program"),
+    AccountSpec::writable("coin_creator_vault_ata"),
+    AccountSpec::readonly("coin_creator_vault_authority"),
+    AccountSpec::readonly("global_volume_accumulator"),
+    AccountSpec::writable("user_volume_accumulator"),
+];
+
+const AMM_SELL_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("pool"),
+    AccountSpec::writable_signer("user"),
+    AccountSpec::readonly("global_config"),
+    AccountSpec::readonly("base_mint"),
+    AccountSpec::readonly("quote_mint"),
+    AccountSpec::writable("user_base_token_account"),
+    AccountSpec::writable("user_quote_token_account"),
+    AccountSpec::writable("pool_base_token_account"),
+    AccountSpec::writable("pool_quote_token_account"),
+    AccountSpec::readonly("protocol_fee_recipient"),
+    AccountSpec::writable("protocol_fee_recipient_token_account"),
+    AccountSpec::readonly("base_token_program"),
+    AccountSpec::readonly("quote_token_program"),
+    AccountSpec::readonly("system_program"),
+    AccountSpec::readonly("associated_token_program"),
+    AccountSpec::readonly("event_authority"),
+    AccountSpec::readonly("program"),
+];
+
+/// Pump 代币当前所处的交易阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PumpMarket {
+    /// 仍在内盘 bonding curve 上交易
+    BondingCurve,
+    /// bonding curve 已完成，代币已迁移到 PumpAMM
+    Migrated,
+    /// 转发的是 PumpAMM 池子账户
+    Amm,
 }
 
-fn calculate_fee(amount: u64, fee_rate: u8) -> u64 {
-    (amount as f64 * fee_rate as f64 / 100.0) as u64
+/// 根据转发账户推断代币处于内盘还是已迁移到外盘，无法识别时返回 None
+pub fn detect_pump_market(forwarded: &[AccountInfo]) -> Option<PumpMarket> {
+    if let Some(pool) = forwarded.get(AMM_POOL_INDEX) {
+        if pool.owner == &PUMP_AMM_PROGRAM_ID {
+            return Some(PumpMarket::Amm);
+        }
+    }
+
+    let curve = forwarded.get(BONDING_CURVE_INDEX)?;
+    if curve.owner != &PUMP_PROGRAM {
+        return None;
+    }
+    let data = curve.try_borrow_data().ok()?;
+    match data.get(CURVE_COMPLETE_OFFSET) {
+        Some(0) => Some(PumpMarket::BondingCurve),
+        Some(_) => Some(PumpMarket::Migrated),
+        None => None,
+    }
 }
 
-fn process_with_fee(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    instruction_data: &[u8],
-    selector: &[u8; 8],
-) -> ProgramResult {
-    let accounts_iter = &mut accounts.iter();
-    
-    // 安全获取账户
-    let fee_account = next_account_info(accounts_iter)?;
-    let system_program = next_account_info(accounts_iter)?;
-    let fee_payer = next_account_info(accounts_iter)?; // 支付手续费的SOL账户
-    let fee_receiver = next_account_info(accounts_iter)?; // 接收手续费的SOL账户
-    
-    // 验证支付者账户签名
-    if !fee_payer.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
+fn check_pump_curve(accounts: &[AccountInfo]) -> ProgramResult {
+    check_pump_market(accounts, false)
+}
+
+fn check_pump_amm(accounts: &[AccountInfo]) -> ProgramResult {
+    check_pump_market(accounts, true)
+}
+
+fn check_pump_amm_buy(accounts: &[AccountInfo]) -> ProgramResult {
+    check_pump_market(accounts, true)?;
+    check_volume_accumulators(accounts.get(4..).unwrap_or(&[]))
+}
+
+// PumpAMM 买入要求传入交易量累计 PDA，缺失或位置错误时内层只会报出账户约束错误，这里按位置提前校验
+fn check_volume_accumulators(forwarded: &[AccountInfo]) -> ProgramResult {
+    let (Some(global), Some(user_accumulator), Some(user)) = (
+        forwarded.get(AMM_GLOBAL_VOLUME_ACCUMULATOR_INDEX),
+        forwarded.get(AMM_USER_VOLUME_ACCUMULATOR_INDEX),
+        forwarded.get(AMM_USER_INDEX),
+    ) else {
+        msg!(
+            "PumpAMM 买入需要 {} 个转发账户（第 {}、{} 个为全局与用户交易量累计账户），实际为 {}",
+            AMM_BUY_MIN_ACCOUNTS,
+            AMM_GLOBAL_VOLUME_ACCUMULATOR_INDEX + 1,
+            AMM_USER_VOLUME_ACCUMULATOR_INDEX + 1,
+            forwarded.len()
+        );
+        return Err(MyError::PumpVolumeAccumulatorMissing.into());
+    };
+
+    let expected_global = Pubkey::find_program_address(&[GLOBAL_VOLUME_ACCUMULATOR_SEED], &PUMP_AMM_PROGRAM_ID).0;
+    let expected_user = Pubkey::find_program_address(&[USER_VOLUME_ACCUMULATOR_SEED, user.key.as_ref()], &PUMP_AMM_PROGRAM_ID).0;
+    if global.key != &expected_global || user_accumulator.key != &expected_user {
+        msg!(
+            "PumpAMM 交易量累计账户应为 {} 与 {}，实际为 {} 与 {}",
+            expected_global,
+            expected_user,
+            global.key,
+            user_accumulator.key
+        );
+        return Err(MyError::PumpVolumeAccumulatorMissing.into());
     }
-    
-    // 反序列化配置
-    let mut trade_fee_config = TradeFeeState::try_from_slice(&fee_account.data.borrow())?;
-    
-    // 验证接收方地址匹配配置
-    if fee_receiver.key != &trade_fee_config.fee_wallet {
-        return Err(ProgramError::InvalidAccountData);
+    Ok(())
+}
+
+// 校验选择器与代币阶段一致，不一致时提示应使用的选择器
+fn check_pump_market(accounts: &[AccountInfo], amm_route: bool) -> ProgramResult {
+    let forwarded = accounts.get(4..).unwrap_or(&[]);
+
+    match (detect_pump_market(forwarded), amm_route) {
+        (Some(PumpMarket::Migrated), false) | (Some(PumpMarket::Amm), false) => {
+            msg!("代币已迁移到 PumpAMM，请改用 PUMP_AMM_SELECTOR / PUMP_AMM_SELL_SELECTOR");
+            Err(MyError::PumpTokenMigrated.into())
+        }
+        (Some(PumpMarket::BondingCurve), true) => {
+            msg!("代币仍在内盘 bonding curve，请改用 PUMP_SELECTOR / PUMP_SELL_SELECTOR");
+            Err(MyError::PumpTokenOnCurve.into())
+        }
+        (Some(PumpMarket::Migrated), true) => {
+            msg!("代币已迁移，但转发的是内盘账户，请传入 PumpAMM 池子账户");
+            Err(ProgramError::InvalidAccountData)
+        }
+        _ => Ok(()),
     }
-    
-    // 解析金额
-    if instruction_data.len() < 8 {
-        return Err(ProgramError::InvalidInstructionData);
+}
+
+pub const PUMP_BUY_ROUTE: FeeRoute = FeeRoute {
+    name: "pump_buy",
+    selector: PUMP_SELECTOR,
+    program: PUMP_PROGRAM,
+    inner_selector: PUMPFUN_BUY_SELECTOR,
+    forward_program_account: true,
+    min_accounts: CURVE_MIN_ACCOUNTS,
+    accounts: CURVE_BUY_ACCOUNTS,
+    side: TradeSide::Buy,
+    token_account_index: CURVE_USER_TOKEN_INDEX,
+    quote_account_index: None,
+    authority_account_index: CURVE_USER_INDEX,
+    output_account_index: Some(CURVE_USER_TOKEN_INDEX),
+    input_account_index: None,
+    fee_from_output: false,
+    sol_input: true,
+    check: check_pump_curve,
+    patch_amount: patch_first_arg::<8>,
+};
+
+pub const PUMP_AMM_BUY_ROUTE: FeeRoute = FeeRoute {
+    name: "pump_amm_buy",
+    selector: PUMP_AMM_SELECTOR,
+    program: PUMP_AMM_PROGRAM_ID,
+    inner_selector: PUMPAMM_BUY_SELECTOR,
+    forward_program_account: true,
+    min_accounts: AMM_BUY_MIN_ACCOUNTS,
+    accounts: AMM_BUY_ACCOUNTS,
+    side: TradeSide::Buy,
+    token_account_index: AMM_USER_BASE_TOKEN_INDEX,
+    quote_account_index: Some(AMM_USER_QUOTE_TOKEN_INDEX),
+    authority_account_index: AMM_USER_INDEX,
+    output_account_index: Some(AMM_USER_BASE_TOKEN_INDEX),
+    input_account_index: None,
+    fee_from_output: false,
+    sol_input: false,
+    check: check_pump_amm_buy,
+    patch_amount: patch_first_arg::<8>,
+};
+
+// 内盘卖出参数为 [amount 代币数量][min_sol_output]，SOL 直接转入用户钱包，
+// 手续费从收到的 SOL 中收取，代币数量原样转发
+pub const PUMP_SELL_ROUTE: FeeRoute = FeeRoute {
+    name: "pump_sell",
+    selector: PUMP_SELL_SELECTOR,
+    program: PUMP_PROGRAM,
+    inner_selector: PUMPFUN_SELL_SELECTOR,
+    forward_program_account: true,
+    min_accounts: CURVE_MIN_ACCOUNTS,
+    accounts: CURVE_SELL_ACCOUNTS,
+    side: TradeSide::Sell,
+    token_account_index: CURVE_USER_TOKEN_INDEX,
+    quote_account_index: None,
+    authority_account_index: CURVE_USER_INDEX,
+    // 内盘卖出所得 SOL 转入 user 账户，按该账户的余额变化计算手续费
+    output_account_index: Some(CURVE_USER_INDEX),
+    input_account_index: None,
+    fee_from_output: true,
+    sol_input: false,
+    check: check_pump_curve,
+    patch_amount: patch_first_arg::<8>,
+};
+
+pub const PUMP_AMM_SELL_ROUTE: FeeRoute = FeeRoute {
+    name: "pump_amm_sell",
+    selector: PUMP_AMM_SELL_SELECTOR,
+    program: PUMP_AMM_PROGRAM_ID,
+    inner_selector: PUMPAMM_SELL_SELECTOR,
+    forward_program_account: true,
+    min_accounts: AMM_MIN_ACCOUNTS,
+    accounts: AMM_SELL_ACCOUNTS,
+    side: TradeSide::Sell,
+    token_account_index: AMM_USER_BASE_TOKEN_INDEX,
+    quote_account_index: Some(AMM_USER_QUOTE_TOKEN_INDEX),
+    authority_account_index: AMM_USER_INDEX,
+    output_account_index: Some(AMM_USER_QUOTE_TOKEN_INDEX),
+    input_account_index: None,
+    fee_from_output: false,
+    sol_input: false,
+    check: check_pump_amm,
+    patch_amount: patch_first_arg::<8>,
+};
+
+pub fn process_pump_buy(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    process_fee_route(program_id, &PUMP_BUY_ROUTE, accounts, instruction_data, &FeeOptions::default())
+}
+
+// ATA 由手续费支付者出资创建，已存在时跳过，与买入在同一指令中执行，买入失败时一并回滚
+pub fn process_pump_buy_with_ata(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    if accounts.len() < 4 + CURVE_MIN_ACCOUNTS {
+        return Err(ProgramError::NotEnoughAccountKeys);
     }
-    let amount = u64::from_le_bytes(
-        instruction_data[0..8]
-            .try_into()
-            .map_err(|_| ProgramError::InvalidInstructionData)?,
+    let (system_program, funder, forwarded) = (&accounts[1], &accounts[2], &accounts[4..]);
+    let ata_program = find_account(accounts, &ASSOCIATED_TOKEN_PROGRAM_ID).ok_or_else(|| {
+        msg!("缺少 ATA 程序账户");
+        ProgramError::NotEnoughAccountKeys
+    })?;
+    let (user_token, user, mint, token_program) = (
+        &forwarded[CURVE_USER_TOKEN_INDEX],
+        &forwarded[CURVE_USER_INDEX],
+        &forwarded[CURVE_MINT_INDEX],
+        &forwarded[CURVE_TOKEN_PROGRAM_INDEX],
     );
-    
-    // 计算费用
-    let fee = (amount * trade_fee_config.fee_rate as u64) / 100;
-    let remaining_amount = amount.checked_sub(fee)
-        .ok_or(ProgramError::InsufficientFunds)?;
-    
-    // 验证支付者有足够余额
-    if **fee_payer.lamports.borrow() < fee {
-        return Err(ProgramError::InsufficientFunds);
+    if user_token.key != &associated_token_address(user.key, mint.key, token_program.key) {
+        msg!("{} 不是用户 {} 的关联代币账户", user_token.key, user.key);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    create_associated_token_account_idempotent(funder, user_token, user, mint, system_program, token_program, ata_program)?;
+
+    process_fee_route(program_id, &PUMP_BUY_ROUTE, accounts, instruction_data, &FeeOptions::default())
+}
+
+fn read_u64_at(account: &AccountInfo, offset: usize) -> Result<Option<u64>, ProgramError> {
+    if account.owner != &PUMP_PROGRAM {
+        return Err(ProgramError::IllegalOwner);
     }
-    
-    // 转账SOL手续费到协议钱包
-    invoke(
-        &system_instruction::transfer(
-            fee_payer.key,
-            fee_receiver.key,
-            fee,
-        ),
-        &[
-            fee_payer.clone(),
-            fee_receiver.clone(),
-            system_program.clone(),
-        ],
-    )?;
-    
-    // 构建原始指令数据（保持原始数据不变）
-    let mut data = Vec::with_capacity(8 + instruction_data.len() - 8);
-    data.extend_from_slice(selector);
-    data.extend_from_slice(&instruction_data[8..]);
-    
-    // 更新金额为扣除费用后的剩余金额
-    data[8..16].copy_from_slice(&remaining_amount.to_le_bytes());
-    
-    // 执行原始交易（使用剩余账户）
-    invoke(
-        &Instruction {
-            program_id: *program_id,
-            accounts: accounts[4..] // 跳过已处理的账户
-                .iter()
-                .map(|acc| AccountMeta {
-                    pubkey: *acc.key,
-                    is_signer: acc.is_signer,
-                    is_writable: acc.is_writable,
-                })
-                .collect(),
-            data,
-        },
-        &accounts[4..],
-    )
+    let data = account.try_borrow_data()?;
+    Ok(data.get(offset..offset + 8).map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap())))
 }
 
-pub fn process_pump_buy(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
-    process_with_fee(&PUMP_PROGRAM, accounts, instruction_data, PUMPFUN_BUY_SELECTOR)
+// 按 bonding curve 的恒定乘积买入 amount 个代币所需的 SOL（向上取整），加上 Global 中的协议与创建者费率，
+// 再按 slippage_bps 放宽，作为 Pump 买入的 max_sol_cost
+pub fn pump_max_sol_cost(forwarded: &[AccountInfo], amount: u64, slippage_bps: u16) -> Result<u64, ProgramError> {
+    let curve = forwarded.get(BONDING_CURVE_INDEX).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let global = forwarded.get(CURVE_GLOBAL_INDEX).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let (Some(virtual_tokens), Some(virtual_sol)) = (
+        read_u64_at(curve, CURVE_VIRTUAL_TOKEN_RESERVES_OFFSET)?,
+        read_u64_at(curve, CURVE_VIRTUAL_SOL_RESERVES_OFFSET)?,
+    ) else {
+        return Err(ProgramError::InvalidAccountData);
+    };
+    if amount == 0 || amount >= virtual_tokens {
+        msg!("买入数量 {} 超出 bonding curve 虚拟代币储备 {}", amount, virtual_tokens);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let fee_bps = read_u64_at(global, GLOBAL_FEE_BPS_OFFSET)?.ok_or(ProgramError::InvalidAccountData)?
+        + read_u64_at(global, GLOBAL_CREATOR_FEE_BPS_OFFSET)?.unwrap_or(0);
+    let numerator = amount as u128 * virtual_sol as u128;
+    let denominator = (virtual_tokens - amount) as u128;
+    let sol_cost = numerator.div_ceil(denominator);
+    let with_fee = sol_cost * (BPS_DENOMINATOR as u128 + fee_bps as u128) / BPS_DENOMINATOR as u128;
+    let max_sol_cost = with_fee * (BPS_DENOMINATOR as u128 + slippage_bps as u128) / BPS_DENOMINATOR as u128;
+    msg!("买入 {} 个代币预计花费 {} lamports，含费率与滑点上限 {}", amount, sol_cost, max_sol_cost);
+    u64::try_from(max_sol_cost).map_err(|_| ProgramError::ArithmeticOverflow)
+}
+
+// 按当前 bonding curve 储备计算 max_sol_cost 后按普通买入执行，数据改写为 [amount][amount][max_sol_cost]
+pub fn process_pump_buy_with_slippage(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    if instruction_data.len() < 10 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let amount = u64::from_le_bytes(<[u8; 8]>::try_from(&instruction_data[..8]).unwrap());
+    let slippage_bps = u16::from_le_bytes(<[u8; 2]>::try_from(&instruction_data[8..10]).unwrap());
+    if slippage_bps as u64 > BPS_DENOMINATOR {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if accounts.len() < 4 + CURVE_MIN_ACCOUNTS {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    check_pump_curve(accounts)?;
+    let max_sol_cost = pump_max_sol_cost(&accounts[4..], amount, slippage_bps)?;
+
+    let mut data = Vec::with_capacity(24);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&max_sol_cost.to_le_bytes());
+    process_fee_route(program_id, &PUMP_BUY_ROUTE, accounts, &data, &FeeOptions::default())
 }
 
-pub fn process_pump_amm_buy(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
-    process_with_fee(&PUMP_AMM_PROGRAM_ID, accounts, instruction_data, PUMPAMM_BUY_SELECTOR)
+pub fn process_pump_amm_buy(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    process_fee_route(program_id, &PUMP_AMM_BUY_ROUTE, accounts, instruction_data, &FeeOptions::default())
 }
 
-pub fn process_pump_sell(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
-    process_with_fee(&PUMP_PROGRAM, accounts, instruction_data, PUMPFUN_SELL_SELECTOR)
+pub fn process_pump_sell(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    process_fee_route(program_id, &PUMP_SELL_ROUTE, accounts, instruction_data, &FeeOptions::default())
 }
 
-pub fn process_pump_amm_sell(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
-    process_with_fee(&PUMP_AMM_PROGRAM_ID, accounts, instruction_data, PUMPAMM_SELL_SELECTOR)
+pub fn process_pump_amm_sell(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    process_fee_route(program_id, &PUMP_AMM_SELL_ROUTE, accounts, instruction_data, &FeeOptions::default())
 }
@@ -1,19 +1,179 @@
 use arrayref::array_ref;
 use solana_program::{
     account_info::AccountInfo,
+    compute_units::sol_remaining_compute_units,
     entrypoint::ProgramResult,
     instruction::{AccountMeta, Instruction},
-    program::invoke_unchecked,
+    msg,
+    program::{invoke_unchecked, set_return_data},
+    program_error::ProgramError,
+    pubkey,
+    pubkey::Pubkey,
+};
+
+use crate::error::MyError;
+use crate::instructions::fee::{
+    no_route_check, output_balance, patch_first_arg, process_fee_route, AccountSpec, FeeBreakdown, FeeOptions,
+    FeeRoute, SwapResult, TradeSide,
 };
 
 pub const RAYDIUM_BUY_SELECTOR: &[u8; 8] = &[182, 77, 232, 39, 117, 138, 183, 72];
 pub const RAYDIUM_SELL_SELECTOR: &[u8; 8] = &[183, 77, 232, 39, 117, 138, 183, 72];
+// 带手续费的 swapBaseOut 买入: [max_amount_in u64][占位 u64][amount_out u64]
+pub const RAYDIUM_BUY_BASE_OUT_SELECTOR: &[u8; 8] = &[184, 77, 232, 39, 117, 138, 183, 72];
+// 带手续费的 CLMM swap_v2 精确输出买入:
+// [max_amount_in u64][amount_out u64][占位 u64][sqrt_price_limit_x64 u128][is_base_input u8 = 0]
+pub const RAYDIUM_CLMM_BUY_EXACT_OUT_SELECTOR: &[u8; 8] = &[185, 77, 232, 39, 117, 138, 183, 72];
+
+const RAYDIUM_AMM_V4_PROGRAM: Pubkey = pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+// swapBaseOut 指令号，参数为 max_amount_in、amount_out
+const RAYDIUM_SWAP_BASE_OUT: &[u8] = &[11];
+// 17 个账户的 swap 布局中用户目标代币账户的位置
+const RAYDIUM_USER_DESTINATION_INDEX: usize = 15;
+const RAYDIUM_USER_OWNER_INDEX: usize = 16;
+// 17 个 swap 账户加上目标程序账户
+const RAYDIUM_MIN_ACCOUNTS: usize = 18;
+const RAYDIUM_SWAP_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::readonly("token_program"),
+    AccountSpec::writable("amm"),
+    AccountSpec::readonly("amm_authority"),
+    AccountSpec::writable("amm_open_orders"),
+    AccountSpec::writable("pool_coin_token_account"),
+    AccountSpec::writable("pool_pc_token_account"),
+    AccountSpec::readonly("serum_program"),
+    AccountSpec::writable("serum_market"),
+    AccountSpec::writable("serum_bids"),
+    AccountSpec::writable("serum_asks"),
+    AccountSpec::writable("serum_event_queue"),
+    AccountSpec::writable("serum_coin_vault"),
+    AccountSpec::writable("serum_pc_vault"),
+    AccountSpec::readonly("serum_vault_signer"),
+    AccountSpec::writable("user_source_token_account"),
+    AccountSpec::writable("user_destination_token_account"),
+    AccountSpec::signer("user_owner"),
+    AccountSpec::readonly("program"),
+];
+
+const RAYDIUM_CLMM_PROGRAM: Pubkey = pubkey!("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK");
+// swap_v2 鉴别器，参数为 amount、other_amount_threshold、sqrt_price_limit_x64、is_base_input
+const RAYDIUM_CLMM_SWAP_V2: &[u8] = &[43, 4, 237, 11, 26, 201, 30, 98];
+// swap_v2 账户布局: payer、amm_config、pool_state、input_token_account、output_token_account、...
+const CLMM_PAYER_INDEX: usize = 0;
+const CLMM_INPUT_TOKEN_INDEX: usize = 3;
+const CLMM_OUTPUT_TOKEN_INDEX: usize = 4;
+// 鉴别器后依次为 amount u64、other_amount_threshold u64、sqrt_price_limit_x64 u128
+const CLMM_THRESHOLD_OFFSET: usize = 16;
+const CLMM_IS_BASE_INPUT_OFFSET: usize = 40;
+// 13 个固定账户、至少 1 个 tick array，加上目标程序账户
+const CLMM_MIN_ACCOUNTS: usize = 15;
+// 只描述 13 个固定账户，之后数量可变的 tick array 与目标程序账户不校验
+const CLMM_SWAP_V2_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::signer("payer"),
+    AccountSpec::readonly("amm_config"),
+    AccountSpec::writable("pool_state"),
+    AccountSpec::writable("input_token_account"),
+    AccountSpec::writable("output_token_account"),
+    AccountSpec::writable("input_vault"),
+    AccountSpec::writable("output_vault"),
+    AccountSpec::writable("observation_state"),
+    AccountSpec::readonly("token_program"),
+    AccountSpec::readonly("token_program_2022"),
+    AccountSpec::readonly("memo_program"),
+    AccountSpec::readonly("input_vault_mint"),
+    AccountSpec::readonly("output_vault_mint"),
+];
+
+// 手续费按 max_amount_in 计算，内层 max_amount_in 改为扣除手续费后的值，
+// 用户总支出仍不超过原始上限
+pub const RAYDIUM_BUY_BASE_OUT_ROUTE: FeeRoute = FeeRoute {
+    name: "raydium_buy_base_out",
+    selector: RAYDIUM_BUY_BASE_OUT_SELECTOR,
+    program: RAYDIUM_AMM_V4_PROGRAM,
+    inner_selector: RAYDIUM_SWAP_BASE_OUT,
+    forward_program_account: false,
+    min_accounts: RAYDIUM_MIN_ACCOUNTS,
+    accounts: RAYDIUM_SWAP_ACCOUNTS,
+    side: TradeSide::Buy,
+    token_account_index: RAYDIUM_USER_DESTINATION_INDEX,
+    quote_account_index: None,
+    authority_account_index: RAYDIUM_USER_OWNER_INDEX,
+    output_account_index: Some(RAYDIUM_USER_DESTINATION_INDEX),
+    input_account_index: None,
+    fee_from_output: false,
+    sol_input: false,
+    check: no_route_check,
+    patch_amount: patch_first_arg::<1>,
+};
+
+// 精确输出时 other_amount_threshold 为最大输入，改为扣除预估手续费后的值；
+// 精确输入的数据会让路由按输出数量计费，直接拒绝
+fn patch_clmm_exact_out(forwarded: &[AccountInfo], data: &mut [u8], remaining: u64) -> ProgramResult {
+    if data.get(CLMM_IS_BASE_INPUT_OFFSET) != Some(&0) {
+        msg!("CLMM 精确输出路由要求 is_base_input 为 false");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    patch_first_arg::<CLMM_THRESHOLD_OFFSET>(forwarded, data, remaining)
+}
+
+// 手续费先按 max_amount_in 预估并据此压低内层最大输入，兑换后按来源账户实际消耗的数量收取，
+// 多估的部分不再收取，用户总支出仍不超过原始上限
+pub const RAYDIUM_CLMM_BUY_EXACT_OUT_ROUTE: FeeRoute = FeeRoute {
+    name: "raydium_clmm_buy_exact_out",
+    selector: RAYDIUM_CLMM_BUY_EXACT_OUT_SELECTOR,
+    program: RAYDIUM_CLMM_PROGRAM,
+    inner_selector: RAYDIUM_CLMM_SWAP_V2,
+    forward_program_account: false,
+    min_accounts: CLMM_MIN_ACCOUNTS,
+    accounts: CLMM_SWAP_V2_ACCOUNTS,
+    side: TradeSide::Buy,
+    token_account_index: CLMM_OUTPUT_TOKEN_INDEX,
+    quote_account_index: None,
+    authority_account_index: CLMM_PAYER_INDEX,
+    output_account_index: Some(CLMM_OUTPUT_TOKEN_INDEX),
+    input_account_index: Some(CLMM_INPUT_TOKEN_INDEX),
+    fee_from_output: false,
+    sol_input: false,
+    check: no_route_check,
+    patch_amount: patch_clmm_exact_out,
+};
 
-pub fn process_raydium_buy(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+// 不收费的 swapBaseIn 路由同样写入 SwapResult，数据为 [指令号 u8][amount_in u64][min_amount_out u64]
+fn write_swap_result(
+    selector: &[u8; 8],
+    instruction_data: &[u8],
+    destination: &AccountInfo,
+    output_before: u64,
+    compute_units: u64,
+) -> ProgramResult {
+    let amount_in = instruction_data
+        .get(1..9)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let result = SwapResult {
+        route_id: *selector,
+        amount_in,
+        amount_out: output_balance(destination)?.saturating_sub(output_before),
+        fee: 0,
+        remaining: amount_in,
+        breakdown: FeeBreakdown::default(),
+        compute_units,
+    };
+    set_return_data(&borsh::to_vec(&result)?);
+    Ok(())
+}
+
+pub fn process_raydium_buy(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
     let [amm_program, token_program, amm_id, amm_authority, amm_coin_vault, amm_pc_vault, user_source_token, user_destination_token, user_source_owner] =
         array_ref![accounts, 0, 9];
 
+    // 目标程序由调用方传入，禁止指向本程序以免递归调用
+    if amm_program.key == program_id {
+        return Err(MyError::SelfInvocation.into());
+    }
+
     let amm_pool = *amm_id.key;
+    let output_before = output_balance(user_destination_token)?;
+    let compute_before = sol_remaining_compute_units();
 
     invoke_unchecked(
         &Instruction {
@@ -40,14 +200,30 @@ pub fn process_raydium_buy(accounts: &[AccountInfo], instruction_data: &[u8]) ->
             data: instruction_data.to_vec(),
         },
         accounts,
+    )?;
+
+    let compute_units = compute_before.saturating_sub(sol_remaining_compute_units());
+    write_swap_result(
+        RAYDIUM_BUY_SELECTOR,
+        instruction_data,
+        user_destination_token,
+        output_before,
+        compute_units,
     )
 }
 
-pub fn process_raydium_sell(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+pub fn process_raydium_sell(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
     let [amm_program, token_program, amm_id, amm_authority, amm_coin_vault, amm_pc_vault, user_source_token, user_destination_token, user_source_owner] =
         array_ref![accounts, 0, 9];
 
+    // 目标程序由调用方传入，禁止指向本程序以免递归调用
+    if amm_program.key == program_id {
+        return Err(MyError::SelfInvocation.into());
+    }
+
     let amm_pool = *amm_id.key;
+    let output_before = output_balance(user_destination_token)?;
+    let compute_before = sol_remaining_compute_units();
 
     invoke_unchecked(
         &Instruction {
@@ -74,5 +250,22 @@ pub fn process_raydium_sell(accounts: &[AccountInfo], instruction_data: &[u8]) -
             data: instruction_data.to_vec(),
         },
         accounts,
+    )?;
+
+    let compute_units = compute_before.saturating_sub(sol_remaining_compute_units());
+    write_swap_result(
+        RAYDIUM_SELL_SELECTOR,
+        instruction_data,
+        user_destination_token,
+        output_before,
+        compute_units,
     )
 }
+
+pub fn process_raydium_buy_base_out(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    process_fee_route(program_id, &RAYDIUM_BUY_BASE_OUT_ROUTE, accounts, instruction_data, &FeeOptions::default())
+}
+
+pub fn process_raydium_clmm_buy_exact_out(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    process_fee_route(program_id, &RAYDIUM_CLMM_BUY_EXACT_OUT_ROUTE, accounts, instruction_data, &FeeOptions::default())
+}
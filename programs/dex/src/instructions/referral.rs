@@ -0,0 +1,180 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::MyError;
+use crate::instructions::fee::{find_fee_route, process_fee_route, FeeOptions};
+use crate::state::{ReferralState, TradeFeeState, BPS_DENOMINATOR};
+use crate::utils::{create_pda_account, find_account};
+
+// 带推荐码的交易：[code u32][路由选择器 8][路由数据...]
+pub const REFERRAL_SWAP_SELECTOR: &[u8; 8] = b"ref_swap";
+// 管理员登记、更新、撤销推荐码
+pub const REGISTER_REFERRAL_SELECTOR: &[u8; 8] = b"ref_reg\0";
+pub const UPDATE_REFERRAL_SELECTOR: &[u8; 8] = b"ref_upd\0";
+pub const REVOKE_REFERRAL_SELECTOR: &[u8; 8] = b"ref_rvk\0";
+
+pub const REFERRAL_SEED: &[u8] = b"referral";
+
+pub fn referral_address(program_id: &Pubkey, code: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REFERRAL_SEED, &code.to_le_bytes()], program_id)
+}
+
+fn parse_code(data: &[u8]) -> Result<u32, ProgramError> {
+    let bytes = data.get(0..4).ok_or(ProgramError::InvalidInstructionData)?;
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+// 解析 [code u32][wallet 32][share_bps u16]
+fn parse_referral_args(data: &[u8]) -> Result<(u32, Pubkey, u16), ProgramError> {
+    if data.len() < 38 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let code = parse_code(data)?;
+    let wallet = Pubkey::new_from_array(<[u8; 32]>::try_from(&data[4..36]).unwrap());
+    let share_bps = u16::from_le_bytes(<[u8; 2]>::try_from(&data[36..38]).unwrap());
+
+    if share_bps as u64 > BPS_DENOMINATOR {
+        return Err(MyError::InvalidShareBps.into());
+    }
+    Ok((code, wallet, share_bps))
+}
+
+// 在交易账户中找到推荐码 PDA，并确认未撤销；推荐码未登记（或未传入其 PDA）时返回 None
+pub fn load_active_referral(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    code: u32,
+) -> Result<Option<ReferralState>, ProgramError> {
+    let (address, _) = referral_address(program_id, code);
+    let account = match find_account(accounts, &address) {
+        Some(account) if account.owner == program_id => account,
+        _ => {
+            msg!("推荐码 {} 未登记", code);
+            return Ok(None);
+        }
+    };
+
+    let referral = ReferralState::try_from_slice(&account.data.borrow())?;
+    if referral.revoked {
+        msg!("推荐码 {} 已撤销", code);
+        return Err(MyError::ReferralRevoked.into());
+    }
+    Ok(Some(referral))
+}
+
+// 账户: [配置账户, 管理员(签名并支付租金), 推荐码 PDA, 系统程序]
+pub fn process_register_referral(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let (code, wallet, share_bps) = parse_referral_args(instruction_data)?;
+
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let referral_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    TradeFeeState::load_as_admin(program_id, config_account, admin)?;
+
+    let (address, bump) = referral_address(program_id, code);
+    if referral_account.key != &address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    create_pda_account(
+        admin,
+        referral_account,
+        system_program,
+        program_id,
+        ReferralState::LEN,
+        &[REFERRAL_SEED, &code.to_le_bytes(), &[bump]],
+    )?;
+
+    let referral = ReferralState {
+        code,
+        wallet,
+        share_bps,
+        revoked: false,
+    };
+    referral.serialize(&mut &mut referral_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// 更新推荐人钱包和分成比例，同时恢复已撤销的推荐码
+// 账户: [配置账户, 管理员, 推荐码 PDA]
+pub fn process_update_referral(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let (code, wallet, share_bps) = parse_referral_args(instruction_data)?;
+
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let referral_account = next_account_info(accounts_iter)?;
+
+    TradeFeeState::load_as_admin(program_id, config_account, admin)?;
+    if referral_account.owner != program_id || referral_account.key != &referral_address(program_id, code).0 {
+        return Err(MyError::ReferralNotFound.into());
+    }
+
+    let mut referral = ReferralState::try_from_slice(&referral_account.data.borrow())?;
+    referral.wallet = wallet;
+    referral.share_bps = share_bps;
+    referral.revoked = false;
+    referral.serialize(&mut &mut referral_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// 撤销推荐码，之后使用该推荐码的交易会被拒绝
+// 账户: [配置账户, 管理员, 推荐码 PDA]
+pub fn process_revoke_referral(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let code = parse_code(instruction_data)?;
+
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let referral_account = next_account_info(accounts_iter)?;
+
+    TradeFeeState::load_as_admin(program_id, config_account, admin)?;
+    if referral_account.owner != program_id || referral_account.key != &referral_address(program_id, code).0 {
+        return Err(MyError::ReferralNotFound.into());
+    }
+
+    let mut referral = ReferralState::try_from_slice(&referral_account.data.borrow())?;
+    referral.revoked = true;
+    referral.serialize(&mut &mut referral_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// 账户与被包装的路由相同，推荐码 PDA 和推荐人钱包追加在末尾
+pub fn process_referral_swap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let code = parse_code(instruction_data)?;
+    let route = instruction_data
+        .get(4..12)
+        .and_then(find_fee_route)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+
+    let options = FeeOptions {
+        referral_code: Some(code),
+        ..FeeOptions::default()
+    };
+    process_fee_route(program_id, route, accounts, &instruction_data[12..], &options)
+}
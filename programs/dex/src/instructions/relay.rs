@@ -0,0 +1,106 @@
+use solana_program::{
+    account_info::AccountInfo,
+    ed25519_program,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::MyError;
+use crate::instructions::fee::{find_fee_route, process_fee_route, FeeOptions};
+use crate::utils::transaction_has_instruction;
+
+// 中继交易：[用户 32][nonce u64][路由选择器 8][路由数据...]
+// 中继者作为手续费支付者签名并付费，用户通过同一交易中的 ed25519 验签指令授权，nonce 防止签名被重放。
+// 用户作为路由的交易发起人时无需签署交易，验签授权代替其签名（目标程序自身要求签名的账户仍需签名）
+pub const RELAYED_SWAP_SELECTOR: &[u8; 8] = b"relay_sw";
+
+// ed25519 验签指令布局: [签名数 u8][填充 u8]，之后每个签名 7 个 u16 偏移
+const ED25519_HEADER_LEN: usize = 2;
+const ED25519_OFFSETS_LEN: usize = 14;
+// 偏移中的指令索引为 u16::MAX 时表示数据位于验签指令自身
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+// 用户签名的消息: [本程序 ID 32][nonce u64][路由选择器 8][路由数据...]
+pub fn relayed_message(program_id: &Pubkey, nonce: u64, route_call: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(40 + route_call.len());
+    message.extend_from_slice(program_id.as_ref());
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message.extend_from_slice(route_call);
+    message
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+// 检查 ed25519 验签指令中是否包含 user 对 message 的签名，签名数据必须都在该指令内
+fn ed25519_signs(data: &[u8], user: &Pubkey, message: &[u8]) -> bool {
+    let count = data.first().copied().unwrap_or(0) as usize;
+
+    (0..count).any(|i| {
+        let base = ED25519_HEADER_LEN + i * ED25519_OFFSETS_LEN;
+        let field = |n: usize| read_u16(data, base + n * 2);
+        let (
+            Some(signature_ix),
+            Some(pubkey_offset),
+            Some(pubkey_ix),
+            Some(message_offset),
+            Some(message_size),
+            Some(message_ix),
+        ) = (field(1), field(2), field(3), field(4), field(5), field(6))
+        else {
+            return false;
+        };
+        if [signature_ix, pubkey_ix, message_ix].iter().any(|ix| *ix != CURRENT_INSTRUCTION) {
+            return false;
+        }
+
+        let pubkey_offset = pubkey_offset as usize;
+        let message_offset = message_offset as usize;
+        data.get(pubkey_offset..pubkey_offset + 32) == Some(user.as_ref())
+            && data.get(message_offset..message_offset + message_size as usize) == Some(message)
+    })
+}
+
+// 在交易的指令列表中查找验证 user 签名的 ed25519 指令（签名本身由 ed25519 程序校验）
+pub fn verify_user_authorization(
+    accounts: &[AccountInfo],
+    user: &Pubkey,
+    message: &[u8],
+) -> ProgramResult {
+    if transaction_has_instruction(accounts, |ix| {
+        ix.program_id == ed25519_program::id() && ed25519_signs(&ix.data, user, message)
+    })? {
+        return Ok(());
+    }
+
+    msg!("交易中缺少用户 {} 的 ed25519 授权签名", user);
+    Err(MyError::MissingUserSignature.into())
+}
+
+// 账户与被包装的路由相同，Instructions sysvar 和用户的 nonce PDA 追加在末尾
+pub fn process_relayed_swap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 48 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let user = Pubkey::new_from_array(<[u8; 32]>::try_from(&instruction_data[..32]).unwrap());
+    let nonce = u64::from_le_bytes(<[u8; 8]>::try_from(&instruction_data[32..40]).unwrap());
+    let route_call = &instruction_data[40..];
+    let route = find_fee_route(&route_call[..8]).ok_or(ProgramError::InvalidInstructionData)?;
+
+    verify_user_authorization(accounts, &user, &relayed_message(program_id, nonce, route_call))?;
+
+    let options = FeeOptions {
+        authorized_user: Some(user),
+        nonce: Some(nonce),
+        ..FeeOptions::default()
+    };
+    process_fee_route(program_id, route, accounts, &route_call[8..], &options)
+}
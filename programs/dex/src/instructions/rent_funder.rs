@@ -0,0 +1,113 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+
+use crate::state::TradeFeeState;
+use crate::utils::{create_pda_account, find_account, transfer_lamports};
+
+// 管理员开启、关闭共享租金账户，开启期间交易中按需创建的 PDA 优先由它支付租金
+pub const OPEN_RENT_FUNDER_SELECTOR: &[u8; 8] = b"rfnd_opn";
+pub const CLOSE_RENT_FUNDER_SELECTOR: &[u8; 8] = b"rfnd_cls";
+
+pub const RENT_FUNDER_SEED: &[u8] = b"rent_funder";
+
+pub fn rent_funder_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RENT_FUNDER_SEED], program_id)
+}
+
+// 本笔交易附带的已开启共享租金账户及其交易开始时的余额，交易结束前据此结清垫付的租金
+pub type RentFunderSnapshot<'a, 'info> = Option<(&'a AccountInfo<'info>, u64)>;
+
+fn active_rent_funder<'a, 'info>(program_id: &Pubkey, accounts: &'a [AccountInfo<'info>]) -> Option<&'a AccountInfo<'info>> {
+    find_account(accounts, &rent_funder_address(program_id).0).filter(|funder| funder.owner == program_id && funder.is_writable)
+}
+
+pub fn rent_funder_snapshot<'a, 'info>(program_id: &Pubkey, accounts: &'a [AccountInfo<'info>]) -> RentFunderSnapshot<'a, 'info> {
+    active_rent_funder(program_id, accounts).map(|funder| (funder, funder.lamports()))
+}
+
+// 共享租金账户只为交易中创建的 PDA 垫付租金，同一笔交易结束前必须归还，余额不会因任何签名者的交易而减少：
+// 由支付者转回本笔已垫付、尚未归还的租金，最多 limit lamports，返回实际归还的数量。
+// 收费时先以协议所得的手续费归还（由协议承担新钱包的租金），不足部分在交易结束前由支付者补齐
+pub fn repay_rent_funder<'info>(
+    snapshot: RentFunderSnapshot<'_, 'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    limit: u64,
+) -> Result<u64, ProgramError> {
+    let Some((funder, before)) = snapshot else {
+        return Ok(0);
+    };
+    let repaid = before.saturating_sub(funder.lamports()).min(limit);
+    transfer_lamports(payer, funder, system_program, repaid)?;
+    Ok(repaid)
+}
+
+// 交易中按需创建 PDA（钱包、nonce、epoch 汇总等）：交易附带了已开启的共享租金账户且其余额（保留自身免租金额后）足够时由其垫付，
+// 否则由本笔交易的手续费支付者支付；垫付的租金按 repay_rent_funder 在交易结束前归还
+pub fn create_trade_pda<'info>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'info>],
+    payer: &AccountInfo<'info>,
+    pda: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    if let Some(funder) = active_rent_funder(program_id, accounts) {
+        let rent = Rent::get()?;
+        let required = rent.minimum_balance(space).saturating_sub(pda.lamports());
+        let available = funder.lamports().saturating_sub(rent.minimum_balance(0));
+        if available >= required {
+            **funder.try_borrow_mut_lamports()? -= required;
+            **pda.try_borrow_mut_lamports()? += required;
+        } else {
+            msg!("共享租金账户余额 {} 不足 {}，改由支付者支付", available, required);
+        }
+    }
+    create_pda_account(payer, pda, system_program, program_id, space, seeds)
+}
+
+// 账户: [配置账户, 管理员(签名并支付租金), 共享租金 PDA, 系统程序]。开启后任何人都可以直接向 PDA 转入 SOL 充值
+pub fn process_open_rent_funder(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let funder = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    TradeFeeState::load_as_admin(program_id, config_account, admin)?;
+    let (address, bump) = rent_funder_address(program_id);
+    if funder.key != &address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if funder.owner != program_id {
+        create_pda_account(admin, funder, system_program, program_id, 0, &[RENT_FUNDER_SEED, &[bump]])?;
+    }
+    Ok(())
+}
+
+// 关闭共享租金账户，剩余 lamports 退还给管理员，之后交易中创建的 PDA 恢复由支付者支付
+// 账户: [配置账户, 管理员, 共享租金 PDA]
+pub fn process_close_rent_funder(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let funder = next_account_info(accounts_iter)?;
+
+    TradeFeeState::load_as_admin(program_id, config_account, admin)?;
+    if funder.owner != program_id || funder.key != &rent_funder_address(program_id).0 {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let lamports = funder.lamports();
+    **funder.try_borrow_mut_lamports()? -= lamports;
+    **admin.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg,
+    program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
+};
+
+use crate::state::{FeeRollupState, TradeFeeState};
+use crate::instructions::rent_funder::create_trade_pda;
+use crate::utils::find_account;
+
+pub const ROLLUP_SEED: &[u8] = b"rollup";
+
+pub fn rollup_address(program_id: &Pubkey, epoch: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ROLLUP_SEED, &epoch.to_le_bytes()], program_id)
+}
+
+// 开启按 epoch 汇总后，把本笔 SOL 手续费累加到当前 epoch 的汇总 PDA
+// 汇总 PDA 追加在账户末尾，当前 epoch 首笔交易时由支付者创建
+pub fn record_epoch_fee<'info>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'info>],
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    config: &TradeFeeState,
+    fee: u64,
+) -> ProgramResult {
+    if !config.epoch_rollup {
+        return Ok(());
+    }
+
+    let epoch = Clock::get()?.epoch;
+    let (address, bump) = rollup_address(program_id, epoch);
+    let rollup_account = find_account(accounts, &address).ok_or_else(|| {
+        msg!("开启 epoch 汇总时需要附带汇总账户 {}", address);
+        ProgramError::NotEnoughAccountKeys
+    })?;
+
+    let mut rollup = if rollup_account.owner != program_id {
+        create_trade_pda(
+            program_id,
+            accounts,
+            payer,
+            rollup_account,
+            system_program,
+            FeeRollupState::LEN,
+            &[ROLLUP_SEED, &epoch.to_le_bytes(), &[bump]],
+        )?;
+        FeeRollupState {
+            epoch,
+            total_fees: 0,
+            trade_count: 0,
+        }
+    } else {
+        FeeRollupState::try_from_slice(&rollup_account.data.borrow())?
+    };
+
+    rollup.total_fees = rollup.total_fees.saturating_add(fee);
+    rollup.trade_count = rollup.trade_count.saturating_add(1);
+    rollup.serialize(&mut &mut rollup_account.data.borrow_mut()[..])?;
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::MyError;
+use crate::instructions::fee::find_fee_route;
+use crate::state::{RouteProgramState, TradeFeeState};
+use crate::utils::{check_upgrade_authority, create_pda_account, find_account};
+
+// 管理员为路由登记、移除额外接受的目标程序: [路由选择器 8][程序 32][版本 u16] / [路由选择器 8][程序 32]
+pub const ADD_ROUTE_PROGRAM_SELECTOR: &[u8; 8] = b"add_rprg";
+pub const REMOVE_ROUTE_PROGRAM_SELECTOR: &[u8; 8] = b"rm_rprg\0";
+
+pub const ROUTE_PROGRAM_SEED: &[u8] = b"route_prog";
+
+pub fn route_program_address(program_id: &Pubkey, selector: &[u8; 8], program: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ROUTE_PROGRAM_SEED, selector, program.as_ref()], program_id)
+}
+
+fn parse_route_program(data: &[u8]) -> Result<([u8; 8], Pubkey), ProgramError> {
+    if data.len() < 40 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let selector = <[u8; 8]>::try_from(&data[..8]).unwrap();
+    let program = Pubkey::new_from_array(data[8..40].try_into().unwrap());
+    if find_fee_route(&selector).is_none() {
+        msg!("选择器 {:?} 不是带手续费的路由", selector);
+        return Err(MyError::UnsupportedRoute.into());
+    }
+    Ok((selector, program))
+}
+
+// 目标程序为该路由登记的版本（登记 PDA 追加在账户末尾），未登记时返回 None
+pub fn route_program_version(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    selector: &[u8; 8],
+    program: &Pubkey,
+) -> Option<u16> {
+    let account = find_account(accounts, &route_program_address(program_id, selector, program).0)?;
+    if account.owner != program_id {
+        return None;
+    }
+    let state = RouteProgramState::try_from_slice(&account.data.borrow()).ok()?;
+    (&state.selector == selector && &state.program == program).then_some(state.version)
+}
+
+// CPI 会带上用户签名，放开目标程序属于高风险操作，管理员必须同时是程序升级权限；已登记时只更新版本
+// 账户: [配置账户, 管理员(签名并支付租金), 登记 PDA, 系统程序, 本程序的 ProgramData, 多签管理员...]
+pub fn process_add_route_program(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let (selector, program) = parse_route_program(instruction_data)?;
+    let version = instruction_data
+        .get(40..42)
+        .map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    if &program == program_id {
+        return Err(MyError::SelfInvocation.into());
+    }
+
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let route_program_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let program_data = next_account_info(accounts_iter)?;
+
+    let config = TradeFeeState::load_as_admin(program_id, config_account, admin)?;
+    check_upgrade_authority(program_id, program_data, admin)?;
+    config.check_multisig(accounts)?;
+
+    let (address, bump) = route_program_address(program_id, &selector, &program);
+    if route_program_account.key != &address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if route_program_account.owner != program_id {
+        create_pda_account(
+            admin,
+            route_program_account,
+            system_program,
+            program_id,
+            RouteProgramState::LEN,
+            &[ROUTE_PROGRAM_SEED, &selector, program.as_ref(), &[bump]],
+        )?;
+    }
+
+    let state = RouteProgramState { selector, program, version };
+    state.serialize(&mut &mut route_program_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// 移除登记只收窄允许范围，管理员签名即可，PDA 租金退还给管理员
+// 账户: [配置账户, 管理员, 登记 PDA]
+pub fn process_remove_route_program(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let (selector, program) = parse_route_program(instruction_data)?;
+
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let route_program_account = next_account_info(accounts_iter)?;
+
+    TradeFeeState::load_as_admin(program_id, config_account, admin)?;
+    if route_program_account.owner != program_id
+        || route_program_account.key != &route_program_address(program_id, &selector, &program).0
+    {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // 清空数据并转出全部 lamports，交易结束后账户即被回收
+    route_program_account.data.borrow_mut().fill(0);
+    let lamports = route_program_account.lamports();
+    **route_program_account.try_borrow_mut_lamports()? -= lamports;
+    **admin.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
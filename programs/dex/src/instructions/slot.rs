@@ -1,20 +1,20 @@
 use solana_program::{
-    clock::Clock, entrypoint::ProgramResult, program_error::ProgramError, sysvar::Sysvar,
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg,
+    program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
 };
 
-pub const EXPIRED_SLOT_SELECTOR: &[u8; 8] = &[169, 134, 33, 62, 168, 2, 246, 176];
+use crate::error::MyError;
+use crate::state::TradeFeeState;
 
-#[derive(Debug, Clone)]
-pub enum MyError {
-    SlotExpired,
-}
+pub const EXPIRED_SLOT_SELECTOR: &[u8; 8] = &[169, 134, 33, 62, 168, 2, 246, 176];
 
-impl From<MyError> for ProgramError {
-    fn from(e: MyError) -> Self {
-        ProgramError::Custom(e as u32) // 自定义错误码更清晰
-    }
-}
-pub fn process_expired_slot(instruction_data: &[u8]) -> ProgramResult {
+// 交易截止槽检查: [expiry_slot u64]。可选传入配置账户，配置了 max_data_age_slots 时
+// 截止槽距当前超过该时长也拒绝，避免很久以前签名、截止槽设得很远的交易仍可上链
+pub fn process_expired_slot(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
     let expiry_slot = u64::from_le_bytes(
         instruction_data
             .try_into()
@@ -27,5 +27,13 @@ pub fn process_expired_slot(instruction_data: &[u8]) -> ProgramResult {
         return Err(MyError::SlotExpired.into());
     }
 
+    if let Some(config_account) = accounts.first().filter(|acc| acc.owner == program_id) {
+        let max_age = TradeFeeState::unpack(&config_account.data.borrow())?.max_data_age_slots;
+        if max_age != 0 && expiry_slot - clock.slot > max_age {
+            msg!("截止槽 {} 距当前 slot {} 超过最大时长 {}", expiry_slot, clock.slot, max_age);
+            return Err(MyError::DeadlineTooFar.into());
+        }
+    }
+
     Ok(())
 }
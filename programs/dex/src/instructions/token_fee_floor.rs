@@ -0,0 +1,92 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::state::{TokenFeeFloorState, TradeFeeState, PREFLIGHT_TOKEN_FEE_FLOOR};
+use crate::token::mint_decimals;
+use crate::utils::{create_pda_account, find_account};
+
+// 管理员设置代币手续费下限: [min_fee_micros u64]，以百万分之一个代币（UI 单位）计，0 表示关闭
+pub const SET_TOKEN_FEE_FLOOR_SELECTOR: &[u8; 8] = b"set_tflr";
+
+pub const TOKEN_FEE_FLOOR_SEED: &[u8] = b"token_floor";
+
+// 下限的 UI 单位精度：1 个代币 = 1_000_000 micros
+const MICROS_PER_TOKEN: u128 = 1_000_000;
+
+pub fn token_fee_floor_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TOKEN_FEE_FLOOR_SEED], program_id)
+}
+
+// 以代币收费时的手续费下限（mint 最小单位），按 mint 的 decimals 换算并向上取整，未开启时为 0：
+// 0 位精度的 mint 上不足 1 个代币的下限按 1 个最小单位收取，不会舍入为 0。
+// 开启后交易必须附带下限 PDA，避免省略账户绕过下限
+pub fn token_fee_floor(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: &TradeFeeState,
+    mint: &AccountInfo,
+) -> Result<u64, ProgramError> {
+    if !config.preflight(PREFLIGHT_TOKEN_FEE_FLOOR) {
+        return Ok(0);
+    }
+    let address = token_fee_floor_address(program_id).0;
+    let account = find_account(accounts, &address)
+        .filter(|account| account.owner == program_id)
+        .ok_or_else(|| {
+            msg!("已开启代币手续费下限，交易须附带下限账户 {}", address);
+            ProgramError::NotEnoughAccountKeys
+        })?;
+    let state = TokenFeeFloorState::try_from_slice(&account.data.borrow())?;
+
+    let scale = 10u128
+        .checked_pow(mint_decimals(mint)? as u32)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    let floor = (state.min_fee_micros as u128 * scale).div_ceil(MICROS_PER_TOKEN);
+    u64::try_from(floor).map_err(|_| ProgramError::ArithmeticOverflow)
+}
+
+// 账户: [配置账户, 管理员(签名并支付租金), 下限 PDA, 系统程序]，已存在时直接更新
+pub fn process_set_token_fee_floor(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let min_fee_micros = instruction_data
+        .get(0..8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidInstructionData)?;
+
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let floor_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    let mut config = TradeFeeState::load_as_admin(program_id, config_account, admin)?;
+
+    let (address, bump) = token_fee_floor_address(program_id);
+    if floor_account.key != &address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if floor_account.owner != program_id {
+        create_pda_account(
+            admin,
+            floor_account,
+            system_program,
+            program_id,
+            TokenFeeFloorState::LEN,
+            &[TOKEN_FEE_FLOOR_SEED, &[bump]],
+        )?;
+    }
+
+    TokenFeeFloorState { min_fee_micros }.serialize(&mut &mut floor_account.data.borrow_mut()[..])?;
+    config.set_preflight(PREFLIGHT_TOKEN_FEE_FLOOR, min_fee_micros > 0);
+    config.store(config_account)?;
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{entrypoint::ProgramResult, program::set_return_data};
+
+use crate::state::MAX_FEE_BPS;
+
+pub const VERSION_SELECTOR: &[u8; 8] = b"version\0";
+
+// 通过 return data 返回给集成方校验的程序信息
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct VersionInfo {
+    pub major: u8,
+    pub minor: u8,
+    pub patch: u8,
+    pub max_fee_bps: u32,
+}
+
+pub fn process_version() -> ProgramResult {
+    let info = VersionInfo {
+        major: env!("CARGO_PKG_VERSION_MAJOR").parse().unwrap_or_default(),
+        minor: env!("CARGO_PKG_VERSION_MINOR").parse().unwrap_or_default(),
+        patch: env!("CARGO_PKG_VERSION_PATCH").parse().unwrap_or_default(),
+        max_fee_bps: MAX_FEE_BPS,
+    };
+
+    set_return_data(&borsh::to_vec(&info)?);
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, msg, program_error::ProgramError, pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+use crate::error::MyError;
+use crate::state::{TradeFeeState, WalletState};
+use crate::instructions::rent_funder::create_trade_pda;
+use crate::utils::{find_account, grow_account};
+
+pub const WALLET_SEED: &[u8] = b"wallet";
+
+pub fn wallet_address(program_id: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[WALLET_SEED, wallet.as_ref()], program_id)
+}
+
+// 记录一笔交易，返回本笔是否在免费额度内；开启冷却时拒绝间隔过短的交易
+// 钱包计数 PDA 追加在账户末尾，首次使用时由支付者创建；未开启冷却且未传入时正常收费
+pub fn record_wallet_trade<'info>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'info>],
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    config: &TradeFeeState,
+) -> Result<bool, ProgramError> {
+    if config.free_trades == 0 && config.trade_cooldown_slots == 0 {
+        return Ok(false);
+    }
+
+    let (address, bump) = wallet_address(program_id, payer.key);
+    let Some(wallet_account) = find_account(accounts, &address) else {
+        if config.trade_cooldown_slots > 0 {
+            msg!("开启交易冷却时需要附带钱包账户 {}", address);
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        return Ok(false);
+    };
+
+    if wallet_account.owner != program_id {
+        create_trade_pda(
+            program_id,
+            accounts,
+            payer,
+            wallet_account,
+            system_program,
+            WalletState::LEN,
+            &[WALLET_SEED, payer.key.as_ref(), &[bump]],
+        )?;
+    } else {
+        // 早期创建的账户只有交易计数，扩容后记录 slot
+        grow_account(payer, wallet_account, system_program, WalletState::LEN)?;
+    }
+
+    let mut state = WalletState::unpack(&wallet_account.data.borrow())?;
+    let slot = Clock::get()?.slot;
+    if config.trade_cooldown_slots > 0
+        && state.last_trade_slot != 0
+        && slot < state.last_trade_slot.saturating_add(config.trade_cooldown_slots)
+    {
+        msg!("钱包 {} 处于交易冷却中，上一笔交易 slot {}", payer.key, state.last_trade_slot);
+        return Err(MyError::TradeCooldown.into());
+    }
+
+    let free = state.trade_count < config.free_trades as u64;
+    state.trade_count = state.trade_count.saturating_add(1);
+    state.last_trade_slot = slot;
+    state.serialize(&mut &mut wallet_account.data.borrow_mut()[..])?;
+    Ok(free)
+}
+
+// 与 record_wallet_trade 相同的免费额度判断，不创建账户也不记录交易，供 explain 预览使用
+pub fn wallet_trade_is_free(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    payer: &AccountInfo,
+    config: &TradeFeeState,
+) -> Result<bool, ProgramError> {
+    if config.free_trades == 0 {
+        return Ok(false);
+    }
+    let Some(wallet_account) = find_account(accounts, &wallet_address(program_id, payer.key).0) else {
+        return Ok(false);
+    };
+    if wallet_account.owner != program_id {
+        // 首笔交易时才创建，计数从 0 开始
+        return Ok(true);
+    }
+    let state = WalletState::unpack(&wallet_account.data.borrow())?;
+    Ok(state.trade_count < config.free_trades as u64)
+}
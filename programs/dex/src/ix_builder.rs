@@ -0,0 +1,1699 @@
+// 客户端指令构造工具，按合约端的解析布局拼装选择器、参数和账户
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_instruction, system_program, sysvar,
+};
+
+use crate::events::event_authority_address;
+use crate::instructions::allowance::{allowance_address, APPROVE_ALLOWANCE_SELECTOR, REVOKE_ALLOWANCE_SELECTOR};
+use crate::instructions::ata::ATA_SELECTOR;
+use crate::instructions::escrow::{escrow_address, INIT_ESCROW_SELECTOR, WITHDRAW_ESCROW_SELECTOR};
+use crate::instructions::fee::{EXPLAIN_SELECTOR, FEE_PREVIEW_SELECTOR};
+use crate::instructions::impact::{IMPACT_SWAP_SELECTOR, MIN_TOKENS_SWAP_SELECTOR};
+use crate::instructions::meteora::{DAMM_V2_BUY_SELECTOR, DAMM_V2_SELL_SELECTOR};
+use crate::instructions::fee_exempt::{fee_exempt_mint_address, ADD_FEE_EXEMPT_MINT_SELECTOR, REMOVE_FEE_EXEMPT_MINT_SELECTOR};
+use crate::instructions::mint_fee::{mint_fee_address, CLEAR_MINT_FEE_SELECTOR, SET_MINT_FEE_SELECTOR};
+use crate::instructions::nonce::{nonce_address, NONCE_SWAP_SELECTOR};
+use crate::instructions::openbook::{BUY_TAKE_PROFIT_SELECTOR, OPENBOOK_BUY_SELECTOR, OPENBOOK_V2_PROGRAM};
+use crate::instructions::pump::{
+    PUMP_AMM_SELECTOR, PUMP_AMM_SELL_SELECTOR, PUMP_BUY_SLIPPAGE_SELECTOR, PUMP_BUY_WITH_ATA_SELECTOR, PUMP_SELECTOR,
+    PUMP_SELL_SELECTOR,
+};
+use crate::instructions::raydium::{
+    RAYDIUM_BUY_BASE_OUT_SELECTOR, RAYDIUM_BUY_SELECTOR, RAYDIUM_CLMM_BUY_EXACT_OUT_SELECTOR,
+    RAYDIUM_SELL_SELECTOR,
+};
+use crate::instructions::referral::{
+    referral_address, REFERRAL_SWAP_SELECTOR, REGISTER_REFERRAL_SELECTOR,
+    REVOKE_REFERRAL_SELECTOR, UPDATE_REFERRAL_SELECTOR,
+};
+use crate::instructions::relay::{relayed_message, RELAYED_SWAP_SELECTOR};
+use crate::instructions::rollup::rollup_address;
+use crate::instructions::rent_funder::{rent_funder_address, CLOSE_RENT_FUNDER_SELECTOR, OPEN_RENT_FUNDER_SELECTOR};
+use crate::instructions::route_program::{route_program_address, ADD_ROUTE_PROGRAM_SELECTOR, REMOVE_ROUTE_PROGRAM_SELECTOR};
+use crate::instructions::slot::EXPIRED_SLOT_SELECTOR;
+use crate::instructions::token_fee_floor::{token_fee_floor_address, SET_TOKEN_FEE_FLOOR_SELECTOR};
+use crate::instructions::version::VERSION_SELECTOR;
+use crate::instructions::wallet::wallet_address;
+use crate::processor::{
+    BLOCK_MINT_SELECTOR, GET_CONFIG_SELECTOR, PAUSE_ROUTE_SELECTOR, SET_BUYBACK_SELECTOR, SET_GUARDIAN_SELECTOR, SET_PAUSED_SELECTOR, SET_BUY_FEE_TIMING_SELECTOR, SET_COOLDOWN_SELECTOR, SET_CPI_CALLERS_SELECTOR, SET_EPOCH_ROLLUP_SELECTOR, SET_FEE_RATE_SELECTOR, SET_COMPACT_EVENTS_SELECTOR, SET_ON_FEE_FAILURE_SELECTOR, SET_FEE_WALLET_BACKUP_SELECTOR, READ_AND_RESET_FEES_SELECTOR, SET_BALANCE_CHECK_SELECTOR, SET_FROZEN_CHECK_SELECTOR, SET_CPI_FEE_WAIVER_SELECTOR, MIGRATE_CONFIG_SELECTOR, CREATE_CONFIG_SELECTOR, UPDATE_CONFIG_SELECTOR, SET_PROMO_WINDOW_SELECTOR, SET_MAX_DATA_AGE_SELECTOR, SET_DORMANT_WAIVER_SELECTOR, config_address, SET_DEFAULT_REFERRER_SELECTOR, SET_FEE_SIDES_SELECTOR, SET_FEE_TIMING_SELECTOR, SET_FREE_TRADES_SELECTOR, SET_PROTOCOL_FEE_WALLET_SELECTOR,
+    SET_LENIENT_SELECTOR, SET_MAX_INNER_DATA_SELECTOR, SET_MAX_WITHDRAW_SELECTOR, SET_QUOTE_FEE_MINT_SELECTOR, SET_EVENT_CPI_SELECTOR, SET_MEMBERSHIP_SELECTOR, SET_MULTISIG_SELECTOR, SET_REPLAY_WINDOW_SELECTOR, SET_REBATE_SELECTOR, SET_WHALE_REBATE_SELECTOR, SET_REQUIRE_MEMO_SELECTOR, SET_ROUTE_FEES_SELECTOR,
+    SET_SHARES_SELECTOR, SET_USD_FEE_SELECTOR, SWEEP_ORPHAN_SELECTOR,
+};
+use crate::state::ConfigUpdate;
+use crate::token::{associated_token_address, ASSOCIATED_TOKEN_PROGRAM_ID};
+use crate::utils::program_data_address;
+
+// Raydium AMM v4 的 swapBaseIn 指令号
+const RAYDIUM_SWAP_BASE_IN: u8 = 9;
+
+// 带手续费路由的前 4 个账户（系统程序由构造函数补齐），配置账户可写以递增事件序号
+// 同一钱包可以同时是 payer、fee_receiver 和转发账户中的交易者，编译 Message 时重复账户会合并为一个，
+// 权限取并集，每次重复只占 1 字节索引
+#[derive(Debug, Clone, Copy)]
+pub struct FeeAccounts {
+    pub config: Pubkey,
+    pub payer: Pubkey,
+    pub fee_receiver: Pubkey,
+}
+
+// 带手续费路由: [选择器][计费金额][内层参数...]
+fn fee_route_ix(
+    program_id: &Pubkey,
+    selector: &[u8; 8],
+    fee_accounts: &FeeAccounts,
+    amount: u64,
+    inner_args: &[u64],
+    forwarded: Vec<AccountMeta>,
+) -> Instruction {
+    let mut data = Vec::with_capacity(16 + inner_args.len() * 8);
+    data.extend_from_slice(selector);
+    data.extend_from_slice(&amount.to_le_bytes());
+    for arg in inner_args {
+        data.extend_from_slice(&arg.to_le_bytes());
+    }
+
+    let mut accounts = Vec::with_capacity(4 + forwarded.len());
+    accounts.extend_from_slice(&[
+        AccountMeta::new(fee_accounts.config, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(fee_accounts.payer, true),
+        AccountMeta::new(fee_accounts.fee_receiver, false),
+    ]);
+    accounts.extend(forwarded);
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
+
+// Pump 内盘买入，第一个内层参数会被合约替换为扣除手续费后的金额
+pub fn pump_buy_ix(
+    program_id: &Pubkey,
+    fee_accounts: &FeeAccounts,
+    amount: u64,
+    max_sol_cost: u64,
+    forwarded: Vec<AccountMeta>,
+) -> Instruction {
+    fee_route_ix(program_id, PUMP_SELECTOR, fee_accounts, amount, &[amount, max_sol_cost], forwarded)
+}
+
+// 首次买入的用户：先幂等创建 associated_user（由 payer 出资），再执行 Pump 内盘买入，ATA 已存在时只执行买入
+pub fn pump_buy_with_ata_ix(
+    program_id: &Pubkey,
+    fee_accounts: &FeeAccounts,
+    amount: u64,
+    max_sol_cost: u64,
+    forwarded: Vec<AccountMeta>,
+) -> Instruction {
+    let mut ix = fee_route_ix(program_id, PUMP_BUY_WITH_ATA_SELECTOR, fee_accounts, amount, &[amount, max_sol_cost], forwarded);
+    ix.accounts.push(AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false));
+    ix
+}
+
+// Pump 内盘买入，max_sol_cost 由合约按 bonding curve 当前储备、Pump 费率与 slippage_bps 计算，账户与 pump_buy_ix 相同
+pub fn pump_buy_with_slippage_ix(
+    program_id: &Pubkey,
+    fee_accounts: &FeeAccounts,
+    amount: u64,
+    slippage_bps: u16,
+    forwarded: Vec<AccountMeta>,
+) -> Instruction {
+    let mut ix = fee_route_ix(program_id, PUMP_BUY_SLIPPAGE_SELECTOR, fee_accounts, amount, &[], forwarded);
+    ix.data.extend_from_slice(&slippage_bps.to_le_bytes());
+    ix
+}
+
+// Pump 内盘卖出
+pub fn pump_sell_ix(
+    program_id: &Pubkey,
+    fee_accounts: &FeeAccounts,
+    amount: u64,
+    min_sol_output: u64,
+    forwarded: Vec<AccountMeta>,
+) -> Instruction {
+    fee_route_ix(program_id, PUMP_SELL_SELECTOR, fee_accounts, amount, &[amount, min_sol_output], forwarded)
+}
+
+// PumpAMM 外盘买入，forwarded 需包含 coin creator 账户及其后的全局、用户交易量累计 PDA（共 21 个）
+pub fn pump_amm_buy_ix(
+    program_id: &Pubkey,
+    fee_accounts: &FeeAccounts,
+    amount: u64,
+    max_quote_amount_in: u64,
+    forwarded: Vec<AccountMeta>,
+) -> Instruction {
+    fee_route_ix(program_id, PUMP_AMM_SELECTOR, fee_accounts, amount, &[amount, max_quote_amount_in], forwarded)
+}
+
+// PumpAMM 外盘卖出
+pub fn pump_amm_sell_ix(
+    program_id: &Pubkey,
+    fee_accounts: &FeeAccounts,
+    amount: u64,
+    min_quote_amount_out: u64,
+    forwarded: Vec<AccountMeta>,
+) -> Instruction {
+    fee_route_ix(program_id, PUMP_AMM_SELL_SELECTOR, fee_accounts, amount, &[amount, min_quote_amount_out], forwarded)
+}
+
+// 带手续费的 Meteora DAMM v2 买入，amount_in 为输入的 quote 代币数量
+// forwarded 为 swap 的 14 个账户（未使用的 referral_token_account 用 DAMM v2 程序 ID 占位），末尾为 DAMM v2 程序账户
+pub fn damm_v2_buy_ix(
+    program_id: &Pubkey,
+    fee_accounts: &FeeAccounts,
+    amount_in: u64,
+    minimum_amount_out: u64,
+    forwarded: Vec<AccountMeta>,
+) -> Instruction {
+    fee_route_ix(program_id, DAMM_V2_BUY_SELECTOR, fee_accounts, amount_in, &[amount_in, minimum_amount_out], forwarded)
+}
+
+// 带手续费的 Meteora DAMM v2 卖出，账户顺序同 damm_v2_buy_ix，输出为 quote 代币账户
+pub fn damm_v2_sell_ix(
+    program_id: &Pubkey,
+    fee_accounts: &FeeAccounts,
+    amount_in: u64,
+    minimum_amount_out: u64,
+    forwarded: Vec<AccountMeta>,
+) -> Instruction {
+    fee_route_ix(program_id, DAMM_V2_SELL_SELECTOR, fee_accounts, amount_in, &[amount_in, minimum_amount_out], forwarded)
+}
+
+fn raydium_swap_ix(
+    program_id: &Pubkey,
+    selector: &[u8; 8],
+    amount_in: u64,
+    min_amount_out: u64,
+    accounts: Vec<AccountMeta>,
+) -> Instruction {
+    let mut data = Vec::with_capacity(25);
+    data.extend_from_slice(selector);
+    data.push(RAYDIUM_SWAP_BASE_IN);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&min_amount_out.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
+
+// Raydium 买入，accounts 依次为: amm 程序、token 程序、amm 池、amm authority、
+// coin vault、pc vault、用户源账户、用户目标账户、用户
+pub fn raydium_buy_ix(
+    program_id: &Pubkey,
+    amount_in: u64,
+    min_amount_out: u64,
+    accounts: Vec<AccountMeta>,
+) -> Instruction {
+    raydium_swap_ix(program_id, RAYDIUM_BUY_SELECTOR, amount_in, min_amount_out, accounts)
+}
+
+// Raydium 卖出，账户顺序同 raydium_buy_ix
+pub fn raydium_sell_ix(
+    program_id: &Pubkey,
+    amount_in: u64,
+    min_amount_out: u64,
+    accounts: Vec<AccountMeta>,
+) -> Instruction {
+    raydium_swap_ix(program_id, RAYDIUM_SELL_SELECTOR, amount_in, min_amount_out, accounts)
+}
+
+// 带手续费的 Raydium swapBaseOut 买入，手续费按 max_amount_in 收取
+// forwarded 为 17 个账户的 Raydium swap 布局，末尾追加 Raydium AMM v4 程序账户
+pub fn raydium_buy_base_out_ix(
+    program_id: &Pubkey,
+    fee_accounts: &FeeAccounts,
+    max_amount_in: u64,
+    amount_out: u64,
+    forwarded: Vec<AccountMeta>,
+) -> Instruction {
+    fee_route_ix(
+        program_id,
+        RAYDIUM_BUY_BASE_OUT_SELECTOR,
+        fee_accounts,
+        max_amount_in,
+        &[max_amount_in, amount_out],
+        forwarded,
+    )
+}
+
+// 带手续费的 Raydium CLMM swap_v2 精确输出买入，手续费按 max_amount_in 预估、按实际消耗的输入收取
+// forwarded 为 swap_v2 的账户列表（含 tick array），末尾追加 CLMM 程序账户
+pub fn raydium_clmm_buy_exact_out_ix(
+    program_id: &Pubkey,
+    fee_accounts: &FeeAccounts,
+    max_amount_in: u64,
+    amount_out: u64,
+    sqrt_price_limit_x64: u128,
+    forwarded: Vec<AccountMeta>,
+) -> Instruction {
+    let mut ix = fee_route_ix(
+        program_id,
+        RAYDIUM_CLMM_BUY_EXACT_OUT_SELECTOR,
+        fee_accounts,
+        max_amount_in,
+        &[amount_out, max_amount_in, sqrt_price_limit_x64 as u64, (sqrt_price_limit_x64 >> 64) as u64],
+        forwarded,
+    );
+    // is_base_input = false
+    ix.data.push(0);
+    ix
+}
+
+// OpenBook v2 买单参数，order_type 为 OpenBook 的 PlaceOrderType（如 ImmediateOrCancel）
+#[derive(Debug, Clone, Copy)]
+pub struct OpenBookOrder {
+    pub price_lots: i64,
+    pub max_base_lots: i64,
+    pub order_type: u8,
+    pub limit: u8,
+}
+
+// 带手续费的 OpenBook v2 买单，quote_amount 为 quote 原生数量，合约按 quote_lot_size 换算为 lots
+// forwarded 为 place_take_order 的账户列表（可选账户用 OpenBook 程序 ID 占位）
+pub fn openbook_buy_ix(
+    program_id: &Pubkey,
+    fee_accounts: &FeeAccounts,
+    quote_amount: u64,
+    order: &OpenBookOrder,
+    forwarded: Vec<AccountMeta>,
+) -> Instruction {
+    let mut ix = fee_route_ix(program_id, OPENBOOK_BUY_SELECTOR, fee_accounts, quote_amount, &[], forwarded);
+    ix.data.push(0); // Side::Bid
+    ix.data.extend_from_slice(&order.price_lots.to_le_bytes());
+    ix.data.extend_from_slice(&order.max_base_lots.to_le_bytes());
+    ix.data.extend_from_slice(&0i64.to_le_bytes());
+    ix.data.push(order.order_type);
+    ix.data.push(order.limit);
+    ix
+}
+
+// OpenBook v2 止盈卖单参数，expiry_timestamp 为 0 表示不过期
+#[derive(Debug, Clone, Copy)]
+pub struct TakeProfitOrder {
+    pub price_lots: i64,
+    pub client_order_id: u64,
+    pub expiry_timestamp: u64,
+}
+
+// 把带手续费的买入指令包装为买入加止盈：买入完成后以买到的全部 base 代币挂出限价卖单
+// order_accounts 为 place_order 的 12 个账户（user_token_account 为买入的输出账户，可选账户用 OpenBook 程序 ID 占位），
+// 末尾自动追加 OpenBook 程序账户
+pub fn buy_take_profit_ix(buy_ix: Instruction, order: &TakeProfitOrder, order_accounts: Vec<AccountMeta>) -> Instruction {
+    let mut data = Vec::with_capacity(25 + buy_ix.data.len());
+    data.extend_from_slice(BUY_TAKE_PROFIT_SELECTOR);
+    data.push((buy_ix.accounts.len() - 4) as u8);
+    data.extend_from_slice(&order.price_lots.to_le_bytes());
+    data.extend_from_slice(&order.client_order_id.to_le_bytes());
+    data.extend_from_slice(&order.expiry_timestamp.to_le_bytes());
+    data.extend_from_slice(&buy_ix.data);
+
+    let mut accounts = buy_ix.accounts;
+    accounts.extend(order_accounts);
+    accounts.push(AccountMeta::new_readonly(OPENBOOK_V2_PROGRAM, false));
+    Instruction {
+        program_id: buy_ix.program_id,
+        accounts,
+        data,
+    }
+}
+
+// 通过代理创建 ATA，idempotent 为 true 时使用 CreateIdempotent
+pub fn create_ata_ix(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    associated_token_account: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    ata_program: &Pubkey,
+    idempotent: bool,
+) -> Instruction {
+    let mut data = Vec::with_capacity(9);
+    data.extend_from_slice(ATA_SELECTOR);
+    data.push(idempotent as u8);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new(*associated_token_account, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(*ata_program, false),
+        ],
+        data,
+    }
+}
+
+// 交易截止槽检查
+pub fn expired_slot_ix(program_id: &Pubkey, expiry_slot: u64) -> Instruction {
+    let mut data = Vec::with_capacity(16);
+    data.extend_from_slice(EXPIRED_SLOT_SELECTOR);
+    data.extend_from_slice(&expiry_slot.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![],
+        data,
+    }
+}
+
+// 带配置账户的截止槽检查，配置了 max_data_age_slots 时截止槽不能超过当前 slot 加该时长
+pub fn bounded_expired_slot_ix(program_id: &Pubkey, config: &Pubkey, expiry_slot: u64) -> Instruction {
+    let mut ix = expired_slot_ix(program_id, expiry_slot);
+    ix.accounts.push(AccountMeta::new_readonly(*config, false));
+    ix
+}
+
+// 管理员更换协议费钱包，开启多签时需用 with_multisig_signers 追加签名
+pub fn set_fee_wallet_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, new_wallet: &Pubkey) -> Instruction {
+    let mut data = Vec::with_capacity(40);
+    data.extend_from_slice(SET_PROTOCOL_FEE_WALLET_SELECTOR);
+    data.extend_from_slice(new_wallet.as_ref());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置费率（pips）
+pub fn set_fee_rate_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, fee_rate_pips: u32) -> Instruction {
+    let mut data = Vec::with_capacity(12);
+    data.extend_from_slice(SET_FEE_RATE_SELECTOR);
+    data.extend_from_slice(&fee_rate_pips.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置新钱包免手续费的交易笔数，0 表示关闭
+pub fn set_free_trades_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, free_trades: u32) -> Instruction {
+    let mut data = Vec::with_capacity(12);
+    data.extend_from_slice(SET_FREE_TRADES_SELECTOR);
+    data.extend_from_slice(&free_trades.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置美元定额手续费，交易时需在账户末尾追加预言机价格账户
+pub fn set_usd_fee_ix(
+    program_id: &Pubkey,
+    config: &Pubkey,
+    admin: &Pubkey,
+    fee_usd_micros: u64,
+    oracle: &Pubkey,
+    max_price_age_secs: u32,
+) -> Instruction {
+    let mut data = Vec::with_capacity(52);
+    data.extend_from_slice(SET_USD_FEE_SELECTOR);
+    data.extend_from_slice(&fee_usd_micros.to_le_bytes());
+    data.extend_from_slice(oracle.as_ref());
+    data.extend_from_slice(&max_price_age_secs.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置未知选择器处理模式，lenient 为 true 时返回 UnsupportedRoute
+pub fn set_lenient_selectors_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, lenient: bool) -> Instruction {
+    let mut data = Vec::with_capacity(9);
+    data.extend_from_slice(SET_LENIENT_SELECTOR);
+    data.push(lenient as u8);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置交易是否必须附带 Memo 指令
+pub fn set_require_memo_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, require_memo: bool) -> Instruction {
+    let mut data = Vec::with_capacity(9);
+    data.extend_from_slice(SET_REQUIRE_MEMO_SELECTOR);
+    data.push(require_memo as u8);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置同一钱包两笔交易之间的最小间隔 slot 数，开启后交易需用 with_wallet_counter 附带钱包 PDA
+pub fn set_trade_cooldown_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, cooldown_slots: u64) -> Instruction {
+    let mut data = Vec::with_capacity(16);
+    data.extend_from_slice(SET_COOLDOWN_SELECTOR);
+    data.extend_from_slice(&cooldown_slots.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员开关 Anchor event CPI，开启后带手续费路由需用 with_event_cpi 追加账户
+pub fn set_event_cpi_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, enabled: bool) -> Instruction {
+    let mut data = Vec::with_capacity(9);
+    data.extend_from_slice(SET_EVENT_CPI_SELECTOR);
+    data.push(enabled as u8);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置备用手续费钱包，开启多签时用 with_multisig_signers 追加签名；交易需用 with_fee_wallet_backup 追加备用钱包
+pub fn set_fee_wallet_backup_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, backup: &Pubkey) -> Instruction {
+    let mut data = Vec::with_capacity(40);
+    data.extend_from_slice(SET_FEE_WALLET_BACKUP_SELECTOR);
+    data.extend_from_slice(backup.as_ref());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 为带手续费路由的指令追加备用手续费钱包，主钱包不可用时 SOL 手续费转入该钱包
+pub fn with_fee_wallet_backup(mut ix: Instruction, backup: &Pubkey) -> Instruction {
+    ix.accounts.push(AccountMeta::new(*backup, false));
+    ix
+}
+
+// 管理员设置手续费转账失败的处理方式（0 中止交易，1 跳过收费并输出 FeeSkipped）
+pub fn set_on_fee_failure_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, policy: u8) -> Instruction {
+    let mut data = Vec::with_capacity(9);
+    data.extend_from_slice(SET_ON_FEE_FAILURE_SELECTOR);
+    data.push(policy);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员开关 CPI 前的支付者余额预检
+pub fn set_balance_check_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, enabled: bool) -> Instruction {
+    let mut data = Vec::with_capacity(9);
+    data.extend_from_slice(SET_BALANCE_CHECK_SELECTOR);
+    data.push(enabled as u8);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员开关买入输出代币账户的冻结预检
+pub fn set_frozen_destination_check_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, enabled: bool) -> Instruction {
+    let mut data = Vec::with_capacity(9);
+    data.extend_from_slice(SET_FROZEN_CHECK_SELECTOR);
+    data.push(enabled as u8);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 创建并初始化配置 PDA，签名者须是程序升级权限，支付租金并成为管理员（fee_wallet）；附带 bump 省去链上推导
+pub fn create_config_ix(program_id: &Pubkey, admin: &Pubkey, fee_rate_pips: u32) -> Instruction {
+    let (config, bump) = config_address(program_id);
+    let mut data = Vec::with_capacity(13);
+    data.extend_from_slice(CREATE_CONFIG_SELECTOR);
+    data.extend_from_slice(&fee_rate_pips.to_le_bytes());
+    data.push(bump);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(config, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(program_data_address(program_id), false),
+        ],
+        data,
+    }
+}
+
+// 管理员一次原子更新多个配置字段，未设置的字段保持不变；修改费率等需多签时用 with_multisig_signers 追加签名
+pub fn update_config_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, update: &ConfigUpdate) -> Instruction {
+    let mut data = UPDATE_CONFIG_SELECTOR.to_vec();
+    data.extend_from_slice(&borsh::to_vec(update).expect("ConfigUpdate 序列化失败"));
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员把旧版配置账户扩容到当前布局，新增部分的租金由管理员支付
+pub fn migrate_config_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: MIGRATE_CONFIG_SELECTOR.to_vec(),
+    }
+}
+
+// 管理员设置截止槽与预言机报价允许的最大时长（slot），0 表示不限制
+pub fn set_max_data_age_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, max_data_age_slots: u64) -> Instruction {
+    let mut data = Vec::with_capacity(16);
+    data.extend_from_slice(SET_MAX_DATA_AGE_SELECTOR);
+    data.extend_from_slice(&max_data_age_slots.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置闲置免费：协议超过 dormant_waiver_slots 个 slot 没有交易时下一笔免收手续费，0 表示关闭；
+// 开启时如启用了多签需用 with_multisig_signers 追加签名
+pub fn set_dormant_waiver_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, dormant_waiver_slots: u32) -> Instruction {
+    let mut data = Vec::with_capacity(12);
+    data.extend_from_slice(SET_DORMANT_WAIVER_SELECTOR);
+    data.extend_from_slice(&dormant_waiver_slots.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置推广免手续费的 slot 区间 [start_slot, end_slot)，end_slot 为 0 表示关闭；开启时如启用了多签需用 with_multisig_signers 追加签名
+pub fn set_promo_window_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, start_slot: u64, end_slot: u64) -> Instruction {
+    let mut data = Vec::with_capacity(24);
+    data.extend_from_slice(SET_PROMO_WINDOW_SELECTOR);
+    data.extend_from_slice(&start_slot.to_le_bytes());
+    data.extend_from_slice(&end_slot.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员开关 CPI 调用免手续费，开启时如启用了多签需用 with_multisig_signers 追加签名
+pub fn set_cpi_fee_waiver_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, enabled: bool) -> Instruction {
+    let mut data = Vec::with_capacity(9);
+    data.extend_from_slice(SET_CPI_FEE_WAIVER_SELECTOR);
+    data.push(enabled as u8);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员开关精简收费事件（events::CompactFeeCollected）
+pub fn set_compact_events_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, enabled: bool) -> Instruction {
+    let mut data = Vec::with_capacity(9);
+    data.extend_from_slice(SET_COMPACT_EVENTS_SELECTOR);
+    data.push(enabled as u8);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 为带手续费路由的指令追加 event CPI 所需的事件 PDA 与本程序账户
+pub fn with_event_cpi(program_id: &Pubkey, mut ix: Instruction) -> Instruction {
+    ix.accounts.push(AccountMeta::new_readonly(event_authority_address(program_id).0, false));
+    ix.accounts.push(AccountMeta::new_readonly(*program_id, false));
+    ix
+}
+
+// 管理员设置回购钱包与分成比例
+pub fn set_buyback_ix(
+    program_id: &Pubkey,
+    config: &Pubkey,
+    admin: &Pubkey,
+    buyback_wallet: &Pubkey,
+    share_bps: u16,
+) -> Instruction {
+    let mut data = Vec::with_capacity(42);
+    data.extend_from_slice(SET_BUYBACK_SELECTOR);
+    data.extend_from_slice(buyback_wallet.as_ref());
+    data.extend_from_slice(&share_bps.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置 nonce 防重放窗口（slot）与新建缓冲区容量，窗口需非零才能使用 nonce 与中继交易
+pub fn set_replay_window_ix(
+    program_id: &Pubkey,
+    config: &Pubkey,
+    admin: &Pubkey,
+    replay_window_slots: u64,
+    nonce_ring_size: u16,
+) -> Instruction {
+    let mut data = Vec::with_capacity(18);
+    data.extend_from_slice(SET_REPLAY_WINDOW_SELECTOR);
+    data.extend_from_slice(&replay_window_slots.to_le_bytes());
+    data.extend_from_slice(&nonce_ring_size.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置卖出收费时机: 0 为兑换前扣除，1 为兑换后从收到的 SOL 中收取
+pub fn set_fee_timing_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, timing: u8) -> Instruction {
+    let mut data = Vec::with_capacity(9);
+    data.extend_from_slice(SET_FEE_TIMING_SELECTOR);
+    data.push(timing);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置收费方向（0 买卖都收，1 只收买入，2 只收卖出）
+pub fn set_fee_sides_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, fee_sides: u8) -> Instruction {
+    let mut data = Vec::with_capacity(9);
+    data.extend_from_slice(SET_FEE_SIDES_SELECTOR);
+    data.push(fee_sides);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置默认推荐人，未带推荐码的交易需用 with_default_referrer 追加该钱包账户；传入默认公钥表示关闭
+pub fn set_default_referrer_ix(
+    program_id: &Pubkey,
+    config: &Pubkey,
+    admin: &Pubkey,
+    default_referrer: &Pubkey,
+    share_bps: u16,
+) -> Instruction {
+    let mut data = Vec::with_capacity(42);
+    data.extend_from_slice(SET_DEFAULT_REFERRER_SELECTOR);
+    data.extend_from_slice(default_referrer.as_ref());
+    data.extend_from_slice(&share_bps.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置买入收费时机（0 兑换前从 SOL 扣除，1 兑换后从收到的代币中收取）
+pub fn set_buy_fee_timing_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, timing: u8) -> Instruction {
+    let mut data = Vec::with_capacity(9);
+    data.extend_from_slice(SET_BUY_FEE_TIMING_SELECTOR);
+    data.push(timing);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置手续费返还比例（基点）
+pub fn set_rebate_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, rebate_bps: u16) -> Instruction {
+    let mut data = Vec::with_capacity(10);
+    data.extend_from_slice(SET_REBATE_SELECTOR);
+    data.extend_from_slice(&rebate_bps.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置大额交易返还，计费金额超过 whale_threshold 时手续费按 whale_rebate_bps 减免，阈值为 0 表示关闭
+pub fn set_whale_rebate_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, whale_threshold: u64, whale_rebate_bps: u16) -> Instruction {
+    let mut data = Vec::with_capacity(18);
+    data.extend_from_slice(SET_WHALE_REBATE_SELECTOR);
+    data.extend_from_slice(&whale_threshold.to_le_bytes());
+    data.extend_from_slice(&whale_rebate_bps.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 暂停或恢复单个路由，reason 为写入事件的原因码；守护者可以直接暂停，
+// 管理员操作或恢复暂停需同时是程序升级权限
+pub fn pause_route_ix(
+    program_id: &Pubkey,
+    config: &Pubkey,
+    authority: &Pubkey,
+    route_selector: &[u8; 8],
+    paused: bool,
+    reason: u16,
+) -> Instruction {
+    let mut data = Vec::with_capacity(19);
+    data.extend_from_slice(PAUSE_ROUTE_SELECTOR);
+    data.extend_from_slice(route_selector);
+    data.push(paused as u8);
+    data.extend_from_slice(&reason.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(program_data_address(program_id), false),
+        ],
+        data,
+    }
+}
+
+// 全局暂停或恢复所有带手续费的路由，权限要求同 pause_route_ix
+pub fn set_paused_ix(program_id: &Pubkey, config: &Pubkey, authority: &Pubkey, paused: bool, reason: u16) -> Instruction {
+    let mut data = Vec::with_capacity(11);
+    data.extend_from_slice(SET_PAUSED_SELECTOR);
+    data.push(paused as u8);
+    data.extend_from_slice(&reason.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(program_data_address(program_id), false),
+        ],
+        data,
+    }
+}
+
+// 管理员开关按 epoch 汇总手续费
+pub fn set_epoch_rollup_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, enabled: bool) -> Instruction {
+    let mut data = Vec::with_capacity(9);
+    data.extend_from_slice(SET_EPOCH_ROLLUP_SELECTOR);
+    data.push(enabled as u8);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 为带手续费路由的指令追加交易所在 epoch 的手续费汇总 PDA
+pub fn with_epoch_rollup(program_id: &Pubkey, mut ix: Instruction, epoch: u64) -> Instruction {
+    ix.accounts.push(AccountMeta::new(rollup_address(program_id, epoch).0, false));
+    ix
+}
+
+// 管理员把代币加入（blocked 为 true）或移出禁止列表
+pub fn block_mint_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, mint: &Pubkey, blocked: bool) -> Instruction {
+    let mut data = Vec::with_capacity(41);
+    data.extend_from_slice(BLOCK_MINT_SELECTOR);
+    data.extend_from_slice(mint.as_ref());
+    data.push(blocked as u8);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置只能暂停的守护者
+pub fn set_guardian_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, guardian: &Pubkey) -> Instruction {
+    let mut data = Vec::with_capacity(40);
+    data.extend_from_slice(SET_GUARDIAN_SELECTOR);
+    data.extend_from_slice(guardian.as_ref());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置以 quote 代币收费的 mint，AMM 交易对需用 with_token_fee_accounts 追加该 mint 的协议钱包账户
+pub fn set_quote_fee_mint_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, quote_fee_mint: &Pubkey) -> Instruction {
+    let mut data = Vec::with_capacity(40);
+    data.extend_from_slice(SET_QUOTE_FEE_MINT_SELECTOR);
+    data.extend_from_slice(quote_fee_mint.as_ref());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 为以代币收费的交易（买入按输出代币、AMM 交易对按 quote 代币）追加协议钱包、其关联代币账户、mint 与 ATA 程序
+pub fn with_token_fee_accounts(mut ix: Instruction, fee_wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Instruction {
+    ix.accounts.extend([
+        AccountMeta::new_readonly(*fee_wallet, false),
+        AccountMeta::new(associated_token_address(fee_wallet, mint, token_program), false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(*token_program, false),
+        AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false),
+    ]);
+    ix
+}
+
+// 管理员设置 CPI 调用方白名单，callers 整体替换原名单（最多 MAX_CPI_CALLERS 个）
+pub fn set_cpi_callers_ix(
+    program_id: &Pubkey,
+    config: &Pubkey,
+    admin: &Pubkey,
+    enabled: bool,
+    callers: &[Pubkey],
+) -> Instruction {
+    let mut data = Vec::with_capacity(10 + callers.len() * 32);
+    data.extend_from_slice(SET_CPI_CALLERS_SELECTOR);
+    data.push(enabled as u8);
+    data.push(callers.len() as u8);
+    for caller in callers {
+        data.extend_from_slice(caller.as_ref());
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置按路由覆盖的费率，overrides 为 (路由选择器, rate_pips)，整体替换原有覆盖
+pub fn set_route_fees_ix(
+    program_id: &Pubkey,
+    config: &Pubkey,
+    admin: &Pubkey,
+    overrides: &[([u8; 8], u32)],
+) -> Instruction {
+    let mut data = Vec::with_capacity(9 + overrides.len() * 12);
+    data.extend_from_slice(SET_ROUTE_FEES_SELECTOR);
+    data.push(overrides.len() as u8);
+    for (selector, rate_pips) in overrides {
+        data.extend_from_slice(selector);
+        data.extend_from_slice(&rate_pips.to_le_bytes());
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员同时设置推荐人分成上限与回购分成比例（基点），两者之和不超过 10_000
+pub fn set_shares_ix(
+    program_id: &Pubkey,
+    config: &Pubkey,
+    admin: &Pubkey,
+    referral_share_bps: u16,
+    buyback_share_bps: u16,
+) -> Instruction {
+    let mut data = Vec::with_capacity(12);
+    data.extend_from_slice(SET_SHARES_SELECTOR);
+    data.extend_from_slice(&referral_share_bps.to_le_bytes());
+    data.extend_from_slice(&buyback_share_bps.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置会员代币与折扣比例（基点，10_000 为全免）
+pub fn set_membership_ix(
+    program_id: &Pubkey,
+    config: &Pubkey,
+    admin: &Pubkey,
+    membership_mint: &Pubkey,
+    discount_bps: u16,
+) -> Instruction {
+    let mut data = Vec::with_capacity(42);
+    data.extend_from_slice(SET_MEMBERSHIP_SELECTOR);
+    data.extend_from_slice(membership_mint.as_ref());
+    data.extend_from_slice(&discount_bps.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置转发指令数据长度上限，0 表示使用默认值
+pub fn set_max_inner_data_len_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, max_len: u32) -> Instruction {
+    let mut data = Vec::with_capacity(12);
+    data.extend_from_slice(SET_MAX_INNER_DATA_SELECTOR);
+    data.extend_from_slice(&max_len.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 管理员设置多签名单与门限，开启多签后需用 with_multisig_signers 附加其他管理员签名
+pub fn set_multisig_ix(
+    program_id: &Pubkey,
+    config: &Pubkey,
+    admin: &Pubkey,
+    threshold: u8,
+    admins: &[Pubkey],
+) -> Instruction {
+    let mut data = Vec::with_capacity(10 + admins.len() * 32);
+    data.extend_from_slice(SET_MULTISIG_SELECTOR);
+    data.push(threshold);
+    data.push(admins.len() as u8);
+    for admin in admins {
+        data.extend_from_slice(admin.as_ref());
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data,
+    }
+}
+
+// 为高风险管理指令（修改费率、提取托管及其上限、回收账户、修改多签、暂停路由、设置备用手续费钱包）追加多签管理员签名账户
+pub fn with_multisig_signers(mut ix: Instruction, signers: &[Pubkey]) -> Instruction {
+    ix.accounts
+        .extend(signers.iter().map(|signer| AccountMeta::new_readonly(*signer, true)));
+    ix
+}
+
+// 为带手续费路由的指令追加交易者的会员代币关联账户，以享受会员折扣
+pub fn with_membership_account(
+    mut ix: Instruction,
+    trader: &Pubkey,
+    membership_mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    let address = associated_token_address(trader, membership_mint, token_program);
+    ix.accounts.push(AccountMeta::new_readonly(address, false));
+    ix
+}
+
+// 为未带推荐码的带手续费路由指令追加配置的默认推荐人钱包，缺少时该笔分成归协议
+pub fn with_default_referrer(mut ix: Instruction, default_referrer: &Pubkey) -> Instruction {
+    ix.accounts.push(AccountMeta::new(*default_referrer, false));
+    ix
+}
+
+// 为带手续费路由的指令追加 Instructions sysvar（开启 require_memo 或 CPI 白名单时需要）
+pub fn with_instructions_sysvar(mut ix: Instruction) -> Instruction {
+    ix.accounts.push(AccountMeta::new_readonly(sysvar::instructions::ID, false));
+    ix
+}
+
+// 为带手续费路由的指令追加支付者的交易计数 PDA，以使用新钱包免费额度（开启交易冷却时必须附带）
+pub fn with_wallet_counter(program_id: &Pubkey, mut ix: Instruction, payer: &Pubkey) -> Instruction {
+    ix.accounts.push(AccountMeta::new(wallet_address(program_id, payer).0, false));
+    ix
+}
+
+// 查询版本与费率上限，结果通过 return data 返回
+pub fn version_ix(program_id: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![],
+        data: VERSION_SELECTOR.to_vec(),
+    }
+}
+
+// 读取完整配置，return data 可用 TradeFeeState::unpack 解码
+pub fn get_config_ix(program_id: &Pubkey, config: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new_readonly(*config, false)],
+        data: GET_CONFIG_SELECTOR.to_vec(),
+    }
+}
+
+// 管理员读取并清零 SOL 手续费累计，return data 为清零前的总额（u64 小端）
+pub fn read_and_reset_fees_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        data: READ_AND_RESET_FEES_SELECTOR.to_vec(),
+    }
+}
+
+// 按给定费率预估手续费，不需要任何账户，结果见 FeePreview
+pub fn fee_preview_ix(program_id: &Pubkey, amount: u64, rate_bps: u16) -> Instruction {
+    let mut data = Vec::with_capacity(18);
+    data.extend_from_slice(FEE_PREVIEW_SELECTOR);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&rate_bps.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![],
+        data,
+    }
+}
+
+// 把构造好的带手续费路由指令包装为 explain，模拟交易后从 return data 解码 RoutePlan
+pub fn explain_ix(route_ix: &Instruction) -> Instruction {
+    let mut data = Vec::with_capacity(8 + route_ix.data.len());
+    data.extend_from_slice(EXPLAIN_SELECTOR);
+    data.extend_from_slice(&route_ix.data);
+
+    Instruction {
+        program_id: route_ix.program_id,
+        accounts: route_ix.accounts.clone(),
+        data,
+    }
+}
+
+// 为带手续费路由的指令附加推荐码，推荐码 PDA 和推荐人钱包追加在账户末尾
+pub fn with_referral(program_id: &Pubkey, ix: Instruction, code: u32, referrer: &Pubkey) -> Instruction {
+    let mut data = Vec::with_capacity(12 + ix.data.len());
+    data.extend_from_slice(REFERRAL_SWAP_SELECTOR);
+    data.extend_from_slice(&code.to_le_bytes());
+    data.extend_from_slice(&ix.data);
+
+    let mut accounts = ix.accounts;
+    accounts.push(AccountMeta::new_readonly(referral_address(program_id, code).0, false));
+    accounts.push(AccountMeta::new(*referrer, false));
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
+
+// 把带手续费路由的指令改为中继交易：ix 的支付者为中继者，user 需对 relay_message 的结果做 ed25519 签名，
+// 并在同一交易中放入对应的 ed25519 验签指令
+pub fn with_relayed_user(program_id: &Pubkey, ix: Instruction, user: &Pubkey, nonce: u64) -> Instruction {
+    let mut data = Vec::with_capacity(48 + ix.data.len());
+    data.extend_from_slice(RELAYED_SWAP_SELECTOR);
+    data.extend_from_slice(user.as_ref());
+    data.extend_from_slice(&nonce.to_le_bytes());
+    data.extend_from_slice(&ix.data);
+
+    let mut accounts = ix.accounts;
+    accounts.push(AccountMeta::new_readonly(sysvar::instructions::ID, false));
+    accounts.push(AccountMeta::new(nonce_address(program_id, user).0, false));
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
+
+// 中继交易中用户需签名的消息，ix 为包装前的带手续费路由指令
+pub fn relay_message(program_id: &Pubkey, ix: &Instruction, nonce: u64) -> Vec<u8> {
+    relayed_message(program_id, nonce, &ix.data)
+}
+
+// 为带手续费路由的指令附加防重放 nonce，payer 的 nonce PDA 追加在账户末尾
+pub fn with_nonce(program_id: &Pubkey, ix: Instruction, payer: &Pubkey, nonce: u64) -> Instruction {
+    let mut data = Vec::with_capacity(16 + ix.data.len());
+    data.extend_from_slice(NONCE_SWAP_SELECTOR);
+    data.extend_from_slice(&nonce.to_le_bytes());
+    data.extend_from_slice(&ix.data);
+
+    let mut accounts = ix.accounts;
+    accounts.push(AccountMeta::new(nonce_address(program_id, payer).0, false));
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
+
+// 为带手续费的买入指令附加价格影响保护，实际收到数量低于 expected_out 超过 max_impact_bps 时交易失败
+pub fn with_price_impact(ix: Instruction, expected_out: u64, max_impact_bps: u16) -> Instruction {
+    let mut data = Vec::with_capacity(18 + ix.data.len());
+    data.extend_from_slice(IMPACT_SWAP_SELECTOR);
+    data.extend_from_slice(&expected_out.to_le_bytes());
+    data.extend_from_slice(&max_impact_bps.to_le_bytes());
+    data.extend_from_slice(&ix.data);
+
+    Instruction {
+        program_id: ix.program_id,
+        accounts: ix.accounts,
+        data,
+    }
+}
+
+// 为带手续费的路由指令附加最小到账校验：兑换后 destination（交易者自己的代币账户）的余额增量低于 min_tokens 时交易失败，
+// destination 不在指令账户中时追加在末尾
+pub fn with_min_tokens(mut ix: Instruction, destination: &Pubkey, min_tokens: u64) -> Instruction {
+    let mut data = Vec::with_capacity(48 + ix.data.len());
+    data.extend_from_slice(MIN_TOKENS_SWAP_SELECTOR);
+    data.extend_from_slice(destination.as_ref());
+    data.extend_from_slice(&min_tokens.to_le_bytes());
+    data.extend_from_slice(&ix.data);
+
+    if !ix.accounts.iter().any(|meta| &meta.pubkey == destination) {
+        ix.accounts.push(AccountMeta::new_readonly(*destination, false));
+    }
+    Instruction {
+        program_id: ix.program_id,
+        accounts: ix.accounts,
+        data,
+    }
+}
+
+fn referral_args_data(selector: &[u8; 8], code: u32, wallet: &Pubkey, share_bps: u16) -> Vec<u8> {
+    let mut data = Vec::with_capacity(46);
+    data.extend_from_slice(selector);
+    data.extend_from_slice(&code.to_le_bytes());
+    data.extend_from_slice(wallet.as_ref());
+    data.extend_from_slice(&share_bps.to_le_bytes());
+    data
+}
+
+// 管理员登记推荐码，管理员同时支付 PDA 租金
+pub fn register_referral_ix(
+    program_id: &Pubkey,
+    config: &Pubkey,
+    admin: &Pubkey,
+    code: u32,
+    wallet: &Pubkey,
+    share_bps: u16,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*config, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(referral_address(program_id, code).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: referral_args_data(REGISTER_REFERRAL_SELECTOR, code, wallet, share_bps),
+    }
+}
+
+// 管理员更新推荐码的钱包和分成比例
+pub fn update_referral_ix(
+    program_id: &Pubkey,
+    config: &Pubkey,
+    admin: &Pubkey,
+    code: u32,
+    wallet: &Pubkey,
+    share_bps: u16,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(referral_address(program_id, code).0, false),
+        ],
+        data: referral_args_data(UPDATE_REFERRAL_SELECTOR, code, wallet, share_bps),
+    }
+}
+
+// 管理员撤销推荐码
+pub fn revoke_referral_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, code: u32) -> Instruction {
+    let mut data = Vec::with_capacity(12);
+    data.extend_from_slice(REVOKE_REFERRAL_SELECTOR);
+    data.extend_from_slice(&code.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(referral_address(program_id, code).0, false),
+        ],
+        data,
+    }
+}
+
+// 代付方为交易发起人预存 lamports 并增加手续费代付额度，首次授权时同时支付 PDA 租金
+pub fn approve_allowance_ix(program_id: &Pubkey, sponsor: &Pubkey, delegate: &Pubkey, lamports: u64) -> Instruction {
+    let mut data = Vec::with_capacity(48);
+    data.extend_from_slice(APPROVE_ALLOWANCE_SELECTOR);
+    data.extend_from_slice(delegate.as_ref());
+    data.extend_from_slice(&lamports.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*sponsor, true),
+            AccountMeta::new(allowance_address(program_id, sponsor, delegate).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+// 代付方撤销额度，剩余额度与 PDA 租金退还给代付方
+pub fn revoke_allowance_ix(program_id: &Pubkey, sponsor: &Pubkey, delegate: &Pubkey) -> Instruction {
+    let mut data = Vec::with_capacity(40);
+    data.extend_from_slice(REVOKE_ALLOWANCE_SELECTOR);
+    data.extend_from_slice(delegate.as_ref());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*sponsor, true),
+            AccountMeta::new(allowance_address(program_id, sponsor, delegate).0, false),
+        ],
+        data,
+    }
+}
+
+// 把带手续费路由的指令改为代付交易：FeeAccounts.payer 填代付方，这里取消其签名并追加额度 PDA，
+// 交易发起人（delegate）需签名并垫付 SOL 手续费，兑换后从额度中报销
+pub fn with_allowance(program_id: &Pubkey, mut ix: Instruction, delegate: &Pubkey) -> Instruction {
+    let sponsor = ix.accounts[2].pubkey;
+    ix.accounts[2] = AccountMeta::new_readonly(sponsor, false);
+    ix.accounts.push(AccountMeta::new(allowance_address(program_id, &sponsor, delegate).0, false));
+    ix
+}
+
+// 管理员设置单个代币的费率，首次设置时由管理员支付 PDA 租金
+pub fn set_mint_fee_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, mint: &Pubkey, rate_pips: u32) -> Instruction {
+    let mut data = Vec::with_capacity(44);
+    data.extend_from_slice(SET_MINT_FEE_SELECTOR);
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(&rate_pips.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*config, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(mint_fee_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+// 管理员清除单个代币的费率，PDA 租金退还给管理员
+pub fn clear_mint_fee_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, mint: &Pubkey) -> Instruction {
+    let mut data = Vec::with_capacity(40);
+    data.extend_from_slice(CLEAR_MINT_FEE_SELECTOR);
+    data.extend_from_slice(mint.as_ref());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*config, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(mint_fee_address(program_id, mint).0, false),
+        ],
+        data,
+    }
+}
+
+// 为带手续费路由的指令追加交易代币的费率 PDA，以使用该代币的专属费率
+pub fn with_mint_fee(program_id: &Pubkey, mut ix: Instruction, mint: &Pubkey) -> Instruction {
+    ix.accounts.push(AccountMeta::new_readonly(mint_fee_address(program_id, mint).0, false));
+    ix
+}
+
+// 管理员登记免手续费的合作方代币，首次登记时由管理员支付 PDA 租金；开启多签时需用 with_multisig_signers 追加签名
+pub fn add_fee_exempt_mint_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, mint: &Pubkey) -> Instruction {
+    let mut data = Vec::with_capacity(40);
+    data.extend_from_slice(ADD_FEE_EXEMPT_MINT_SELECTOR);
+    data.extend_from_slice(mint.as_ref());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*config, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(fee_exempt_mint_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+// 管理员移除免手续费代币，PDA 租金退还给管理员
+pub fn remove_fee_exempt_mint_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, mint: &Pubkey) -> Instruction {
+    let mut data = Vec::with_capacity(40);
+    data.extend_from_slice(REMOVE_FEE_EXEMPT_MINT_SELECTOR);
+    data.extend_from_slice(mint.as_ref());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*config, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(fee_exempt_mint_address(program_id, mint).0, false),
+        ],
+        data,
+    }
+}
+
+// 为带手续费路由的指令追加交易代币的免手续费登记 PDA
+pub fn with_fee_exempt_mint(program_id: &Pubkey, mut ix: Instruction, mint: &Pubkey) -> Instruction {
+    ix.accounts.push(AccountMeta::new_readonly(fee_exempt_mint_address(program_id, mint).0, false));
+    ix
+}
+
+// 管理员设置代币手续费下限（百万分之一个代币），0 表示关闭；首次设置时由管理员支付 PDA 租金
+pub fn set_token_fee_floor_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, min_fee_micros: u64) -> Instruction {
+    let mut data = Vec::with_capacity(16);
+    data.extend_from_slice(SET_TOKEN_FEE_FLOOR_SELECTOR);
+    data.extend_from_slice(&min_fee_micros.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(token_fee_floor_address(program_id).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+// 开启代币手续费下限后，为以代币收费的交易追加下限 PDA
+pub fn with_token_fee_floor(program_id: &Pubkey, mut ix: Instruction) -> Instruction {
+    ix.accounts.push(AccountMeta::new_readonly(token_fee_floor_address(program_id).0, false));
+    ix
+}
+
+// 管理员为路由登记额外接受的目标程序及版本，签名者须同时是程序升级权限，首次登记时由管理员支付 PDA 租金
+// 开启多签时需用 with_multisig_signers 追加多签管理员
+pub fn add_route_program_ix(
+    program_id: &Pubkey,
+    config: &Pubkey,
+    admin: &Pubkey,
+    selector: &[u8; 8],
+    program: &Pubkey,
+    version: u16,
+) -> Instruction {
+    let mut data = Vec::with_capacity(50);
+    data.extend_from_slice(ADD_ROUTE_PROGRAM_SELECTOR);
+    data.extend_from_slice(selector);
+    data.extend_from_slice(program.as_ref());
+    data.extend_from_slice(&version.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*config, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(route_program_address(program_id, selector, program).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(program_data_address(program_id), false),
+        ],
+        data,
+    }
+}
+
+// 管理员移除路由登记的目标程序，PDA 租金退还给管理员
+pub fn remove_route_program_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, selector: &[u8; 8], program: &Pubkey) -> Instruction {
+    let mut data = Vec::with_capacity(48);
+    data.extend_from_slice(REMOVE_ROUTE_PROGRAM_SELECTOR);
+    data.extend_from_slice(selector);
+    data.extend_from_slice(program.as_ref());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*config, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(route_program_address(program_id, selector, program).0, false),
+        ],
+        data,
+    }
+}
+
+// 管理员开启共享租金账户并支付其免租金额，之后可用 fund_rent_funder_ix 充值
+pub fn open_rent_funder_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*config, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(rent_funder_address(program_id).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: OPEN_RENT_FUNDER_SELECTOR.to_vec(),
+    }
+}
+
+// 管理员关闭共享租金账户，剩余 lamports 退还给管理员
+pub fn close_rent_funder_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*config, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(rent_funder_address(program_id).0, false),
+        ],
+        data: CLOSE_RENT_FUNDER_SELECTOR.to_vec(),
+    }
+}
+
+// 向共享租金账户充值，直接通过系统程序转账，不经过本程序
+pub fn fund_rent_funder_ix(program_id: &Pubkey, from: &Pubkey, lamports: u64) -> Instruction {
+    system_instruction::transfer(from, &rent_funder_address(program_id).0, lamports)
+}
+
+// 为带手续费路由的指令追加共享租金账户，交易中按需创建的钱包、nonce、epoch 汇总 PDA 改由其垫付租金，
+// 交易结束前从本笔手续费中归还，手续费不足的部分由支付者补齐
+pub fn with_rent_funder(program_id: &Pubkey, mut ix: Instruction) -> Instruction {
+    ix.accounts.push(AccountMeta::new(rent_funder_address(program_id).0, false));
+    ix
+}
+
+// 为带手续费路由的指令追加目标程序的登记 PDA，以调用登记的其他程序版本（转发账户中的程序地址需一并替换）
+pub fn with_route_program(program_id: &Pubkey, mut ix: Instruction, selector: &[u8; 8], program: &Pubkey) -> Instruction {
+    ix.accounts.push(AccountMeta::new_readonly(route_program_address(program_id, selector, program).0, false));
+    ix
+}
+
+// 管理员创建协议收入托管 PDA，每个 epoch 最多释放 release_per_epoch lamports
+pub fn init_escrow_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, release_per_epoch: u64) -> Instruction {
+    let mut data = Vec::with_capacity(16);
+    data.extend_from_slice(INIT_ESCROW_SELECTOR);
+    data.extend_from_slice(&release_per_epoch.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*config, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(escrow_address(program_id).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+// 管理员提取已到期的托管收入，转入协议费钱包；管理员需同时是程序升级权限
+// amount 为 0 时提取全部可释放金额
+pub fn withdraw_escrow_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, amount: u64) -> Instruction {
+    let mut data = Vec::with_capacity(16);
+    data.extend_from_slice(WITHDRAW_ESCROW_SELECTOR);
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*config, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(escrow_address(program_id).0, false),
+            AccountMeta::new_readonly(program_data_address(program_id), false),
+        ],
+        data,
+    }
+}
+
+// 管理员回收遗留的本程序账户，lamports 转入协议费钱包；管理员需同时是程序升级权限，遗留账户的密钥对需签名
+pub fn sweep_orphan_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, orphan: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*config, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(*orphan, true),
+            AccountMeta::new_readonly(program_data_address(program_id), false),
+        ],
+        data: SWEEP_ORPHAN_SELECTOR.to_vec(),
+    }
+}
+
+// 管理员设置托管单次提取上限；管理员需同时是程序升级权限
+pub fn set_max_withdraw_ix(program_id: &Pubkey, config: &Pubkey, admin: &Pubkey, max_withdraw_per_tx: u64) -> Instruction {
+    let mut data = Vec::with_capacity(16);
+    data.extend_from_slice(SET_MAX_WITHDRAW_SELECTOR);
+    data.extend_from_slice(&max_withdraw_per_tx.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*config, false),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(program_data_address(program_id), false),
+        ],
+        data,
+    }
+}
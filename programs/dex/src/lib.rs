@@ -3,11 +3,17 @@
 use solana_program::{
     account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, pubkey::Pubkey,
 };
-use std::sync::LazyLock;
 
+pub mod error;
+pub mod events;
 pub mod instructions;
+#[cfg(feature = "client")]
+pub mod ix_builder;
+pub mod oracle;
 pub mod processor;
 pub mod state;
+pub mod token;
+pub mod utils;
 
 entrypoint!(process_instruction);
 
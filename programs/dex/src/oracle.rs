@@ -0,0 +1,90 @@
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, msg, program_error::ProgramError, pubkey,
+    pubkey::Pubkey,
+};
+
+use crate::error::MyError;
+
+pub const PYTH_PROGRAM_ID: Pubkey = pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+
+// Pyth 价格账户布局（legacy v2）中用到的字段偏移
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+const EXPO_OFFSET: usize = 20;
+const TIMESTAMP_OFFSET: usize = 96;
+const AGG_PRICE_OFFSET: usize = 208;
+const AGG_STATUS_OFFSET: usize = 224;
+const AGG_PUB_SLOT_OFFSET: usize = 232;
+const PRICE_ACCOUNT_MIN_LEN: usize = 240;
+// agg.status = 1 表示正常报价
+const STATUS_TRADING: u32 = 1;
+
+const LAMPORTS_PER_SOL: u128 = 1_000_000_000;
+const USD_MICROS: u128 = 1_000_000;
+
+// SOL/USD 报价：price * 10^expo 美元
+#[derive(Debug, Clone, Copy)]
+pub struct OraclePrice {
+    pub price: i64,
+    pub expo: i32,
+    pub timestamp: i64,
+    pub pub_slot: u64,
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+// 读取 Pyth 聚合价格，报价超过 max_age_secs 未更新、发布 slot 早于 max_age_slots（非 0 时）或状态异常时拒绝
+pub fn read_pyth_price(
+    account: &AccountInfo,
+    clock: &Clock,
+    max_age_secs: u32,
+    max_age_slots: u64,
+) -> Result<OraclePrice, ProgramError> {
+    if account.owner != &PYTH_PROGRAM_ID {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let data = account.try_borrow_data()?;
+    if data.len() < PRICE_ACCOUNT_MIN_LEN || read_u32(&data, 0) != PYTH_MAGIC {
+        return Err(MyError::InvalidOraclePrice.into());
+    }
+
+    let price = OraclePrice {
+        price: read_i64(&data, AGG_PRICE_OFFSET),
+        expo: read_u32(&data, EXPO_OFFSET) as i32,
+        timestamp: read_i64(&data, TIMESTAMP_OFFSET),
+        pub_slot: read_i64(&data, AGG_PUB_SLOT_OFFSET) as u64,
+    };
+    if price.price <= 0 || read_u32(&data, AGG_STATUS_OFFSET) != STATUS_TRADING {
+        return Err(MyError::InvalidOraclePrice.into());
+    }
+    if clock.unix_timestamp.saturating_sub(price.timestamp) > max_age_secs as i64 {
+        msg!("预言机报价已过期: {}，当前 {}", price.timestamp, clock.unix_timestamp);
+        return Err(MyError::StaleOraclePrice.into());
+    }
+    if max_age_slots != 0 && clock.slot.saturating_sub(price.pub_slot) > max_age_slots {
+        msg!("预言机报价发布于 slot {}，当前 {}，超过最大时长 {}", price.pub_slot, clock.slot, max_age_slots);
+        return Err(MyError::StaleOraclePrice.into());
+    }
+    Ok(price)
+}
+
+// 把以百万分之一美元计的金额按 SOL/USD 报价换算为 lamports
+pub fn usd_micros_to_lamports(usd_micros: u64, price: &OraclePrice) -> Result<u64, ProgramError> {
+    let scale = 10u128
+        .checked_pow(price.expo.unsigned_abs())
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    let usd = usd_micros as u128 * LAMPORTS_PER_SOL;
+    let (numerator, denominator) = if price.expo < 0 {
+        (usd.checked_mul(scale), price.price as u128 * USD_MICROS)
+    } else {
+        (Some(usd), price.price as u128 * scale * USD_MICROS)
+    };
+
+    let lamports = numerator.ok_or(ProgramError::ArithmeticOverflow)? / denominator;
+    u64::try_from(lamports).map_err(|_| ProgramError::ArithmeticOverflow)
+}
@@ -1,75 +1,393 @@
 use solana_program::{
     account_info::AccountInfo, 
     entrypoint::ProgramResult, 
+    msg,
+    program::{set_return_data, MAX_RETURN_DATA},
     program_error::ProgramError,
     pubkey::Pubkey,
 };
 
-use borsh::{BorshDeserialize,BorshSerialize};
+use borsh::BorshDeserialize;
 
+use crate::instructions::allowance::{
+    process_approve_allowance, process_revoke_allowance, APPROVE_ALLOWANCE_SELECTOR, REVOKE_ALLOWANCE_SELECTOR,
+};
 use crate::instructions::ata::{process_create_associated_token_account, ATA_SELECTOR};
+use crate::instructions::escrow::{
+    is_escrow_account, process_init_escrow, process_withdraw_escrow, INIT_ESCROW_SELECTOR, WITHDRAW_ESCROW_SELECTOR,
+};
+use crate::instructions::fee::{
+    find_fee_route, process_explain, process_fee_preview, EXPLAIN_SELECTOR, FEE_PREVIEW_SELECTOR,
+};
+use crate::instructions::impact::{
+    process_impact_swap, process_min_tokens_swap, IMPACT_SWAP_SELECTOR, MIN_TOKENS_SWAP_SELECTOR,
+};
+use crate::instructions::fee_exempt::{
+    process_add_fee_exempt_mint, process_remove_fee_exempt_mint, ADD_FEE_EXEMPT_MINT_SELECTOR, REMOVE_FEE_EXEMPT_MINT_SELECTOR,
+};
+use crate::instructions::mint_fee::{
+    process_clear_mint_fee, process_set_mint_fee, CLEAR_MINT_FEE_SELECTOR, SET_MINT_FEE_SELECTOR,
+};
+use crate::instructions::nonce::{
+    process_nonce_swap, MAX_NONCE_RING_SIZE, NONCE_SWAP_SELECTOR,
+};
+use crate::instructions::meteora::{
+    process_damm_v2_buy, process_damm_v2_sell, DAMM_V2_BUY_SELECTOR, DAMM_V2_SELL_SELECTOR,
+};
+use crate::instructions::openbook::{process_buy_take_profit, process_openbook_buy, BUY_TAKE_PROFIT_SELECTOR, OPENBOOK_BUY_SELECTOR};
 use crate::instructions::pump::{
-    process_pump_amm_buy, process_pump_amm_sell, process_pump_buy, process_pump_sell,
-    PUMP_AMM_SELL_SELECTOR, PUMP_AMM_SELECTOR, PUMP_SELL_SELECTOR, PUMP_SELECTOR,
+    process_pump_amm_buy, process_pump_amm_sell, process_pump_buy, process_pump_buy_with_ata,
+    process_pump_buy_with_slippage, process_pump_sell, PUMP_AMM_SELL_SELECTOR, PUMP_AMM_SELECTOR,
+    PUMP_BUY_SLIPPAGE_SELECTOR, PUMP_BUY_WITH_ATA_SELECTOR, PUMP_SELL_SELECTOR, PUMP_SELECTOR,
+};
+use crate::instructions::raydium::{
+    process_raydium_buy, process_raydium_buy_base_out, process_raydium_clmm_buy_exact_out,
+    process_raydium_sell, RAYDIUM_BUY_BASE_OUT_SELECTOR, RAYDIUM_BUY_SELECTOR,
+    RAYDIUM_CLMM_BUY_EXACT_OUT_SELECTOR, RAYDIUM_SELL_SELECTOR,
+};
+use crate::instructions::referral::{
+    process_referral_swap, process_register_referral, process_revoke_referral,
+    process_update_referral, REFERRAL_SWAP_SELECTOR, REGISTER_REFERRAL_SELECTOR,
+    REVOKE_REFERRAL_SELECTOR, UPDATE_REFERRAL_SELECTOR,
+};
+use crate::instructions::relay::{process_relayed_swap, RELAYED_SWAP_SELECTOR};
+use crate::instructions::rent_funder::{
+    process_close_rent_funder, process_open_rent_funder, CLOSE_RENT_FUNDER_SELECTOR, OPEN_RENT_FUNDER_SELECTOR,
+};
+use crate::instructions::route_program::{
+    process_add_route_program, process_remove_route_program, ADD_ROUTE_PROGRAM_SELECTOR, REMOVE_ROUTE_PROGRAM_SELECTOR,
 };
-use crate::instructions::raydium::{process_raydium_buy, process_raydium_sell, RAYDIUM_BUY_SELECTOR, RAYDIUM_SELL_SELECTOR};
 use crate::instructions::slot::{process_expired_slot, EXPIRED_SLOT_SELECTOR};
-use crate::state::TradeFeeState;
+use crate::instructions::token_fee_floor::{process_set_token_fee_floor, SET_TOKEN_FEE_FLOOR_SELECTOR};
+use crate::instructions::version::{process_version, VERSION_SELECTOR};
+use crate::error::MyError;
+use crate::events::{process_event_cpi, ProtocolPauseChanged, RoutePauseChanged, EVENT_IX_TAG_LE};
+use crate::utils::{check_upgrade_authority, create_pda_account, grow_account};
+use crate::state::{
+    ConfigUpdate, FeeFailurePolicy, FeeSides, FeeTiming, RouteFeeOverride, TradeFeeState, BPS_DENOMINATOR, MAX_CPI_CALLERS, MAX_FEE_RATE_PIPS,
+    MAX_BLOCKED_MINTS, MAX_MULTISIG_ADMINS, MAX_PAUSED_ROUTES, MAX_ROUTE_FEE_OVERRIDES, PREFLIGHT_FROZEN_DESTINATION, PREFLIGHT_PAYER_BALANCE,
+};
+
+type SelectorHandler = fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult;
+
+// 由 CREATE_CONFIG 创建的配置 PDA 种子为 [b"config"]
+pub const CONFIG_SEED: &[u8] = b"config";
+
+pub fn config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], program_id)
+}
 
-type SelectorHandler = fn(&[AccountInfo], &[u8]) -> ProgramResult;
+// 客户端传入 bump 时只用 create_program_address 计算一次，省去 find_program_address 逐个尝试 bump 的开销；
+// 未传入时回退到 find_program_address。bump 无法得到合法 PDA 时返回 InvalidSeeds
+pub fn config_address_with_bump(program_id: &Pubkey, bump: Option<u8>) -> Result<(Pubkey, u8), ProgramError> {
+    match bump {
+        Some(bump) => Pubkey::create_program_address(&[CONFIG_SEED, &[bump]], program_id)
+            .map(|address| (address, bump))
+            .map_err(|_| ProgramError::InvalidSeeds),
+        None => Ok(config_address(program_id)),
+    }
+}
 
 // 添加设置协议费钱包的选择器
-const SET_PROTOCOL_FEE_WALLET_SELECTOR: &[u8; 8] = b"set_fee\0";
+pub const SET_PROTOCOL_FEE_WALLET_SELECTOR: &[u8; 8] = b"set_fee\0";
+// 设置协议费率（pips）的选择器
+pub const SET_FEE_RATE_SELECTOR: &[u8; 8] = b"set_rate";
+// 设置新钱包免手续费交易笔数的选择器
+pub const SET_FREE_TRADES_SELECTOR: &[u8; 8] = b"set_free";
+// 设置美元定额手续费及预言机的选择器
+pub const SET_USD_FEE_SELECTOR: &[u8; 8] = b"set_usd\0";
+// 设置未知选择器处理模式（严格/宽松）的选择器
+pub const SET_LENIENT_SELECTOR: &[u8; 8] = b"set_lnt\0";
+// 设置交易是否必须附带 Memo 指令的选择器
+pub const SET_REQUIRE_MEMO_SELECTOR: &[u8; 8] = b"set_memo";
+// 设置回购钱包与分成比例的选择器
+pub const SET_BUYBACK_SELECTOR: &[u8; 8] = b"set_bbk\0";
+// 设置 nonce 防重放窗口与缓冲区大小的选择器
+pub const SET_REPLAY_WINDOW_SELECTOR: &[u8; 8] = b"set_rply";
+// 设置卖出收费时机的选择器
+pub const SET_FEE_TIMING_SELECTOR: &[u8; 8] = b"set_timg";
+// 设置 CPI 调用方白名单的选择器
+pub const SET_CPI_CALLERS_SELECTOR: &[u8; 8] = b"set_cpi\0";
+// 设置按路由覆盖费率的选择器
+pub const SET_ROUTE_FEES_SELECTOR: &[u8; 8] = b"set_rfee";
+// 同时设置推荐人分成上限与回购分成比例的选择器
+pub const SET_SHARES_SELECTOR: &[u8; 8] = b"set_shr\0";
+// 只读查询完整配置的选择器
+pub const GET_CONFIG_SELECTOR: &[u8; 8] = b"get_cfg\0";
+// 设置会员代币与折扣比例的选择器
+pub const SET_MEMBERSHIP_SELECTOR: &[u8; 8] = b"set_mbr\0";
+// 设置转发数据长度上限的选择器
+pub const SET_MAX_INNER_DATA_SELECTOR: &[u8; 8] = b"set_dlen";
+// 设置多签管理员与门限的选择器
+pub const SET_MULTISIG_SELECTOR: &[u8; 8] = b"set_msig";
+// 开关 Anchor event CPI 的选择器
+pub const SET_EVENT_CPI_SELECTOR: &[u8; 8] = b"set_evcp";
+// 设置钱包交易冷却的选择器
+pub const SET_COOLDOWN_SELECTOR: &[u8; 8] = b"set_cool";
+// 设置买入收费时机的选择器
+pub const SET_BUY_FEE_TIMING_SELECTOR: &[u8; 8] = b"set_btmg";
+// 暂停或恢复单个路由的选择器
+pub const PAUSE_ROUTE_SELECTOR: &[u8; 8] = b"pause_rt";
+// 设置手续费返还比例的选择器
+pub const SET_REBATE_SELECTOR: &[u8; 8] = b"set_rbt\0";
+// 设置大额交易返还的选择器
+pub const SET_WHALE_REBATE_SELECTOR: &[u8; 8] = b"set_whal";
+// 设置托管单次提取上限的选择器
+pub const SET_MAX_WITHDRAW_SELECTOR: &[u8; 8] = b"set_mxwd";
+// 设置以 quote 代币收费的 mint 的选择器
+pub const SET_QUOTE_FEE_MINT_SELECTOR: &[u8; 8] = b"set_qfee";
+// 全局暂停或恢复的选择器
+pub const SET_PAUSED_SELECTOR: &[u8; 8] = b"pause\0\0\0";
+// 设置守护者的选择器
+pub const SET_GUARDIAN_SELECTOR: &[u8; 8] = b"set_grdn";
+// 开关按 epoch 汇总手续费的选择器
+pub const SET_EPOCH_ROLLUP_SELECTOR: &[u8; 8] = b"set_rlup";
+// 加入或移出禁止列表的选择器
+pub const BLOCK_MINT_SELECTOR: &[u8; 8] = b"blk_mint";
+// 回收遗留的本程序账户的选择器
+pub const SWEEP_ORPHAN_SELECTOR: &[u8; 8] = b"sweep_or";
+// 设置收费方向的选择器
+pub const SET_FEE_SIDES_SELECTOR: &[u8; 8] = b"set_side";
+// 设置默认推荐人的选择器
+pub const SET_DEFAULT_REFERRER_SELECTOR: &[u8; 8] = b"set_dref";
+// 开关精简收费事件的选择器
+pub const SET_COMPACT_EVENTS_SELECTOR: &[u8; 8] = b"set_cevt";
+// 设置手续费转账失败处理方式的选择器
+pub const SET_ON_FEE_FAILURE_SELECTOR: &[u8; 8] = b"set_fail";
+// 设置备用手续费钱包的选择器
+pub const SET_FEE_WALLET_BACKUP_SELECTOR: &[u8; 8] = b"set_bkup";
+// 读取并清零手续费累计的选择器
+pub const READ_AND_RESET_FEES_SELECTOR: &[u8; 8] = b"rst_fees";
+// 开关支付者余额预检的选择器
+pub const SET_BALANCE_CHECK_SELECTOR: &[u8; 8] = b"set_bchk";
+// 开关冻结账户预检的选择器
+pub const SET_FROZEN_CHECK_SELECTOR: &[u8; 8] = b"set_frzn";
+// 开关 CPI 调用免手续费的选择器
+pub const SET_CPI_FEE_WAIVER_SELECTOR: &[u8; 8] = b"set_cwav";
+// 设置推广免手续费 slot 区间的选择器
+pub const SET_PROMO_WINDOW_SELECTOR: &[u8; 8] = b"set_prmo";
+// 设置数据最大时长（slot）的选择器
+pub const SET_MAX_DATA_AGE_SELECTOR: &[u8; 8] = b"set_dage";
+// 设置闲置后首笔交易免手续费的选择器
+pub const SET_DORMANT_WAIVER_SELECTOR: &[u8; 8] = b"set_dorm";
+// 一次原子更新多个配置字段的选择器
+pub const UPDATE_CONFIG_SELECTOR: &[u8; 8] = b"upd_cfg\0";
+// 创建并初始化配置 PDA 的选择器
+pub const CREATE_CONFIG_SELECTOR: &[u8; 8] = b"mk_cfg\0\0";
+// 把旧版配置账户扩容到当前布局的选择器
+pub const MIGRATE_CONFIG_SELECTOR: &[u8; 8] = b"mig_cfg\0";
+
+// 完整配置需要能放进一次 return data
+const _: () = assert!(TradeFeeState::LEN <= MAX_RETURN_DATA);
 
-const SELECTORS: [(&[u8; 8], SelectorHandler); 9] = [  // 注意数组大小改为9
-    (PUMP_SELECTOR, |accounts, rest| {
-        process_pump_buy(accounts, rest)
+const SELECTORS: [(&[u8; 8], SelectorHandler); 85] = [
+    (PUMP_SELECTOR, |program_id, accounts, rest| {
+        process_pump_buy(program_id, accounts, rest)
     }),
-    (PUMP_AMM_SELECTOR, |accounts, rest: &[u8]| {
-        process_pump_amm_buy(accounts, rest)
+    (PUMP_AMM_SELECTOR, |program_id, accounts, rest: &[u8]| {
+        process_pump_amm_buy(program_id, accounts, rest)
     }),
-    (PUMP_SELL_SELECTOR, |accounts, rest| {
-        process_pump_sell(accounts, rest)
+    (PUMP_SELL_SELECTOR, |program_id, accounts, rest| {
+        process_pump_sell(program_id, accounts, rest)
     }),
-    (PUMP_AMM_SELL_SELECTOR, |accounts, rest| {
-        process_pump_amm_sell(accounts, rest)
+    (PUMP_AMM_SELL_SELECTOR, |program_id, accounts, rest| {
+        process_pump_amm_sell(program_id, accounts, rest)
     }),
-    (ATA_SELECTOR, |accounts, rest| {
+    (PUMP_BUY_WITH_ATA_SELECTOR, process_pump_buy_with_ata),
+    (PUMP_BUY_SLIPPAGE_SELECTOR, process_pump_buy_with_slippage),
+    (ATA_SELECTOR, |_, accounts, rest| {
         process_create_associated_token_account(accounts, rest)
     }),
-    (EXPIRED_SLOT_SELECTOR, |_, rest| process_expired_slot(rest)),
-    (RAYDIUM_BUY_SELECTOR, |accounts, rest| {
-        process_raydium_buy(accounts, rest)
-    }),
-    (RAYDIUM_SELL_SELECTOR, |accounts, rest| {
-        process_raydium_sell(accounts, rest)
-    }),
+    (EXPIRED_SLOT_SELECTOR, process_expired_slot),
+    (RAYDIUM_BUY_SELECTOR, process_raydium_buy),
+    (RAYDIUM_SELL_SELECTOR, process_raydium_sell),
+    (RAYDIUM_BUY_BASE_OUT_SELECTOR, process_raydium_buy_base_out),
+    (RAYDIUM_CLMM_BUY_EXACT_OUT_SELECTOR, process_raydium_clmm_buy_exact_out),
+    (OPENBOOK_BUY_SELECTOR, process_openbook_buy),
+    (BUY_TAKE_PROFIT_SELECTOR, process_buy_take_profit),
+    (DAMM_V2_BUY_SELECTOR, process_damm_v2_buy),
+    (DAMM_V2_SELL_SELECTOR, process_damm_v2_sell),
     // 添加设置协议费钱包的路由
-    (SET_PROTOCOL_FEE_WALLET_SELECTOR, |accounts, rest| {
-        set_protocol_fee_wallet(accounts, rest)
+    (SET_PROTOCOL_FEE_WALLET_SELECTOR, |program_id, accounts, rest| {
+        set_protocol_fee_wallet(program_id, accounts, rest)
     }),
+    (SET_FEE_RATE_SELECTOR, |program_id, accounts, rest| {
+        set_fee_rate(program_id, accounts, rest)
+    }),
+    (SET_FREE_TRADES_SELECTOR, set_free_trades),
+    (SET_USD_FEE_SELECTOR, set_usd_fee),
+    (SET_LENIENT_SELECTOR, set_lenient_selectors),
+    (SET_REQUIRE_MEMO_SELECTOR, set_require_memo),
+    (SET_BUYBACK_SELECTOR, set_buyback),
+    (SET_REPLAY_WINDOW_SELECTOR, set_replay_window),
+    (SET_FEE_TIMING_SELECTOR, set_fee_timing),
+    (SET_CPI_CALLERS_SELECTOR, set_cpi_callers),
+    (SET_ROUTE_FEES_SELECTOR, set_route_fees),
+    (SET_SHARES_SELECTOR, set_shares),
+    (SET_MEMBERSHIP_SELECTOR, set_membership),
+    (SET_MAX_INNER_DATA_SELECTOR, set_max_inner_data_len),
+    (SET_MULTISIG_SELECTOR, set_multisig),
+    (SET_EVENT_CPI_SELECTOR, set_event_cpi),
+    (SET_COOLDOWN_SELECTOR, set_trade_cooldown),
+    (SET_BUY_FEE_TIMING_SELECTOR, set_buy_fee_timing),
+    (PAUSE_ROUTE_SELECTOR, pause_route),
+    (SET_REBATE_SELECTOR, set_rebate),
+    (SET_WHALE_REBATE_SELECTOR, set_whale_rebate),
+    (SET_MAX_WITHDRAW_SELECTOR, set_max_withdraw),
+    (SET_QUOTE_FEE_MINT_SELECTOR, set_quote_fee_mint),
+    (SET_PAUSED_SELECTOR, set_paused),
+    (SET_GUARDIAN_SELECTOR, set_guardian),
+    (SET_EPOCH_ROLLUP_SELECTOR, set_epoch_rollup),
+    (BLOCK_MINT_SELECTOR, block_mint),
+    (SWEEP_ORPHAN_SELECTOR, sweep_orphan),
+    (SET_FEE_SIDES_SELECTOR, set_fee_sides),
+    (SET_DEFAULT_REFERRER_SELECTOR, set_default_referrer),
+    (SET_COMPACT_EVENTS_SELECTOR, set_compact_events),
+    (SET_ON_FEE_FAILURE_SELECTOR, set_on_fee_failure),
+    (SET_FEE_WALLET_BACKUP_SELECTOR, set_fee_wallet_backup),
+    (SET_BALANCE_CHECK_SELECTOR, set_balance_check),
+    (SET_FROZEN_CHECK_SELECTOR, set_frozen_destination_check),
+    (SET_CPI_FEE_WAIVER_SELECTOR, set_cpi_fee_waiver),
+    (CREATE_CONFIG_SELECTOR, create_config),
+    (UPDATE_CONFIG_SELECTOR, update_config),
+    (SET_PROMO_WINDOW_SELECTOR, set_promo_window),
+    (SET_MAX_DATA_AGE_SELECTOR, set_max_data_age),
+    (SET_DORMANT_WAIVER_SELECTOR, set_dormant_waiver),
+    (MIGRATE_CONFIG_SELECTOR, |program_id, accounts, _| migrate_config(program_id, accounts)),
+    (READ_AND_RESET_FEES_SELECTOR, |program_id, accounts, _| read_and_reset_fees(program_id, accounts)),
+    (VERSION_SELECTOR, |_, _, _| process_version()),
+    (EVENT_IX_TAG_LE, |program_id, accounts, _| process_event_cpi(program_id, accounts)),
+    (GET_CONFIG_SELECTOR, |program_id, accounts, _| get_config(program_id, accounts)),
+    (FEE_PREVIEW_SELECTOR, |_, _, rest| process_fee_preview(rest)),
+    (EXPLAIN_SELECTOR, process_explain),
+    (REFERRAL_SWAP_SELECTOR, process_referral_swap),
+    (RELAYED_SWAP_SELECTOR, process_relayed_swap),
+    (NONCE_SWAP_SELECTOR, process_nonce_swap),
+    (IMPACT_SWAP_SELECTOR, process_impact_swap),
+    (MIN_TOKENS_SWAP_SELECTOR, process_min_tokens_swap),
+    (REGISTER_REFERRAL_SELECTOR, process_register_referral),
+    (UPDATE_REFERRAL_SELECTOR, process_update_referral),
+    (REVOKE_REFERRAL_SELECTOR, process_revoke_referral),
+    (SET_MINT_FEE_SELECTOR, process_set_mint_fee),
+    (CLEAR_MINT_FEE_SELECTOR, process_clear_mint_fee),
+    (ADD_FEE_EXEMPT_MINT_SELECTOR, process_add_fee_exempt_mint),
+    (REMOVE_FEE_EXEMPT_MINT_SELECTOR, process_remove_fee_exempt_mint),
+    (SET_TOKEN_FEE_FLOOR_SELECTOR, process_set_token_fee_floor),
+    (ADD_ROUTE_PROGRAM_SELECTOR, process_add_route_program),
+    (REMOVE_ROUTE_PROGRAM_SELECTOR, process_remove_route_program),
+    (OPEN_RENT_FUNDER_SELECTOR, |program_id, accounts, _| process_open_rent_funder(program_id, accounts)),
+    (CLOSE_RENT_FUNDER_SELECTOR, |program_id, accounts, _| process_close_rent_funder(program_id, accounts)),
+    (APPROVE_ALLOWANCE_SELECTOR, process_approve_allowance),
+    (REVOKE_ALLOWANCE_SELECTOR, process_revoke_allowance),
+    (INIT_ESCROW_SELECTOR, process_init_escrow),
+    (WITHDRAW_ESCROW_SELECTOR, process_withdraw_escrow),
 ];
 
 pub fn process_instruction(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
+    #[cfg(feature = "debug-logs")]
+    log_accounts(accounts);
+
+    // 不足一个选择器的数据直接拒绝，避免 split_at 越界
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
     let (method, rest) = instruction_data.split_at(8);
 
     for (selector, handler) in SELECTORS.iter() {
         if method == selector.as_slice() {
-            return handler(accounts, rest);
+            return handler(program_id, accounts, rest);
         }
     }
 
+    unknown_selector(program_id, accounts, method)
+}
+
+// 只在 debug-logs feature 下编译，关闭时不产生任何计算单元开销
+#[cfg(feature = "debug-logs")]
+fn log_accounts(accounts: &[AccountInfo]) {
+    msg!("账户数 {}", accounts.len());
+    for (index, account) in accounts.iter().enumerate() {
+        msg!(
+            "#{} {} signer={} writable={}",
+            index,
+            account.key,
+            account.is_signer,
+            account.is_writable
+        );
+    }
+}
+
+// 未知选择器与已知选择器同属一个系列（文本选择器前 4 字节相同，或数字选择器仅首字节不同）时，
+// 宽松模式返回 UnsupportedRoute，便于先于合约升级的客户端识别版本不匹配
+fn unknown_selector(program_id: &Pubkey, accounts: &[AccountInfo], method: &[u8]) -> ProgramResult {
+    let known_family = SELECTORS
+        .iter()
+        .any(|(selector, _)| selector[..4] == method[..4] || selector[1..] == method[1..]);
+    let lenient = accounts
+        .first()
+        .filter(|config| config.owner == program_id)
+        .and_then(|config| TradeFeeState::unpack(&config.data.borrow()).ok())
+        .is_some_and(|config| config.lenient_selectors);
+
+    if known_family && lenient {
+        msg!("选择器 {:?} 暂不支持，请确认合约版本", method);
+        return Err(MyError::UnsupportedRoute.into());
+    }
     Err(ProgramError::InvalidInstructionData)
 }
 
+// 所有设置费率的入口都必须经过上限检查
+fn check_fee_rate(fee_rate_pips: u32) -> ProgramResult {
+    if fee_rate_pips > MAX_FEE_RATE_PIPS {
+        return Err(MyError::FeeRateTooHigh.into());
+    }
+    Ok(())
+}
+
+// 只读返回 borsh 编码的完整配置，账户: [config]
+pub fn get_config(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let config_account = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if config_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let config = TradeFeeState::unpack(&config_account.data.borrow())?;
+    set_return_data(&borsh::to_vec(&config)?);
+    Ok(())
+}
+
+// 结算周期标记：通过 return data 返回清零前的 total_fees_collected（u64 小端），并在同一指令中清零
+// 账户: [配置账户, 管理员]
+pub fn read_and_reset_fees(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    let total = trade_fee_config.total_fees_collected;
+    trade_fee_config.total_fees_collected = 0;
+    trade_fee_config.store(fee_account)?;
+
+    set_return_data(&total.to_le_bytes());
+    Ok(())
+}
+
+// 推荐人与回购分成之和不能超过 100%
+fn check_shares(referral_share_bps: u16, buyback_share_bps: u16) -> ProgramResult {
+    if referral_share_bps as u64 + buyback_share_bps as u64 > BPS_DENOMINATOR {
+        msg!("推荐人分成 {} 与回购分成 {} 之和超过 10000 基点", referral_share_bps, buyback_share_bps);
+        return Err(MyError::InvalidShareBps.into());
+    }
+    Ok(())
+}
+
 // 修复1：添加初始化配置账户函数
 pub fn initialize_config_account(
     accounts: &[AccountInfo],
-    fee_rate: u8,
+    fee_rate_pips: u32,
 ) -> ProgramResult {
     let config_account = &accounts[0];
     let admin = &accounts[1];
@@ -78,19 +396,148 @@ pub fn initialize_config_account(
     if !admin.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
+
+    check_fee_rate(fee_rate_pips)?;
     
     // 初始化配置
     let config = TradeFeeState {
-        fee_rate,
+        fee_rate_pips,
         fee_wallet: *admin.key,  // 初始化为管理员地址
+        seq: 0,
+        free_trades: 0,
+        fee_usd_micros: 0,
+        oracle: Pubkey::default(),
+        max_price_age_secs: 0,
+        lenient_selectors: false,
+        fee_remainder_accumulator: 0,
+        require_memo: false,
+        buyback_wallet: Pubkey::default(),
+        buyback_share_bps: 0,
+        replay_window_slots: 0,
+        nonce_ring_size: 0,
+        fee_timing: FeeTiming::PreSwap,
+        cpi_allowlist_enabled: false,
+        cpi_callers: [Pubkey::default(); MAX_CPI_CALLERS],
+        route_fee_overrides: [RouteFeeOverride::default(); MAX_ROUTE_FEE_OVERRIDES],
+        referral_share_bps: 0,
+        membership_mint: Pubkey::default(),
+        membership_discount_bps: 0,
+        max_inner_data_len: 0,
+        multisig_admins: [Pubkey::default(); MAX_MULTISIG_ADMINS],
+        multisig_threshold: 0,
+        event_cpi: false,
+        trade_cooldown_slots: 0,
+        buy_fee_timing: FeeTiming::PreSwap,
+        paused_routes: [[0u8; 8]; MAX_PAUSED_ROUTES],
+        rebate_bps: 0,
+        max_withdraw_per_tx: 0,
+        quote_fee_mint: Pubkey::default(),
+        guardian: Pubkey::default(),
+        paused: false,
+        epoch_rollup: false,
+        blocked_mints: [Pubkey::default(); MAX_BLOCKED_MINTS],
+        fee_sides: FeeSides::Both,
+        default_referrer: Pubkey::default(),
+        default_referrer_share_bps: 0,
+        compact_events: false,
+        on_fee_failure: FeeFailurePolicy::Abort,
+        fee_wallet_backup: Pubkey::default(),
+        total_fees_collected: 0,
+        preflight_checks: 0,
+        waive_cpi_fees: false,
+        whale_threshold: 0,
+        whale_rebate_bps: 0,
+        promo_start_slot: 0,
+        promo_end_slot: 0,
+        max_data_age_slots: 0,
+        last_trade_slot: 0,
+        dormant_waiver_slots: 0,
     };
     
-    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+    config.store(config_account)?;
+    Ok(())
+}
+
+// 一次更新多个配置字段: [borsh 编码的 ConfigUpdate]，字段全部写入后按各单项指令的规则统一校验，任一不通过则整体不生效。
+// 修改费率，或结果会免收手续费（全额返还、大额全额返还）时，开启多签需达到门限
+// 账户: [配置账户, 管理员, 多签管理员...]
+pub fn update_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let update = ConfigUpdate::try_from_slice(instruction_data).map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    update.apply(&mut trade_fee_config);
+
+    check_fee_rate(trade_fee_config.fee_rate_pips)?;
+    check_shares(trade_fee_config.referral_share_bps, trade_fee_config.buyback_share_bps)?;
+    check_shares(trade_fee_config.default_referrer_share_bps, trade_fee_config.buyback_share_bps)?;
+    if trade_fee_config.rebate_bps as u64 > BPS_DENOMINATOR
+        || trade_fee_config.whale_rebate_bps as u64 > BPS_DENOMINATOR
+    {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let zeroes_fee = update.rebate_bps.is_some_and(|bps| bps as u64 == BPS_DENOMINATOR)
+        || ((update.whale_rebate_bps.is_some() || update.whale_threshold.is_some())
+            && trade_fee_config.whale_threshold != 0
+            && trade_fee_config.whale_rebate_bps as u64 == BPS_DENOMINATOR);
+    trade_fee_config.check_zero_fee_multisig(accounts, update.fee_rate_pips.is_some() || zeroes_fee)?;
+
+    trade_fee_config.store(fee_account)?;
     Ok(())
 }
 
+// 一条指令完成配置 PDA 的创建与初始化: [fee_rate_pips u32][bump u8，可选]
+// 管理员支付租金并成为 fee_wallet；全局只有一个配置 PDA，为防止被抢先创建，管理员必须是程序升级权限
+// 账户: [配置 PDA, 管理员(签名并支付租金), 系统程序, 本程序的 ProgramData]
+pub fn create_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let fee_rate_pips = instruction_data
+        .get(..4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    if accounts.len() < 4 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let config_account = &accounts[0];
+    let admin = &accounts[1];
+    let system_program = &accounts[2];
+    let program_data = &accounts[3];
+
+    check_upgrade_authority(program_id, program_data, admin)?;
+    check_fee_rate(fee_rate_pips)?;
+
+    let (address, bump) = config_address_with_bump(program_id, instruction_data.get(4).copied())?;
+    if config_account.key != &address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if config_account.owner == program_id {
+        msg!("配置 PDA {} 已创建", address);
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_pda_account(
+        admin,
+        config_account,
+        system_program,
+        program_id,
+        TradeFeeState::LEN,
+        &[CONFIG_SEED, &[bump]],
+    )?;
+    initialize_config_account(accounts, fee_rate_pips)
+}
+
 // 修复2：修改函数签名并添加权限检查
 pub fn set_protocol_fee_wallet(
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
@@ -103,25 +550,1019 @@ pub fn set_protocol_fee_wallet(
     // 账户验证
     let fee_account = &accounts[0];
     let admin_account = &accounts[1];
-    
-    // 1. 验证管理员签名
-    if !admin_account.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
+
+    // 协议费钱包同时是管理员身份，属于高风险设置，开启多签时需要达到门限
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.check_multisig(accounts)?;
+
+    trade_fee_config.fee_wallet = new_wallet;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置协议费率，单位为 pip（百分之一基点），例如 3.5 bps = 350 pips
+pub fn set_fee_rate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    // 解析新费率 (4字节)
+    if instruction_data.len() < 4 {
+        return Err(ProgramError::InvalidInstructionData);
     }
-    
-    // 2. 反序列化配置
-    let mut trade_fee_config = TradeFeeState::try_from_slice(&fee_account.data.borrow())?;
-    
-    // 3. 验证调用者是当前管理员
-    if *admin_account.key != trade_fee_config.fee_wallet {
-        return Err(ProgramError::IllegalOwner);
+    let fee_rate_pips = u32::from_le_bytes(<[u8; 4]>::try_from(&instruction_data[..4]).unwrap());
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    // 修改费率属于高风险操作，开启多签时需要达到门限
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.check_multisig(accounts)?;
+
+    check_fee_rate(fee_rate_pips)?;
+
+    trade_fee_config.fee_rate_pips = fee_rate_pips;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置新钱包免手续费的交易笔数，0 表示关闭
+pub fn set_free_trades(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 4 {
+        return Err(ProgramError::InvalidInstructionData);
     }
-    
-    // 4. 更新协议费钱包地址
-    trade_fee_config.fee_wallet = new_wallet;
-    
-    // 5. 序列化并存储
-    trade_fee_config.serialize(&mut &mut fee_account.data.borrow_mut()[..])?;
+    let free_trades = u32::from_le_bytes(<[u8; 4]>::try_from(&instruction_data[..4]).unwrap());
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.free_trades = free_trades;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置美元定额手续费: [fee_usd_micros u64][预言机价格账户 32][报价最大时长秒 u32]
+// fee_usd_micros 为 0 时恢复按费率收取
+pub fn set_usd_fee(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 44 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let fee_usd_micros = u64::from_le_bytes(<[u8; 8]>::try_from(&instruction_data[..8]).unwrap());
+    let oracle = Pubkey::new_from_array(<[u8; 32]>::try_from(&instruction_data[8..40]).unwrap());
+    let max_price_age_secs = u32::from_le_bytes(<[u8; 4]>::try_from(&instruction_data[40..44]).unwrap());
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.fee_usd_micros = fee_usd_micros;
+    trade_fee_config.oracle = oracle;
+    trade_fee_config.max_price_age_secs = max_price_age_secs;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置未知选择器处理模式: [lenient u8]，非 0 为宽松模式
+pub fn set_lenient_selectors(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let lenient = *instruction_data.first().ok_or(ProgramError::InvalidInstructionData)? != 0;
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.lenient_selectors = lenient;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置交易是否必须附带 Memo 指令: [require u8]，开启后交易需在账户末尾追加 Instructions sysvar
+pub fn set_require_memo(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let require_memo = *instruction_data.first().ok_or(ProgramError::InvalidInstructionData)? != 0;
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.require_memo = require_memo;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置回购分成: [回购钱包 32][share_bps u16]，开启后交易需在账户末尾追加回购钱包
+pub fn set_buyback(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 34 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let buyback_wallet = Pubkey::new_from_array(<[u8; 32]>::try_from(&instruction_data[..32]).unwrap());
+    let buyback_share_bps = u16::from_le_bytes(<[u8; 2]>::try_from(&instruction_data[32..34]).unwrap());
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    check_shares(trade_fee_config.referral_share_bps, buyback_share_bps)?;
+    trade_fee_config.buyback_wallet = buyback_wallet;
+    trade_fee_config.buyback_share_bps = buyback_share_bps;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置防重放参数: [窗口 slot u64][缓冲区容量 u16]，容量只影响之后新建的 nonce 账户；窗口为 0 时拒绝带 nonce 的交易
+pub fn set_replay_window(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 10 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let replay_window_slots = u64::from_le_bytes(<[u8; 8]>::try_from(&instruction_data[..8]).unwrap());
+    let nonce_ring_size = u16::from_le_bytes(<[u8; 2]>::try_from(&instruction_data[8..10]).unwrap());
+    if nonce_ring_size > MAX_NONCE_RING_SIZE {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.replay_window_slots = replay_window_slots;
+    trade_fee_config.nonce_ring_size = nonce_ring_size;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置卖出收费时机: [timing u8]，0 为兑换前扣除，1 为兑换后从收到的 SOL 中收取
+pub fn set_fee_timing(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let fee_timing = parse_fee_timing(instruction_data)?;
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.fee_timing = fee_timing;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+fn parse_fee_timing(instruction_data: &[u8]) -> Result<FeeTiming, ProgramError> {
+    match instruction_data.first() {
+        Some(0) => Ok(FeeTiming::PreSwap),
+        Some(1) => Ok(FeeTiming::PostSwap),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+// 设置备用手续费钱包: [钱包 32]，默认公钥表示关闭
+// 备用钱包会接收协议收入，开启多签时需要达到门限
+// 账户: [配置账户, 管理员, 多签管理员...]
+pub fn set_fee_wallet_backup(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 32 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let backup = Pubkey::new_from_array(<[u8; 32]>::try_from(&instruction_data[..32]).unwrap());
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.check_multisig(accounts)?;
+    trade_fee_config.fee_wallet_backup = backup;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置手续费转账失败的处理方式: [0 = 中止交易, 1 = 跳过收费并输出 FeeSkipped]
+pub fn set_on_fee_failure(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let on_fee_failure = match instruction_data.first() {
+        Some(0) => FeeFailurePolicy::Abort,
+        Some(1) => FeeFailurePolicy::Skip,
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.on_fee_failure = on_fee_failure;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置收费方向: [0 = 买卖都收, 1 = 只收买入, 2 = 只收卖出]
+pub fn set_fee_sides(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let fee_sides = match instruction_data.first() {
+        Some(0) => FeeSides::Both,
+        Some(1) => FeeSides::BuyOnly,
+        Some(2) => FeeSides::SellOnly,
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.fee_sides = fee_sides;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置默认推荐人: [钱包 32][分成 bps u16]，默认公钥表示关闭；分成同样受 referral_share_bps 上限约束
+pub fn set_default_referrer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 34 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let default_referrer = Pubkey::new_from_array(<[u8; 32]>::try_from(&instruction_data[..32]).unwrap());
+    let share_bps = u16::from_le_bytes(<[u8; 2]>::try_from(&instruction_data[32..34]).unwrap());
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    check_shares(share_bps, trade_fee_config.buyback_share_bps)?;
+    trade_fee_config.default_referrer = default_referrer;
+    trade_fee_config.default_referrer_share_bps = share_bps;
+    trade_fee_config.store(fee_account)?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+// 设置买入收费时机: [0 = 兑换前从输入 SOL 扣除, 1 = 兑换后从收到的代币中收取]
+// 按代币收费时，协议钱包、其关联代币账户、mint 与 ATA 程序需追加在账户末尾
+pub fn set_buy_fee_timing(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let buy_fee_timing = parse_fee_timing(instruction_data)?;
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.buy_fee_timing = buy_fee_timing;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置 CPI 调用方白名单: [enabled u8][数量 u8][程序 ID 32 * 数量]，整体替换原名单
+pub fn set_cpi_callers(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 2 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let enabled = instruction_data[0] != 0;
+    let count = instruction_data[1] as usize;
+    if count > MAX_CPI_CALLERS || instruction_data.len() < 2 + count * 32 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let mut cpi_callers = [Pubkey::default(); MAX_CPI_CALLERS];
+    for (i, caller) in cpi_callers.iter_mut().take(count).enumerate() {
+        let offset = 2 + i * 32;
+        *caller = Pubkey::new_from_array(<[u8; 32]>::try_from(&instruction_data[offset..offset + 32]).unwrap());
+    }
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.cpi_allowlist_enabled = enabled;
+    trade_fee_config.cpi_callers = cpi_callers;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置按路由覆盖的费率: [数量 u8][(路由选择器 8, rate_pips u32) * 数量]，整体替换原有覆盖
+pub fn set_route_fees(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let count = *instruction_data.first().ok_or(ProgramError::InvalidInstructionData)? as usize;
+    if count > MAX_ROUTE_FEE_OVERRIDES || instruction_data.len() < 1 + count * RouteFeeOverride::LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let mut overrides = [RouteFeeOverride::default(); MAX_ROUTE_FEE_OVERRIDES];
+    for (i, entry) in overrides.iter_mut().take(count).enumerate() {
+        let offset = 1 + i * RouteFeeOverride::LEN;
+        let selector = <[u8; 8]>::try_from(&instruction_data[offset..offset + 8]).unwrap();
+        let rate_pips = u32::from_le_bytes(<[u8; 4]>::try_from(&instruction_data[offset + 8..offset + 12]).unwrap());
+        if find_fee_route(&selector).is_none() {
+            msg!("选择器 {:?} 不是带手续费的路由", selector);
+            return Err(MyError::UnsupportedRoute.into());
+        }
+        check_fee_rate(rate_pips)?;
+        *entry = RouteFeeOverride { selector, rate_pips };
+    }
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.check_zero_fee_multisig(accounts, overrides[..count].iter().any(|o| o.rate_pips == 0))?;
+    trade_fee_config.route_fee_overrides = overrides;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 同时设置推荐人分成上限与回购分成比例: [referral_share_bps u16][buyback_share_bps u16]，
+// 一次校验两者之和，避免分两步设置时出现超过 100% 的中间状态
+pub fn set_shares(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 4 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let referral_share_bps = u16::from_le_bytes(<[u8; 2]>::try_from(&instruction_data[..2]).unwrap());
+    let buyback_share_bps = u16::from_le_bytes(<[u8; 2]>::try_from(&instruction_data[2..4]).unwrap());
+    check_shares(referral_share_bps, buyback_share_bps)?;
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.referral_share_bps = referral_share_bps;
+    trade_fee_config.buyback_share_bps = buyback_share_bps;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置会员折扣: [会员代币 mint 32][折扣 bps u16]，折扣为 0 表示关闭
+pub fn set_membership(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 34 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let membership_mint = Pubkey::new_from_array(<[u8; 32]>::try_from(&instruction_data[..32]).unwrap());
+    let membership_discount_bps = u16::from_le_bytes(<[u8; 2]>::try_from(&instruction_data[32..34]).unwrap());
+    if membership_discount_bps as u64 > BPS_DENOMINATOR {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.membership_mint = membership_mint;
+    trade_fee_config.membership_discount_bps = membership_discount_bps;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置手续费返还比例: [rebate_bps u16]，不超过 10_000
+pub fn set_rebate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 2 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let rebate_bps = u16::from_le_bytes(<[u8; 2]>::try_from(&instruction_data[..2]).unwrap());
+    if rebate_bps as u64 > BPS_DENOMINATOR {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.check_zero_fee_multisig(accounts, rebate_bps as u64 == BPS_DENOMINATOR)?;
+    trade_fee_config.rebate_bps = rebate_bps;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置转发指令数据长度上限: [长度 u32]，0 表示恢复默认值
+pub fn set_max_inner_data_len(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 4 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let max_inner_data_len = u32::from_le_bytes(<[u8; 4]>::try_from(&instruction_data[..4]).unwrap());
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.max_inner_data_len = max_inner_data_len;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置多签: [门限 u8][数量 u8][管理员 32 * 数量]，整体替换原名单；门限为 0 表示关闭多签。
+// 修改名单本身也需要满足当前的多签门限，其他管理员签名账户追加在末尾
+pub fn set_multisig(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 2 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let threshold = instruction_data[0];
+    let count = instruction_data[1] as usize;
+    if count > MAX_MULTISIG_ADMINS
+        || threshold as usize > count
+        || instruction_data.len() < 2 + count * 32
+    {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let mut admins = [Pubkey::default(); MAX_MULTISIG_ADMINS];
+    for i in 0..count {
+        let offset = 2 + i * 32;
+        let admin = Pubkey::new_from_array(<[u8; 32]>::try_from(&instruction_data[offset..offset + 32]).unwrap());
+        // 名单需互不相同，保证门限按不同签名者计数
+        if admin == Pubkey::default() || admins[..i].contains(&admin) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        admins[i] = admin;
+    }
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.check_multisig(accounts)?;
+    trade_fee_config.multisig_admins = admins;
+    trade_fee_config.multisig_threshold = threshold;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 开关 Anchor event CPI: [enabled u8]，开启后带手续费路由需追加事件 PDA 与本程序账户
+pub fn set_event_cpi(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let event_cpi = *instruction_data.first().ok_or(ProgramError::InvalidInstructionData)? != 0;
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.event_cpi = event_cpi;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 配置迁移：旧版账户按当时较短的布局分配，扩容到当前 TradeFeeState::LEN，新增部分的租金由管理员补足。
+// 扩容前先按账户现有长度对应的旧布局解析（初版 33 字节布局的百分比费率换算为 pips），并按旧布局中的管理员校验签名，
+// 再以当前布局整体重写，缺失的新字段按 0（默认值）写回；账户已足够大时只按当前布局重写
+// 账户: [配置账户, 管理员(签名并支付租金), 系统程序]
+pub fn migrate_config(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+    let system_program = &accounts[2];
+
+    if fee_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let old_len = fee_account.data_len();
+    let trade_fee_config = TradeFeeState::unpack(&fee_account.data.borrow())?;
+    trade_fee_config.check_admin(admin_account)?;
+
+    grow_account(admin_account, fee_account, system_program, TradeFeeState::LEN)?;
+    trade_fee_config.store(fee_account)?;
+
+    if old_len == TradeFeeState::LEGACY_LEN {
+        msg!("初版配置费率已换算为 {} pips", trade_fee_config.fee_rate_pips);
+    }
+    if old_len < TradeFeeState::LEN {
+        msg!("配置账户已从 {} 字节扩容到 {} 字节", old_len, TradeFeeState::LEN);
+    }
+    Ok(())
+}
+
+// 设置大额交易返还: [whale_threshold u64][whale_rebate_bps u16]，阈值为 0 表示关闭，返还比例不超过 10_000
+pub fn set_whale_rebate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 10 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let whale_threshold = u64::from_le_bytes(<[u8; 8]>::try_from(&instruction_data[..8]).unwrap());
+    let whale_rebate_bps = u16::from_le_bytes(<[u8; 2]>::try_from(&instruction_data[8..10]).unwrap());
+    if whale_rebate_bps as u64 > BPS_DENOMINATOR {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.check_zero_fee_multisig(accounts, whale_threshold != 0 && whale_rebate_bps as u64 == BPS_DENOMINATOR)?;
+    trade_fee_config.whale_threshold = whale_threshold;
+    trade_fee_config.whale_rebate_bps = whale_rebate_bps;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置推广免手续费区间: [promo_start_slot u64][promo_end_slot u64]，区间左闭右开，结束 slot 为 0 表示关闭
+// 开启等于在区间内放弃协议收入，开启多签时需达到门限
+pub fn set_promo_window(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 16 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let promo_start_slot = u64::from_le_bytes(<[u8; 8]>::try_from(&instruction_data[..8]).unwrap());
+    let promo_end_slot = u64::from_le_bytes(<[u8; 8]>::try_from(&instruction_data[8..16]).unwrap());
+    if promo_end_slot != 0 && promo_end_slot <= promo_start_slot {
+        msg!("推广区间结束 slot {} 必须大于开始 slot {}", promo_end_slot, promo_start_slot);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.check_zero_fee_multisig(accounts, promo_end_slot != 0)?;
+    trade_fee_config.promo_start_slot = promo_start_slot;
+    trade_fee_config.promo_end_slot = promo_end_slot;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置数据最大时长: [max_data_age_slots u64]，0 表示不限制。
+// 截止槽检查拒绝距当前超过该时长的截止槽，预言机报价的发布 slot 早于该时长时同样拒绝
+pub fn set_max_data_age(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let max_data_age_slots = u64::from_le_bytes(
+        instruction_data
+            .get(..8)
+            .and_then(|bytes| <[u8; 8]>::try_from(bytes).ok())
+            .ok_or(ProgramError::InvalidInstructionData)?,
+    );
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.max_data_age_slots = max_data_age_slots;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置闲置免费: [dormant_waiver_slots u32]，协议超过该 slot 数没有交易时，下一笔交易免收手续费，0 表示关闭
+// 开启等于放弃部分协议收入，开启多签时需达到门限
+pub fn set_dormant_waiver(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let dormant_waiver_slots = u32::from_le_bytes(
+        instruction_data
+            .get(..4)
+            .and_then(|bytes| <[u8; 4]>::try_from(bytes).ok())
+            .ok_or(ProgramError::InvalidInstructionData)?,
+    );
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.check_zero_fee_multisig(accounts, dormant_waiver_slots != 0)?;
+    trade_fee_config.dormant_waiver_slots = dormant_waiver_slots;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 开关 CPI 调用免手续费: [enabled u8]，开启等于对合作方程序放弃协议收入，开启多签时需达到门限
+pub fn set_cpi_fee_waiver(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let waive_cpi_fees = *instruction_data.first().ok_or(ProgramError::InvalidInstructionData)? != 0;
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.check_zero_fee_multisig(accounts, waive_cpi_fees)?;
+    trade_fee_config.waive_cpi_fees = waive_cpi_fees;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 开关支付者余额预检: [enabled u8]
+pub fn set_balance_check(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let payer_balance_check = *instruction_data.first().ok_or(ProgramError::InvalidInstructionData)? != 0;
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.set_preflight(PREFLIGHT_PAYER_BALANCE, payer_balance_check);
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 开关冻结账户预检: [enabled u8]，开启后买入的输出代币账户处于冻结状态时在收费前拒绝
+pub fn set_frozen_destination_check(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let enabled = *instruction_data.first().ok_or(ProgramError::InvalidInstructionData)? != 0;
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.set_preflight(PREFLIGHT_FROZEN_DESTINATION, enabled);
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 开关精简收费事件: [enabled u8]，格式见 events::CompactFeeCollected
+pub fn set_compact_events(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let compact_events = *instruction_data.first().ok_or(ProgramError::InvalidInstructionData)? != 0;
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.compact_events = compact_events;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置以 quote 代币收费的 mint: [mint 32]，默认公钥表示关闭
+pub fn set_quote_fee_mint(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 32 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let quote_fee_mint = Pubkey::new_from_array(<[u8; 32]>::try_from(&instruction_data[..32]).unwrap());
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.quote_fee_mint = quote_fee_mint;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 开关按 epoch 汇总手续费: [enabled u8]
+pub fn set_epoch_rollup(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let epoch_rollup = *instruction_data.first().ok_or(ProgramError::InvalidInstructionData)? != 0;
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.epoch_rollup = epoch_rollup;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 把代币加入或移出禁止列表: [mint 32][blocked u8]
+pub fn block_mint(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 33 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let mint = Pubkey::new_from_array(<[u8; 32]>::try_from(&instruction_data[..32]).unwrap());
+    let blocked = instruction_data[32] != 0;
+    if mint == Pubkey::default() {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    let slots = &mut trade_fee_config.blocked_mints;
+    if blocked {
+        if !slots.contains(&mint) {
+            let slot = slots.iter_mut().find(|s| **s == Pubkey::default()).ok_or_else(|| {
+                msg!("禁止列表最多 {} 个代币", MAX_BLOCKED_MINTS);
+                ProgramError::InvalidInstructionData
+            })?;
+            *slot = mint;
+        }
+    } else {
+        slots.iter_mut().filter(|s| **s == mint).for_each(|s| *s = Pubkey::default());
+    }
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置钱包交易冷却: [slot 数 u64]，0 表示关闭
+pub fn set_trade_cooldown(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let trade_cooldown_slots = u64::from_le_bytes(<[u8; 8]>::try_from(&instruction_data[..8]).unwrap());
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.trade_cooldown_slots = trade_cooldown_slots;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 暂停或恢复单个带手续费的路由: [路由选择器 8 字节][paused u8][原因码 u16]
+// 账户: [配置账户, 管理员或守护者, 本程序的 ProgramData, 多签管理员...]
+pub fn pause_route(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 11 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let selector = <[u8; 8]>::try_from(&instruction_data[..8]).unwrap();
+    let paused = instruction_data[8] != 0;
+    let reason = u16::from_le_bytes(<[u8; 2]>::try_from(&instruction_data[9..11]).unwrap());
+    if find_fee_route(&selector).is_none() {
+        msg!("选择器 {:?} 不是带手续费的路由", selector);
+        return Err(MyError::UnsupportedRoute.into());
+    }
+
+    let mut trade_fee_config = load_pause_authority(program_id, accounts, paused)?;
+    let (fee_account, authority) = (&accounts[0], &accounts[1]);
+
+    let slots = &mut trade_fee_config.paused_routes;
+    if paused {
+        if !slots.contains(&selector) {
+            let slot = slots.iter_mut().find(|s| **s == [0u8; 8]).ok_or_else(|| {
+                msg!("最多同时暂停 {} 个路由", MAX_PAUSED_ROUTES);
+                ProgramError::InvalidInstructionData
+            })?;
+            *slot = selector;
+        }
+    } else {
+        slots.iter_mut().filter(|s| **s == selector).for_each(|s| *s = [0u8; 8]);
+    }
+    trade_fee_config.store(fee_account)?;
+
+    RoutePauseChanged { selector, paused, reason, authority: *authority.key }.emit()
+}
+
+// 守护者只能暂停，恢复暂停属于高风险操作，需要管理员同时是程序升级权限并满足多签
+fn load_pause_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    pausing: bool,
+) -> Result<TradeFeeState, ProgramError> {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let fee_account = &accounts[0];
+    let authority = &accounts[1];
+    if fee_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let config = TradeFeeState::unpack(&fee_account.data.borrow())?;
+    if pausing && authority.is_signer && config.is_guardian(authority.key) {
+        return Ok(config);
+    }
+
+    config.check_admin(authority)?;
+    let program_data = accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    check_upgrade_authority(program_id, program_data, authority)?;
+    config.check_multisig(accounts)?;
+    Ok(config)
+}
+
+// 全局暂停或恢复所有带手续费的路由: [paused u8][原因码 u16]
+// 账户: [配置账户, 管理员或守护者, 本程序的 ProgramData, 多签管理员...]
+pub fn set_paused(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 3 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let paused = instruction_data[0] != 0;
+    let reason = u16::from_le_bytes(<[u8; 2]>::try_from(&instruction_data[1..3]).unwrap());
+
+    let mut trade_fee_config = load_pause_authority(program_id, accounts, paused)?;
+    let (fee_account, authority) = (&accounts[0], &accounts[1]);
+    trade_fee_config.paused = paused;
+    trade_fee_config.store(fee_account)?;
+
+    ProtocolPauseChanged { paused, reason, authority: *authority.key }.emit()
+}
+
+// 设置守护者: [guardian 32]，默认公钥表示不设守护者
+pub fn set_guardian(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 32 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let guardian = Pubkey::new_from_array(<[u8; 32]>::try_from(&instruction_data[..32]).unwrap());
+
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    trade_fee_config.guardian = guardian;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 设置托管单次提取上限: [lamports u64]，0 表示不限制
+// 放宽上限与提取同属高风险操作，管理员必须同时是程序升级权限
+// 账户: [配置账户, 管理员, 本程序的 ProgramData, 多签管理员...]
+pub fn set_max_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let max_withdraw_per_tx = u64::from_le_bytes(<[u8; 8]>::try_from(&instruction_data[..8]).unwrap());
+
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+    let program_data = &accounts[2];
+
+    let mut trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    check_upgrade_authority(program_id, program_data, admin_account)?;
+    trade_fee_config.check_multisig(accounts)?;
+    trade_fee_config.max_withdraw_per_tx = max_withdraw_per_tx;
+    trade_fee_config.store(fee_account)?;
+
+    Ok(())
+}
+
+// 把遗留的本程序账户中的 lamports 全部转给协议费钱包并关闭该账户
+// 遗留账户是以密钥对地址创建的本程序账户（如改用 [b"config"] PDA 之前的配置账户），需由该密钥对签名证明：
+// 本程序的 PDA（额度、nonce、钱包、推荐码、共享租金等在用状态）无法签名，因而不可能被回收。
+// 不允许回收正在使用的配置账户
+// 账户: [配置账户, 管理员(协议费钱包), 待回收账户(签名), 本程序的 ProgramData, 多签管理员...]
+pub fn sweep_orphan(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    if accounts.len() < 4 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let fee_account = &accounts[0];
+    let admin_account = &accounts[1];
+    let orphan = &accounts[2];
+    let program_data = &accounts[3];
+
+    let trade_fee_config = TradeFeeState::load_as_admin(program_id, fee_account, admin_account)?;
+    check_upgrade_authority(program_id, program_data, admin_account)?;
+    trade_fee_config.check_multisig(accounts)?;
+
+    if orphan.key == fee_account.key || is_escrow_account(program_id, orphan) {
+        msg!("{} 是正在使用的配置或托管账户，不能回收", orphan.key);
+        return Err(ProgramError::InvalidArgument);
+    }
+    if !orphan.is_signer {
+        msg!("待回收账户 {} 未签名，只能回收以密钥对地址创建的遗留账户", orphan.key);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if orphan.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    // 清空数据并转出全部 lamports，交易结束后账户即被回收
+    orphan.data.borrow_mut().fill(0);
+    let lamports = orphan.lamports();
+    **orphan.try_borrow_mut_lamports()? -= lamports;
+    **admin_account.try_borrow_mut_lamports()? += lamports;
+    msg!("已回收账户 {} 的 {} lamports", orphan.key, lamports);
+    Ok(())
+}
@@ -1,8 +1,552 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::pubkey::Pubkey;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::MyError;
+
+// CPI 调用方白名单容量
+pub const MAX_CPI_CALLERS: usize = 4;
+// 按路由覆盖费率的条目数
+pub const MAX_ROUTE_FEE_OVERRIDES: usize = 4;
+// 多签管理员数量上限
+pub const MAX_MULTISIG_ADMINS: usize = 5;
+// 单独暂停的路由数量上限
+pub const MAX_PAUSED_ROUTES: usize = 4;
+// 禁止交易的代币数量上限
+pub const MAX_BLOCKED_MINTS: usize = 8;
+
+// 未配置时转发指令数据的长度上限，已超过单笔交易大小，正常调用不会触及
+pub const DEFAULT_MAX_INNER_DATA_LEN: u32 = 1232;
+
+// 费率单位为 pip（百分之一基点），1_000_000 pips = 100%
+pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+// 费率硬上限（基点），编译期固定，管理员无法绕过
+pub const MAX_FEE_BPS: u32 = 500;
+pub const MAX_FEE_RATE_PIPS: u32 = MAX_FEE_BPS * 100;
+// 分成比例单位为基点，10_000 = 100%
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+// preflight_checks 中各项收费前预检的标志位
+pub const PREFLIGHT_PAYER_BALANCE: u8 = 1;
+pub const PREFLIGHT_FROZEN_DESTINATION: u8 = 2;
+pub const PREFLIGHT_TOKEN_FEE_FLOOR: u8 = 4;
+
+// 收费时机：兑换前从输入中扣除，或兑换后从收到的输出中收取
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum FeeTiming {
+    #[default]
+    PreSwap,
+    PostSwap,
+}
+
+// 收费方向：买卖都收，或只在其中一侧收费
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum FeeSides {
+    #[default]
+    Both,
+    BuyOnly,
+    SellOnly,
+}
+
+// 手续费转账无法完成时的处理：中止整笔交易，或跳过收费按完整数量继续兑换
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum FeeFailurePolicy {
+    #[default]
+    Abort,
+    Skip,
+}
+
+// 单条路由的费率覆盖，selector 全零表示空位
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct RouteFeeOverride {
+    pub selector: [u8; 8],
+    pub rate_pips: u32,
+}
+
+impl RouteFeeOverride {
+    pub const LEN: usize = 8 + 4;
+}
 
 #[derive(Debug, BorshSerialize, BorshDeserialize)]
 pub struct TradeFeeState {
-    pub fee_rate: u8,
+    pub fee_rate_pips: u32,
     pub fee_wallet: Pubkey,
-}
\ No newline at end of file
+    // 手续费事件序号，每次收费递增
+    pub seq: u64,
+    // 新钱包前 N 笔交易免手续费，0 表示关闭
+    pub free_trades: u32,
+    // 以百万分之一美元计的固定手续费，按预言机报价换算为 SOL，0 表示按费率收取
+    pub fee_usd_micros: u64,
+    // SOL/USD 价格账户
+    pub oracle: Pubkey,
+    // 报价允许的最大时长（秒）
+    pub max_price_age_secs: u32,
+    // 宽松模式下，已知系列的未知选择器返回 UnsupportedRoute
+    pub lenient_selectors: bool,
+    // 按费率收费时向下取整舍去的零头累计，单位 1/1_000_000 lamport
+    pub fee_remainder_accumulator: u64,
+    // 交易必须附带 Memo 指令
+    pub require_memo: bool,
+    // 回购钱包及其手续费分成比例（基点），0 表示不分成
+    pub buyback_wallet: Pubkey,
+    pub buyback_share_bps: u16,
+    // nonce 防重放窗口（slot），0 表示未配置，此时拒绝带 nonce 的交易
+    pub replay_window_slots: u64,
+    // 新建 nonce 缓冲区的容量，0 表示使用默认值
+    pub nonce_ring_size: u16,
+    // 卖出路由的收费时机
+    pub fee_timing: FeeTiming,
+    // 开启后只允许白名单中的程序通过 CPI 调用交易路由，空位为默认公钥
+    pub cpi_allowlist_enabled: bool,
+    pub cpi_callers: [Pubkey; MAX_CPI_CALLERS],
+    // 按路由选择器覆盖全局费率，未命中时使用 fee_rate_pips
+    pub route_fee_overrides: [RouteFeeOverride; MAX_ROUTE_FEE_OVERRIDES],
+    // 推荐人分成比例上限（基点），与 buyback_share_bps 之和不超过 10_000，0 表示按推荐码登记的比例
+    pub referral_share_bps: u16,
+    // 持有会员代币的交易者按 membership_discount_bps 减免手续费（10_000 为全免），默认公钥表示关闭
+    pub membership_mint: Pubkey,
+    pub membership_discount_bps: u16,
+    // 转发给目标程序的指令数据长度上限，0 表示使用 DEFAULT_MAX_INNER_DATA_LEN
+    pub max_inner_data_len: u32,
+    // 多签管理员与门限，门限为 0 表示关闭，开启后高风险操作需要 M 个不同的多签管理员签名
+    pub multisig_admins: [Pubkey; MAX_MULTISIG_ADMINS],
+    pub multisig_threshold: u8,
+    // 额外以 Anchor event CPI 格式输出 FeeCollected，供 Anchor 索引器直接解码
+    pub event_cpi: bool,
+    // 同一钱包两笔交易之间至少间隔的 slot 数，0 表示关闭；开启后交易必须附带钱包 PDA
+    pub trade_cooldown_slots: u64,
+    // 买入路由的收费时机：兑换前从输入 SOL 中扣除，或兑换后从收到的代币中收取
+    pub buy_fee_timing: FeeTiming,
+    // 单独暂停的路由选择器，空位为全零
+    pub paused_routes: [[u8; 8]; MAX_PAUSED_ROUTES],
+    // 返还给手续费支付者的比例（基点），用于抵扣其优先费，0 表示不返还
+    pub rebate_bps: u16,
+    // 单次提取托管收入的上限（lamports），限制管理员密钥泄露时的损失，0 表示不限制
+    pub max_withdraw_per_tx: u64,
+    // AMM 交易对的 quote 为该代币（如 USDC）时以 quote 代币收取手续费，默认公钥表示关闭
+    pub quote_fee_mint: Pubkey,
+    // 守护者只能暂停（全局或单个路由），不能修改费率或提取资金，默认公钥表示未设置
+    pub guardian: Pubkey,
+    // 全局暂停所有带手续费的路由
+    pub paused: bool,
+    // 按 epoch 汇总 SOL 手续费到 [b"rollup", epoch] PDA，开启后交易必须附带当前 epoch 的汇总账户
+    pub epoch_rollup: bool,
+    // 禁止路由的代币 mint，空位为默认公钥
+    pub blocked_mints: [Pubkey; MAX_BLOCKED_MINTS],
+    // 收费方向，另一侧的交易免收手续费
+    pub fee_sides: FeeSides,
+    // 未带推荐码（或推荐码未登记）的交易按 default_referrer_share_bps 分成给默认推荐人，默认公钥表示关闭
+    pub default_referrer: Pubkey,
+    pub default_referrer_share_bps: u16,
+    // 以 CompactFeeCollected 代替 FeeCollected 输出收费事件，减少日志字节
+    pub compact_events: bool,
+    // 收费前预检发现手续费转账会失败时的处理方式
+    pub on_fee_failure: FeeFailurePolicy,
+    // 主手续费钱包不再归系统程序所有时，SOL 手续费改投该备用钱包，默认公钥表示未设置
+    pub fee_wallet_backup: Pubkey,
+    // 自上次 rst_fees 以来收取的 SOL 手续费总额（lamports，含推荐与回购分成），代币手续费不计入
+    pub total_fees_collected: u64,
+    // 收费前的可选预检，按位开启：PREFLIGHT_PAYER_BALANCE 预检支付者（及直接花费 lamports 的交易发起人）余额
+    // 是否足够支付手续费、输入金额与免租金额，PREFLIGHT_FROZEN_DESTINATION 拒绝买入到被冻结的代币账户，
+    // PREFLIGHT_TOKEN_FEE_FLOOR 由 set_tflr 维护，开启时以代币收费的交易须附带下限 PDA 并按下限收费。
+    // 配置账户已达 return data 上限，由原 payer_balance_check 布尔字段扩展而来，旧账户中的 1 仍表示只开启余额预检
+    pub preflight_checks: u8,
+    // 通过 CPI 调用（调用栈高度大于 1，如合作方程序的组合流程）时免收手续费；与 CPI 白名单同时开启时只有白名单程序能调用
+    pub waive_cpi_fees: bool,
+    // 大额交易返还：计费金额超过 whale_threshold（lamports）时，手续费再按 whale_rebate_bps 减免，阈值为 0 表示关闭
+    pub whale_threshold: u64,
+    pub whale_rebate_bps: u16,
+    // 推广活动期间免收手续费的 slot 区间 [promo_start_slot, promo_end_slot)，结束 slot 为 0 表示关闭
+    pub promo_start_slot: u64,
+    pub promo_end_slot: u64,
+    // 读取 Clock 或预言机数据时允许的最大时长（slot），截止槽检查与预言机报价统一使用，0 表示不限制
+    pub max_data_age_slots: u64,
+    // 最近一笔经过收费流程的交易所在 slot
+    pub last_trade_slot: u64,
+    // 距上一笔交易超过 dormant_waiver_slots 个 slot 后的第一笔交易免收手续费，用于唤回用户，0 表示关闭
+    pub dormant_waiver_slots: u32,
+}
+
+impl TradeFeeState {
+    pub const LEN: usize =
+        4 + 32 + 8 + 4 + 8 + 32 + 4 + 1 + 8 + 1 + 32 + 2 + 8 + 2 + 1 + 1 + 32 * MAX_CPI_CALLERS
+        + RouteFeeOverride::LEN * MAX_ROUTE_FEE_OVERRIDES
+        + 2
+        + 32
+        + 2
+        + 4
+        + 32 * MAX_MULTISIG_ADMINS
+        + 1
+        + 1
+        + 8
+        + 1
+        + 8 * MAX_PAUSED_ROUTES
+        + 2
+        + 8
+        + 32
+        + 32
+        + 1
+        + 1
+        + 32 * MAX_BLOCKED_MINTS
+        + 1
+        + 32
+        + 2
+        + 1
+        + 1
+        + 32
+        + 8
+        + 1
+        + 1
+        + 8
+        + 2
+        + 8
+        + 8
+        + 8
+        + 8
+        + 4;
+
+    // 初版配置布局：[fee_rate u8，单位为百分比][fee_wallet 32]
+    pub const LEGACY_LEN: usize = 1 + 32;
+
+    // 旧版本配置账户较短，缺失的新字段按 0 解析；账户多余的尾部数据忽略。
+    // 初版布局的费率字段宽度与单位都不同，按长度识别后单独换算
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() == Self::LEGACY_LEN {
+            return Self::unpack_legacy(data);
+        }
+        let mut buf = [0u8; Self::LEN];
+        let len = data.len().min(Self::LEN);
+        buf[..len].copy_from_slice(&data[..len]);
+        Ok(Self::deserialize(&mut &buf[..])?)
+    }
+
+    // 初版费率为整数百分比，1% = 10_000 pips，超过费率上限的按上限换算；其余字段按 0（默认值）解析
+    fn unpack_legacy(data: &[u8]) -> Result<Self, ProgramError> {
+        let mut config = Self::deserialize(&mut &[0u8; Self::LEN][..])?;
+        let fee_rate_pips = data[0] as u32 * (FEE_RATE_DENOMINATOR / 100) as u32;
+        if fee_rate_pips > MAX_FEE_RATE_PIPS {
+            msg!("初版配置费率 {}% 超过上限，按 {} pips 解析", data[0], MAX_FEE_RATE_PIPS);
+        }
+        config.fee_rate_pips = fee_rate_pips.min(MAX_FEE_RATE_PIPS);
+        config.fee_wallet = Pubkey::new_from_array(<[u8; 32]>::try_from(&data[1..Self::LEGACY_LEN]).unwrap());
+        Ok(config)
+    }
+
+    // 路由实际使用的费率：命中覆盖条目时使用覆盖费率，否则为全局费率
+    pub fn fee_rate_for(&self, selector: &[u8; 8]) -> u32 {
+        self.route_fee_overrides
+            .iter()
+            .find(|o| o.selector != [0u8; 8] && &o.selector == selector)
+            .map_or(self.fee_rate_pips, |o| o.rate_pips)
+    }
+
+    pub fn is_route_paused(&self, selector: &[u8; 8]) -> bool {
+        self.paused_routes.iter().any(|s| s != &[0u8; 8] && s == selector)
+    }
+
+    // 扣除返还部分后实际收取的手续费: fee * (10_000 - rebate_bps) / 10_000
+    pub fn fee_after_rebate(&self, fee: u64) -> u64 {
+        let kept = BPS_DENOMINATOR.saturating_sub(self.rebate_bps as u64);
+        (fee as u128 * kept as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+
+    // 计费金额超过大额阈值时扣除大额返还: fee * (10_000 - whale_rebate_bps) / 10_000，恰好等于阈值时不返还
+    pub fn fee_after_whale_rebate(&self, amount: u64, fee: u64) -> u64 {
+        if self.whale_threshold == 0 || amount <= self.whale_threshold {
+            return fee;
+        }
+        let kept = BPS_DENOMINATOR.saturating_sub(self.whale_rebate_bps as u64);
+        (fee as u128 * kept as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+
+    // 协议已闲置超过 dormant_waiver_slots 个 slot，尚无交易记录时不视为闲置
+    pub fn is_dormant(&self, slot: u64) -> bool {
+        self.dormant_waiver_slots != 0
+            && self.last_trade_slot != 0
+            && slot.saturating_sub(self.last_trade_slot) > self.dormant_waiver_slots as u64
+    }
+
+    pub fn preflight(&self, check: u8) -> bool {
+        self.preflight_checks & check != 0
+    }
+
+    pub fn set_preflight(&mut self, check: u8, enabled: bool) {
+        match enabled {
+            true => self.preflight_checks |= check,
+            false => self.preflight_checks &= !check,
+        }
+    }
+
+    pub fn in_promo_window(&self, slot: u64) -> bool {
+        self.promo_end_slot != 0 && (self.promo_start_slot..self.promo_end_slot).contains(&slot)
+    }
+
+    pub fn inner_data_limit(&self) -> usize {
+        match self.max_inner_data_len {
+            0 => DEFAULT_MAX_INNER_DATA_LEN as usize,
+            limit => limit as usize,
+        }
+    }
+
+    // 推荐码实际分成比例，不超过配置的上限
+    pub fn referral_share_for(&self, registered_bps: u16) -> u16 {
+        match self.referral_share_bps {
+            0 => registered_bps,
+            cap => registered_bps.min(cap),
+        }
+    }
+
+    pub fn is_mint_blocked(&self, mint: &Pubkey) -> bool {
+        mint != &Pubkey::default() && self.blocked_mints.contains(mint)
+    }
+
+    // 默认推荐人按推荐码登记的形式返回，code 记为 0
+    pub fn default_referral(&self) -> Option<ReferralState> {
+        if self.default_referrer == Pubkey::default() {
+            return None;
+        }
+        Some(ReferralState {
+            code: 0,
+            wallet: self.default_referrer,
+            share_bps: self.default_referrer_share_bps,
+            revoked: false,
+        })
+    }
+
+    pub fn fee_wallet_backup(&self) -> Option<Pubkey> {
+        (self.fee_wallet_backup != Pubkey::default()).then_some(self.fee_wallet_backup)
+    }
+
+    pub fn is_guardian(&self, key: &Pubkey) -> bool {
+        self.guardian != Pubkey::default() && &self.guardian == key
+    }
+
+    // 管理员即当前协议费钱包，必须签名
+    pub fn check_admin(&self, admin: &AccountInfo) -> ProgramResult {
+        if !admin.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if *admin.key != self.fee_wallet {
+            return Err(ProgramError::IllegalOwner);
+        }
+        Ok(())
+    }
+
+    // 统计账户中签名的不同多签管理员，未达到门限时拒绝
+    pub fn check_multisig(&self, accounts: &[AccountInfo]) -> ProgramResult {
+        if self.multisig_threshold == 0 {
+            return Ok(());
+        }
+        let approvals = self
+            .multisig_admins
+            .iter()
+            .filter(|admin| **admin != Pubkey::default())
+            .filter(|admin| accounts.iter().any(|acc| acc.is_signer && acc.key == *admin))
+            .count();
+        if approvals < self.multisig_threshold as usize {
+            msg!("多签管理员签名数 {} 未达到门限 {}", approvals, self.multisig_threshold);
+            return Err(MyError::MultisigThresholdNotMet.into());
+        }
+        Ok(())
+    }
+
+    // 把费率降为 0（或全额返还）等于放弃协议收入，开启多签时即使是普通参数也需要达到门限
+    pub fn check_zero_fee_multisig(&self, accounts: &[AccountInfo], zeroes_fee: bool) -> ProgramResult {
+        if zeroes_fee {
+            return self.check_multisig(accounts);
+        }
+        Ok(())
+    }
+
+    // 写回配置账户；账户按旧版较短的布局分配时无法容纳新字段，明确报错提示先迁移扩容
+    pub fn store(&self, account: &AccountInfo) -> ProgramResult {
+        if account.data_len() < Self::LEN {
+            msg!("配置账户长度 {} 小于当前布局所需的 {}，请先执行 mig_cfg 迁移（realloc）配置账户", account.data_len(), Self::LEN);
+            return Err(MyError::ConfigAccountTooSmall.into());
+        }
+        self.serialize(&mut &mut account.data.borrow_mut()[..])?;
+        Ok(())
+    }
+
+    // 读取配置并校验管理员，配置账户必须归本程序所有
+    pub fn load_as_admin(
+        program_id: &Pubkey,
+        config_account: &AccountInfo,
+        admin: &AccountInfo,
+    ) -> Result<Self, ProgramError> {
+        if config_account.owner != program_id {
+            return Err(ProgramError::IllegalOwner);
+        }
+        let config = Self::unpack(&config_account.data.borrow())?;
+        config.check_admin(admin)?;
+        Ok(config)
+    }
+}
+
+// UPDATE_CONFIG 的部分更新，None 表示保持不变；字段只在末尾追加，客户端需按完整结构 borsh 编码。
+// 费率钱包、多签、白名单等高风险设置仍使用各自的指令
+#[derive(Debug, Default, BorshSerialize, BorshDeserialize)]
+pub struct ConfigUpdate {
+    pub fee_rate_pips: Option<u32>,
+    pub free_trades: Option<u32>,
+    pub referral_share_bps: Option<u16>,
+    pub buyback_wallet: Option<Pubkey>,
+    pub buyback_share_bps: Option<u16>,
+    pub default_referrer_share_bps: Option<u16>,
+    pub rebate_bps: Option<u16>,
+    pub fee_timing: Option<FeeTiming>,
+    pub buy_fee_timing: Option<FeeTiming>,
+    pub fee_sides: Option<FeeSides>,
+    pub on_fee_failure: Option<FeeFailurePolicy>,
+    pub max_inner_data_len: Option<u32>,
+    pub whale_threshold: Option<u64>,
+    pub whale_rebate_bps: Option<u16>,
+    pub payer_balance_check: Option<bool>,
+    pub compact_events: Option<bool>,
+}
+
+impl ConfigUpdate {
+    // 把给出的字段写入配置，校验由调用方在写入后对完整配置统一进行
+    pub fn apply(&self, config: &mut TradeFeeState) {
+        fn set<T: Copy>(field: &mut T, value: Option<T>) {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
+        set(&mut config.fee_rate_pips, self.fee_rate_pips);
+        set(&mut config.free_trades, self.free_trades);
+        set(&mut config.referral_share_bps, self.referral_share_bps);
+        set(&mut config.buyback_wallet, self.buyback_wallet);
+        set(&mut config.buyback_share_bps, self.buyback_share_bps);
+        set(&mut config.default_referrer_share_bps, self.default_referrer_share_bps);
+        set(&mut config.rebate_bps, self.rebate_bps);
+        set(&mut config.fee_timing, self.fee_timing);
+        set(&mut config.buy_fee_timing, self.buy_fee_timing);
+        set(&mut config.fee_sides, self.fee_sides);
+        set(&mut config.on_fee_failure, self.on_fee_failure);
+        set(&mut config.max_inner_data_len, self.max_inner_data_len);
+        set(&mut config.whale_threshold, self.whale_threshold);
+        set(&mut config.whale_rebate_bps, self.whale_rebate_bps);
+        if let Some(enabled) = self.payer_balance_check {
+            config.set_preflight(PREFLIGHT_PAYER_BALANCE, enabled);
+        }
+        set(&mut config.compact_events, self.compact_events);
+    }
+}
+
+// 推荐码登记，PDA 种子为 [b"referral", code]
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct ReferralState {
+    pub code: u32,
+    pub wallet: Pubkey,
+    pub share_bps: u16,
+    pub revoked: bool,
+}
+
+impl ReferralState {
+    pub const LEN: usize = 4 + 32 + 2 + 1;
+}
+
+// 单个代币的费率覆盖，PDA 种子为 [b"mint_fee", mint]
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct MintFeeState {
+    pub mint: Pubkey,
+    pub rate_pips: u32,
+}
+
+impl MintFeeState {
+    pub const LEN: usize = 32 + 4;
+}
+
+// 免手续费的合作方代币登记，PDA 种子为 [b"fee_exempt", mint]
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct FeeExemptMintState {
+    pub mint: Pubkey,
+}
+
+impl FeeExemptMintState {
+    pub const LEN: usize = 32;
+}
+
+// 以代币收费时的手续费下限，PDA 种子为 [b"token_floor"]，按交易代币的 decimals 换算为最小单位
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct TokenFeeFloorState {
+    // 以百万分之一个代币（UI 单位）计
+    pub min_fee_micros: u64,
+}
+
+impl TokenFeeFloorState {
+    pub const LEN: usize = 8;
+}
+
+// 代付额度，PDA 种子为 [b"allowance", 代付方, 交易发起人]；PDA 中除租金外的 lamports 即为代付方预存的手续费
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct AllowanceState {
+    pub sponsor: Pubkey,
+    pub delegate: Pubkey,
+    pub remaining: u64,
+}
+
+impl AllowanceState {
+    pub const LEN: usize = 32 + 32 + 8;
+}
+
+// 路由额外接受的目标程序（DEX 重新部署后的新地址或迁移前的旧地址），PDA 种子为 [b"route_prog", 路由选择器, 程序]
+// version 为运营自定的版本号，只用于日志
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct RouteProgramState {
+    pub selector: [u8; 8],
+    pub program: Pubkey,
+    pub version: u16,
+}
+
+impl RouteProgramState {
+    pub const LEN: usize = 8 + 32 + 2;
+}
+
+// 协议收入托管，PDA 种子为 [b"escrow"]，每个 epoch 最多释放 release_per_epoch lamports
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct EscrowState {
+    pub total: u64,
+    pub released: u64,
+    pub last_release_slot: u64,
+    pub release_per_epoch: u64,
+}
+
+impl EscrowState {
+    pub const LEN: usize = 8 * 4;
+}
+
+// 单个 epoch 的手续费汇总，PDA 种子为 [b"rollup", epoch u64]
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct FeeRollupState {
+    pub epoch: u64,
+    pub total_fees: u64,
+    pub trade_count: u64,
+}
+
+impl FeeRollupState {
+    pub const LEN: usize = 8 * 3;
+}
+
+// 钱包交易计数，PDA 种子为 [b"wallet", 钱包地址]
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct WalletState {
+    pub trade_count: u64,
+    // 最近一笔交易的 slot，用于交易冷却
+    pub last_trade_slot: u64,
+}
+
+impl WalletState {
+    pub const LEN: usize = 8 + 8;
+
+    // 旧版本账户只有 trade_count，缺失的字段按 0 解析
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        let mut buf = [0u8; Self::LEN];
+        let len = data.len().min(Self::LEN);
+        buf[..len].copy_from_slice(&data[..len]);
+        Ok(Self::deserialize(&mut &buf[..])?)
+    }
+}
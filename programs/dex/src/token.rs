@@ -0,0 +1,182 @@
+use arrayref::{array_ref, array_refs};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::invoke,
+    program_error::ProgramError,
+    pubkey,
+    pubkey::Pubkey,
+    system_program,
+};
+
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+// SPL Token 账户基础布局长度，Token-2022 的扩展数据位于其后
+const TOKEN_ACCOUNT_LEN: usize = 165;
+// Mint 账户中 decimals 的偏移（mint_authority COption 36 + supply 8）
+const MINT_DECIMALS_OFFSET: usize = 44;
+// Token-2022 扩展：基础布局补齐到 165 字节后为 1 字节账户类型，随后为 TLV 扩展 [类型 u16][长度 u16][数据]
+const EXTENSIONS_OFFSET: usize = TOKEN_ACCOUNT_LEN + 1;
+// TransferHook 扩展数据：[authority 32][hook 程序 32]，hook 程序为全零表示未挂载
+const EXTENSION_TRANSFER_HOOK: u16 = 14;
+// 代币账户 state 字段：0 未初始化，1 正常，2 冻结
+pub const ACCOUNT_STATE_FROZEN: u8 = 2;
+// Token 程序 TransferChecked 指令号，ATA 程序 CreateIdempotent 指令号
+const TRANSFER_CHECKED: u8 = 12;
+const CREATE_IDEMPOTENT: u8 = 1;
+
+// 只解析路由校验用到的字段
+#[derive(Debug, Clone)]
+pub struct TokenAccount {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub delegate: Option<Pubkey>,
+    pub state: u8,
+    pub delegated_amount: u64,
+}
+
+impl TokenAccount {
+    pub fn unpack(account: &AccountInfo) -> Result<Self, ProgramError> {
+        if account.owner != &TOKEN_PROGRAM_ID && account.owner != &TOKEN_2022_PROGRAM_ID {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let data = account.try_borrow_data()?;
+        if data.len() < TOKEN_ACCOUNT_LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let src = array_ref![data, 0, TOKEN_ACCOUNT_LEN];
+        let (mint, owner, amount, delegate, state, _is_native, delegated_amount, _close_authority) =
+            array_refs![src, 32, 32, 8, 36, 1, 12, 8, 36];
+
+        Ok(TokenAccount {
+            mint: Pubkey::new_from_array(*mint),
+            owner: Pubkey::new_from_array(*owner),
+            amount: u64::from_le_bytes(*amount),
+            delegate: unpack_coption_key(delegate),
+            state: state[0],
+            delegated_amount: u64::from_le_bytes(*delegated_amount),
+        })
+    }
+}
+
+// 钱包在指定代币程序下的关联代币账户地址
+pub fn associated_token_address(wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+pub fn mint_decimals(mint: &AccountInfo) -> Result<u8, ProgramError> {
+    if mint.owner != &TOKEN_PROGRAM_ID && mint.owner != &TOKEN_2022_PROGRAM_ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let data = mint.try_borrow_data()?;
+    data.get(MINT_DECIMALS_OFFSET).copied().ok_or(ProgramError::InvalidAccountData)
+}
+
+// Token-2022 mint 的 TransferHook 扩展挂载了 hook 程序时返回该程序，其余 mint 返回 None
+pub fn transfer_hook_program(mint: &AccountInfo) -> Result<Option<Pubkey>, ProgramError> {
+    if mint.owner != &TOKEN_2022_PROGRAM_ID {
+        return Ok(None);
+    }
+    let data = mint.try_borrow_data()?;
+    let mut offset = EXTENSIONS_OFFSET;
+    while let Some(header) = data.get(offset..offset + 4) {
+        let extension = u16::from_le_bytes([header[0], header[1]]);
+        let len = u16::from_le_bytes([header[2], header[3]]) as usize;
+        // 类型 0 为未初始化的填充
+        if extension == 0 {
+            break;
+        }
+        if extension == EXTENSION_TRANSFER_HOOK {
+            let program = data.get(offset + 4 + 32..offset + 4 + 64).ok_or(ProgramError::InvalidAccountData)?;
+            let program = Pubkey::new_from_array(program.try_into().unwrap());
+            return Ok((program != Pubkey::default()).then_some(program));
+        }
+        offset += 4 + len;
+    }
+    Ok(None)
+}
+
+// TransferChecked 兼容 Token 与 Token-2022，authority 需在交易中签名
+pub fn transfer_checked<'info>(
+    token_program: &AccountInfo<'info>,
+    source: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+) -> ProgramResult {
+    let mut data = Vec::with_capacity(10);
+    data.push(TRANSFER_CHECKED);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(mint_decimals(mint)?);
+
+    invoke(
+        &Instruction {
+            program_id: *token_program.key,
+            accounts: vec![
+                AccountMeta::new(*source.key, false),
+                AccountMeta::new_readonly(*mint.key, false),
+                AccountMeta::new(*destination.key, false),
+                AccountMeta::new_readonly(*authority.key, true),
+            ],
+            data,
+        },
+        &[source.clone(), mint.clone(), destination.clone(), authority.clone(), token_program.clone()],
+    )
+}
+
+// 关联代币账户已存在时不做处理，否则由 funder 创建
+pub fn create_associated_token_account_idempotent<'info>(
+    funder: &AccountInfo<'info>,
+    associated_account: &AccountInfo<'info>,
+    wallet: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    associated_token_program: &AccountInfo<'info>,
+) -> ProgramResult {
+    if associated_account.owner == token_program.key {
+        return Ok(());
+    }
+    invoke(
+        &Instruction {
+            program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*funder.key, true),
+                AccountMeta::new(*associated_account.key, false),
+                AccountMeta::new_readonly(*wallet.key, false),
+                AccountMeta::new_readonly(*mint.key, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(*token_program.key, false),
+            ],
+            data: vec![CREATE_IDEMPOTENT],
+        },
+        &[
+            funder.clone(),
+            associated_account.clone(),
+            wallet.clone(),
+            mint.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            associated_token_program.clone(),
+        ],
+    )
+}
+
+// COption<Pubkey>: 4 字节标记 + 32 字节公钥
+fn unpack_coption_key(src: &[u8; 36]) -> Option<Pubkey> {
+    let (tag, key) = array_refs![src, 4, 32];
+    match u32::from_le_bytes(*tag) {
+        1 => Some(Pubkey::new_from_array(*key)),
+        _ => None,
+    }
+}
@@ -0,0 +1,183 @@
+use solana_program::{
+    account_info::AccountInfo,
+    bpf_loader_upgradeable,
+    entrypoint::ProgramResult,
+    instruction::{get_stack_height, Instruction, TRANSACTION_LEVEL_STACK_HEIGHT},
+    msg,
+    program_error::ProgramError,
+    program::{invoke, invoke_signed},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::{
+        instructions::{
+            self as instructions_sysvar, load_current_index_checked, load_instruction_at_checked,
+        },
+        Sysvar,
+    },
+};
+
+use crate::error::MyError;
+
+// ProgramData 账户布局: [u32 枚举标记 = 3][slot u64][Option<Pubkey>: 1 + 32]
+const PROGRAM_DATA_TAG: u32 = 3;
+const PROGRAM_DATA_HEADER_LEN: usize = 4 + 8 + 1 + 32;
+
+// 按地址在账户列表中查找扩展账户（推荐人、PDA 等追加在转发账户之后）
+pub fn find_account<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    key: &Pubkey,
+) -> Option<&'a AccountInfo<'info>> {
+    accounts.iter().find(|acc| acc.key == key)
+}
+
+// 通过系统程序转账 SOL，金额为 0 或转给自己（同一钱包重复出现）时直接跳过
+pub fn transfer_lamports<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    lamports: u64,
+) -> ProgramResult {
+    if lamports == 0 || from.key == to.key {
+        return Ok(());
+    }
+    invoke(
+        &system_instruction::transfer(from.key, to.key, lamports),
+        &[from.clone(), to.clone(), system_program.clone()],
+    )
+}
+
+// 创建由本程序拥有的 PDA 账户；若地址上已有 lamports，则补足租金后 allocate + assign
+pub fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    pda: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    program_id: &Pubkey,
+    space: usize,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let required = Rent::get()?.minimum_balance(space);
+    let current = pda.lamports();
+
+    if current == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(payer.key, pda.key, required, space as u64, program_id),
+            &[payer.clone(), pda.clone(), system_program.clone()],
+            &[seeds],
+        );
+    }
+
+    transfer_lamports(payer, pda, system_program, required.saturating_sub(current))?;
+    invoke_signed(
+        &system_instruction::allocate(pda.key, space as u64),
+        &[pda.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(pda.key, program_id),
+        &[pda.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
+
+// 扩大本程序所有的账户，由 payer 补足新长度所需的租金，账户已足够大时不做处理
+pub fn grow_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> ProgramResult {
+    if account.data_len() >= new_len {
+        return Ok(());
+    }
+    let required = Rent::get()?.minimum_balance(new_len);
+    transfer_lamports(payer, account, system_program, required.saturating_sub(account.lamports()))?;
+    account.resize(new_len)
+}
+
+// 遍历交易中的全部指令（需要在账户中传入 Instructions sysvar），存在满足条件的指令时返回 true
+pub fn transaction_has_instruction(
+    accounts: &[AccountInfo],
+    predicate: impl Fn(&Instruction) -> bool,
+) -> Result<bool, ProgramError> {
+    let sysvar = instructions_sysvar_account(accounts)?;
+
+    let mut index = 0;
+    while let Ok(ix) = load_instruction_at_checked(index, sysvar) {
+        if predicate(&ix) {
+            return Ok(true);
+        }
+        index += 1;
+    }
+    Ok(false)
+}
+
+fn instructions_sysvar_account<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+) -> Result<&'a AccountInfo<'info>, ProgramError> {
+    find_account(accounts, &instructions_sysvar::ID).ok_or_else(|| {
+        msg!("缺少 Instructions sysvar 账户");
+        ProgramError::NotEnoughAccountKeys
+    })
+}
+
+// 当前指令是否由其他程序通过 CPI 调用，只读取调用栈高度，不需要 Instructions sysvar
+pub fn invoked_via_cpi() -> bool {
+    get_stack_height() > TRANSACTION_LEVEL_STACK_HEIGHT
+}
+
+// 通过 CPI 调用时返回发起调用的顶层指令所属程序，直接调用时返回 None
+// 多层嵌套时只能识别最外层程序
+pub fn cpi_caller(accounts: &[AccountInfo]) -> Result<Option<Pubkey>, ProgramError> {
+    if !invoked_via_cpi() {
+        return Ok(None);
+    }
+    let sysvar = instructions_sysvar_account(accounts)?;
+    let index = load_current_index_checked(sysvar)?;
+    Ok(Some(load_instruction_at_checked(index as usize, sysvar)?.program_id))
+}
+
+pub fn program_data_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id()).0
+}
+
+// 从 BpfLoaderUpgradeable 的 ProgramData 账户读取升级权限，程序不可升级时返回 None
+pub fn load_upgrade_authority(
+    program_id: &Pubkey,
+    program_data: &AccountInfo,
+) -> Result<Option<Pubkey>, ProgramError> {
+    if program_data.owner != &bpf_loader_upgradeable::id()
+        || program_data.key != &program_data_address(program_id)
+    {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let data = program_data.try_borrow_data()?;
+    if data.len() < PROGRAM_DATA_HEADER_LEN
+        || u32::from_le_bytes(data[0..4].try_into().unwrap()) != PROGRAM_DATA_TAG
+    {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    match data[12] {
+        0 => Ok(None),
+        _ => Ok(Some(Pubkey::new_from_array(data[13..45].try_into().unwrap()))),
+    }
+}
+
+// 高风险管理操作额外要求签名者是程序的升级权限
+pub fn check_upgrade_authority(
+    program_id: &Pubkey,
+    program_data: &AccountInfo,
+    signer: &AccountInfo,
+) -> ProgramResult {
+    if !signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    match load_upgrade_authority(program_id, program_data)? {
+        Some(authority) if authority == *signer.key => Ok(()),
+        _ => {
+            msg!("{} 不是程序的升级权限", signer.key);
+            Err(MyError::NotUpgradeAuthority.into())
+        }
+    }
+}
@@ -0,0 +1,86 @@
+// 转发账户顺序校验：按路由的账户描述逐个比对可写、签名标记，顺序错误时在收费与 CPI 前返回 AccountOrderMismatch，
+// 日志给出出错位置、期望的账户与实际传入的地址；explain 同样先做此校验
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::raydium::RAYDIUM_BUY_BASE_OUT_ROUTE,
+    ix_builder::{explain_ix, pump_buy_ix, pump_sell_ix, raydium_buy_base_out_ix},
+};
+use common::{
+    mocks::{PUMP_PROGRAM, RAYDIUM_AMM_V4_PROGRAM},
+    route_accounts, runtime::program_output, PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+fn setup() -> (TestEnv, Pubkey, PumpCurve) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 5_000_000);
+    (env, user, curve)
+}
+
+// mint 与 bonding_curve 互换：第 3 个转发账户应为可写的 bonding_curve，实际为只读的 mint
+fn swapped_curve_buy(env: &TestEnv, user: &Pubkey, curve: &PumpCurve) -> Instruction {
+    let mut forwarded = curve.buy_accounts();
+    forwarded.swap(2, 3);
+    pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, forwarded)
+}
+
+fn assert_rejected_before_cpi(env: &mut TestEnv, ix: &Instruction, program: &Pubkey, user: &Pubkey) {
+    let (user_before, treasury_before) = (env.lamports(user), env.lamports(&env.admin));
+    let result = env.process(ix);
+    assert!(result.cpis_to(program).is_empty());
+    assert_eq!(result.unwrap_err(), MyError::AccountOrderMismatch.into());
+    assert_eq!((env.lamports(user), env.lamports(&env.admin)), (user_before, treasury_before));
+}
+
+#[test]
+fn misordered_pump_accounts_are_rejected() {
+    let (mut env, user, curve) = setup();
+    let ix = swapped_curve_buy(&env, &user, &curve);
+    assert_rejected_before_cpi(&mut env, &ix, &PUMP_PROGRAM, &user);
+
+    // 卖出时按买入的顺序传入 creator_vault 与 token_program
+    let ix = pump_sell_ix(&PROGRAM_ID, &env.fee_accounts(&user), 1_000_000, 0, curve.buy_accounts());
+    assert_rejected_before_cpi(&mut env, &ix, &PUMP_PROGRAM, &user);
+
+    // 顺序正确时照常转发
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    assert_eq!(env.process(&ix).assert_ok().cpis_to(&PUMP_PROGRAM).len(), 1);
+}
+
+#[test]
+fn misordered_raydium_accounts_are_rejected() {
+    let (mut env, user, _) = setup();
+    // token_program 与 amm 互换：第 1 个转发账户应为可写的 amm
+    let mut forwarded = route_accounts(&RAYDIUM_BUY_BASE_OUT_ROUTE, 17, &user);
+    forwarded.push(AccountMeta::new_readonly(RAYDIUM_AMM_V4_PROGRAM, false));
+    forwarded.swap(0, 1);
+    let ix = raydium_buy_base_out_ix(&PROGRAM_ID, &env.fee_accounts(&user), 2 * SOL, 123_456, forwarded);
+    assert_rejected_before_cpi(&mut env, &ix, &RAYDIUM_AMM_V4_PROGRAM, &user);
+}
+
+#[test]
+fn explain_reports_the_same_mismatch() {
+    let (mut env, user, curve) = setup();
+    let ix = explain_ix(&swapped_curve_buy(&env, &user, &curve));
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::AccountOrderMismatch.into());
+}
+
+#[test]
+fn mismatch_log_names_the_position_and_the_expected_account() {
+    let (mut env, user, curve) = setup();
+    let ix = swapped_curve_buy(&env, &user, &curve);
+    let Some(output) = program_output("mismatch_log_names_the_position_and_the_expected_account", || {
+        assert_eq!(env.process(&ix).unwrap_err(), MyError::AccountOrderMismatch.into());
+    }) else {
+        return;
+    };
+    // 子进程中重新生成的地址与本进程不同，只比较到地址之前
+    let line = "路由 pump_buy 转发账户 3 应为可写的 bonding_curve，实际为只读的 ";
+    assert!(output.contains(line), "{line}\n{output}");
+}
@@ -0,0 +1,87 @@
+// 代付额度：代付方预存手续费到 [b"allowance", 代付方, 交易发起人] PDA，代付交易中支付者不签名，
+// 交易发起人先行垫付 SOL 手续费，兑换后按实际手续费从额度中扣减并报销；额度不足时整笔回滚
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::allowance::allowance_address,
+    ix_builder::{approve_allowance_ix, pump_buy_ix, revoke_allowance_ix, with_allowance},
+    state::AllowanceState,
+};
+use borsh::BorshDeserialize;
+use common::{PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey, rent::Rent};
+
+const FEE: u64 = SOL / 100;
+
+// 代付方授权 lamports 额度给交易发起人
+fn setup(lamports: u64) -> (TestEnv, Pubkey, Pubkey, PumpCurve, Pubkey) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let sponsor = env.wallet(SOL);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    env.process(&approve_allowance_ix(&PROGRAM_ID, &sponsor, &user, lamports)).assert_ok();
+    let allowance = allowance_address(&PROGRAM_ID, &sponsor, &user).0;
+    (env, sponsor, user, curve, allowance)
+}
+
+fn sponsored_buy(env: &TestEnv, sponsor: &Pubkey, user: &Pubkey, curve: &PumpCurve) -> Instruction {
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(sponsor), SOL, 2 * SOL, curve.buy_accounts());
+    with_allowance(&PROGRAM_ID, ix, user)
+}
+
+fn remaining(env: &TestEnv, allowance: &Pubkey) -> u64 {
+    AllowanceState::try_from_slice(env.data(allowance)).unwrap().remaining
+}
+
+#[test]
+fn trade_within_the_allowance_is_reimbursed() {
+    let (mut env, sponsor, user, curve, allowance) = setup(2 * FEE);
+    let rent = Rent::default().minimum_balance(AllowanceState::LEN);
+    assert_eq!(env.lamports(&allowance), rent + 2 * FEE);
+    assert_eq!(remaining(&env, &allowance), 2 * FEE);
+
+    let (user_before, sponsor_before, treasury_before) = (env.lamports(&user), env.lamports(&sponsor), env.lamports(&env.admin));
+    let ix = sponsored_buy(&env, &sponsor, &user, &curve);
+    assert!(!ix.accounts[2].is_signer);
+    env.process(&ix).assert_ok();
+
+    // 手续费由额度承担，交易发起人垫付后全额报销，只支付扣费后的兑换金额；代付方钱包不变
+    assert_eq!(env.lamports(&env.admin) - treasury_before, FEE);
+    assert_eq!((user_before - env.lamports(&user), env.lamports(&sponsor)), (SOL - FEE, sponsor_before));
+    assert_eq!((remaining(&env, &allowance), env.lamports(&allowance)), (FEE, rent + FEE));
+
+    // 剩余额度恰好覆盖第二笔
+    let ix = sponsored_buy(&env, &sponsor, &user, &curve);
+    env.process(&ix).assert_ok();
+    assert_eq!((remaining(&env, &allowance), env.lamports(&allowance)), (0, rent));
+}
+
+#[test]
+fn trade_over_the_allowance_is_rolled_back() {
+    let (mut env, sponsor, user, curve, allowance) = setup(FEE - 1);
+    let (user_before, treasury_before) = (env.lamports(&user), env.lamports(&env.admin));
+
+    let ix = sponsored_buy(&env, &sponsor, &user, &curve);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::AllowanceExceeded.into());
+    assert_eq!((env.lamports(&user), env.lamports(&env.admin)), (user_before, treasury_before));
+    assert_eq!(remaining(&env, &allowance), FEE - 1);
+
+    // 撤销后额度与租金退还给代付方
+    let sponsor_before = env.lamports(&sponsor);
+    let refund = env.lamports(&allowance);
+    env.process(&revoke_allowance_ix(&PROGRAM_ID, &sponsor, &user)).assert_ok();
+    assert!(env.account(&allowance).is_none());
+    assert_eq!(env.lamports(&sponsor) - sponsor_before, refund);
+}
+
+#[test]
+fn unsigned_payer_needs_an_allowance_for_this_delegate() {
+    let (mut env, sponsor, _, _, _) = setup(2 * FEE);
+    // 额度授权给了另一个交易发起人
+    let other = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&other, 0);
+    let ix = sponsored_buy(&env, &sponsor, &other, &curve);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::MissingRequiredSignature);
+    assert_eq!(env.lamports(&other), 10 * SOL);
+}
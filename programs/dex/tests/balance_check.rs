@@ -0,0 +1,69 @@
+// 支付者余额预检：开启后在收费与 CPI 前确认支付者能承担手续费、直接花费的输入与免租金额，不足时提前返回 InsufficientFunds
+mod common;
+
+use amm_proxy_contract::{
+    ix_builder::{pump_buy_ix, pump_sell_ix, set_balance_check_ix},
+    state::PREFLIGHT_PAYER_BALANCE,
+};
+use common::{
+    mocks::{set_swap_behavior, SwapBehavior, PUMP_PROGRAM},
+    TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{program_error::ProgramError, rent::Rent};
+
+fn env_with_check(enabled: bool) -> TestEnv {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_balance_check_ix(&PROGRAM_ID, &env.config, &env.admin, enabled);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().preflight(PREFLIGHT_PAYER_BALANCE), enabled);
+    env
+}
+
+#[test]
+fn under_funded_payer_is_rejected_before_the_cpi() {
+    // 1 SOL 买入后剩余不足免租金额
+    let rent = Rent::default().minimum_balance(0);
+    for enabled in [true, false] {
+        let mut env = env_with_check(enabled);
+        let user = env.wallet(SOL + rent / 2);
+        let curve = env.pump_curve(&user, 0);
+        let treasury_before = env.lamports(&env.admin);
+
+        let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+        let result = env.process(&ix);
+        // 未开启时照常收费并调用 Pump，在内层转账后才失败
+        assert_eq!(result.cpis_to(&PUMP_PROGRAM).is_empty(), enabled);
+        if enabled {
+            assert_eq!(result.unwrap_err(), ProgramError::InsufficientFunds);
+        } else {
+            assert!(result.result.is_err());
+        }
+        assert_eq!((env.lamports(&user), env.lamports(&env.admin)), (SOL + rent / 2, treasury_before));
+    }
+}
+
+#[test]
+fn funded_payer_passes_the_check() {
+    let mut env = env_with_check(true);
+    let rent = Rent::default().minimum_balance(0);
+    let user = env.wallet(SOL + rent);
+    let curve = env.pump_curve(&user, 0);
+
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    env.process(&ix).assert_ok();
+    assert_eq!(env.lamports(&user), rent);
+}
+
+#[test]
+fn sell_is_charged_from_the_proceeds_without_a_check() {
+    // 卖出兑换后从所得中收费，支付者只有免租金额时同样可以卖出
+    let mut env = env_with_check(true);
+    let rent = Rent::default().minimum_balance(0);
+    let user = env.wallet(rent);
+    let curve = env.pump_curve(&user, SOL);
+    set_swap_behavior(SwapBehavior { output: Some(SOL), ..Default::default() });
+
+    let ix = pump_sell_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 1, curve.sell_accounts());
+    env.process(&ix).assert_ok();
+    assert_eq!(env.lamports(&user), rent + SOL - SOL / 100);
+}
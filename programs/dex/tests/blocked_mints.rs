@@ -0,0 +1,63 @@
+// 禁止的 mint：转发账户中出现被禁止的 mint，或用户代币账户属于被禁止的 mint 时，在收费与 CPI 之前拒绝
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::meteora::DAMM_V2_SELL_ROUTE,
+    ix_builder::{block_mint_ix, damm_v2_sell_ix, pump_buy_ix},
+    token::TOKEN_PROGRAM_ID,
+};
+use common::{
+    mocks::{DAMM_V2_PROGRAM, PUMP_PROGRAM},
+    route_accounts, Account, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::instruction::AccountMeta;
+
+#[test]
+fn blocked_mint_is_rejected_while_others_trade() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let (blocked, allowed) = (env.pump_curve(&user, 0), env.pump_curve(&user, 0));
+    let ix = block_mint_ix(&PROGRAM_ID, &env.config, &env.admin, &blocked.mint, true);
+    env.process(&ix).assert_ok();
+    assert!(env.config_state().blocked_mints.contains(&blocked.mint));
+
+    let treasury_before = env.lamports(&env.admin);
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, blocked.buy_accounts());
+    let result = env.process(&ix);
+    assert!(result.cpis_to(&PUMP_PROGRAM).is_empty());
+    assert_eq!(result.unwrap_err(), MyError::MintBlocked.into());
+    assert_eq!(env.lamports(&env.admin), treasury_before);
+
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, allowed.buy_accounts());
+    env.process(&ix).assert_ok();
+
+    // 移出列表后恢复交易
+    let ix = block_mint_ix(&PROGRAM_ID, &env.config, &env.admin, &blocked.mint, false);
+    env.process(&ix).assert_ok();
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, blocked.buy_accounts());
+    env.process(&ix).assert_ok();
+}
+
+#[test]
+fn user_token_account_of_a_blocked_mint_is_rejected() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(SOL);
+    let mint = env.add_mint(6, &TOKEN_PROGRAM_ID);
+    let input = env.add_ata(&user, &mint, 5_000_000, &TOKEN_PROGRAM_ID);
+    let ix = block_mint_ix(&PROGRAM_ID, &env.config, &env.admin, &mint, true);
+    env.process(&ix).assert_ok();
+
+    // 转发账户中的 token_a_mint、token_b_mint 均为其他地址，只能从用户代币账户识别
+    let mut forwarded = route_accounts(&DAMM_V2_SELL_ROUTE, 13, &user);
+    forwarded[2] = AccountMeta::new(input, false);
+    forwarded.push(AccountMeta::new_readonly(DAMM_V2_PROGRAM, false));
+    env.set_account(forwarded[1].pubkey, Account::rent_exempt(vec![0; 64], DAMM_V2_PROGRAM));
+    let ix = damm_v2_sell_ix(&PROGRAM_ID, &env.fee_accounts(&user), 5_000_000, 1, forwarded);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::MintBlocked.into());
+
+    let stranger = env.wallet(SOL);
+    let ix = block_mint_ix(&PROGRAM_ID, &env.config, &stranger, &mint, false);
+    assert!(env.process(&ix).result.is_err());
+    assert!(env.config_state().blocked_mints.contains(&mint));
+}
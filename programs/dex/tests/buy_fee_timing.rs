@@ -0,0 +1,74 @@
+// 买入按输出代币收费：完整输入兑换后按代币账户的实际余额变化收费，转入协议钱包的关联代币账户（不存在时幂等创建）
+mod common;
+
+use amm_proxy_contract::{
+    instructions::fee::SwapResult,
+    ix_builder::{pump_buy_ix, set_buy_fee_timing_ix, with_token_fee_accounts},
+    state::FeeTiming,
+    token::{associated_token_address, TOKEN_PROGRAM_ID},
+};
+use borsh::BorshDeserialize;
+use common::{
+    mocks::{set_swap_behavior, SwapBehavior},
+    PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+
+const RECEIVED: u64 = 1_234_567;
+
+fn setup() -> (TestEnv, Pubkey, PumpCurve) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_buy_fee_timing_ix(&PROGRAM_ID, &env.config, &env.admin, FeeTiming::PostSwap as u8);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().buy_fee_timing, FeeTiming::PostSwap);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    set_swap_behavior(SwapBehavior { output: Some(RECEIVED), ..Default::default() });
+    (env, user, curve)
+}
+
+fn buy(env: &TestEnv, user: &Pubkey, curve: &PumpCurve) -> Instruction {
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, curve.buy_accounts());
+    with_token_fee_accounts(ix, &env.admin, &curve.mint, &TOKEN_PROGRAM_ID)
+}
+
+#[test]
+fn post_swap_buy_skims_the_measured_output() {
+    let (mut env, user, curve) = setup();
+    let treasury_ata = associated_token_address(&env.admin, &curve.mint, &TOKEN_PROGRAM_ID);
+    assert!(env.account(&treasury_ata).is_none());
+    let treasury_before = env.lamports(&env.admin);
+
+    // 首笔买入由 payer 出资创建协议钱包的关联代币账户
+    let ix = buy(&env, &user, &curve);
+    let result = env.process(&ix).assert_ok();
+    let fee = RECEIVED / 100;
+    assert_eq!(env.token_balance(&treasury_ata), fee);
+    assert_eq!(env.token_balance(&curve.associated_user), RECEIVED - fee);
+    // 完整输入进入兑换，不收取 SOL 手续费
+    assert_eq!(env.lamports(&env.admin), treasury_before);
+    assert_eq!(10 * SOL - env.lamports(&user), SOL + env.lamports(&treasury_ata));
+    let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!(
+        (swap.amount_in, swap.amount_out, swap.fee, swap.remaining, swap.breakdown.treasury),
+        (SOL, RECEIVED, fee, RECEIVED - fee, fee)
+    );
+
+    // 关联代币账户已存在时直接转入
+    let user_before = env.lamports(&user);
+    let ix = buy(&env, &user, &curve);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.token_balance(&treasury_ata), 2 * fee);
+    assert_eq!(env.token_balance(&curve.associated_user), 2 * (RECEIVED - fee));
+    assert_eq!(user_before - env.lamports(&user), SOL);
+}
+
+#[test]
+fn post_swap_buy_requires_the_token_fee_accounts() {
+    let (mut env, user, curve) = setup();
+
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::NotEnoughAccountKeys);
+    assert_eq!(env.lamports(&user), 10 * SOL);
+    assert_eq!(env.token_balance(&curve.associated_user), 0);
+}
@@ -0,0 +1,118 @@
+// Raydium CLMM 精确输出买入：手续费按 max_amount_in 预估并压低内层最大输入，兑换后按实际消耗的输入收取
+mod common;
+
+use amm_proxy_contract::{
+    instructions::{fee::SwapResult, raydium::RAYDIUM_CLMM_BUY_EXACT_OUT_ROUTE},
+    ix_builder::raydium_clmm_buy_exact_out_ix,
+    token::TOKEN_PROGRAM_ID,
+};
+use borsh::BorshDeserialize;
+use common::{
+    mocks::{read_u64, set_swap_behavior, SwapBehavior, RAYDIUM_CLMM_PROGRAM, RAYDIUM_CLMM_TOO_MUCH_INPUT_PAID},
+    route_accounts, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+const MAX_AMOUNT_IN: u64 = 2 * SOL;
+const AMOUNT_OUT: u64 = 5_000_000;
+
+struct Clmm {
+    env: TestEnv,
+    user: Pubkey,
+    input: Pubkey,
+    input_vault: Pubkey,
+}
+
+fn setup() -> Clmm {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let mint = env.add_mint(9, &TOKEN_PROGRAM_ID);
+    let input = env.add_ata(&user, &mint, MAX_AMOUNT_IN, &TOKEN_PROGRAM_ID);
+    let input_vault = env.add_ata(&Pubkey::new_unique(), &mint, 0, &TOKEN_PROGRAM_ID);
+    Clmm { env, user, input, input_vault }
+}
+
+impl Clmm {
+    // 13 个固定账户、1 个 tick array 与 CLMM 程序账户
+    fn buy_ix(&self) -> Instruction {
+        let mut forwarded = route_accounts(&RAYDIUM_CLMM_BUY_EXACT_OUT_ROUTE, 14, &self.user);
+        forwarded[3] = AccountMeta::new(self.input, false);
+        forwarded[5] = AccountMeta::new(self.input_vault, false);
+        forwarded[8] = AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false);
+        forwarded.push(AccountMeta::new_readonly(RAYDIUM_CLMM_PROGRAM, false));
+        let fee_accounts = self.env.fee_accounts(&self.user);
+        raydium_clmm_buy_exact_out_ix(&PROGRAM_ID, &fee_accounts, MAX_AMOUNT_IN, AMOUNT_OUT, 0, forwarded)
+    }
+}
+
+#[test]
+fn partial_fill_is_charged_on_the_consumed_input() {
+    let mut clmm = setup();
+    let consumed = MAX_AMOUNT_IN * 3 / 5;
+    set_swap_behavior(SwapBehavior { input: Some(consumed), ..Default::default() });
+    let (user_before, treasury_before) = (clmm.env.lamports(&clmm.user), clmm.env.lamports(&clmm.env.admin));
+
+    let ix = clmm.buy_ix();
+    let result = clmm.env.process(&ix).assert_ok();
+
+    // 内层 other_amount_threshold 为扣除预估手续费后的最大输入
+    let estimated_fee = MAX_AMOUNT_IN / 100;
+    let cpis = result.cpis_to(&RAYDIUM_CLMM_PROGRAM);
+    assert_eq!(read_u64(&cpis[0].data, 8), AMOUNT_OUT);
+    assert_eq!(read_u64(&cpis[0].data, 16), MAX_AMOUNT_IN - estimated_fee);
+
+    // 只按实际消耗的 3/5 收取，多估的部分留在用户钱包
+    let fee = estimated_fee * 3 / 5;
+    let swap = SwapResult::try_from_slice(&result.return_data.as_ref().unwrap().1).unwrap();
+    assert_eq!((swap.amount_in, swap.fee, swap.remaining), (consumed, fee, MAX_AMOUNT_IN - estimated_fee));
+    assert_eq!(clmm.env.lamports(&clmm.env.admin) - treasury_before, fee);
+    assert_eq!(user_before - clmm.env.lamports(&clmm.user), fee);
+    assert_eq!(clmm.env.token_balance(&clmm.input), MAX_AMOUNT_IN - consumed);
+    assert_eq!(clmm.env.token_balance(&clmm.input_vault), consumed);
+}
+
+#[test]
+fn full_fill_is_charged_the_estimated_fee() {
+    let mut clmm = setup();
+    let treasury_before = clmm.env.lamports(&clmm.env.admin);
+
+    // 消耗全部压低后的最大输入，手续费按消耗比例缩减
+    let ix = clmm.buy_ix();
+    let result = clmm.env.process(&ix).assert_ok();
+    let estimated_fee = MAX_AMOUNT_IN / 100;
+    let consumed = MAX_AMOUNT_IN - estimated_fee;
+    let fee = (estimated_fee as u128 * consumed as u128 / MAX_AMOUNT_IN as u128) as u64;
+    let swap = SwapResult::try_from_slice(&result.return_data.as_ref().unwrap().1).unwrap();
+    assert_eq!((swap.amount_in, swap.fee), (consumed, fee));
+    assert_eq!(clmm.env.lamports(&clmm.env.admin) - treasury_before, fee);
+}
+
+#[test]
+fn input_beyond_the_lowered_max_is_rolled_back() {
+    let mut clmm = setup();
+    set_swap_behavior(SwapBehavior { input: Some(MAX_AMOUNT_IN), ..Default::default() });
+    let treasury_before = clmm.env.lamports(&clmm.env.admin);
+
+    let ix = clmm.buy_ix();
+    assert_eq!(
+        clmm.env.process(&ix).unwrap_err(),
+        ProgramError::Custom(RAYDIUM_CLMM_TOO_MUCH_INPUT_PAID)
+    );
+    assert_eq!(clmm.env.lamports(&clmm.env.admin), treasury_before);
+    assert_eq!(clmm.env.token_balance(&clmm.input), MAX_AMOUNT_IN);
+}
+
+#[test]
+fn exact_input_data_is_rejected() {
+    let mut clmm = setup();
+    let mut ix = clmm.buy_ix();
+    // is_base_input = true
+    *ix.data.last_mut().unwrap() = 1;
+    let result = clmm.env.process(&ix);
+    assert!(result.cpis_to(&RAYDIUM_CLMM_PROGRAM).is_empty());
+    assert_eq!(result.unwrap_err(), ProgramError::InvalidInstructionData);
+}
@@ -0,0 +1,572 @@
+// 测试用的外部程序：最小化的 SPL Token / ATA 程序、记录收到的 CPI 的 Pump 内盘程序、可转出兑换所得的 PumpAMM、DAMM v2 与 Raydium 程序、
+// 按实际消耗转入输入的 Raydium CLMM 程序、
+// ed25519 验签程序、转发 CPI 的调用方程序，以及什么都不做的空程序。
+// 模拟程序与链上一样只修改自己拥有的账户，其他账户的变动通过 CPI 完成
+use std::cell::RefCell;
+
+use ed25519_dalek::{PublicKey, Signature};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program,
+};
+
+use amm_proxy_contract::token::ASSOCIATED_TOKEN_PROGRAM_ID;
+
+use super::runtime::{consume_compute_units, log};
+
+pub const PUMP_PROGRAM: Pubkey = pubkey!("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P");
+pub const PUMP_BUY_DISCRIMINATOR: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+pub const PUMP_SELL_DISCRIMINATOR: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+// Pump 的 TooMuchSolRequired、TooLittleSolReceived
+pub const PUMP_TOO_MUCH_SOL_REQUIRED: u32 = 6002;
+pub const PUMP_TOO_LITTLE_SOL_RECEIVED: u32 = 6003;
+pub const BONDING_CURVE_SEED: &[u8] = b"bonding-curve";
+pub const PUMP_AMM_PROGRAM: Pubkey = pubkey!("pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA");
+pub const PUMP_AMM_POOL_SEED: &[u8] = b"pool";
+pub const DAMM_V2_PROGRAM: Pubkey = pubkey!("cpamdpZCGKUy5JxQXB4dcpGPiikHawvSWAd6mEn1sGG");
+pub const RAYDIUM_AMM_V4_PROGRAM: Pubkey = pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+// swapBaseIn、swapBaseOut 指令号
+pub const RAYDIUM_SWAP_BASE_IN: u8 = 9;
+pub const RAYDIUM_SWAP_BASE_OUT: u8 = 11;
+pub const RAYDIUM_CLMM_PROGRAM: Pubkey = pubkey!("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK");
+// 模拟的 TooMuchInputPaid：实际输入超过 other_amount_threshold
+pub const RAYDIUM_CLMM_TOO_MUCH_INPUT_PAID: u32 = 6022;
+
+// SPL Token 布局与错误码
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+pub const MINT_LEN: usize = 82;
+pub const TOKEN_INSUFFICIENT_FUNDS: u32 = 1;
+pub const TOKEN_MINT_MISMATCH: u32 = 3;
+pub const TOKEN_OWNER_MISMATCH: u32 = 4;
+pub const TOKEN_ACCOUNT_FROZEN: u32 = 17;
+pub const TOKEN_MINT_DECIMALS_MISMATCH: u32 = 18;
+
+// PrecompileError::InvalidSignature
+pub const ED25519_INVALID_SIGNATURE: u32 = 2;
+
+const TOKEN_TRANSFER: u8 = 3;
+const TOKEN_CLOSE_ACCOUNT: u8 = 9;
+const TOKEN_TRANSFER_CHECKED: u8 = 12;
+const TOKEN_SYNC_NATIVE: u8 = 17;
+const TOKEN_INITIALIZE_ACCOUNT3: u8 = 18;
+
+// 模拟 DEX 的兑换结果：output 为兑换所得（未设置时按 1:1 兑换），input 为精确输出兑换实际消耗的输入
+// （未设置时消耗全部最大输入），error 使下一次兑换以自定义错误失败
+#[derive(Debug, Default, Clone)]
+pub struct SwapBehavior {
+    pub output: Option<u64>,
+    pub input: Option<u64>,
+    pub error: Option<u32>,
+    pub compute_units: u64,
+}
+
+thread_local! {
+    static SWAP: RefCell<SwapBehavior> = RefCell::new(SwapBehavior::default());
+}
+
+pub fn set_swap_behavior(behavior: SwapBehavior) {
+    SWAP.with(|swap| *swap.borrow_mut() = behavior);
+}
+
+pub fn swap_behavior() -> SwapBehavior {
+    SWAP.with(|swap| swap.borrow().clone())
+}
+
+pub fn noop_processor(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
+
+// 通过 CPI 调用其他程序的调用方：把指令数据原样发给第一个账户对应的程序，其余账户按原权限传入
+pub fn forwarder_processor(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (target, accounts) = accounts.split_first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let metas = accounts
+        .iter()
+        .map(|account| AccountMeta {
+            pubkey: *account.key,
+            is_signer: account.is_signer,
+            is_writable: account.is_writable,
+        })
+        .collect();
+    invoke(&Instruction { program_id: *target.key, accounts: metas, data: data.to_vec() }, accounts)
+}
+
+pub fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+pub fn pack_token_account(mint: &Pubkey, owner: &Pubkey, amount: u64, state: u8) -> Vec<u8> {
+    let mut data = vec![0u8; TOKEN_ACCOUNT_LEN];
+    data[0..32].copy_from_slice(mint.as_ref());
+    data[32..64].copy_from_slice(owner.as_ref());
+    data[64..72].copy_from_slice(&amount.to_le_bytes());
+    data[108] = state;
+    data
+}
+
+pub fn pack_mint(decimals: u8) -> Vec<u8> {
+    let mut data = vec![0u8; MINT_LEN];
+    data[44] = decimals;
+    data[45] = 1;
+    data
+}
+
+pub fn token_amount(data: &[u8]) -> u64 {
+    read_u64(data, 64)
+}
+
+fn set_token_amount(account: &AccountInfo, amount: u64) -> ProgramResult {
+    account.try_borrow_mut_data()?[64..72].copy_from_slice(&amount.to_le_bytes());
+    Ok(())
+}
+
+fn token_account<'a, 'info>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'info>],
+    index: usize,
+) -> Result<&'a AccountInfo<'info>, ProgramError> {
+    let account = accounts
+        .get(index)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if account.owner != program_id || account.data_len() < TOKEN_ACCOUNT_LEN {
+        log(format!("Token: {} 不是代币账户", account.key));
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(account)
+}
+
+// 所有者或委托额度足够的委托人签名，委托转账扣减剩余额度
+fn check_token_authority(
+    source: &AccountInfo,
+    authority: &AccountInfo,
+    amount: u64,
+) -> ProgramResult {
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut data = source.try_borrow_mut_data()?;
+    if data[32..64] == authority.key.to_bytes() {
+        return Ok(());
+    }
+    let delegated = read_u64(&data, 121);
+    if data[72] == 1 && data[76..108] == authority.key.to_bytes() && delegated >= amount {
+        data[121..129].copy_from_slice(&(delegated - amount).to_le_bytes());
+        return Ok(());
+    }
+    Err(ProgramError::Custom(TOKEN_OWNER_MISMATCH))
+}
+
+fn transfer_tokens(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    source: usize,
+    destination: usize,
+    authority: usize,
+    amount: u64,
+) -> ProgramResult {
+    let (source, destination) = (
+        token_account(program_id, accounts, source)?,
+        token_account(program_id, accounts, destination)?,
+    );
+    let authority = accounts
+        .get(authority)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let (source_data, destination_data) = (
+        source.data.borrow().to_vec(),
+        destination.data.borrow().to_vec(),
+    );
+    if source_data[108] == 2 || destination_data[108] == 2 {
+        return Err(ProgramError::Custom(TOKEN_ACCOUNT_FROZEN));
+    }
+    if source_data[0..32] != destination_data[0..32] {
+        return Err(ProgramError::Custom(TOKEN_MINT_MISMATCH));
+    }
+    check_token_authority(source, authority, amount)?;
+    let balance = token_amount(&source_data);
+    if balance < amount {
+        return Err(ProgramError::Custom(TOKEN_INSUFFICIENT_FUNDS));
+    }
+    if source.key == destination.key {
+        return Ok(());
+    }
+    set_token_amount(source, balance - amount)?;
+    set_token_amount(destination, token_amount(&destination_data) + amount)
+}
+
+// Token 与 Token-2022 共用：Transfer、TransferChecked、CloseAccount、SyncNative、InitializeAccount3
+pub fn token_processor(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    match data.first().copied() {
+        Some(TOKEN_TRANSFER) => transfer_tokens(program_id, accounts, 0, 1, 2, read_u64(data, 1)),
+        Some(TOKEN_TRANSFER_CHECKED) => {
+            let mint = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if mint.owner != program_id
+                || token_account(program_id, accounts, 0)?.data.borrow()[0..32]
+                    != mint.key.to_bytes()
+            {
+                return Err(ProgramError::Custom(TOKEN_MINT_MISMATCH));
+            }
+            if mint.data.borrow()[44] != data[9] {
+                return Err(ProgramError::Custom(TOKEN_MINT_DECIMALS_MISMATCH));
+            }
+            transfer_tokens(program_id, accounts, 0, 2, 3, read_u64(data, 1))
+        }
+        Some(TOKEN_CLOSE_ACCOUNT) => {
+            let account = token_account(program_id, accounts, 0)?;
+            let (destination, owner) = (&accounts[1], &accounts[2]);
+            if !owner.is_signer || account.data.borrow()[32..64] != owner.key.to_bytes() {
+                return Err(ProgramError::Custom(TOKEN_OWNER_MISMATCH));
+            }
+            let lamports = account.lamports();
+            **account.try_borrow_mut_lamports()? = 0;
+            **destination.try_borrow_mut_lamports()? += lamports;
+            account.try_borrow_mut_data()?.fill(0);
+            account.resize(0)?;
+            account.assign(&system_program::id());
+            Ok(())
+        }
+        Some(TOKEN_SYNC_NATIVE) => {
+            let account = token_account(program_id, accounts, 0)?;
+            let amount = account
+                .lamports()
+                .saturating_sub(Rent::default().minimum_balance(TOKEN_ACCOUNT_LEN));
+            set_token_amount(account, amount)
+        }
+        Some(TOKEN_INITIALIZE_ACCOUNT3) => {
+            let (account, mint) = (&accounts[0], &accounts[1]);
+            let mut account_data = account.try_borrow_mut_data()?;
+            if account.owner != program_id
+                || account_data.len() < TOKEN_ACCOUNT_LEN
+                || account_data[108] != 0
+            {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            account_data[..TOKEN_ACCOUNT_LEN].copy_from_slice(&pack_token_account(
+                mint.key,
+                &Pubkey::new_from_array(data[1..33].try_into().unwrap()),
+                0,
+                1,
+            ));
+            Ok(())
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+pub fn associated_token_address(
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+}
+
+// ATA 程序 Create / CreateIdempotent: [funder, ata, wallet, mint, system_program, token_program]
+pub fn ata_processor(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let [funder, ata, wallet, mint, system, token_program, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let (expected, bump) = associated_token_address(wallet.key, mint.key, token_program.key);
+    if ata.key != &expected {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if ata.owner == token_program.key {
+        return match data.first() {
+            Some(1) => Ok(()),
+            _ => Err(ProgramError::Custom(0)),
+        };
+    }
+
+    invoke_signed(
+        &system_instruction::create_account(
+            funder.key,
+            ata.key,
+            Rent::default().minimum_balance(TOKEN_ACCOUNT_LEN),
+            TOKEN_ACCOUNT_LEN as u64,
+            token_program.key,
+        ),
+        &[funder.clone(), ata.clone(), system.clone()],
+        &[&[
+            wallet.key.as_ref(),
+            token_program.key.as_ref(),
+            mint.key.as_ref(),
+            &[bump],
+        ]],
+    )?;
+    let mut initialize = vec![TOKEN_INITIALIZE_ACCOUNT3];
+    initialize.extend_from_slice(wallet.key.as_ref());
+    invoke(
+        &Instruction {
+            program_id: *token_program.key,
+            accounts: vec![
+                AccountMeta::new(*ata.key, false),
+                AccountMeta::new_readonly(*mint.key, false),
+            ],
+            data: initialize,
+        },
+        &[ata.clone(), mint.clone()],
+    )
+}
+
+pub fn bonding_curve_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BONDING_CURVE_SEED, mint.as_ref()], &PUMP_PROGRAM)
+}
+
+pub fn pump_amm_pool_address(base_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PUMP_AMM_POOL_SEED, base_mint.as_ref()], &PUMP_AMM_PROGRAM)
+}
+
+// ed25519 验签预编译程序：逐个校验偏移指向的签名，数据只能位于本指令内。验签失败时以
+// PrecompileError::InvalidSignature 的错误码使整笔交易失败
+pub fn ed25519_processor(_program_id: &Pubkey, _accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let field = |offset: usize| {
+        data.get(offset..offset + 2)
+            .map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()) as usize)
+            .ok_or(ProgramError::InvalidInstructionData)
+    };
+    let slice = |offset: usize, len: usize| data.get(offset..offset + len).ok_or(ProgramError::InvalidInstructionData);
+
+    let count = *data.first().ok_or(ProgramError::InvalidInstructionData)? as usize;
+    for base in (0..count).map(|index| 2 + index * 14) {
+        if [2, 6, 12].iter().any(|n| field(base + n) != Ok(u16::MAX as usize)) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let signature = Signature::from_bytes(slice(field(base)?, 64)?);
+        let public_key = PublicKey::from_bytes(slice(field(base + 4)?, 32)?);
+        let message = slice(field(base + 8)?, field(base + 10)?)?;
+        match (public_key, signature) {
+            (Ok(public_key), Ok(signature)) if public_key.verify_strict(message, &signature).is_ok() => {}
+            _ => {
+                log("Ed25519: 签名无效".to_string());
+                return Err(ProgramError::Custom(ED25519_INVALID_SIGNATURE));
+            }
+        }
+    }
+    Ok(())
+}
+
+// 不移动资产的 DEX：只按 SwapBehavior 失败或消耗计算单元，用于只关心转发内容与前置校验的路由
+pub fn dex_processor(_program_id: &Pubkey, _accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    apply_behavior("DEX").map(|_| ())
+}
+
+// Meteora DAMM v2 swap [swap 鉴别器][amount_in][minimum_amount_out]，账户为 swap 的 14 个账户。
+// 设置了 SwapBehavior::output 时把 output lamports 从池子（本程序所有）转入 output_token_account，
+// 模拟卖出所得的原生 SOL；未设置时与 dex_processor 相同，不移动资产
+pub fn damm_v2_processor(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 14 || data.len() < 24 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let behavior = apply_behavior("DAMM v2")?;
+    pay_out(program_id, &accounts[1], &accounts[3], behavior.output)
+}
+
+// Raydium AMM v4 swapBaseIn / swapBaseOut [指令号][数量][数量]，账户为 swap 的 17 个账户。
+// 与 damm_v2_processor 相同，设置了 SwapBehavior::output 时把 output lamports 从 amm（本程序所有）转入 user_destination
+pub fn raydium_processor(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 17 || data.len() < 17 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let behavior = apply_behavior("Raydium")?;
+    pay_out(program_id, &accounts[1], &accounts[15], behavior.output)
+}
+
+// Raydium CLMM swap_v2 [鉴别器][amount][other_amount_threshold][sqrt_price_limit_x64][is_base_input]，
+// 账户为 13 个固定账户及 tick array。精确输出时把 input（默认为 other_amount_threshold）个代币
+// 由 payer 签名从 input_token_account 转入 input_vault，超过 other_amount_threshold 时失败；不转出输出代币
+pub fn raydium_clmm_processor(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 14 || data.len() < 41 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let behavior = apply_behavior("Raydium CLMM")?;
+    if data[40] != 0 {
+        return Ok(());
+    }
+    let (payer, input_tokens, input_vault, token_program) = (&accounts[0], &accounts[3], &accounts[5], &accounts[8]);
+    let threshold = read_u64(data, 16);
+    let input = behavior.input.unwrap_or(threshold);
+    if input > threshold {
+        log(format!("Raydium CLMM: 需要输入 {}，超过 other_amount_threshold {}", input, threshold));
+        return Err(ProgramError::Custom(RAYDIUM_CLMM_TOO_MUCH_INPUT_PAID));
+    }
+
+    let mut transfer = vec![TOKEN_TRANSFER];
+    transfer.extend_from_slice(&input.to_le_bytes());
+    invoke(
+        &Instruction {
+            program_id: *token_program.key,
+            accounts: vec![
+                AccountMeta::new(*input_tokens.key, false),
+                AccountMeta::new(*input_vault.key, false),
+                AccountMeta::new_readonly(*payer.key, true),
+            ],
+            data: transfer,
+        },
+        &[input_tokens.clone(), input_vault.clone(), payer.clone()],
+    )
+}
+
+fn pay_out(program_id: &Pubkey, pool: &AccountInfo, destination: &AccountInfo, output: Option<u64>) -> ProgramResult {
+    let Some(output) = output else {
+        return Ok(());
+    };
+    if pool.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    **pool.try_borrow_mut_lamports()? = pool.lamports().checked_sub(output).ok_or(ProgramError::InsufficientFunds)?;
+    **destination.try_borrow_mut_lamports()? += output;
+    Ok(())
+}
+
+// 模拟 SwapBehavior 中配置的失败与额外计算单元
+fn apply_behavior(program: &str) -> Result<SwapBehavior, ProgramError> {
+    let behavior = swap_behavior();
+    consume_compute_units(behavior.compute_units);
+    if let Some(code) = behavior.error {
+        log(format!("{}: 模拟失败，错误码 {}", program, code));
+        return Err(ProgramError::Custom(code));
+    }
+    Ok(behavior)
+}
+
+// Pump 内盘买入 [buy 鉴别器][amount][max_sol_cost] 与卖出 [sell 鉴别器][amount][min_sol_output]，账户为内盘买卖的 12 个账户。
+// 买入把指令中的 amount 视为花费的 lamports，从 user 转入 bonding curve，并从 bonding curve 的代币账户
+// 转出 output（默认与 amount 相同）个代币；卖出把 amount 个代币转回 bonding curve，并把 output（默认与 amount 相同）
+// lamports 从 bonding curve 转给 user
+pub fn pump_processor(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 12 || data.len() < 24 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let behavior = apply_behavior("Pump")?;
+    let (mint, bonding_curve, curve_tokens, user_tokens, user, system) = (
+        &accounts[2],
+        &accounts[3],
+        &accounts[4],
+        &accounts[5],
+        &accounts[6],
+        &accounts[7],
+    );
+    // 卖出时 creator_vault 排在 token_program 之前
+    let token_program = if data[..8] == PUMP_SELL_DISCRIMINATOR {
+        &accounts[9]
+    } else {
+        &accounts[8]
+    };
+    let (expected_curve, bump) = bonding_curve_address(mint.key);
+    if bonding_curve.key != &expected_curve || bonding_curve.owner != program_id {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let (amount, limit) = (read_u64(data, 8), read_u64(data, 16));
+    let output = behavior.output.unwrap_or(amount);
+    let token_transfer =
+        |source: &AccountInfo, destination: &AccountInfo, authority: &AccountInfo, amount: u64| {
+            let mut data = vec![TOKEN_TRANSFER];
+            data.extend_from_slice(&amount.to_le_bytes());
+            Instruction {
+                program_id: *token_program.key,
+                accounts: vec![
+                    AccountMeta::new(*source.key, false),
+                    AccountMeta::new(*destination.key, false),
+                    AccountMeta::new_readonly(*authority.key, true),
+                ],
+                data,
+            }
+        };
+
+    match <[u8; 8]>::try_from(&data[..8]).unwrap() {
+        PUMP_BUY_DISCRIMINATOR => {
+            if amount > limit {
+                log(format!(
+                    "Pump: 需要 {} lamports，超过 max_sol_cost {}",
+                    amount, limit
+                ));
+                return Err(ProgramError::Custom(PUMP_TOO_MUCH_SOL_REQUIRED));
+            }
+            invoke(
+                &system_instruction::transfer(user.key, bonding_curve.key, amount),
+                &[user.clone(), bonding_curve.clone(), system.clone()],
+            )?;
+            invoke_signed(
+                &token_transfer(curve_tokens, user_tokens, bonding_curve, output),
+                &[
+                    curve_tokens.clone(),
+                    user_tokens.clone(),
+                    bonding_curve.clone(),
+                ],
+                &[&[BONDING_CURVE_SEED, mint.key.as_ref(), &[bump]]],
+            )
+        }
+        PUMP_SELL_DISCRIMINATOR => {
+            if output < limit {
+                log(format!(
+                    "Pump: 卖出所得 {} lamports 低于 min_sol_output {}",
+                    output, limit
+                ));
+                return Err(ProgramError::Custom(PUMP_TOO_LITTLE_SOL_RECEIVED));
+            }
+            invoke(
+                &token_transfer(user_tokens, curve_tokens, user, amount),
+                &[user_tokens.clone(), curve_tokens.clone(), user.clone()],
+            )?;
+            let remaining = bonding_curve
+                .lamports()
+                .checked_sub(output)
+                .ok_or(ProgramError::InsufficientFunds)?;
+            **bonding_curve.try_borrow_mut_lamports()? = remaining;
+            **user.try_borrow_mut_lamports()? += output;
+            Ok(())
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+// PumpAMM 外盘买入与卖出 [鉴别器][数量][数量]，账户为外盘买卖的转发账户，pool 为 [b"pool", base_mint] PDA。
+// 卖出且设置了 SwapBehavior::output 时，由 pool 签名把 output 个 quote 代币从 pool_quote_token_account 转入
+// user_quote_token_account；其余情况与 dex_processor 相同，不移动资产
+pub fn pump_amm_processor(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 17 || data.len() < 24 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let behavior = apply_behavior("PumpAMM")?;
+    let (Some(output), PUMP_SELL_DISCRIMINATOR) = (behavior.output, <[u8; 8]>::try_from(&data[..8]).unwrap()) else {
+        return Ok(());
+    };
+    let (pool, base_mint, user_quote, pool_quote, quote_token_program) =
+        (&accounts[0], &accounts[3], &accounts[6], &accounts[8], &accounts[12]);
+    let (expected_pool, bump) = pump_amm_pool_address(base_mint.key);
+    if pool.key != &expected_pool || pool.owner != program_id {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let mut transfer = vec![TOKEN_TRANSFER];
+    transfer.extend_from_slice(&output.to_le_bytes());
+    invoke_signed(
+        &Instruction {
+            program_id: *quote_token_program.key,
+            accounts: vec![
+                AccountMeta::new(*pool_quote.key, false),
+                AccountMeta::new(*user_quote.key, false),
+                AccountMeta::new_readonly(*pool.key, true),
+            ],
+            data: transfer,
+        },
+        &[pool_quote.clone(), user_quote.clone(), pool.clone()],
+        &[&[PUMP_AMM_POOL_SEED, base_mint.key.as_ref(), &[bump]]],
+    )
+}
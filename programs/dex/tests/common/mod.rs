@@ -0,0 +1,306 @@
+// 集成测试公共部分：进程内运行时、模拟程序，以及创建配置、资金账户与 Pump 账户集的夹具。
+// 各测试文件只用到其中一部分
+#![allow(dead_code)]
+
+pub mod mocks;
+pub mod runtime;
+
+use std::ops::{Deref, DerefMut};
+
+use solana_program::{
+    bpf_loader_upgradeable, ed25519_program,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+use amm_proxy_contract::{
+    events::EVENT_AUTHORITY_SEED,
+    instructions::fee::{FeeRoute, FEE_ROUTES},
+    ix_builder::{create_config_ix, FeeAccounts},
+    processor::{config_address, process_instruction},
+    state::TradeFeeState,
+    token::{ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID},
+    utils::program_data_address,
+};
+
+use mocks::{
+    associated_token_address, ata_processor, bonding_curve_address, noop_processor, pack_mint,
+    pack_token_account, pump_processor, token_amount, dex_processor, DAMM_V2_PROGRAM, PUMP_AMM_PROGRAM,
+    PUMP_PROGRAM, RAYDIUM_AMM_V4_PROGRAM, RAYDIUM_CLMM_PROGRAM,
+};
+pub use runtime::{Account, TestRuntime};
+
+pub const PROGRAM_ID: Pubkey = Pubkey::new_from_array([0x42; 32]);
+pub const SOL: u64 = 1_000_000_000;
+// 默认费率 1%（10_000 pips）
+pub const DEFAULT_FEE_RATE_PIPS: u32 = 10_000;
+pub const MEMO_PROGRAM_ID: Pubkey =
+    solana_program::pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+// 选择器 + 参数拼成的指令数据，参数按小端序依次写入
+pub fn selector_data(selector: &[u8; 8], args: &[&[u8]]) -> Vec<u8> {
+    let mut data = selector.to_vec();
+    for arg in args {
+        data.extend_from_slice(arg);
+    }
+    data
+}
+
+pub fn u64_args(args: &[u64]) -> Vec<u8> {
+    args.iter().flat_map(|arg| arg.to_le_bytes()).collect()
+}
+
+// ProgramData 账户: [u32 = 3][slot u64][Some(升级权限)]
+pub fn program_data_account(upgrade_authority: Option<&Pubkey>) -> Account {
+    let mut data = 3u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&0u64.to_le_bytes());
+    match upgrade_authority {
+        Some(authority) => {
+            data.push(1);
+            data.extend_from_slice(authority.as_ref());
+        }
+        None => data.extend_from_slice(&[0; 33]),
+    }
+    Account::rent_exempt(data, bpf_loader_upgradeable::id())
+}
+
+// 部署了本程序和模拟程序的运行时，升级权限为 admin
+pub struct TestEnv {
+    pub runtime: TestRuntime,
+    pub admin: Pubkey,
+    pub config: Pubkey,
+}
+
+impl Deref for TestEnv {
+    type Target = TestRuntime;
+
+    fn deref(&self) -> &TestRuntime {
+        &self.runtime
+    }
+}
+
+impl DerefMut for TestEnv {
+    fn deref_mut(&mut self) -> &mut TestRuntime {
+        &mut self.runtime
+    }
+}
+
+impl TestEnv {
+    // 尚未创建配置账户
+    pub fn new() -> Self {
+        let mut runtime = TestRuntime::new();
+        runtime.add_program(PROGRAM_ID, process_instruction);
+        runtime.add_program(TOKEN_PROGRAM_ID, mocks::token_processor);
+        runtime.add_program(TOKEN_2022_PROGRAM_ID, mocks::token_processor);
+        runtime.add_program(ASSOCIATED_TOKEN_PROGRAM_ID, ata_processor);
+        // 各路由的目标 DEX 默认只校验转发内容，Pump 内盘与外盘、DAMM v2、Raydium AMM v4 与 CLMM 另有移动资产的模拟
+        for route in FEE_ROUTES {
+            runtime.add_program(route.program, dex_processor);
+        }
+        runtime.add_program(PUMP_PROGRAM, pump_processor);
+        runtime.add_program(PUMP_AMM_PROGRAM, mocks::pump_amm_processor);
+        runtime.add_program(DAMM_V2_PROGRAM, mocks::damm_v2_processor);
+        runtime.add_program(RAYDIUM_AMM_V4_PROGRAM, mocks::raydium_processor);
+        runtime.add_program(RAYDIUM_CLMM_PROGRAM, mocks::raydium_clmm_processor);
+        runtime.add_program(MEMO_PROGRAM_ID, noop_processor);
+        runtime.add_program(ed25519_program::id(), mocks::ed25519_processor);
+
+        let admin = Pubkey::new_unique();
+        runtime.fund(&admin, 100 * SOL);
+        runtime.set_account(
+            program_data_address(&PROGRAM_ID),
+            program_data_account(Some(&admin)),
+        );
+        mocks::set_swap_behavior(Default::default());
+        TestEnv {
+            runtime,
+            admin,
+            config: config_address(&PROGRAM_ID).0,
+        }
+    }
+
+    // 由升级权限创建配置，fee_wallet 为 admin
+    pub fn with_config(fee_rate_pips: u32) -> Self {
+        let mut env = Self::new();
+        let ix = create_config_ix(&PROGRAM_ID, &env.admin, fee_rate_pips);
+        env.process(&ix).assert_ok();
+        env
+    }
+
+    pub fn config_state(&self) -> TradeFeeState {
+        TradeFeeState::unpack(self.data(&self.config)).unwrap()
+    }
+
+    // 直接改写配置账户，用于准备管理指令之外的测试状态
+    pub fn update_config(&mut self, update: impl FnOnce(&mut TradeFeeState)) {
+        let mut state = self.config_state();
+        update(&mut state);
+        let bytes = borsh::to_vec(&state).unwrap();
+        let config = self.config;
+        self.account_mut(&config).data[..bytes.len()].copy_from_slice(&bytes);
+    }
+
+    // 新建并充值一个系统程序钱包
+    pub fn wallet(&mut self, lamports: u64) -> Pubkey {
+        let wallet = Pubkey::new_unique();
+        self.fund(&wallet, lamports);
+        wallet
+    }
+
+    // payer 付费，手续费转入配置的 fee_wallet
+    pub fn fee_accounts(&self, payer: &Pubkey) -> FeeAccounts {
+        FeeAccounts {
+            config: self.config,
+            payer: *payer,
+            fee_receiver: self.config_state().fee_wallet,
+        }
+    }
+
+    pub fn add_mint(&mut self, decimals: u8, token_program: &Pubkey) -> Pubkey {
+        let mint = Pubkey::new_unique();
+        self.set_account(
+            mint,
+            Account::rent_exempt(pack_mint(decimals), *token_program),
+        );
+        mint
+    }
+
+    pub fn add_token_account(
+        &mut self,
+        key: Pubkey,
+        mint: &Pubkey,
+        owner: &Pubkey,
+        amount: u64,
+        token_program: &Pubkey,
+    ) -> Pubkey {
+        self.set_account(
+            key,
+            Account::rent_exempt(pack_token_account(mint, owner, amount, 1), *token_program),
+        );
+        key
+    }
+
+    // 创建 wallet 的关联代币账户
+    pub fn add_ata(
+        &mut self,
+        wallet: &Pubkey,
+        mint: &Pubkey,
+        amount: u64,
+        token_program: &Pubkey,
+    ) -> Pubkey {
+        let ata = associated_token_address(wallet, mint, token_program).0;
+        self.add_token_account(ata, mint, wallet, amount, token_program)
+    }
+
+    pub fn token_balance(&self, key: &Pubkey) -> u64 {
+        token_amount(self.data(key))
+    }
+
+    // 新建一个处于内盘阶段的 Pump 代币及 user 的账户集
+    pub fn pump_curve(&mut self, user: &Pubkey, user_tokens: u64) -> PumpCurve {
+        let mint = self.add_mint(6, &TOKEN_PROGRAM_ID);
+        let bonding_curve = bonding_curve_address(&mint).0;
+        let mut curve_data = vec![0u8; 81];
+        curve_data[8..16].copy_from_slice(&1_073_000_000_000_000u64.to_le_bytes());
+        curve_data[16..24].copy_from_slice(&(30 * SOL).to_le_bytes());
+        let mut curve = Account::rent_exempt(curve_data, PUMP_PROGRAM);
+        curve.lamports += 100 * SOL;
+        self.set_account(bonding_curve, curve);
+
+        // Global: 协议费率 95 bps、创建者费率 5 bps
+        let mut global_data = vec![0u8; 162];
+        global_data[105..113].copy_from_slice(&95u64.to_le_bytes());
+        global_data[154..162].copy_from_slice(&5u64.to_le_bytes());
+        let global = Pubkey::new_unique();
+        self.set_account(global, Account::rent_exempt(global_data, PUMP_PROGRAM));
+
+        let associated_bonding_curve = self.add_ata(
+            &bonding_curve,
+            &mint,
+            1_000_000_000_000_000,
+            &TOKEN_PROGRAM_ID,
+        );
+        let associated_user = self.add_ata(user, &mint, user_tokens, &TOKEN_PROGRAM_ID);
+        let fee_recipient = self.wallet(SOL);
+        let creator_vault = self.wallet(SOL);
+        PumpCurve {
+            global,
+            fee_recipient,
+            mint,
+            bonding_curve,
+            associated_bonding_curve,
+            associated_user,
+            user: *user,
+            creator_vault,
+            event_authority: Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], &PUMP_PROGRAM).0,
+        }
+    }
+}
+
+impl Default for TestEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PumpCurve {
+    pub global: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub associated_bonding_curve: Pubkey,
+    pub associated_user: Pubkey,
+    pub user: Pubkey,
+    pub creator_vault: Pubkey,
+    pub event_authority: Pubkey,
+}
+
+impl PumpCurve {
+    // 内盘买入的 12 个转发账户
+    pub fn buy_accounts(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.global, false),
+            AccountMeta::new(self.fee_recipient, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new(self.bonding_curve, false),
+            AccountMeta::new(self.associated_bonding_curve, false),
+            AccountMeta::new(self.associated_user, false),
+            AccountMeta::new(self.user, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            AccountMeta::new(self.creator_vault, false),
+            AccountMeta::new_readonly(self.event_authority, false),
+            AccountMeta::new_readonly(PUMP_PROGRAM, false),
+        ]
+    }
+
+    // 内盘卖出的 12 个转发账户，creator_vault 位于 token_program 之前
+    pub fn sell_accounts(&self) -> Vec<AccountMeta> {
+        let mut accounts = self.buy_accounts();
+        accounts.swap(8, 9);
+        accounts
+    }
+}
+
+// 按路由的账户描述生成 len 个转发账户，签名位置使用 signer，其余为新地址；描述之外的账户为只读
+pub fn route_accounts(route: &FeeRoute, len: usize, signer: &Pubkey) -> Vec<AccountMeta> {
+    (0..len)
+        .map(|index| match route.accounts.get(index) {
+            Some(spec) => {
+                let key = if spec.signer { *signer } else { Pubkey::new_unique() };
+                AccountMeta { pubkey: key, is_signer: spec.signer, is_writable: spec.writable }
+            }
+            None => AccountMeta::new_readonly(Pubkey::new_unique(), false),
+        })
+        .collect()
+}
+
+// 指令中与 key 匹配的账户都改为不签名
+pub fn without_signer(mut ix: Instruction, key: &Pubkey) -> Instruction {
+    for meta in ix.accounts.iter_mut().filter(|meta| &meta.pubkey == key) {
+        meta.is_signer = false;
+    }
+    ix
+}
@@ -0,0 +1,882 @@
+// 进程内运行时：通过 syscall stub 模拟 CPI、return data、日志与 Clock/Rent/EpochSchedule，
+// 顶层指令按链上输入布局序列化账户后交给 entrypoint::deserialize，resize 等依赖内存布局的操作与链上一致。
+// 每次调用结束后按链上规则检查账户修改：只有所有者程序能改数据、扣 lamports、改所有者，只读账户不能修改
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    sync::Once,
+};
+
+use solana_program::{
+    account_info::AccountInfo,
+    bpf_loader_upgradeable,
+    clock::Clock,
+    entrypoint::{self, ProgramResult, MAX_PERMITTED_DATA_INCREASE, SUCCESS},
+    epoch_schedule::EpochSchedule,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program, sysvar,
+};
+
+pub type Processor = fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult;
+
+// 每笔交易的计算单元预算，每次进入程序扣除固定开销，模拟程序可额外扣除
+pub const COMPUTE_BUDGET: u64 = 1_400_000;
+pub const INVOKE_COST: u64 = 1_000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Account {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+impl Account {
+    pub fn new(lamports: u64, data: Vec<u8>, owner: Pubkey) -> Self {
+        Account {
+            lamports,
+            data,
+            owner,
+            executable: false,
+        }
+    }
+
+    // 按数据长度存入免租金额
+    pub fn rent_exempt(data: Vec<u8>, owner: Pubkey) -> Self {
+        Account::new(Rent::default().minimum_balance(data.len()), data, owner)
+    }
+}
+
+// 一次程序调用（顶层指令的 stack_height 为 1）
+#[derive(Debug, Clone)]
+pub struct Invocation {
+    pub program_id: Pubkey,
+    pub accounts: Vec<AccountMeta>,
+    pub data: Vec<u8>,
+    pub stack_height: usize,
+}
+
+#[derive(Default)]
+struct Context {
+    programs: HashMap<Pubkey, Processor>,
+    stack: Vec<Pubkey>,
+    return_data: Option<(Pubkey, Vec<u8>)>,
+    logs: Vec<String>,
+    data_logs: Vec<Vec<Vec<u8>>>,
+    invocations: Vec<Invocation>,
+    // 调用栈中每层调用开始时的账户状态，被调用方成功返回后用其修改结果刷新调用方的基准
+    baselines: Vec<Vec<Snapshot>>,
+    clock: Clock,
+    compute_units: u64,
+    // 被调用方执行失败时的错误，整笔交易随即终止
+    aborted: Option<ProgramError>,
+}
+
+// 被调用方执行失败时展开调用栈的标记：链上此时整笔交易直接终止，控制权不会回到调用方的 invoke
+struct CalleeAborted;
+
+thread_local! {
+    static CONTEXT: RefCell<Context> = RefCell::new(Context::default());
+}
+
+fn with_context<R>(f: impl FnOnce(&mut Context) -> R) -> R {
+    CONTEXT.with(|context| f(&mut context.borrow_mut()))
+}
+
+pub fn log(message: String) {
+    with_context(|context| context.logs.push(message));
+}
+
+// 模拟程序额外消耗的计算单元
+pub fn consume_compute_units(units: u64) {
+    with_context(|context| context.compute_units = context.compute_units.saturating_sub(units));
+}
+
+fn epoch_schedule() -> EpochSchedule {
+    EpochSchedule::without_warmup()
+}
+
+// stub 全局只安装一次，运行状态放在线程局部变量中，每个测试线程互不影响
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, message: &str) {
+        log(message.to_string());
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        with_context(|context| {
+            context
+                .data_logs
+                .push(fields.iter().map(|field| field.to_vec()).collect())
+        });
+    }
+
+    fn sol_remaining_compute_units(&self) -> u64 {
+        with_context(|context| context.compute_units)
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        invoke(instruction, account_infos, signers_seeds)
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = with_context(|context| context.clock.clone());
+        unsafe { std::ptr::write(var_addr as *mut Clock, clock) };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { std::ptr::write(var_addr as *mut Rent, Rent::default()) };
+        SUCCESS
+    }
+
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { std::ptr::write(var_addr as *mut EpochSchedule, epoch_schedule()) };
+        SUCCESS
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        with_context(|context| context.return_data.clone())
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        with_context(|context| {
+            let program_id = *context.stack.last().expect("调用栈为空");
+            context.return_data = (!data.is_empty()).then(|| (program_id, data.to_vec()));
+        });
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        with_context(|context| context.stack.len() as u64)
+    }
+}
+
+// CPI：按指令中的账户顺序从调用方传入的 AccountInfo 构造被调用方视图，与调用方共享 lamports 和数据，
+// 签名权限只能来自调用方已有的签名或调用方程序派生的 PDA，写权限只能来自调用方已有的写权限。
+// 与链上一致，被调用方失败时不返回调用方，整笔交易以被调用方的错误终止
+fn invoke(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let caller = with_context(|context| *context.stack.last().expect("调用栈为空"));
+    let pda_signers = signers_seeds
+        .iter()
+        .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| ProgramError::InvalidSeeds)?;
+
+    let mut callee_accounts = Vec::with_capacity(instruction.accounts.len());
+    for meta in &instruction.accounts {
+        let Some(info) = account_infos.iter().find(|info| info.key == &meta.pubkey) else {
+            log(format!("CPI 缺少账户 {}", meta.pubkey));
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        let same_key = || {
+            instruction
+                .accounts
+                .iter()
+                .filter(|other| other.pubkey == meta.pubkey)
+        };
+        let is_signer = same_key().any(|other| other.is_signer);
+        let is_writable = same_key().any(|other| other.is_writable);
+        if is_signer && !info.is_signer && !pda_signers.contains(&meta.pubkey) {
+            log(format!("CPI 签名权限提升: {}", meta.pubkey));
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if is_writable && !info.is_writable {
+            log(format!("CPI 写权限提升: {}", meta.pubkey));
+            return Err(ProgramError::InvalidArgument);
+        }
+        callee_accounts.push(AccountInfo {
+            is_signer,
+            is_writable,
+            ..info.clone()
+        });
+    }
+
+    // 上面账户与权限的校验失败会返回给调用方；被调用方开始执行后的失败则终止整笔交易
+    if let Err(err) = execute(&instruction.program_id, &callee_accounts, &instruction.data) {
+        with_context(|context| context.aborted = Some(err));
+        std::panic::resume_unwind(Box::new(CalleeAborted));
+    }
+    Ok(())
+}
+
+struct Snapshot {
+    key: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    owner: Pubkey,
+    is_writable: bool,
+}
+
+fn snapshot(accounts: &[AccountInfo]) -> Result<Vec<Snapshot>, ProgramError> {
+    let mut seen = HashSet::new();
+    let mut snapshots = Vec::new();
+    for account in accounts {
+        if !seen.insert(*account.key) {
+            continue;
+        }
+        snapshots.push(Snapshot {
+            key: *account.key,
+            lamports: account.try_lamports()?,
+            data: account.try_borrow_data()?.to_vec(),
+            owner: *account.owner,
+            is_writable: accounts
+                .iter()
+                .any(|other| other.key == account.key && other.is_writable),
+        });
+    }
+    Ok(snapshots)
+}
+
+// 把当前账户状态写回基准，用于被调用方返回后刷新调用方的基准
+fn refresh(baseline: &mut [Snapshot], accounts: &[AccountInfo]) {
+    for snapshot in baseline {
+        if let Some(account) = accounts.iter().find(|account| account.key == &snapshot.key) {
+            snapshot.lamports = account.lamports();
+            snapshot.data = account.data.borrow().to_vec();
+            snapshot.owner = *account.owner;
+        }
+    }
+}
+
+// Instructions sysvar 数据：[指令数 u16][各指令偏移 u16]，每条指令为
+// [账户数 u16][(标志 u8, pubkey)...][program_id][数据长度 u16][数据]，末尾 2 字节为当前指令序号
+fn instructions_sysvar_data(
+    instructions: &[Instruction],
+    privileges: &HashMap<Pubkey, (bool, bool)>,
+) -> Vec<u8> {
+    let mut data = (instructions.len() as u16).to_le_bytes().to_vec();
+    data.resize(2 + instructions.len() * 2, 0);
+    for (index, instruction) in instructions.iter().enumerate() {
+        let offset = data.len() as u16;
+        data[2 + index * 2..4 + index * 2].copy_from_slice(&offset.to_le_bytes());
+        data.extend_from_slice(&(instruction.accounts.len() as u16).to_le_bytes());
+        for meta in &instruction.accounts {
+            let (is_signer, is_writable) = privileges[&meta.pubkey];
+            data.push(is_signer as u8 | (is_writable as u8) << 1);
+            data.extend_from_slice(meta.pubkey.as_ref());
+        }
+        data.extend_from_slice(instruction.program_id.as_ref());
+        data.extend_from_slice(&(instruction.data.len() as u16).to_le_bytes());
+        data.extend_from_slice(&instruction.data);
+    }
+    data.extend_from_slice(&[0; 2]);
+    data
+}
+
+// 调用结束（或发起 CPI）时的账户修改规则，只检查 accounts 中出现的账户；发起 CPI 时只传入部分账户，不检查 lamports 总量
+fn verify_changes(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    before: &[Snapshot],
+    check_total: bool,
+) -> ProgramResult {
+    let (mut lamports_before, mut lamports_after) = (0u128, 0u128);
+    for snapshot in before {
+        let Some(account) = accounts.iter().find(|account| account.key == &snapshot.key) else {
+            continue;
+        };
+        let (lamports, owner) = (account.lamports(), *account.owner);
+        let data_changed = account.data.borrow()[..] != snapshot.data[..];
+        lamports_before += snapshot.lamports as u128;
+        lamports_after += lamports as u128;
+
+        let changed = lamports != snapshot.lamports || owner != snapshot.owner || data_changed;
+        let violation = if changed && !snapshot.is_writable {
+            Some("修改了只读账户")
+        } else if snapshot.owner != *program_id && (data_changed || owner != snapshot.owner) {
+            Some("修改了不属于自己的账户数据或所有者")
+        } else if snapshot.owner != *program_id && lamports < snapshot.lamports {
+            Some("扣减了不属于自己的账户的 lamports")
+        } else {
+            None
+        };
+        if let Some(violation) = violation {
+            log(format!(
+                "程序 {} {}: {}",
+                program_id, violation, snapshot.key
+            ));
+            return Err(ProgramError::InvalidAccountData);
+        }
+    }
+    if check_total && lamports_before != lamports_after {
+        log(format!(
+            "程序 {} 调用前后 lamports 总量不一致: {} -> {}",
+            program_id, lamports_before, lamports_after
+        ));
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+fn execute(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let metas = accounts
+        .iter()
+        .map(|account| AccountMeta {
+            pubkey: *account.key,
+            is_signer: account.is_signer,
+            is_writable: account.is_writable,
+        })
+        .collect();
+    // 发起 CPI 前先按调用方的基准检查调用方此前的修改
+    let caller = with_context(|context| context.stack.last().copied().zip(context.baselines.pop()));
+    if let Some((caller, baseline)) = caller {
+        let checked = verify_changes(&caller, accounts, &baseline, false);
+        with_context(|context| context.baselines.push(baseline));
+        checked?;
+    }
+
+    let processor = with_context(|context| {
+        // 只允许直接递归调用自身，A -> B -> A 的重入会被拒绝
+        if context.stack.contains(program_id) && context.stack.last() != Some(program_id) {
+            return Err(ProgramError::Custom(u32::MAX));
+        }
+        context.invocations.push(Invocation {
+            program_id: *program_id,
+            accounts: metas,
+            data: data.to_vec(),
+            stack_height: context.stack.len() + 1,
+        });
+        context.stack.push(*program_id);
+        context.return_data = None;
+        context.compute_units = context.compute_units.saturating_sub(INVOKE_COST);
+        Ok(context.programs.get(program_id).copied())
+    })
+    .inspect_err(|_| log(format!("程序 {} 不允许重入", program_id)))?;
+
+    let result = match (processor, snapshot(accounts)) {
+        (None, _) => {
+            log(format!("程序 {} 未注册", program_id));
+            Err(ProgramError::IncorrectProgramId)
+        }
+        (_, Err(err)) => Err(err),
+        (Some(processor), Ok(before)) => {
+            with_context(|context| context.baselines.push(before));
+            let result = processor(program_id, accounts, data);
+            let before = with_context(|context| context.baselines.pop().unwrap());
+            result.and_then(|()| verify_changes(program_id, accounts, &before, true))
+        }
+    };
+    with_context(|context| {
+        context.stack.pop();
+        if let (Ok(()), Some(caller)) = (&result, context.baselines.last_mut()) {
+            refresh(caller, accounts);
+        }
+    });
+    result
+}
+
+// 系统程序：CreateAccount、Assign、Transfer、Allocate
+const CREATE_ACCOUNT: u32 = 0;
+const ASSIGN: u32 = 1;
+const TRANSFER: u32 = 2;
+const ALLOCATE: u32 = 8;
+
+// 系统程序的自定义错误：AccountAlreadyInUse、ResultWithNegativeLamports
+pub const SYSTEM_ACCOUNT_ALREADY_IN_USE: u32 = 0;
+pub const SYSTEM_INSUFFICIENT_LAMPORTS: u32 = 1;
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64, ProgramError> {
+    data.get(offset..offset + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidInstructionData)
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey, ProgramError> {
+    data.get(offset..offset + 32)
+        .map(|bytes| Pubkey::new_from_array(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidInstructionData)
+}
+
+fn system_account<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    index: usize,
+) -> Result<&'a AccountInfo<'info>, ProgramError> {
+    let account = accounts
+        .get(index)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if !account.is_signer {
+        log(format!("系统程序: {} 未签名", account.key));
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(account)
+}
+
+fn allocate(account: &AccountInfo, space: u64) -> ProgramResult {
+    if account.data_len() > 0 || account.owner != &system_program::id() {
+        log(format!("系统程序: 账户 {} 已在使用中", account.key));
+        return Err(ProgramError::Custom(SYSTEM_ACCOUNT_ALREADY_IN_USE));
+    }
+    account.resize(space as usize)
+}
+
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    if from.lamports() < lamports {
+        log(format!(
+            "系统程序: {} 余额 {} 不足 {}",
+            from.key,
+            from.lamports(),
+            lamports
+        ));
+        return Err(ProgramError::Custom(SYSTEM_INSUFFICIENT_LAMPORTS));
+    }
+    **from.try_borrow_mut_lamports()? -= lamports;
+    **to.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
+
+pub fn system_processor(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let tag = data
+        .get(..4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    match tag {
+        CREATE_ACCOUNT => {
+            let (lamports, space, owner) = (
+                read_u64(data, 4)?,
+                read_u64(data, 12)?,
+                read_pubkey(data, 20)?,
+            );
+            let (from, to) = (system_account(accounts, 0)?, system_account(accounts, 1)?);
+            if to.lamports() > 0 {
+                log(format!("系统程序: 账户 {} 已在使用中", to.key));
+                return Err(ProgramError::Custom(SYSTEM_ACCOUNT_ALREADY_IN_USE));
+            }
+            allocate(to, space)?;
+            move_lamports(from, to, lamports)?;
+            to.assign(&owner);
+            Ok(())
+        }
+        ASSIGN => {
+            let account = system_account(accounts, 0)?;
+            if account.owner != &system_program::id() {
+                return Err(ProgramError::IllegalOwner);
+            }
+            account.assign(&read_pubkey(data, 4)?);
+            Ok(())
+        }
+        TRANSFER => {
+            let from = system_account(accounts, 0)?;
+            let to = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if from.data_len() > 0 || from.owner != &system_program::id() {
+                log(format!("系统程序: 转出账户 {} 不能带数据", from.key));
+                return Err(ProgramError::InvalidArgument);
+            }
+            move_lamports(from, to, read_u64(data, 4)?)
+        }
+        ALLOCATE => allocate(system_account(accounts, 0)?, read_u64(data, 4)?),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+// 一笔交易的执行结果，失败时账户状态整体回滚
+#[derive(Debug)]
+pub struct TxResult {
+    pub result: ProgramResult,
+    // 运行时与模拟程序的日志；本程序的 msg! 在链下由 solana-msg 直接打印到标准输出，不经过 syscall 桩，
+    // 需要断言时用 program_output 读取
+    pub logs: Vec<String>,
+    // sol_log_data 输出的字段
+    pub data_logs: Vec<Vec<Vec<u8>>>,
+    pub invocations: Vec<Invocation>,
+    pub return_data: Option<(Pubkey, Vec<u8>)>,
+    pub compute_units_consumed: u64,
+}
+
+impl TxResult {
+    pub fn assert_ok(self) -> Self {
+        if let Err(err) = &self.result {
+            panic!("交易失败: {:?}\n{}", err, self.logs.join("\n"));
+        }
+        self
+    }
+
+    pub fn unwrap_err(&self) -> ProgramError {
+        match &self.result {
+            Ok(()) => panic!("交易应当失败\n{}", self.logs.join("\n")),
+            Err(err) => err.clone(),
+        }
+    }
+
+    // 通过 CPI 调用目标程序的记录，不含顶层指令
+    pub fn cpis_to(&self, program_id: &Pubkey) -> Vec<&Invocation> {
+        self.invocations
+            .iter()
+            .filter(|invocation| {
+                invocation.stack_height > 1 && &invocation.program_id == program_id
+            })
+            .collect()
+    }
+
+    pub fn has_log(&self, needle: &str) -> bool {
+        self.logs.iter().any(|log| log.contains(needle))
+    }
+
+    // 以 tag 开头的事件日志中 tag 之后的字段
+    pub fn events(&self, tag: &[u8]) -> Vec<Vec<u8>> {
+        self.data_logs
+            .iter()
+            .filter(|fields| fields.first().map(Vec::as_slice) == Some(tag))
+            .map(|fields| fields.get(1).cloned().unwrap_or_default())
+            .collect()
+    }
+}
+
+const CAPTURE_ENV: &str = "AMM_PROXY_CAPTURE_TEST";
+
+// 在子进程中单独重新运行名为 test 的测试并返回其标准输出（测试框架只能截获、不能读取 println!）。
+// 子进程内直接执行 body 并返回 None，测试函数应就此结束：
+//     let Some(output) = program_output("name", || { ... }) else { return };
+pub fn program_output(test: &str, body: impl FnOnce()) -> Option<String> {
+    if std::env::var(CAPTURE_ENV).as_deref() == Ok(test) {
+        body();
+        return None;
+    }
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args([test, "--exact", "--nocapture", "--test-threads=1"])
+        .env(CAPTURE_ENV, test)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    assert!(
+        output.status.success(),
+        "子进程中的测试 {} 失败\n{}\n{}",
+        test,
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Some(stdout)
+}
+
+pub struct TestRuntime {
+    accounts: HashMap<Pubkey, Account>,
+    programs: HashMap<Pubkey, Processor>,
+    clock: Clock,
+}
+
+impl Default for TestRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestRuntime {
+    pub fn new() -> Self {
+        static INSTALL: Once = Once::new();
+        INSTALL.call_once(|| {
+            set_syscall_stubs(Box::new(Stubs));
+        });
+
+        let mut runtime = TestRuntime {
+            accounts: HashMap::new(),
+            programs: HashMap::new(),
+            clock: Clock {
+                slot: 1_000,
+                unix_timestamp: 1_700_000_000,
+                ..Clock::default()
+            },
+        };
+        runtime.add_program(system_program::id(), system_processor);
+        runtime
+    }
+
+    // 登记可执行程序账户及其处理函数
+    pub fn add_program(&mut self, program_id: Pubkey, processor: Processor) {
+        self.programs.insert(program_id, processor);
+        self.accounts.insert(
+            program_id,
+            Account {
+                lamports: 1,
+                data: Vec::new(),
+                owner: bpf_loader_upgradeable::id(),
+                executable: true,
+            },
+        );
+    }
+
+    // 以 keys 对应账户的副本构造可写的 AccountInfo，用于直接调用程序中的辅助函数
+    pub fn with_account_infos<R>(&self, keys: &[Pubkey], f: impl FnOnce(&[AccountInfo]) -> R) -> R {
+        let mut accounts: Vec<(Pubkey, Account)> =
+            keys.iter().map(|key| (*key, self.accounts.get(key).cloned().unwrap_or_default())).collect();
+        let infos: Vec<AccountInfo> = accounts
+            .iter_mut()
+            .map(|(key, account)| {
+                let Account { lamports, data, owner, executable } = account;
+                AccountInfo::new(key, false, true, lamports, data, owner, *executable, 0)
+            })
+            .collect();
+        f(&infos)
+    }
+
+    pub fn set_account(&mut self, key: Pubkey, account: Account) {
+        self.accounts.insert(key, account);
+    }
+
+    pub fn remove_account(&mut self, key: &Pubkey) {
+        self.accounts.remove(key);
+    }
+
+    pub fn account(&self, key: &Pubkey) -> Option<&Account> {
+        self.accounts.get(key)
+    }
+
+    pub fn account_mut(&mut self, key: &Pubkey) -> &mut Account {
+        self.accounts
+            .entry(*key)
+            .or_insert_with(|| Account::new(0, Vec::new(), system_program::id()))
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.accounts.get(key).map_or(0, |account| account.lamports)
+    }
+
+    pub fn data(&self, key: &Pubkey) -> &[u8] {
+        self.accounts.get(key).map_or(&[], |account| &account.data)
+    }
+
+    // 给系统程序所有的钱包充值
+    pub fn fund(&mut self, key: &Pubkey, lamports: u64) {
+        self.account_mut(key).lamports += lamports;
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    // 跳到指定 slot，epoch 按不带预热的 EpochSchedule 换算，时间按每 slot 400ms 推进
+    pub fn warp_to_slot(&mut self, slot: u64) {
+        let elapsed_ms = slot.saturating_sub(self.clock.slot) as i64 * 400;
+        self.clock.slot = slot;
+        self.clock.epoch = epoch_schedule().get_epoch(slot);
+        self.clock.unix_timestamp += elapsed_ms / 1_000;
+    }
+
+    pub fn set_unix_timestamp(&mut self, unix_timestamp: i64) {
+        self.clock.unix_timestamp = unix_timestamp;
+    }
+
+    #[must_use]
+    pub fn process(&mut self, instruction: &Instruction) -> TxResult {
+        self.process_transaction(std::slice::from_ref(instruction))
+    }
+
+    // 依次执行交易中的指令，Instructions sysvar 覆盖整笔交易；任一指令失败时全部回滚
+    #[must_use]
+    pub fn process_transaction(&mut self, instructions: &[Instruction]) -> TxResult {
+        with_context(|context| {
+            *context = Context {
+                programs: self.programs.clone(),
+                clock: self.clock.clone(),
+                compute_units: COMPUTE_BUDGET,
+                ..Context::default()
+            }
+        });
+
+        let snapshot = self.accounts.clone();
+        let result = self.execute_transaction(instructions);
+        if result.is_err() {
+            self.accounts = snapshot;
+        }
+
+        with_context(|context| TxResult {
+            result,
+            logs: std::mem::take(&mut context.logs),
+            data_logs: std::mem::take(&mut context.data_logs),
+            invocations: std::mem::take(&mut context.invocations),
+            return_data: context.return_data.take(),
+            compute_units_consumed: COMPUTE_BUDGET - context.compute_units,
+        })
+    }
+
+    fn execute_transaction(&mut self, instructions: &[Instruction]) -> ProgramResult {
+        // 交易消息中同一账户只有一份权限，取所有指令中的并集
+        let mut privileges: HashMap<Pubkey, (bool, bool)> = HashMap::new();
+        for meta in instructions
+            .iter()
+            .flat_map(|instruction| &instruction.accounts)
+        {
+            let entry = privileges.entry(meta.pubkey).or_default();
+            entry.0 |= meta.is_signer;
+            entry.1 |= meta.is_writable;
+        }
+        let mut sysvar_data = instructions_sysvar_data(instructions, &privileges);
+
+        for (index, instruction) in instructions.iter().enumerate() {
+            let len = sysvar_data.len();
+            sysvar_data[len - 2..].copy_from_slice(&(index as u16).to_le_bytes());
+            self.accounts.insert(
+                sysvar::instructions::ID,
+                Account::new(1, sysvar_data.clone(), sysvar::id()),
+            );
+            self.execute_instruction(instruction, &privileges)?;
+        }
+        Ok(())
+    }
+
+    fn execute_instruction(
+        &mut self,
+        instruction: &Instruction,
+        privileges: &HashMap<Pubkey, (bool, bool)>,
+    ) -> ProgramResult {
+        let before: Vec<(Pubkey, Account)> = self
+            .unique_keys(instruction)
+            .into_iter()
+            .map(|key| (key, self.account_mut(&key).clone()))
+            .collect();
+        let mut input = SerializedInput::new(instruction, privileges, &self.accounts);
+
+        let (program_id, accounts, data) = unsafe { entrypoint::deserialize(input.as_mut_ptr()) };
+        let executed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| execute(program_id, &accounts, data)));
+        match executed {
+            Ok(result) => result?,
+            Err(payload) if payload.is::<CalleeAborted>() => {
+                return Err(with_context(|context| {
+                    context.stack.clear();
+                    context.baselines.clear();
+                    context.aborted.take().unwrap()
+                }));
+            }
+            Err(payload) => std::panic::resume_unwind(payload),
+        }
+        drop(accounts);
+
+        for (key, before) in before {
+            let after = input.read_account(&key, before.executable);
+            rent_state_check(&key, &before, &after)?;
+            if after.lamports == 0 {
+                self.accounts.remove(&key);
+            } else {
+                self.accounts.insert(key, after);
+            }
+        }
+        Ok(())
+    }
+
+    fn unique_keys(&self, instruction: &Instruction) -> Vec<Pubkey> {
+        let mut keys = Vec::new();
+        for meta in &instruction.accounts {
+            if !keys.contains(&meta.pubkey) {
+                keys.push(meta.pubkey);
+            }
+        }
+        keys
+    }
+}
+
+// 交易结束时账户不能从免租状态变为欠租状态（lamports 为 0 的账户会被回收）
+fn rent_state_check(key: &Pubkey, before: &Account, after: &Account) -> ProgramResult {
+    let rent = Rent::default();
+    let paying = |account: &Account| {
+        account.lamports > 0 && account.lamports < rent.minimum_balance(account.data.len())
+    };
+    if paying(after) && (!paying(before) || before.data.len() != after.data.len()) {
+        log(format!(
+            "账户 {} 余额 {} 低于免租金额 {}",
+            key,
+            after.lamports,
+            rent.minimum_balance(after.data.len())
+        ));
+        return Err(ProgramError::InsufficientFunds);
+    }
+    Ok(())
+}
+
+// 链上对齐输入布局：[账户数 u64] 每个账户 [0xff][signer][writable][executable][原始长度 u32][key][owner]
+// [lamports u64][数据长度 u64][数据][MAX_PERMITTED_DATA_INCREASE 预留][8 字节对齐][rent_epoch u64]，
+// 重复账户为 [首次出现的序号][7 字节填充]；其后是 [指令数据长度 u64][指令数据][program_id]
+struct SerializedInput {
+    buffer: Vec<u64>,
+    // 每个账户 key 字段的偏移
+    key_offsets: HashMap<Pubkey, usize>,
+}
+
+impl SerializedInput {
+    fn new(
+        instruction: &Instruction,
+        privileges: &HashMap<Pubkey, (bool, bool)>,
+        accounts: &HashMap<Pubkey, Account>,
+    ) -> Self {
+        let mut bytes = Vec::new();
+        let mut key_offsets = HashMap::new();
+        let mut positions: Vec<Pubkey> = Vec::new();
+        bytes.extend_from_slice(&(instruction.accounts.len() as u64).to_le_bytes());
+        for meta in &instruction.accounts {
+            if let Some(index) = positions.iter().position(|key| key == &meta.pubkey) {
+                bytes.push(index as u8);
+                bytes.extend_from_slice(&[0; 7]);
+                positions.push(meta.pubkey);
+                continue;
+            }
+            positions.push(meta.pubkey);
+            let default = Account::new(0, Vec::new(), system_program::id());
+            let account = accounts.get(&meta.pubkey).unwrap_or(&default);
+            let (is_signer, is_writable) = privileges[&meta.pubkey];
+            bytes.extend_from_slice(&[
+                u8::MAX,
+                is_signer as u8,
+                is_writable as u8,
+                account.executable as u8,
+            ]);
+            bytes.extend_from_slice(&[0; 4]);
+            key_offsets.insert(meta.pubkey, bytes.len());
+            bytes.extend_from_slice(meta.pubkey.as_ref());
+            bytes.extend_from_slice(account.owner.as_ref());
+            bytes.extend_from_slice(&account.lamports.to_le_bytes());
+            bytes.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(&account.data);
+            bytes.resize(bytes.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+            bytes.resize(bytes.len().next_multiple_of(8), 0);
+            bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(instruction.data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&instruction.data);
+        bytes.extend_from_slice(instruction.program_id.as_ref());
+
+        let mut buffer = vec![0u64; bytes.len().div_ceil(8)];
+        Self::bytes_mut(&mut buffer)[..bytes.len()].copy_from_slice(&bytes);
+        SerializedInput {
+            buffer,
+            key_offsets,
+        }
+    }
+
+    fn bytes_mut(buffer: &mut [u64]) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u8, buffer.len() * 8) }
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.buffer.as_mut_ptr() as *mut u8
+    }
+
+    fn read_account(&mut self, key: &Pubkey, executable: bool) -> Account {
+        let offset = self.key_offsets[key];
+        let bytes = Self::bytes_mut(&mut self.buffer);
+        let owner = Pubkey::new_from_array(bytes[offset + 32..offset + 64].try_into().unwrap());
+        let lamports = u64::from_le_bytes(bytes[offset + 64..offset + 72].try_into().unwrap());
+        let data_len =
+            u64::from_le_bytes(bytes[offset + 72..offset + 80].try_into().unwrap()) as usize;
+        Account {
+            lamports,
+            data: bytes[offset + 80..offset + 80 + data_len].to_vec(),
+            owner,
+            executable,
+        }
+    }
+}
@@ -0,0 +1,44 @@
+// 紧凑账户布局：同一钱包同时作为支付者、交易者与手续费接收方，编译交易时重复账户合并为一个
+mod common;
+
+use amm_proxy_contract::{instructions::fee::SwapResult, ix_builder::pump_buy_ix};
+use borsh::BorshDeserialize;
+use common::{TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::program_error::ProgramError;
+
+#[test]
+fn one_wallet_pays_trades_and_receives_the_fee() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    // 配置的 fee_wallet 即 admin，由它自己发起交易
+    let wallet = env.admin;
+    let curve = env.pump_curve(&wallet, 0);
+    let before = env.lamports(&wallet);
+
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&wallet), SOL, 2 * SOL, curve.buy_accounts());
+    assert_eq!((ix.accounts[2].pubkey, ix.accounts[3].pubkey), (wallet, wallet));
+    let result = env.process(&ix).assert_ok();
+
+    // 手续费转给自己不发生转账，钱包只支付扣费后转发给 Pump 的数量
+    let fee = SOL / 100;
+    assert_eq!(before - env.lamports(&wallet), SOL - fee);
+    assert_eq!(env.token_balance(&curve.associated_user), SOL - fee);
+    let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!((swap.fee, swap.breakdown.treasury), (fee, fee));
+}
+
+#[test]
+fn merged_accounts_keep_the_writable_flags_required_for_the_fee() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+
+    // 支付者在前 4 个账户中只读，但在转发账户中作为可写的交易者出现，合并后仍可写
+    let mut ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    ix.accounts[2].is_writable = false;
+    env.process(&ix).assert_ok();
+
+    // 接收方只出现一次且只读，合并后仍不可写
+    let mut ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    ix.accounts[3].is_writable = false;
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidAccountData);
+}
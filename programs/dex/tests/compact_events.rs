@@ -0,0 +1,62 @@
+// 精简收费事件：开启 compact_events 后只输出一段 [route_id][fee 变长][wallet_index]，按文档中的格式解码
+mod common;
+
+use amm_proxy_contract::{
+    events::{CompactFeeCollected, FEE_COLLECTED_EVENT},
+    instructions::pump::PUMP_SELECTOR,
+    ix_builder::{pump_buy_ix, set_compact_events_ix},
+};
+use common::{TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+
+#[test]
+fn compact_event_replaces_the_full_event() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_compact_events_ix(&PROGRAM_ID, &env.config, &env.admin, true);
+    env.process(&ix).assert_ok();
+    assert!(env.config_state().compact_events);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let result = env.process(&ix).assert_ok();
+    assert!(result.events(FEE_COLLECTED_EVENT).is_empty());
+    let compact: Vec<_> = result.data_logs.iter().filter(|fields| fields.len() == 1).collect();
+    assert_eq!(compact.len(), 1);
+
+    // 1% 手续费 10_000_000 lamports 的变长编码为 4 字节
+    let data = &compact[0][0];
+    assert_eq!(data.len(), 4 + 4 + 1);
+    let event = CompactFeeCollected::decode(data).unwrap();
+    assert_eq!((event.route_id, event.fee), (<[u8; 4]>::try_from(&PUMP_SELECTOR[..4]).unwrap(), SOL / 100));
+    assert_eq!(ix.accounts[event.wallet_index as usize].pubkey, user);
+
+    // 关闭后恢复输出完整事件
+    let ix = set_compact_events_ix(&PROGRAM_ID, &env.config, &env.admin, false);
+    env.process(&ix).assert_ok();
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let result = env.process(&ix).assert_ok();
+    assert_eq!(result.events(FEE_COLLECTED_EVENT).len(), 1);
+    assert!(result.data_logs.iter().all(|fields| fields.len() != 1));
+}
+
+#[test]
+fn fee_varint_round_trips() {
+    for (fee, len) in [(0, 1), (127, 1), (128, 2), (SOL / 100, 4), (u64::MAX, 10)] {
+        let event = CompactFeeCollected { route_id: [1, 2, 3, 4], fee, wallet_index: 3 };
+        let data = event.encode();
+        assert_eq!(data.len(), 4 + len + 1);
+        assert_eq!(CompactFeeCollected::decode(&data), Some(event));
+    }
+    // 128 = 0x80 0x01
+    assert_eq!(CompactFeeCollected { route_id: [0; 4], fee: 128, wallet_index: 0 }.encode()[4..6], [0x80, 0x01]);
+}
+
+#[test]
+fn malformed_compact_event_is_not_decoded() {
+    let data = CompactFeeCollected { route_id: [1, 2, 3, 4], fee: SOL, wallet_index: 2 }.encode();
+    // 缺少 wallet_index、末尾多余字节、变长编码未结束
+    assert_eq!(CompactFeeCollected::decode(&data[..data.len() - 1]), None);
+    assert_eq!(CompactFeeCollected::decode(&[&data[..], &[0]].concat()), None);
+    assert_eq!(CompactFeeCollected::decode(&[1, 2, 3, 4, 0x80, 0x80]), None);
+    assert_eq!(CompactFeeCollected::decode(&[[0; 4].as_slice(), &[0xff; 10], &[0]].concat()), None);
+}
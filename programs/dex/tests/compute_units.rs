@@ -0,0 +1,55 @@
+// SwapResult.compute_units：目标程序 CPI 前后剩余计算单元之差，包含目标程序自身及其内层 CPI 的消耗
+mod common;
+
+use amm_proxy_contract::{
+    instructions::fee::SwapResult,
+    ix_builder::{pump_buy_ix, raydium_buy_ix},
+};
+use borsh::BorshDeserialize;
+use common::{
+    mocks::{set_swap_behavior, SwapBehavior, RAYDIUM_AMM_V4_PROGRAM},
+    runtime::{TxResult, INVOKE_COST},
+    TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{instruction::AccountMeta, pubkey::Pubkey};
+
+const DEX_UNITS: u64 = 50_000;
+
+fn compute_units(result: &TxResult) -> u64 {
+    SwapResult::try_from_slice(&result.return_data.as_ref().unwrap().1).unwrap().compute_units
+}
+
+#[test]
+fn fee_route_reports_the_cpi_compute_units() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    set_swap_behavior(SwapBehavior { compute_units: DEX_UNITS, ..Default::default() });
+
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let result = env.process(&ix).assert_ok();
+
+    // 目标程序消耗的单元加上对 Pump 及其 SOL、代币转账的调用开销，不含手续费转账等路由自身的消耗
+    let units = compute_units(&result);
+    assert!(units >= DEX_UNITS + INVOKE_COST, "{units}");
+    assert!(units <= DEX_UNITS + 3 * INVOKE_COST, "{units}");
+    assert!(units < result.compute_units_consumed);
+
+    // 目标程序更重时随之增加
+    set_swap_behavior(SwapBehavior { compute_units: 2 * DEX_UNITS, ..Default::default() });
+    let heavier = compute_units(&env.process(&ix).assert_ok());
+    assert_eq!(heavier - units, DEX_UNITS);
+}
+
+#[test]
+fn uncharged_raydium_route_reports_the_cpi_compute_units() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(SOL);
+    set_swap_behavior(SwapBehavior { compute_units: DEX_UNITS, ..Default::default() });
+
+    let mut accounts = vec![AccountMeta::new_readonly(RAYDIUM_AMM_V4_PROGRAM, false)];
+    accounts.extend((0..7).map(|_| AccountMeta::new(Pubkey::new_unique(), false)));
+    accounts.push(AccountMeta::new_readonly(user, true));
+    let result = env.process(&raydium_buy_ix(&PROGRAM_ID, SOL, 1, accounts)).assert_ok();
+    assert_eq!(compute_units(&result), DEX_UNITS + INVOKE_COST);
+}
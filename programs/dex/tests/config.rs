@@ -0,0 +1,231 @@
+// 配置账户的创建、管理指令与校验
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::{pump::PUMP_SELECTOR, version::VersionInfo},
+    ix_builder::{
+        create_config_ix, get_config_ix, set_fee_rate_ix, set_mint_fee_ix, set_route_fees_ix, set_shares_ix, update_config_ix,
+        version_ix,
+    },
+    processor::{config_address, config_address_with_bump},
+    state::{ConfigUpdate, TradeFeeState, MAX_FEE_BPS, MAX_FEE_RATE_PIPS},
+};
+use borsh::BorshDeserialize;
+use common::{TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey, rent::Rent};
+
+fn fee_rate_too_high() -> ProgramError {
+    MyError::FeeRateTooHigh.into()
+}
+
+#[test]
+fn version_reports_the_fee_ceiling() {
+    let mut env = TestEnv::new();
+    let result = env.process(&version_ix(&PROGRAM_ID)).assert_ok();
+    let info = VersionInfo::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!(info.max_fee_bps, MAX_FEE_BPS);
+    assert_eq!(MAX_FEE_RATE_PIPS, MAX_FEE_BPS * 100);
+}
+
+#[test]
+fn create_config_creates_the_pda_from_scratch() {
+    let mut env = TestEnv::new();
+    assert!(env.account(&env.config).is_none());
+    let admin_before = env.lamports(&env.admin);
+
+    let ix = create_config_ix(&PROGRAM_ID, &env.admin, DEFAULT_FEE_RATE_PIPS);
+    env.process(&ix).assert_ok();
+    let rent = Rent::default().minimum_balance(TradeFeeState::LEN);
+    let account = env.account(&env.config).unwrap();
+    assert_eq!((account.owner, account.data.len(), account.lamports), (PROGRAM_ID, TradeFeeState::LEN, rent));
+    // 管理员支付租金并成为手续费钱包
+    assert_eq!(admin_before - env.lamports(&env.admin), rent);
+    let state = env.config_state();
+    assert_eq!((state.fee_rate_pips, state.fee_wallet), (DEFAULT_FEE_RATE_PIPS, env.admin));
+
+    // 已创建的配置不能再次创建
+    let ix = create_config_ix(&PROGRAM_ID, &env.admin, 2 * DEFAULT_FEE_RATE_PIPS);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::AccountAlreadyInitialized);
+    assert_eq!(env.config_state().fee_rate_pips, DEFAULT_FEE_RATE_PIPS);
+}
+
+#[test]
+fn create_config_requires_the_upgrade_authority() {
+    let mut env = TestEnv::new();
+    let stranger = env.wallet(SOL);
+    let ix = create_config_ix(&PROGRAM_ID, &stranger, DEFAULT_FEE_RATE_PIPS);
+    assert!(env.process(&ix).result.is_err());
+    assert!(env.account(&env.config).is_none());
+}
+
+#[test]
+fn create_config_rejects_rate_above_ceiling() {
+    let mut env = TestEnv::new();
+    let ix = create_config_ix(&PROGRAM_ID, &env.admin, MAX_FEE_RATE_PIPS + 1);
+    assert_eq!(env.process(&ix).unwrap_err(), fee_rate_too_high());
+    assert!(env.data(&env.config).is_empty());
+
+    let ix = create_config_ix(&PROGRAM_ID, &env.admin, MAX_FEE_RATE_PIPS);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().fee_rate_pips, MAX_FEE_RATE_PIPS);
+}
+
+#[test]
+fn fee_rate_setters_reject_rate_above_ceiling() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let (config, admin) = (env.config, env.admin);
+    let over = MAX_FEE_RATE_PIPS + 1;
+
+    let update = ConfigUpdate {
+        fee_rate_pips: Some(over),
+        ..Default::default()
+    };
+    let rejected = [
+        set_fee_rate_ix(&PROGRAM_ID, &config, &admin, over),
+        update_config_ix(&PROGRAM_ID, &config, &admin, &update),
+        set_route_fees_ix(&PROGRAM_ID, &config, &admin, &[(*PUMP_SELECTOR, over)]),
+        set_mint_fee_ix(&PROGRAM_ID, &config, &admin, &Pubkey::new_unique(), over),
+    ];
+    for ix in &rejected {
+        assert_eq!(env.process(ix).unwrap_err(), fee_rate_too_high());
+    }
+    assert_eq!(env.config_state().fee_rate_pips, DEFAULT_FEE_RATE_PIPS);
+
+    env.process(&set_fee_rate_ix(&PROGRAM_ID, &config, &admin, MAX_FEE_RATE_PIPS)).assert_ok();
+    assert_eq!(env.config_state().fee_rate_pips, MAX_FEE_RATE_PIPS);
+}
+
+#[test]
+fn set_shares_rejects_a_combination_over_100_percent() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let (config, admin) = (env.config, env.admin);
+    env.process(&set_shares_ix(&PROGRAM_ID, &config, &admin, 2_000, 1_000)).assert_ok();
+
+    // 单独看都合法，合计 10_001 基点，两个字段都保持原值
+    let ix = set_shares_ix(&PROGRAM_ID, &config, &admin, 6_000, 4_001);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::InvalidShareBps.into());
+    let state = env.config_state();
+    assert_eq!((state.referral_share_bps, state.buyback_share_bps), (2_000, 1_000));
+}
+
+#[test]
+fn set_shares_updates_both_shares_together() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let (config, admin) = (env.config, env.admin);
+    env.process(&set_shares_ix(&PROGRAM_ID, &config, &admin, 6_000, 4_000)).assert_ok();
+    let state = env.config_state();
+    assert_eq!((state.referral_share_bps, state.buyback_share_bps), (6_000, 4_000));
+}
+
+#[test]
+fn update_config_applies_several_fields_at_once() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let update = ConfigUpdate {
+        fee_rate_pips: Some(2 * DEFAULT_FEE_RATE_PIPS),
+        free_trades: Some(3),
+        referral_share_bps: Some(6_000),
+        buyback_share_bps: Some(4_000),
+        compact_events: Some(true),
+        ..Default::default()
+    };
+    let ix = update_config_ix(&PROGRAM_ID, &env.config, &env.admin, &update);
+    env.process(&ix).assert_ok();
+    let state = env.config_state();
+    assert_eq!((state.fee_rate_pips, state.free_trades), (2 * DEFAULT_FEE_RATE_PIPS, 3));
+    assert_eq!((state.referral_share_bps, state.buyback_share_bps, state.compact_events), (6_000, 4_000, true));
+}
+
+#[test]
+fn update_config_rejects_an_invalid_combination_as_a_whole() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let (config, admin) = (env.config, env.admin);
+    env.process(&set_shares_ix(&PROGRAM_ID, &config, &admin, 0, 5_000)).assert_ok();
+    let before = env.data(&config).to_vec();
+
+    // 分成单独合法，但与已存储的回购分成合计超过 100%，同一次更新中的费率也不生效
+    let update = ConfigUpdate {
+        fee_rate_pips: Some(2 * DEFAULT_FEE_RATE_PIPS),
+        referral_share_bps: Some(6_000),
+        ..Default::default()
+    };
+    let ix = update_config_ix(&PROGRAM_ID, &config, &admin, &update);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::InvalidShareBps.into());
+    assert_eq!(env.data(&config), &before[..]);
+
+    // 返还比例超过 100%
+    let update = ConfigUpdate {
+        free_trades: Some(3),
+        rebate_bps: Some(10_001),
+        ..Default::default()
+    };
+    let ix = update_config_ix(&PROGRAM_ID, &config, &admin, &update);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidInstructionData);
+    assert_eq!(env.data(&config), &before[..]);
+
+    // 非管理员不能更新
+    let stranger = env.wallet(SOL);
+    let update = ConfigUpdate {
+        free_trades: Some(3),
+        ..Default::default()
+    };
+    assert!(env.process(&update_config_ix(&PROGRAM_ID, &config, &stranger, &update)).result.is_err());
+    assert_eq!(env.data(&config), &before[..]);
+}
+
+#[test]
+fn config_bump_fast_path_matches_the_derived_address() {
+    let derived = config_address(&PROGRAM_ID);
+    assert_eq!(config_address_with_bump(&PROGRAM_ID, None).unwrap(), derived);
+    assert_eq!(config_address_with_bump(&PROGRAM_ID, Some(derived.1)).unwrap(), derived);
+}
+
+#[test]
+fn create_config_with_and_without_bump_targets_the_same_pda() {
+    let mut with_bump = TestEnv::new();
+    let ix = create_config_ix(&PROGRAM_ID, &with_bump.admin, DEFAULT_FEE_RATE_PIPS);
+    with_bump.process(&ix).assert_ok();
+
+    // 去掉末尾的 bump，由链上 find_program_address 推导
+    let mut without_bump = TestEnv::new();
+    let mut ix = create_config_ix(&PROGRAM_ID, &without_bump.admin, DEFAULT_FEE_RATE_PIPS);
+    ix.data.pop();
+    without_bump.process(&ix).assert_ok();
+
+    assert_eq!(ix.accounts[0].pubkey, with_bump.config);
+    for env in [&with_bump, &without_bump] {
+        assert_eq!(env.account(&env.config).unwrap().owner, PROGRAM_ID);
+        assert_eq!(env.config_state().fee_rate_pips, DEFAULT_FEE_RATE_PIPS);
+    }
+}
+
+#[test]
+fn create_config_rejects_a_non_canonical_bump() {
+    let mut env = TestEnv::new();
+    let mut ix = create_config_ix(&PROGRAM_ID, &env.admin, DEFAULT_FEE_RATE_PIPS);
+    *ix.data.last_mut().unwrap() = config_address(&PROGRAM_ID).1.wrapping_sub(1);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidSeeds);
+    assert!(env.account(&env.config).is_none());
+}
+
+#[test]
+fn get_config_returns_the_whole_config_without_mutation() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_route_fees_ix(&PROGRAM_ID, &env.config, &env.admin, &[(*PUMP_SELECTOR, 5_000)]);
+    env.process(&ix).assert_ok();
+    let before = env.data(&env.config).to_vec();
+
+    let ix = get_config_ix(&PROGRAM_ID, &env.config);
+    let result = env.process(&ix).assert_ok();
+    let (program, data) = result.return_data.unwrap();
+    assert_eq!(program, PROGRAM_ID);
+    let config = TradeFeeState::unpack(&data).unwrap();
+    assert_eq!((config.fee_wallet, config.fee_rate_pips), (env.admin, DEFAULT_FEE_RATE_PIPS));
+    assert_eq!(config.fee_rate_for(PUMP_SELECTOR), 5_000);
+    assert_eq!(borsh::to_vec(&config).unwrap(), borsh::to_vec(&env.config_state()).unwrap());
+    assert_eq!(env.data(&env.config), &before[..]);
+
+    // 只接受本程序所有的配置账户
+    let stranger = env.wallet(1);
+    assert_eq!(env.process(&get_config_ix(&PROGRAM_ID, &stranger)).unwrap_err(), ProgramError::IllegalOwner);
+}
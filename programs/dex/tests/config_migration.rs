@@ -0,0 +1,97 @@
+// 旧布局的配置账户：长度不足以写回当前布局时返回 ConfigAccountTooSmall，读取不受影响；
+// mig_cfg 由管理员补足租金把账户扩容到当前布局，新增字节清零
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    ix_builder::{migrate_config_ix, pump_buy_ix, set_fee_rate_ix},
+    state::TradeFeeState,
+};
+use common::{Account, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::rent::Rent;
+
+// 把配置账户截短为 len 字节，模拟按旧布局分配的账户
+fn shrink_config(env: &mut TestEnv, len: usize) -> Vec<u8> {
+    let mut data = env.data(&env.config).to_vec();
+    data.truncate(len);
+    let config = env.config;
+    env.set_account(config, Account::rent_exempt(data.clone(), PROGRAM_ID));
+    data
+}
+
+#[test]
+fn undersized_config_account_is_reported() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let data = shrink_config(&mut env, TradeFeeState::LEN - 16);
+    // 缺失的尾部字段按 0 解析
+    assert_eq!(env.config_state().fee_rate_pips, DEFAULT_FEE_RATE_PIPS);
+
+    let ix = set_fee_rate_ix(&PROGRAM_ID, &env.config, &env.admin, 2 * DEFAULT_FEE_RATE_PIPS);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::ConfigAccountTooSmall.into());
+
+    // 交易同样需要写回配置，写回失败时整笔回滚
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::ConfigAccountTooSmall.into());
+    assert_eq!(env.lamports(&user), 10 * SOL);
+    assert_eq!(env.data(&env.config), &data[..]);
+}
+
+#[test]
+fn migration_grows_the_account_to_the_current_layout() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let old_len = TradeFeeState::LEN - 64;
+    let data = shrink_config(&mut env, old_len);
+    let config = env.config;
+    let (admin_before, config_before) = (env.lamports(&env.admin), env.lamports(&config));
+
+    let ix = migrate_config_ix(&PROGRAM_ID, &config, &env.admin);
+    env.process(&ix).assert_ok();
+    let migrated = env.data(&config);
+    assert_eq!(migrated.len(), TradeFeeState::LEN);
+    assert_eq!(&migrated[..old_len], &data[..]);
+    assert!(migrated[old_len..].iter().all(|byte| *byte == 0));
+
+    // 管理员补足新长度的免租金额
+    let rent = Rent::default().minimum_balance(TradeFeeState::LEN);
+    assert_eq!(env.lamports(&config), rent);
+    assert_eq!(admin_before - env.lamports(&env.admin), rent - config_before);
+
+    // 扩容后可以正常写回
+    let ix = set_fee_rate_ix(&PROGRAM_ID, &config, &env.admin, 2 * DEFAULT_FEE_RATE_PIPS);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().fee_rate_pips, 2 * DEFAULT_FEE_RATE_PIPS);
+
+    // 已是当前布局时不再扩容
+    let admin_before = env.lamports(&env.admin);
+    let ix = migrate_config_ix(&PROGRAM_ID, &config, &env.admin);
+    env.process(&ix).assert_ok();
+    assert_eq!((env.data(&config).len(), env.lamports(&env.admin)), (TradeFeeState::LEN, admin_before));
+}
+
+#[test]
+fn legacy_layout_is_migrated_with_the_rate_converted() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    // 初版布局：[费率百分比 u8][fee_wallet 32]
+    let mut legacy = vec![2u8];
+    legacy.extend_from_slice(env.admin.as_ref());
+    let config = env.config;
+    env.set_account(config, Account::rent_exempt(legacy, PROGRAM_ID));
+
+    let ix = migrate_config_ix(&PROGRAM_ID, &config, &env.admin);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.data(&config).len(), TradeFeeState::LEN);
+    let state = env.config_state();
+    assert_eq!((state.fee_rate_pips, state.fee_wallet), (20_000, env.admin));
+}
+
+#[test]
+fn migration_requires_the_admin() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let data = shrink_config(&mut env, TradeFeeState::LEN - 64);
+    let stranger = env.wallet(SOL);
+    let ix = migrate_config_ix(&PROGRAM_ID, &env.config, &stranger);
+    assert!(env.process(&ix).result.is_err());
+    assert_eq!(env.data(&env.config), &data[..]);
+}
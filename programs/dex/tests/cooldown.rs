@@ -0,0 +1,64 @@
+// 交易冷却：同一钱包在 trade_cooldown_slots 内的第二笔交易被拒绝，钱包 PDA 首次交易时创建
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::wallet::wallet_address,
+    ix_builder::{pump_buy_ix, set_trade_cooldown_ix, with_wallet_counter},
+    state::WalletState,
+};
+use common::{runtime::TxResult, PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+const COOLDOWN_SLOTS: u64 = 5;
+
+fn setup() -> (TestEnv, Pubkey, PumpCurve) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_trade_cooldown_ix(&PROGRAM_ID, &env.config, &env.admin, COOLDOWN_SLOTS);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().trade_cooldown_slots, COOLDOWN_SLOTS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    (env, user, curve)
+}
+
+fn buy(env: &mut TestEnv, user: &Pubkey, curve: &PumpCurve) -> TxResult {
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL / 10, SOL, curve.buy_accounts());
+    env.process(&with_wallet_counter(&PROGRAM_ID, ix, user))
+}
+
+#[test]
+fn trade_within_the_cooldown_is_rejected() {
+    let (mut env, user, curve) = setup();
+    env.warp_to_slot(100);
+    buy(&mut env, &user, &curve).assert_ok();
+    let state = WalletState::unpack(env.data(&wallet_address(&PROGRAM_ID, &user).0)).unwrap();
+    assert_eq!((state.trade_count, state.last_trade_slot), (1, 100));
+
+    env.warp_to_slot(100 + COOLDOWN_SLOTS - 1);
+    assert_eq!(buy(&mut env, &user, &curve).unwrap_err(), MyError::TradeCooldown.into());
+}
+
+#[test]
+fn trade_after_the_cooldown_is_allowed() {
+    let (mut env, user, curve) = setup();
+    env.warp_to_slot(100);
+    buy(&mut env, &user, &curve).assert_ok();
+
+    env.warp_to_slot(100 + COOLDOWN_SLOTS);
+    buy(&mut env, &user, &curve).assert_ok();
+    let state = WalletState::unpack(env.data(&wallet_address(&PROGRAM_ID, &user).0)).unwrap();
+    assert_eq!((state.trade_count, state.last_trade_slot), (2, 100 + COOLDOWN_SLOTS));
+
+    // 其他钱包不受影响
+    let other = env.wallet(10 * SOL);
+    let other_curve = env.pump_curve(&other, 0);
+    buy(&mut env, &other, &other_curve).assert_ok();
+}
+
+#[test]
+fn cooldown_requires_the_wallet_account() {
+    let (mut env, user, curve) = setup();
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL / 10, SOL, curve.buy_accounts());
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::NotEnoughAccountKeys);
+}
@@ -0,0 +1,78 @@
+// CPI 调用方白名单：开启后只有名单中的程序可以通过 CPI 发起带手续费的路由，直接调用不受影响
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    ix_builder::{pump_buy_ix, set_cpi_callers_ix, with_instructions_sysvar},
+};
+use common::{mocks::forwarder_processor, runtime::TxResult, PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+struct Callers {
+    env: TestEnv,
+    user: Pubkey,
+    curve: PumpCurve,
+    allowed: Pubkey,
+    other: Pubkey,
+}
+
+fn setup() -> Callers {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let (allowed, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+    env.add_program(allowed, forwarder_processor);
+    env.add_program(other, forwarder_processor);
+    let ix = set_cpi_callers_ix(&PROGRAM_ID, &env.config, &env.admin, true, &[allowed]);
+    env.process(&ix).assert_ok();
+    let config = env.config_state();
+    assert!(config.cpi_allowlist_enabled);
+    assert_eq!(config.cpi_callers[0], allowed);
+
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    Callers { env, user, curve, allowed, other }
+}
+
+impl Callers {
+    fn buy_ix(&self) -> Instruction {
+        let ix = pump_buy_ix(&PROGRAM_ID, &self.env.fee_accounts(&self.user), SOL, 2 * SOL, self.curve.buy_accounts());
+        with_instructions_sysvar(ix)
+    }
+
+    // caller 程序把买入指令以 CPI 转发给本程序
+    fn buy_via(&mut self, caller: &Pubkey) -> TxResult {
+        let inner = self.buy_ix();
+        let mut accounts = vec![AccountMeta::new_readonly(PROGRAM_ID, false)];
+        accounts.extend(inner.accounts);
+        let ix = Instruction { program_id: *caller, accounts, data: inner.data };
+        self.env.process(&ix)
+    }
+}
+
+#[test]
+fn allowed_caller_can_route_via_cpi() {
+    let mut callers = setup();
+    let treasury_before = callers.env.lamports(&callers.env.admin);
+    let allowed = callers.allowed;
+    callers.buy_via(&allowed).assert_ok();
+    assert_eq!(callers.env.lamports(&callers.env.admin) - treasury_before, SOL / 100);
+}
+
+#[test]
+fn caller_outside_the_allowlist_is_rejected() {
+    let mut callers = setup();
+    let other = callers.other;
+    assert_eq!(callers.buy_via(&other).unwrap_err(), MyError::CpiCallerNotAllowed.into());
+    assert_eq!(callers.env.lamports(&callers.user), 10 * SOL);
+
+    // 直接调用不受白名单限制
+    let ix = callers.buy_ix();
+    callers.env.process(&ix).assert_ok();
+
+    // 关闭白名单后任何调用方都可以
+    let ix = set_cpi_callers_ix(&PROGRAM_ID, &callers.env.config, &callers.env.admin, false, &[]);
+    callers.env.process(&ix).assert_ok();
+    callers.buy_via(&other).assert_ok();
+}
@@ -0,0 +1,31 @@
+// 内层 DEX 执行失败时整笔交易以内层错误码终止（链上不会回到本程序的 invoke），错误码原样传出，状态全部回滚
+mod common;
+
+use amm_proxy_contract::ix_builder::pump_buy_ix;
+use common::{
+    mocks::{set_swap_behavior, SwapBehavior, PUMP_PROGRAM},
+    TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::program_error::ProgramError;
+
+const INNER_ERROR: u32 = 6_100;
+
+#[test]
+fn inner_program_error_is_propagated() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let treasury_before = env.lamports(&env.admin);
+    set_swap_behavior(SwapBehavior {
+        error: Some(INNER_ERROR),
+        ..Default::default()
+    });
+
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let result = env.process(&ix);
+    assert_eq!(result.unwrap_err(), ProgramError::Custom(INNER_ERROR));
+    assert!(result.has_log("模拟失败"));
+    assert_eq!(result.cpis_to(&PUMP_PROGRAM).len(), 1);
+    // 兑换前已转出的手续费随整笔交易回滚
+    assert_eq!((env.lamports(&user), env.lamports(&env.admin)), (10 * SOL, treasury_before));
+}
@@ -0,0 +1,71 @@
+// CPI 调用方免手续费：开启 waive_cpi_fees 后其他程序通过 CPI 发起的交易不收费，直接调用照常收费
+mod common;
+
+use amm_proxy_contract::{
+    instructions::fee::SwapResult,
+    ix_builder::{pump_buy_ix, set_cpi_fee_waiver_ix},
+};
+use borsh::BorshDeserialize;
+use common::{
+    mocks::{forwarder_processor, PUMP_PROGRAM},
+    runtime::TxResult,
+    PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+fn setup(waive: bool) -> (TestEnv, Pubkey, PumpCurve, Pubkey) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_cpi_fee_waiver_ix(&PROGRAM_ID, &env.config, &env.admin, waive);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().waive_cpi_fees, waive);
+    let caller = Pubkey::new_unique();
+    env.add_program(caller, forwarder_processor);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    (env, user, curve, caller)
+}
+
+fn buy_ix(env: &TestEnv, user: &Pubkey, curve: &PumpCurve) -> Instruction {
+    pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, curve.buy_accounts())
+}
+
+// caller 程序把买入指令以 CPI 转发给本程序
+fn via_caller(ix: Instruction, caller: &Pubkey) -> Instruction {
+    let mut accounts = vec![AccountMeta::new_readonly(PROGRAM_ID, false)];
+    accounts.extend(ix.accounts);
+    Instruction { program_id: *caller, accounts, data: ix.data }
+}
+
+fn charged(env: &mut TestEnv, ix: &Instruction) -> (u64, TxResult) {
+    let treasury_before = env.lamports(&env.admin);
+    let result = env.process(ix).assert_ok();
+    (env.lamports(&env.admin) - treasury_before, result)
+}
+
+#[test]
+fn cpi_call_is_waived_and_top_level_call_is_charged() {
+    let (mut env, user, curve, caller) = setup(true);
+
+    let ix = via_caller(buy_ix(&env, &user, &curve), &caller);
+    let (fee, result) = charged(&mut env, &ix);
+    assert_eq!(fee, 0);
+    // 本程序位于第 2 层，Pump 位于第 3 层，且按完整数量兑换
+    assert!(result.invocations.iter().any(|ix| ix.program_id == PROGRAM_ID && ix.stack_height == 2));
+    assert_eq!(result.cpis_to(&PUMP_PROGRAM)[0].data[8..16], SOL.to_le_bytes());
+
+    let ix = buy_ix(&env, &user, &curve);
+    let (fee, result) = charged(&mut env, &ix);
+    assert_eq!(fee, SOL / 100);
+    let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!(swap.fee, SOL / 100);
+}
+
+#[test]
+fn cpi_call_is_charged_without_the_waiver() {
+    let (mut env, user, curve, caller) = setup(false);
+    let ix = via_caller(buy_ix(&env, &user, &curve), &caller);
+    assert_eq!(charged(&mut env, &ix).0, SOL / 100);
+}
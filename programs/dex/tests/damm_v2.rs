@@ -0,0 +1,62 @@
+// Meteora DAMM v2 路由：转发到 DAMM v2 程序的 swap，内层 amount_in（偏移 8）为扣除手续费后的数量
+mod common;
+
+use amm_proxy_contract::{
+    instructions::meteora::{DAMM_V2_BUY_ROUTE, DAMM_V2_BUY_SELECTOR},
+    ix_builder::damm_v2_buy_ix,
+    token::TOKEN_PROGRAM_ID,
+};
+use common::{mocks::DAMM_V2_PROGRAM, route_accounts, u64_args, Account, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+const DAMM_V2_SWAP: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+const AMOUNT_IN: u64 = SOL;
+
+// 以原生 SOL 买入：input_token_account 为用户钱包，输出到用户的代币账户
+fn buy(env: &mut TestEnv, user: &Pubkey, pool_owner: Pubkey) -> (Instruction, Vec<AccountMeta>) {
+    let mint = env.add_mint(6, &TOKEN_PROGRAM_ID);
+    let output = env.add_ata(user, &mint, 0, &TOKEN_PROGRAM_ID);
+    let mut forwarded = route_accounts(&DAMM_V2_BUY_ROUTE, 13, user);
+    forwarded[2] = AccountMeta::new(*user, false);
+    forwarded[3] = AccountMeta::new(output, false);
+    forwarded.push(AccountMeta::new_readonly(DAMM_V2_PROGRAM, false));
+    env.set_account(forwarded[1].pubkey, Account::rent_exempt(vec![0; 64], pool_owner));
+    let ix = damm_v2_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), AMOUNT_IN, 1, forwarded.clone());
+    (ix, forwarded)
+}
+
+#[test]
+fn buy_is_forwarded_to_damm_v2_with_the_amount_patched() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let treasury_before = env.lamports(&env.admin);
+    let (ix, forwarded) = buy(&mut env, &user, DAMM_V2_PROGRAM);
+    assert_eq!(ix.data[..8], DAMM_V2_BUY_SELECTOR[..]);
+    let result = env.process(&ix).assert_ok();
+
+    let fee = AMOUNT_IN / 100;
+    let cpis = result.cpis_to(&DAMM_V2_PROGRAM);
+    assert_eq!(cpis.len(), 1);
+    assert_eq!(cpis[0].data[..8], DAMM_V2_SWAP);
+    assert_eq!(cpis[0].data[8..], u64_args(&[AMOUNT_IN - fee, 1])[..]);
+    // 14 个 swap 账户按原顺序转发，末尾为用于 event CPI 的程序账户
+    let keys: Vec<Pubkey> = cpis[0].accounts.iter().map(|meta| meta.pubkey).collect();
+    let expected: Vec<Pubkey> = forwarded.iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(keys, expected);
+    assert_eq!(env.lamports(&env.admin) - treasury_before, fee);
+}
+
+#[test]
+fn pool_not_owned_by_damm_v2_is_rejected() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let (ix, _) = buy(&mut env, &user, Pubkey::new_unique());
+    let result = env.process(&ix);
+    assert!(result.cpis_to(&DAMM_V2_PROGRAM).is_empty());
+    assert_eq!(result.unwrap_err(), ProgramError::IllegalOwner);
+    assert_eq!(env.lamports(&user), 10 * SOL);
+}
@@ -0,0 +1,26 @@
+// debug-logs feature：入口处逐个打印账户的地址与签名、可写标记；未启用时不输出。
+// 启用后运行：cargo test --features debug-logs --test debug_logs
+mod common;
+
+use amm_proxy_contract::ix_builder::set_fee_rate_ix;
+use common::{runtime::program_output, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID};
+
+#[test]
+fn accounts_are_logged_at_entry_only_with_the_feature() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_fee_rate_ix(&PROGRAM_ID, &env.config, &env.admin, 2 * DEFAULT_FEE_RATE_PIPS);
+    let Some(output) = program_output("accounts_are_logged_at_entry_only_with_the_feature", || {
+        env.process(&ix).assert_ok();
+    }) else {
+        return;
+    };
+
+    let lines = [
+        format!("账户数 {}", ix.accounts.len()),
+        format!("#0 {} signer=false writable={}", ix.accounts[0].pubkey, ix.accounts[0].is_writable),
+        format!("#1 {} signer=true writable={}", env.admin, ix.accounts[1].is_writable),
+    ];
+    for line in lines {
+        assert_eq!(output.contains(&line), cfg!(feature = "debug-logs"), "{line}\n{output}");
+    }
+}
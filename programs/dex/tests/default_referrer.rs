@@ -0,0 +1,96 @@
+// 默认推荐人：带推荐码的交易按登记的推荐人分成，未带推荐码时分成给配置的默认推荐人，未配置时手续费全部归协议
+mod common;
+
+use amm_proxy_contract::{
+    instructions::fee::SwapResult,
+    ix_builder::{pump_buy_ix, register_referral_ix, set_default_referrer_ix, with_default_referrer, with_referral},
+};
+use borsh::BorshDeserialize;
+use common::{runtime::TxResult, PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+
+const CODE: u32 = 7;
+const FEE: u64 = SOL / 100;
+
+fn setup(default_share_bps: Option<u16>) -> (TestEnv, Pubkey, PumpCurve, Pubkey) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let house = env.wallet(SOL);
+    if let Some(share_bps) = default_share_bps {
+        let ix = set_default_referrer_ix(&PROGRAM_ID, &env.config, &env.admin, &house, share_bps);
+        env.process(&ix).assert_ok();
+        let config = env.config_state();
+        assert_eq!((config.default_referrer, config.default_referrer_share_bps), (house, share_bps));
+    }
+    (env, user, curve, house)
+}
+
+fn buy(env: &TestEnv, user: &Pubkey, curve: &PumpCurve) -> Instruction {
+    pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, curve.buy_accounts())
+}
+
+fn breakdown(result: TxResult) -> (u64, u64) {
+    let breakdown = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap().breakdown;
+    (breakdown.referrer, breakdown.treasury)
+}
+
+#[test]
+fn attributed_trade_pays_the_registered_referrer() {
+    let (mut env, user, curve, house) = setup(Some(1_000));
+    let referrer = env.wallet(SOL);
+    let ix = register_referral_ix(&PROGRAM_ID, &env.config, &env.admin, CODE, &referrer, 2_000);
+    env.process(&ix).assert_ok();
+
+    // 同时附带默认推荐人账户，也只按推荐码分成
+    let treasury_before = env.lamports(&env.admin);
+    let ix = with_default_referrer(with_referral(&PROGRAM_ID, buy(&env, &user, &curve), CODE, &referrer), &house);
+    let result = env.process(&ix).assert_ok();
+    let referrer_fee = FEE * 2_000 / 10_000;
+    assert_eq!(breakdown(result), (referrer_fee, FEE - referrer_fee));
+    assert_eq!(env.lamports(&referrer) - SOL, referrer_fee);
+    assert_eq!(env.lamports(&house), SOL);
+    assert_eq!(env.lamports(&env.admin) - treasury_before, FEE - referrer_fee);
+}
+
+#[test]
+fn unattributed_trade_pays_the_default_referrer() {
+    let (mut env, user, curve, house) = setup(Some(1_000));
+    let treasury_before = env.lamports(&env.admin);
+    let ix = with_default_referrer(buy(&env, &user, &curve), &house);
+    let result = env.process(&ix).assert_ok();
+
+    let house_fee = FEE * 1_000 / 10_000;
+    assert_eq!(breakdown(result), (house_fee, FEE - house_fee));
+    assert_eq!(env.lamports(&house) - SOL, house_fee);
+    assert_eq!(env.lamports(&env.admin) - treasury_before, FEE - house_fee);
+
+    // 未附带默认推荐人账户时该笔分成归协议
+    let treasury_before = env.lamports(&env.admin);
+    let ix = buy(&env, &user, &curve);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.lamports(&house) - SOL, house_fee);
+    assert_eq!(env.lamports(&env.admin) - treasury_before, FEE);
+}
+
+#[test]
+fn unattributed_trade_without_a_default_goes_to_the_treasury() {
+    let (mut env, user, curve, house) = setup(None);
+    assert_eq!(env.config_state().default_referrer, Pubkey::default());
+
+    let treasury_before = env.lamports(&env.admin);
+    let ix = with_default_referrer(buy(&env, &user, &curve), &house);
+    let result = env.process(&ix).assert_ok();
+    assert_eq!(breakdown(result), (0, FEE));
+    assert_eq!(env.lamports(&house), SOL);
+    assert_eq!(env.lamports(&env.admin) - treasury_before, FEE);
+}
+
+#[test]
+fn setting_the_default_referrer_requires_the_admin() {
+    let (mut env, _, _, house) = setup(None);
+    let stranger = env.wallet(SOL);
+    let ix = set_default_referrer_ix(&PROGRAM_ID, &env.config, &stranger, &house, 1_000);
+    assert!(env.process(&ix).result.is_err());
+    assert_eq!(env.config_state().default_referrer, Pubkey::default());
+}
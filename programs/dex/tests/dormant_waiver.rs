@@ -0,0 +1,67 @@
+// 闲置免费：协议超过 dormant_waiver_slots 个 slot 没有交易时，下一笔交易免收手续费；
+// 每笔交易都会刷新配置中的 last_trade_slot
+mod common;
+
+use amm_proxy_contract::{
+    instructions::fee::SwapResult,
+    ix_builder::{pump_buy_ix, set_dormant_waiver_ix},
+};
+use borsh::BorshDeserialize;
+use common::{TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::pubkey::Pubkey;
+
+const DORMANT_SLOTS: u32 = 50;
+const START: u64 = 1_000;
+
+fn charged(env: &mut TestEnv, user: &Pubkey) -> u64 {
+    let curve = env.pump_curve(user, 0);
+    let treasury_before = env.lamports(&env.admin);
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, curve.buy_accounts());
+    let result = env.process(&ix).assert_ok();
+    let fee = env.lamports(&env.admin) - treasury_before;
+    assert_eq!(SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap().fee, fee);
+    fee
+}
+
+fn setup(dormant_slots: u32) -> (TestEnv, Pubkey) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_dormant_waiver_ix(&PROGRAM_ID, &env.config, &env.admin, dormant_slots);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().dormant_waiver_slots, dormant_slots);
+    env.warp_to_slot(START);
+    let user = env.wallet(10 * SOL);
+    (env, user)
+}
+
+#[test]
+fn first_trade_after_a_dormant_period_is_free() {
+    let (mut env, user) = setup(DORMANT_SLOTS);
+    // 尚无交易记录时不视为闲置
+    assert_eq!(charged(&mut env, &user), SOL / 100);
+    assert_eq!(env.config_state().last_trade_slot, START);
+
+    // 间隔恰好等于 dormant_waiver_slots 时照常收费
+    let slot = START + DORMANT_SLOTS as u64;
+    env.warp_to_slot(slot);
+    assert_eq!(charged(&mut env, &user), SOL / 100);
+
+    // 超过后的第一笔免收，免收的交易同样刷新 last_trade_slot，下一笔照常收费
+    let slot = slot + DORMANT_SLOTS as u64 + 1;
+    env.warp_to_slot(slot);
+    assert_eq!(charged(&mut env, &user), 0);
+    assert_eq!(env.config_state().last_trade_slot, slot);
+    assert_eq!(charged(&mut env, &user), SOL / 100);
+}
+
+#[test]
+fn zero_disables_the_waiver() {
+    let (mut env, user) = setup(0);
+    assert_eq!(charged(&mut env, &user), SOL / 100);
+    env.warp_to_slot(START + 1_000_000);
+    assert_eq!(charged(&mut env, &user), SOL / 100);
+
+    let stranger = env.wallet(SOL);
+    let ix = set_dormant_waiver_ix(&PROGRAM_ID, &env.config, &stranger, DORMANT_SLOTS);
+    assert!(env.process(&ix).result.is_err());
+    assert_eq!(env.config_state().dormant_waiver_slots, 0);
+}
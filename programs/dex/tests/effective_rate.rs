@@ -0,0 +1,111 @@
+// 有效费率：基础费率取代币费率 PDA > 路由覆盖 > 全局费率中优先级最高的一项，
+// 再依次叠乘会员折扣、全局返还与大额返还；explain 报告的有效费率与实际收费一致
+mod common;
+
+use amm_proxy_contract::{
+    instructions::{
+        fee::{RoutePlan, SwapResult},
+        pump::PUMP_SELECTOR,
+    },
+    ix_builder::{
+        explain_ix, pump_buy_ix, set_membership_ix, set_mint_fee_ix, set_rebate_ix, set_route_fees_ix,
+        set_whale_rebate_ix, with_membership_account, with_mint_fee,
+    },
+    token::TOKEN_PROGRAM_ID,
+};
+use borsh::BorshDeserialize;
+use common::{PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+
+const ROUTE_RATE_PIPS: u32 = 20_000;
+const MINT_RATE_PIPS: u32 = 30_000;
+
+struct Setup {
+    env: TestEnv,
+    user: Pubkey,
+    curve: PumpCurve,
+    membership_mint: Pubkey,
+}
+
+// 路由覆盖与代币费率同时存在，会员折扣 50%、全局返还 20%、超过 1 SOL 的大额返还 50%
+fn setup() -> Setup {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let (config, admin) = (env.config, env.admin);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let membership_mint = env.add_mint(0, &TOKEN_PROGRAM_ID);
+    env.add_ata(&user, &membership_mint, 1, &TOKEN_PROGRAM_ID);
+    let setters = [
+        set_route_fees_ix(&PROGRAM_ID, &config, &admin, &[(*PUMP_SELECTOR, ROUTE_RATE_PIPS)]),
+        set_mint_fee_ix(&PROGRAM_ID, &config, &admin, &curve.mint, MINT_RATE_PIPS),
+        set_membership_ix(&PROGRAM_ID, &config, &admin, &membership_mint, 5_000),
+        set_rebate_ix(&PROGRAM_ID, &config, &admin, 2_000),
+        set_whale_rebate_ix(&PROGRAM_ID, &config, &admin, SOL, 5_000),
+    ];
+    for ix in &setters {
+        env.process(ix).assert_ok();
+    }
+    Setup { env, user, curve, membership_mint }
+}
+
+fn buy(setup: &Setup, amount: u64, mint_fee: bool, member: bool) -> Instruction {
+    let Setup { env, user, curve, membership_mint } = setup;
+    let mut ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), amount, 2 * amount, curve.buy_accounts());
+    if mint_fee {
+        ix = with_mint_fee(&PROGRAM_ID, ix, &curve.mint);
+    }
+    if member {
+        ix = with_membership_account(ix, user, membership_mint, &TOKEN_PROGRAM_ID);
+    }
+    ix
+}
+
+// 返回 explain 报告的基础费率与有效费率，并确认实际执行收取的手续费与计划一致
+fn rates(setup: &mut Setup, ix: &Instruction) -> (u32, u32) {
+    let result = setup.env.process(&explain_ix(ix)).assert_ok();
+    let plan = RoutePlan::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!(plan.fee, plan.amount * plan.effective_rate_pips as u64 / 1_000_000);
+
+    let treasury_before = setup.env.lamports(&setup.env.admin);
+    let result = setup.env.process(ix).assert_ok();
+    let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!(swap.fee, plan.fee);
+    assert_eq!(setup.env.lamports(&setup.env.admin) - treasury_before, plan.fee);
+    (plan.fee_rate_pips, plan.effective_rate_pips)
+}
+
+#[test]
+fn base_rate_takes_only_the_highest_priority_source() {
+    let mut setup = setup();
+    // 代币费率 PDA 优先于路由覆盖，未附带时取路由覆盖，二者不叠加；非会员且未超过大额阈值时只有全局返还
+    let ix = buy(&setup, SOL, true, false);
+    assert_eq!(rates(&mut setup, &ix), (MINT_RATE_PIPS, MINT_RATE_PIPS * 8 / 10));
+    let ix = buy(&setup, SOL, false, false);
+    assert_eq!(rates(&mut setup, &ix), (ROUTE_RATE_PIPS, ROUTE_RATE_PIPS * 8 / 10));
+}
+
+#[test]
+fn discounts_stack_multiplicatively_in_order() {
+    let mut setup = setup();
+    // 30_000 → 会员 15_000 → 全局返还 12_000
+    let ix = buy(&setup, SOL, true, true);
+    assert_eq!(rates(&mut setup, &ix), (MINT_RATE_PIPS, 12_000));
+    // 超过大额阈值时再减半为 6_000
+    let ix = buy(&setup, 2 * SOL, true, true);
+    assert_eq!(rates(&mut setup, &ix), (MINT_RATE_PIPS, 6_000));
+    // 非会员的大额交易：30_000 → 24_000 → 12_000
+    let ix = buy(&setup, 2 * SOL, true, false);
+    assert_eq!(rates(&mut setup, &ix), (MINT_RATE_PIPS, 12_000));
+}
+
+#[test]
+fn each_step_truncates_before_the_next() {
+    let mut setup = setup();
+    let (config, admin) = (setup.env.config, setup.env.admin);
+    let ix = set_mint_fee_ix(&PROGRAM_ID, &config, &admin, &setup.curve.mint, 10_001);
+    setup.env.process(&ix).assert_ok();
+    // 会员折扣额向下取整，两项返还对返还后的费率向下取整：
+    // 10_001 → 会员 5_001（折扣 5_000.5 取 5_000）→ 全局返还 4_000（4_000.8）→ 大额 2_000
+    let ix = buy(&setup, 2 * SOL, true, true);
+    assert_eq!(rates(&mut setup, &ix), (10_001, 2_000));
+}
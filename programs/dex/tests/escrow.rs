@@ -0,0 +1,112 @@
+// 协议收入托管：手续费转入托管 PDA，按 epoch 释放计划提取，单次提取不超过 max_withdraw_per_tx
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::escrow::{escrow_address, releasable_amount},
+    ix_builder::{init_escrow_ix, pump_buy_ix, set_max_withdraw_ix, withdraw_escrow_ix, FeeAccounts},
+    state::EscrowState,
+};
+use borsh::BorshDeserialize;
+use common::{TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{epoch_schedule::EpochSchedule, program_error::ProgramError};
+
+const FEE: u64 = SOL / 100;
+const RELEASE_PER_EPOCH: u64 = FEE / 2;
+
+fn slots_per_epoch() -> u64 {
+    EpochSchedule::without_warmup().slots_per_epoch
+}
+
+fn escrow_state(env: &TestEnv) -> EscrowState {
+    EscrowState::try_from_slice(env.data(&escrow_address(&PROGRAM_ID).0)).unwrap()
+}
+
+// 创建托管并以托管 PDA 为手续费接收方完成 buys 笔 1 SOL 的买入
+fn funded_escrow(buys: usize) -> TestEnv {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = init_escrow_ix(&PROGRAM_ID, &env.config, &env.admin, RELEASE_PER_EPOCH);
+    env.process(&ix).assert_ok();
+
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let accounts = FeeAccounts {
+        fee_receiver: escrow_address(&PROGRAM_ID).0,
+        ..env.fee_accounts(&user)
+    };
+    for _ in 0..buys {
+        let ix = pump_buy_ix(&PROGRAM_ID, &accounts, SOL, 2 * SOL, curve.buy_accounts());
+        env.process(&ix).assert_ok();
+    }
+    env
+}
+
+fn withdraw(env: &mut TestEnv, amount: u64) -> Result<u64, ProgramError> {
+    let before = env.lamports(&env.admin);
+    let ix = withdraw_escrow_ix(&PROGRAM_ID, &env.config, &env.admin, amount);
+    env.process(&ix).result?;
+    Ok(env.lamports(&env.admin) - before)
+}
+
+#[test]
+fn fees_accrue_in_the_escrow() {
+    let env = funded_escrow(2);
+    let escrow = escrow_state(&env);
+    assert_eq!((escrow.total, escrow.released, escrow.release_per_epoch), (2 * FEE, 0, RELEASE_PER_EPOCH));
+}
+
+#[test]
+fn withdrawals_follow_the_epoch_schedule() {
+    let mut env = funded_escrow(2);
+
+    // 同一 epoch 内没有可释放金额
+    assert_eq!(withdraw(&mut env, 0), Err(MyError::NothingToRelease.into()));
+
+    env.warp_to_slot(slots_per_epoch());
+    assert_eq!(withdraw(&mut env, 0), Ok(RELEASE_PER_EPOCH));
+    assert_eq!(withdraw(&mut env, 0), Err(MyError::NothingToRelease.into()));
+
+    // 经过两个 epoch，超出可释放金额的提取被拒绝，部分提取后剩余部分重新计时
+    env.warp_to_slot(3 * slots_per_epoch());
+    assert_eq!(withdraw(&mut env, 2 * RELEASE_PER_EPOCH + 1), Err(ProgramError::InsufficientFunds));
+    assert_eq!(withdraw(&mut env, RELEASE_PER_EPOCH), Ok(RELEASE_PER_EPOCH));
+    assert_eq!(withdraw(&mut env, 0), Err(MyError::NothingToRelease.into()));
+
+    // 可释放金额不超过尚未释放的累计收入
+    env.warp_to_slot(10 * slots_per_epoch());
+    assert_eq!(withdraw(&mut env, 0), Ok(2 * FEE - 2 * RELEASE_PER_EPOCH));
+    let escrow = escrow_state(&env);
+    assert_eq!((escrow.total, escrow.released), (2 * FEE, 2 * FEE));
+    assert_eq!(escrow.last_release_slot, 10 * slots_per_epoch());
+}
+
+#[test]
+fn single_withdrawal_is_capped() {
+    let mut env = funded_escrow(2);
+    let limit = RELEASE_PER_EPOCH / 2;
+    let ix = set_max_withdraw_ix(&PROGRAM_ID, &env.config, &env.admin, limit);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().max_withdraw_per_tx, limit);
+    env.warp_to_slot(slots_per_epoch());
+
+    // 超过上限被拒绝（含不指定金额时提取全部可释放金额），恰好等于上限时通过
+    assert_eq!(withdraw(&mut env, limit + 1), Err(MyError::WithdrawLimitExceeded.into()));
+    assert_eq!(withdraw(&mut env, 0), Err(MyError::WithdrawLimitExceeded.into()));
+    assert_eq!(escrow_state(&env).released, 0);
+    assert_eq!(withdraw(&mut env, limit), Ok(limit));
+    assert_eq!(escrow_state(&env).released, limit);
+}
+
+#[test]
+fn releasable_amount_counts_elapsed_epochs() {
+    let schedule = EpochSchedule::without_warmup();
+    let escrow = EscrowState {
+        total: 1_000,
+        released: 100,
+        last_release_slot: schedule.get_first_slot_in_epoch(2) + 5,
+        release_per_epoch: 200,
+    };
+    for (epoch, expected) in [(0, 0), (2, 0), (3, 200), (5, 600), (6, 800), (100, 900)] {
+        assert_eq!(releasable_amount(&escrow, &schedule, epoch), expected, "epoch {epoch}");
+    }
+}
@@ -0,0 +1,140 @@
+// 收费事件：sol_log_data 输出的 FeeCollected 及其序号、计价单位，以及 Anchor 格式的 event CPI
+mod common;
+
+use amm_proxy_contract::{
+    events::{
+        event_authority_address, FeeCollected, EVENT_IX_TAG_LE, FEE_COLLECTED_DISCRIMINATOR, FEE_COLLECTED_EVENT,
+    },
+    instructions::pump::PUMP_SELECTOR,
+    ix_builder::{pump_buy_ix, set_buy_fee_timing_ix, set_event_cpi_ix, with_event_cpi, with_token_fee_accounts},
+    state::FeeTiming,
+    token::TOKEN_PROGRAM_ID,
+};
+use borsh::BorshDeserialize;
+use common::{
+    mocks::{set_swap_behavior, SwapBehavior},
+    runtime::TxResult,
+    PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{
+    hash::hash,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+fn buy(env: &mut TestEnv, user: &Pubkey, curve: &PumpCurve) -> TxResult {
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, curve.buy_accounts());
+    env.process(&ix)
+}
+
+fn fee_collected(result: &TxResult) -> Vec<FeeCollected> {
+    result
+        .events(FEE_COLLECTED_EVENT)
+        .iter()
+        .map(|data| FeeCollected::try_from_slice(data).unwrap())
+        .collect()
+}
+
+#[test]
+fn sequence_increments_across_consecutive_swaps() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+
+    for seq in 1..=3 {
+        let result = buy(&mut env, &user, &curve).assert_ok();
+        let events = fee_collected(&result);
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.seq, seq);
+        assert_eq!((event.selector, event.payer), (*PUMP_SELECTOR, user));
+        assert_eq!((event.amount, event.fee, event.referral_fee), (SOL, SOL / 100, 0));
+        assert_eq!((event.fee_raw, event.decimals, event.fee_mint), (SOL / 100, 9, Pubkey::default()));
+        assert_eq!(env.config_state().seq, seq);
+    }
+}
+
+#[test]
+fn token_fee_is_reported_in_the_mint_units() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_buy_fee_timing_ix(&PROGRAM_ID, &env.config, &env.admin, FeeTiming::PostSwap as u8);
+    env.process(&ix).assert_ok();
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    set_swap_behavior(SwapBehavior { output: Some(1_000_000), ..Default::default() });
+
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let ix = with_token_fee_accounts(ix, &env.admin, &curve.mint, &TOKEN_PROGRAM_ID);
+    let events = fee_collected(&env.process(&ix).assert_ok());
+    assert_eq!(events.len(), 1);
+    let event = &events[0];
+    // 兑换得到 1 个 6 位精度的代币，按 1% 收取 10_000 个最小单位，不计入 lamports 口径的 fee
+    assert_eq!((event.amount, event.fee, event.referral_fee), (1_000_000, 0, 0));
+    assert_eq!((event.fee_raw, event.decimals, event.fee_mint), (10_000, 6, curve.mint));
+}
+
+#[test]
+fn failed_swap_does_not_consume_a_sequence_number() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    buy(&mut env, &user, &curve).assert_ok();
+
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, SOL / 2, curve.buy_accounts());
+    assert!(env.process(&ix).result.is_err());
+    assert_eq!(env.config_state().seq, 1);
+    assert_eq!(fee_collected(&buy(&mut env, &user, &curve).assert_ok())[0].seq, 2);
+}
+
+#[test]
+fn sequence_overflow_is_rejected() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    env.update_config(|config| config.seq = u64::MAX);
+
+    assert_eq!(buy(&mut env, &user, &curve).unwrap_err(), ProgramError::ArithmeticOverflow);
+    assert_eq!(env.lamports(&user), 10 * SOL);
+}
+
+#[test]
+fn event_cpi_carries_an_anchor_encoded_fee_collected() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let ix = set_event_cpi_ix(&PROGRAM_ID, &env.config, &env.admin, true);
+    env.process(&ix).assert_ok();
+
+    // 缺少事件 PDA 与本程序账户时拒绝，不会静默丢失事件
+    assert_eq!(buy(&mut env, &user, &curve).unwrap_err(), ProgramError::NotEnoughAccountKeys);
+
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let result = env.process(&with_event_cpi(&PROGRAM_ID, ix)).assert_ok();
+    let cpis = result.cpis_to(&PROGRAM_ID);
+    assert_eq!(cpis.len(), 1);
+    let authority = event_authority_address(&PROGRAM_ID).0;
+    assert_eq!((cpis[0].accounts[0].pubkey, cpis[0].accounts[0].is_signer), (authority, true));
+
+    // [EVENT_IX_TAG_LE][sha256("event:FeeCollected")[..8]][borsh 事件体]
+    let data = &cpis[0].data;
+    assert_eq!(&data[..8], EVENT_IX_TAG_LE);
+    assert_eq!(data[8..16], hash(b"event:FeeCollected").to_bytes()[..8]);
+    assert_eq!(&data[8..16], FEE_COLLECTED_DISCRIMINATOR);
+    let event = FeeCollected::try_from_slice(&data[16..]).unwrap();
+    assert_eq!((event.selector, event.payer, event.amount, event.fee), (*PUMP_SELECTOR, user, SOL, SOL / 100));
+    assert_eq!((event.fee_raw, event.decimals, event.fee_mint), (SOL / 100, 9, Pubkey::default()));
+}
+
+#[test]
+fn event_instruction_requires_the_event_authority_signature() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let mut data = EVENT_IX_TAG_LE.to_vec();
+    data.extend_from_slice(FEE_COLLECTED_DISCRIMINATOR);
+    let forged = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: vec![AccountMeta::new_readonly(event_authority_address(&PROGRAM_ID).0, false)],
+        data,
+    };
+    assert_eq!(env.process(&forged).unwrap_err(), ProgramError::MissingRequiredSignature);
+}
@@ -0,0 +1,55 @@
+// 截止槽检查：当前 slot 超过截止槽时拒绝；附带配置账户且配置了 max_data_age_slots 时，
+// 截止槽距当前 slot 超过该时长同样拒绝，与预言机报价的发布 slot 使用同一上限
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    ix_builder::{bounded_expired_slot_ix, expired_slot_ix, set_max_data_age_ix},
+};
+use common::{TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+
+const NOW: u64 = 100;
+const MAX_AGE: u64 = 10;
+
+fn setup(max_age: u64) -> TestEnv {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_max_data_age_ix(&PROGRAM_ID, &env.config, &env.admin, max_age);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().max_data_age_slots, max_age);
+    env.warp_to_slot(NOW);
+    env
+}
+
+#[test]
+fn past_deadline_is_rejected() {
+    let mut env = setup(MAX_AGE);
+    for ix in [expired_slot_ix(&PROGRAM_ID, NOW - 1), bounded_expired_slot_ix(&PROGRAM_ID, &env.config, NOW - 1)] {
+        assert_eq!(env.process(&ix).unwrap_err(), MyError::SlotExpired.into());
+    }
+    let ix = bounded_expired_slot_ix(&PROGRAM_ID, &env.config, NOW);
+    env.process(&ix).assert_ok();
+}
+
+#[test]
+fn deadline_too_far_ahead_is_rejected_with_the_config() {
+    let mut env = setup(MAX_AGE);
+    let ix = bounded_expired_slot_ix(&PROGRAM_ID, &env.config, NOW + MAX_AGE);
+    env.process(&ix).assert_ok();
+    let ix = bounded_expired_slot_ix(&PROGRAM_ID, &env.config, NOW + MAX_AGE + 1);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::DeadlineTooFar.into());
+
+    // 不附带配置账户时不限制截止槽的远近
+    env.process(&expired_slot_ix(&PROGRAM_ID, u64::MAX)).assert_ok();
+}
+
+#[test]
+fn zero_max_age_disables_the_bound() {
+    let mut env = setup(0);
+    let ix = bounded_expired_slot_ix(&PROGRAM_ID, &env.config, u64::MAX);
+    env.process(&ix).assert_ok();
+
+    let stranger = env.wallet(SOL);
+    let ix = set_max_data_age_ix(&PROGRAM_ID, &env.config, &stranger, MAX_AGE);
+    assert!(env.process(&ix).result.is_err());
+    assert_eq!(env.config_state().max_data_age_slots, 0);
+}
@@ -0,0 +1,129 @@
+// explain：按两段路由（Pump 买入 + Raydium 精确输出买入）解析执行计划，不转账、不调用目标程序，
+// 计划中的内层数据与账户与实际执行时转发的一致，手续费与执行时按同样的免收规则计算
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::{
+        fee::RoutePlan,
+        pump::PUMP_SELECTOR,
+        raydium::{RAYDIUM_BUY_BASE_OUT_ROUTE, RAYDIUM_BUY_BASE_OUT_SELECTOR},
+    },
+    ix_builder::{
+        add_fee_exempt_mint_ix, explain_ix, pause_route_ix, pump_buy_ix, raydium_buy_base_out_ix, set_buy_fee_timing_ix,
+        set_promo_window_ix, with_fee_exempt_mint,
+    },
+    state::FeeTiming,
+};
+use borsh::BorshDeserialize;
+use common::{
+    mocks::{PUMP_PROGRAM, RAYDIUM_AMM_V4_PROGRAM},
+    route_accounts, runtime::TxResult, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+struct Batch {
+    env: TestEnv,
+    user: Pubkey,
+    legs: [Instruction; 2],
+}
+
+fn setup() -> Batch {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let pump = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let mut forwarded = route_accounts(&RAYDIUM_BUY_BASE_OUT_ROUTE, 17, &user);
+    forwarded.push(AccountMeta::new_readonly(RAYDIUM_AMM_V4_PROGRAM, false));
+    let raydium = raydium_buy_base_out_ix(&PROGRAM_ID, &env.fee_accounts(&user), 2 * SOL, 123_456, forwarded);
+    Batch { env, user, legs: [pump, raydium] }
+}
+
+fn plan(result: &TxResult) -> RoutePlan {
+    RoutePlan::try_from_slice(&result.return_data.as_ref().unwrap().1).unwrap()
+}
+
+fn plan_fee(env: &mut TestEnv, ix: &Instruction) -> u64 {
+    plan(&env.process(&explain_ix(ix)).assert_ok()).fee
+}
+
+#[test]
+fn explain_describes_each_leg_without_executing() {
+    let mut batch = setup();
+    let (user_before, treasury_before) = (batch.env.lamports(&batch.user), batch.env.lamports(&batch.env.admin));
+    let config_before = batch.env.data(&batch.env.config).to_vec();
+
+    // 整批 explain 只有最后一段的计划留在 return data 中，且不发起任何 CPI
+    let explained = batch.legs.each_ref().map(explain_ix);
+    let result = batch.env.process_transaction(&explained).assert_ok();
+    assert!(result.invocations.iter().all(|invocation| invocation.stack_height == 1));
+    assert_eq!(plan(&result).route_id, *RAYDIUM_BUY_BASE_OUT_SELECTOR);
+    assert_eq!(batch.env.lamports(&batch.user), user_before);
+    assert_eq!(batch.env.lamports(&batch.env.admin), treasury_before);
+    assert_eq!(batch.env.data(&batch.env.config), &config_before[..]);
+
+    let expected = [(PUMP_SELECTOR, PUMP_PROGRAM, SOL), (RAYDIUM_BUY_BASE_OUT_SELECTOR, RAYDIUM_AMM_V4_PROGRAM, 2 * SOL)];
+    for (leg, (selector, program, amount)) in batch.legs.iter().zip(expected) {
+        let result = batch.env.process(&explain_ix(leg)).assert_ok();
+        let plan = plan(&result);
+        let fee = amount / 100;
+        assert_eq!(
+            (plan.route_id, plan.program, plan.is_buy, plan.post_swap),
+            (*selector, program, true, false)
+        );
+        assert_eq!(
+            (plan.amount, plan.fee_rate_pips, plan.effective_rate_pips, plan.fee, plan.inner_amount),
+            (amount, DEFAULT_FEE_RATE_PIPS, DEFAULT_FEE_RATE_PIPS, fee, amount - fee)
+        );
+
+        // 实际执行时转发的数据与账户与计划一致
+        let result = batch.env.process(leg).assert_ok();
+        let cpis = result.cpis_to(&program);
+        assert_eq!(cpis[0].data, plan.inner_data);
+        assert_eq!(cpis[0].accounts.iter().map(|meta| meta.pubkey).collect::<Vec<_>>(), plan.accounts);
+    }
+}
+
+#[test]
+fn explain_reflects_post_swap_and_paused_routes() {
+    let mut batch = setup();
+
+    // 买入改为兑换后收费时手续费取决于成交量，计划中为 0，内层为完整数量
+    let ix = set_buy_fee_timing_ix(&PROGRAM_ID, &batch.env.config, &batch.env.admin, FeeTiming::PostSwap as u8);
+    batch.env.process(&ix).assert_ok();
+    let plan = plan(&batch.env.process(&explain_ix(&batch.legs[0])).assert_ok());
+    assert_eq!((plan.post_swap, plan.fee, plan.inner_amount), (true, 0, SOL));
+
+    // 暂停的路由 explain 同样被拒绝
+    let ix = pause_route_ix(&PROGRAM_ID, &batch.env.config, &batch.env.admin, RAYDIUM_BUY_BASE_OUT_SELECTOR, true, 1);
+    batch.env.process(&ix).assert_ok();
+    let explained = batch.legs.each_ref().map(explain_ix);
+    assert_eq!(batch.env.process_transaction(&explained).unwrap_err(), MyError::RoutePaused.into());
+}
+
+#[test]
+fn explain_applies_the_same_waivers_as_execution() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let ix = add_fee_exempt_mint_ix(&PROGRAM_ID, &env.config, &env.admin, &curve.mint);
+    env.process(&ix).assert_ok();
+    let buy = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+
+    // 免手续费代币：计划与执行都不收费，内层为完整数量
+    let exempt = with_fee_exempt_mint(&PROGRAM_ID, buy.clone(), &curve.mint);
+    let plan = plan(&env.process(&explain_ix(&exempt)).assert_ok());
+    assert_eq!((plan.fee, plan.inner_amount), (0, SOL));
+    let treasury_before = env.lamports(&env.admin);
+    env.process(&exempt).assert_ok();
+    assert_eq!(env.lamports(&env.admin), treasury_before);
+
+    // 未附带登记 PDA 时照常收费；推广区间内同样免收
+    assert_eq!(plan_fee(&mut env, &buy), SOL / 100);
+    let ix = set_promo_window_ix(&PROGRAM_ID, &env.config, &env.admin, 0, u64::MAX);
+    env.process(&ix).assert_ok();
+    assert_eq!(plan_fee(&mut env, &buy), 0);
+}
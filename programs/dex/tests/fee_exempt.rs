@@ -0,0 +1,57 @@
+// 免手续费代币：登记为合作代币（[b"fee_exempt", mint] PDA）的交易代币在附带该 PDA 时不收手续费，其他代币照常收费
+mod common;
+
+use amm_proxy_contract::{
+    instructions::{fee::SwapResult, fee_exempt::fee_exempt_mint_address},
+    ix_builder::{add_fee_exempt_mint_ix, pump_buy_ix, remove_fee_exempt_mint_ix, with_fee_exempt_mint},
+    state::FeeExemptMintState,
+};
+use borsh::BorshDeserialize;
+use common::{PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::pubkey::Pubkey;
+
+// 买入 curve 的代币并附带 exempt_mint 的登记 PDA，返回国库收到的手续费
+fn buy_fee(env: &mut TestEnv, user: &Pubkey, curve: &PumpCurve, exempt_mint: Option<&Pubkey>) -> u64 {
+    let treasury_before = env.lamports(&env.admin);
+    let mut ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, curve.buy_accounts());
+    if let Some(mint) = exempt_mint {
+        ix = with_fee_exempt_mint(&PROGRAM_ID, ix, mint);
+    }
+    let result = env.process(&ix).assert_ok();
+    let fee = env.lamports(&env.admin) - treasury_before;
+    assert_eq!(SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap().fee, fee);
+    fee
+}
+
+#[test]
+fn exempt_mint_trades_free_and_others_are_charged() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let (partner, regular) = (env.pump_curve(&user, 0), env.pump_curve(&user, 0));
+
+    let ix = add_fee_exempt_mint_ix(&PROGRAM_ID, &env.config, &env.admin, &partner.mint);
+    env.process(&ix).assert_ok();
+    let pda = fee_exempt_mint_address(&PROGRAM_ID, &partner.mint).0;
+    assert_eq!(FeeExemptMintState::try_from_slice(env.data(&pda)).unwrap().mint, partner.mint);
+
+    assert_eq!(buy_fee(&mut env, &user, &partner, Some(&partner.mint)), 0);
+    // 未附带登记 PDA，或附带的是其他代币的登记 PDA 时照常收费
+    assert_eq!(buy_fee(&mut env, &user, &partner, None), SOL / 100);
+    assert_eq!(buy_fee(&mut env, &user, &regular, Some(&partner.mint)), SOL / 100);
+
+    // 移除后关闭登记 PDA，恢复收费
+    let ix = remove_fee_exempt_mint_ix(&PROGRAM_ID, &env.config, &env.admin, &partner.mint);
+    env.process(&ix).assert_ok();
+    assert!(env.account(&pda).is_none());
+    assert_eq!(buy_fee(&mut env, &user, &partner, Some(&partner.mint)), SOL / 100);
+}
+
+#[test]
+fn only_the_admin_registers_exempt_mints() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let stranger = env.wallet(SOL);
+    let mint = Pubkey::new_unique();
+    let ix = add_fee_exempt_mint_ix(&PROGRAM_ID, &env.config, &stranger, &mint);
+    assert!(env.process(&ix).result.is_err());
+    assert!(env.account(&fee_exempt_mint_address(&PROGRAM_ID, &mint).0).is_none());
+}
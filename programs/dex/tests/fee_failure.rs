@@ -0,0 +1,123 @@
+// 手续费转账无法完成时的处理：Abort 中止整笔交易，Skip 跳过收费、按完整数量兑换并输出 FeeSkipped
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    events::{FeeSkipReason, FeeSkipped, FEE_SKIPPED_EVENT},
+    instructions::{fee::SwapResult, pump::PUMP_SELECTOR},
+    ix_builder::{pump_buy_ix, set_buyback_ix, set_fee_wallet_ix, set_on_fee_failure_ix},
+    state::FeeFailurePolicy,
+};
+use borsh::BorshDeserialize;
+use common::{mocks::PUMP_PROGRAM, runtime::TxResult, PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+
+fn setup(policy: FeeFailurePolicy) -> (TestEnv, Pubkey, PumpCurve) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_on_fee_failure_ix(&PROGRAM_ID, &env.config, &env.admin, policy as u8);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().on_fee_failure, policy);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    (env, user, curve)
+}
+
+fn buy(env: &TestEnv, user: &Pubkey, curve: &PumpCurve, amount: u64) -> Instruction {
+    pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), amount, 2 * amount, curve.buy_accounts())
+}
+
+fn skipped_events(result: &TxResult) -> Vec<FeeSkipped> {
+    result
+        .events(FEE_SKIPPED_EVENT)
+        .iter()
+        .map(|data| FeeSkipped::try_from_slice(data).unwrap())
+        .collect()
+}
+
+// 配置了回购分成但交易未附带回购钱包账户，分成转账无法完成
+fn without_buyback_account(env: &mut TestEnv) {
+    let buyback = env.wallet(SOL);
+    let ix = set_buyback_ix(&PROGRAM_ID, &env.config, &env.admin, &buyback, 3_000);
+    env.process(&ix).assert_ok();
+}
+
+// 协议钱包已关闭（0 lamports），不足免租金额的手续费无法转入
+fn closed_fee_wallet(env: &mut TestEnv) -> Pubkey {
+    let fee_wallet = Pubkey::new_unique();
+    let ix = set_fee_wallet_ix(&PROGRAM_ID, &env.config, &env.admin, &fee_wallet);
+    env.process(&ix).assert_ok();
+    fee_wallet
+}
+
+#[test]
+fn abort_fails_the_trade() {
+    let (mut env, user, curve) = setup(FeeFailurePolicy::Abort);
+    without_buyback_account(&mut env);
+    let treasury_before = env.lamports(&env.admin);
+
+    let ix = buy(&env, &user, &curve, SOL);
+    let result = env.process(&ix);
+    assert!(result.cpis_to(&PUMP_PROGRAM).is_empty());
+    assert_eq!(result.unwrap_err(), MyError::BuybackAccountMissing.into());
+    assert_eq!((env.lamports(&user), env.lamports(&env.admin)), (10 * SOL, treasury_before));
+
+    // 协议钱包无法接收不足免租金额的手续费，转账失败时整笔回滚
+    let (mut env, user, curve) = setup(FeeFailurePolicy::Abort);
+    let fee_wallet = closed_fee_wallet(&mut env);
+    let ix = buy(&env, &user, &curve, SOL / 100);
+    assert!(env.process(&ix).result.is_err());
+    assert_eq!((env.lamports(&user), env.lamports(&fee_wallet)), (10 * SOL, 0));
+}
+
+#[test]
+fn skip_proceeds_with_the_full_amount() {
+    let (mut env, user, curve) = setup(FeeFailurePolicy::Skip);
+    without_buyback_account(&mut env);
+    let treasury_before = env.lamports(&env.admin);
+
+    let ix = buy(&env, &user, &curve, SOL);
+    let result = env.process(&ix).assert_ok();
+    // 内层按完整数量兑换，不收取手续费
+    assert_eq!(result.cpis_to(&PUMP_PROGRAM)[0].data[8..16], SOL.to_le_bytes());
+    let swap = SwapResult::try_from_slice(&result.return_data.as_ref().unwrap().1).unwrap();
+    assert_eq!((swap.fee, swap.remaining), (0, SOL));
+    let events = skipped_events(&result);
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        (events[0].selector, events[0].payer, events[0].fee, events[0].reason),
+        (*PUMP_SELECTOR, user, SOL / 100, FeeSkipReason::BuybackAccountMissing)
+    );
+    assert_eq!(env.lamports(&env.admin), treasury_before);
+    assert_eq!(env.token_balance(&curve.associated_user), SOL);
+}
+
+#[test]
+fn skip_covers_a_receiver_that_cannot_take_the_fee() {
+    let (mut env, user, curve) = setup(FeeFailurePolicy::Skip);
+    let fee_wallet = closed_fee_wallet(&mut env);
+
+    let ix = buy(&env, &user, &curve, SOL / 100);
+    let result = env.process(&ix).assert_ok();
+    let events = skipped_events(&result);
+    assert_eq!(events.len(), 1);
+    assert_eq!((events[0].fee, events[0].reason), (SOL / 10_000, FeeSkipReason::ReceiverCannotReceive));
+    assert_eq!(env.lamports(&fee_wallet), 0);
+    assert_eq!(env.lamports(&user), 10 * SOL - SOL / 100);
+
+    // 可以转账时照常收费，不输出 FeeSkipped
+    let ix = buy(&env, &user, &curve, SOL);
+    let result = env.process(&ix).assert_ok();
+    assert!(skipped_events(&result).is_empty());
+    assert_eq!(env.lamports(&fee_wallet), SOL / 100);
+}
+
+#[test]
+fn policy_is_set_by_the_admin_only() {
+    let (mut env, _, _) = setup(FeeFailurePolicy::Abort);
+    let stranger = env.wallet(SOL);
+    let ix = set_on_fee_failure_ix(&PROGRAM_ID, &env.config, &stranger, FeeFailurePolicy::Skip as u8);
+    assert!(env.process(&ix).result.is_err());
+    let ix = set_on_fee_failure_ix(&PROGRAM_ID, &env.config, &env.admin, 2);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidInstructionData);
+    assert_eq!(env.config_state().on_fee_failure, FeeFailurePolicy::Abort);
+}
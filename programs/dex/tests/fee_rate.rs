@@ -0,0 +1,145 @@
+// 以 pip（百分之一基点）表示的费率：亚基点精度、u128 中间值、初版百分比布局的换算，以及超过 100% 的错误费率
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::fee::{FeePreview, SwapResult},
+    ix_builder::{fee_preview_ix, pump_buy_ix},
+    state::{TradeFeeState, MAX_FEE_RATE_PIPS},
+};
+use borsh::BorshDeserialize;
+use common::{mocks::PUMP_PROGRAM, u64_args, TestEnv, PROGRAM_ID, SOL};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+fn preview(env: &mut TestEnv, amount: u64, rate_bps: u16) -> FeePreview {
+    let result = env.process(&fee_preview_ix(&PROGRAM_ID, amount, rate_bps)).assert_ok();
+    FeePreview::try_from_slice(&result.return_data.unwrap().1).unwrap()
+}
+
+#[test]
+fn half_basis_point_rate_is_charged_exactly() {
+    // 3.5 bps = 350 pips
+    let mut env = TestEnv::with_config(350);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let treasury_before = env.lamports(&env.admin);
+
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let result = env.process(&ix).assert_ok();
+
+    let fee = 350_000;
+    assert_eq!(env.lamports(&env.admin) - treasury_before, fee);
+    assert_eq!(result.cpis_to(&PUMP_PROGRAM)[0].data[8..], u64_args(&[SOL - fee, 2 * SOL])[..]);
+    let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!((swap.fee, swap.remaining), (fee, SOL - fee));
+}
+
+#[test]
+fn fee_math_does_not_overflow_at_max_amount() {
+    let mut env = TestEnv::new();
+
+    // u64::MAX * 1_000_000 pips 超出 u64，按 u128 计算后结果仍在 u64 范围内
+    let full = preview(&mut env, u64::MAX, 10_000);
+    assert_eq!((full.fee, full.remaining), (u64::MAX, 0));
+
+    let one_bps = preview(&mut env, u64::MAX, 1);
+    assert_eq!(one_bps.fee, u64::MAX / 10_000);
+    assert_eq!(one_bps.remaining, u64::MAX - u64::MAX / 10_000);
+}
+
+#[test]
+fn legacy_percent_rate_is_converted_to_pips() {
+    let fee_wallet = Pubkey::new_unique();
+    let legacy = |percent: u8| {
+        let mut data = vec![percent];
+        data.extend_from_slice(fee_wallet.as_ref());
+        TradeFeeState::unpack(&data).unwrap()
+    };
+
+    let config = legacy(2);
+    assert_eq!((config.fee_rate_pips, config.fee_wallet), (20_000, fee_wallet));
+    // 超过上限的旧费率按上限解析
+    assert_eq!(legacy(50).fee_rate_pips, MAX_FEE_RATE_PIPS);
+}
+
+#[test]
+fn rate_above_100_percent_is_rejected_as_fee_exceeds_amount() {
+    let mut env = TestEnv::with_config(10_000);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    // 绕过设置指令的上限写入 150% 的费率，模拟上限生效前配置的错误费率
+    env.update_config(|config| config.fee_rate_pips = 1_500_000);
+    let treasury_before = env.lamports(&env.admin);
+
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let result = env.process(&ix);
+    assert!(result.cpis_to(&PUMP_PROGRAM).is_empty());
+    assert_eq!(result.unwrap_err(), MyError::FeeExceedsAmount.into());
+    assert_eq!((env.lamports(&user), env.lamports(&env.admin)), (10 * SOL, treasury_before));
+}
+
+#[test]
+fn fee_preview_needs_no_accounts() {
+    // 未创建配置账户，指令不带任何账户
+    let mut env = TestEnv::new();
+    for (amount, rate_bps, fee) in [
+        (SOL, 100, SOL / 100),
+        (SOL, 0, 0),
+        (12_345, 30, 37),
+        (9_999, 1, 0),
+        (1_000_000, 2_500, 250_000),
+    ] {
+        let preview = preview(&mut env, amount, rate_bps);
+        assert_eq!((preview.fee, preview.remaining), (fee, amount - fee), "{amount} @ {rate_bps} bps");
+    }
+}
+
+#[test]
+fn fee_preview_rejects_invalid_input() {
+    let mut env = TestEnv::new();
+    let ix = fee_preview_ix(&PROGRAM_ID, SOL, 10_001);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidInstructionData);
+
+    // 缺少费率参数
+    let mut ix = fee_preview_ix(&PROGRAM_ID, SOL, 100);
+    ix.data.truncate(16);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidInstructionData);
+}
+
+#[test]
+fn fractional_fees_carry_over_between_trades() {
+    // 1% 费率下 150 lamports 的手续费为 1.5 lamports，零头累计到下一笔
+    let mut env = TestEnv::with_config(10_000);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let treasury_before = env.lamports(&env.admin);
+
+    let mut fees = Vec::new();
+    for _ in 0..10 {
+        let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), 150, 300, curve.buy_accounts());
+        let result = env.process(&ix).assert_ok();
+        fees.push(SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap().fee);
+    }
+
+    assert_eq!(fees, [1, 2, 1, 2, 1, 2, 1, 2, 1, 2]);
+    assert_eq!(env.lamports(&env.admin) - treasury_before, 15);
+    assert_eq!(env.config_state().fee_remainder_accumulator, 0);
+    assert_eq!(env.config_state().total_fees_collected, 15);
+}
+
+#[test]
+fn carried_remainder_never_reaches_a_whole_lamport() {
+    let mut env = TestEnv::with_config(350);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+
+    // 3.5 bps 下 1_001 lamports 的手续费为 0.350350 lamports
+    for _ in 0..5 {
+        let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), 1_001, 2_002, curve.buy_accounts());
+        env.process(&ix).assert_ok();
+        assert!(env.config_state().fee_remainder_accumulator < 1_000_000);
+    }
+    // 5 笔累计 1.75175 lamports，已收取 1 lamport
+    assert_eq!(env.config_state().total_fees_collected, 1);
+    assert_eq!(env.config_state().fee_remainder_accumulator, 751_750);
+}
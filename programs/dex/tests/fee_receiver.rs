@@ -0,0 +1,78 @@
+// 手续费接收方校验：配置账户或其他非系统程序所有的账户不能作为 SOL 手续费接收方，主钱包不可用时转入备用钱包
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    events::{FeeWalletFailover, FEE_WALLET_FAILOVER_EVENT},
+    ix_builder::{pump_buy_ix, set_fee_wallet_backup_ix, with_fee_wallet_backup, FeeAccounts},
+};
+use borsh::BorshDeserialize;
+use common::{mocks::PUMP_PROGRAM, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey, system_program};
+
+#[test]
+fn config_account_cannot_be_the_fee_receiver() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let config_before = env.lamports(&env.config);
+
+    let fee_accounts = FeeAccounts { fee_receiver: env.config, ..env.fee_accounts(&user) };
+    let ix = pump_buy_ix(&PROGRAM_ID, &fee_accounts, SOL, 2 * SOL, curve.buy_accounts());
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidAccountData);
+    assert_eq!(env.lamports(&env.config), config_before);
+    assert_eq!(env.lamports(&user), 10 * SOL);
+}
+
+#[test]
+fn fee_wallet_owned_by_another_program_is_rejected() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+
+    // 配置的 fee_wallet 已被其他程序接管，且未配置备用钱包
+    let fee_wallet = env.admin;
+    env.account_mut(&fee_wallet).owner = Pubkey::new_unique();
+    let fee_wallet_before = env.lamports(&fee_wallet);
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let result = env.process(&ix);
+    // 在任何转账与兑换之前拒绝
+    assert!(result.cpis_to(&system_program::id()).is_empty());
+    assert!(result.cpis_to(&PUMP_PROGRAM).is_empty());
+    assert_eq!(result.unwrap_err(), MyError::FeeReceiverNotSystemOwned.into());
+    assert_eq!((env.lamports(&user), env.lamports(&fee_wallet)), (10 * SOL, fee_wallet_before));
+}
+
+#[test]
+fn unusable_primary_fails_over_to_the_backup() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let backup = env.wallet(SOL);
+    let ix = set_fee_wallet_backup_ix(&PROGRAM_ID, &env.config, &env.admin, &backup);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().fee_wallet_backup, backup);
+
+    // 主钱包可用时不使用备用钱包
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let ix = with_fee_wallet_backup(ix, &backup);
+    let result = env.process(&ix).assert_ok();
+    assert!(result.events(FEE_WALLET_FAILOVER_EVENT).is_empty());
+    assert_eq!(env.lamports(&backup), SOL);
+
+    // 主钱包被其他程序接管后，手续费转入备用钱包并输出 FeeWalletFailover
+    let primary = env.admin;
+    env.account_mut(&primary).owner = Pubkey::new_unique();
+    let primary_before = env.lamports(&primary);
+    let result = env.process(&ix).assert_ok();
+    assert_eq!(env.lamports(&backup) - SOL, SOL / 100);
+    assert_eq!(env.lamports(&primary), primary_before);
+    let events = result.events(FEE_WALLET_FAILOVER_EVENT);
+    assert_eq!(events.len(), 1);
+    let event = FeeWalletFailover::try_from_slice(&events[0]).unwrap();
+    assert_eq!((event.primary, event.backup), (primary, backup));
+
+    // 未附带备用钱包账户时仍然拒绝
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::FeeReceiverNotSystemOwned.into());
+}
@@ -0,0 +1,190 @@
+// 带手续费路由在 CPI 之前的账户与数据校验
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::{
+        fee::{FeeRoute, FEE_ROUTES},
+        pump::{PUMP_AMM_SELECTOR, PUMP_SELECTOR},
+        route_program::route_program_address,
+    },
+    ix_builder::{add_route_program_ix, pump_buy_ix, raydium_buy_ix, set_max_inner_data_len_ix, with_route_program},
+    processor::process_instruction,
+    state::{RouteProgramState, DEFAULT_MAX_INNER_DATA_LEN},
+};
+use common::{
+    mocks::{DAMM_V2_PROGRAM, PUMP_PROGRAM},
+    route_accounts, selector_data, u64_args, Account, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{instruction::AccountMeta, program_error::ProgramError, pubkey::Pubkey};
+
+#[test]
+fn bogus_system_program_is_rejected_before_any_transfer() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let bogus = env.wallet(SOL);
+
+    let mut ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    ix.accounts[1].pubkey = bogus;
+    let result = env.process(&ix);
+    assert_eq!(result.unwrap_err(), ProgramError::IncorrectProgramId);
+    assert!(result.cpis_to(&PUMP_PROGRAM).is_empty());
+    assert_eq!(env.lamports(&user), 10 * SOL);
+}
+
+#[test]
+fn router_program_as_raydium_target_is_rejected() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(SOL);
+    let mut accounts: Vec<AccountMeta> = (0..9).map(|_| AccountMeta::new(Pubkey::new_unique(), false)).collect();
+    accounts[0] = AccountMeta::new_readonly(PROGRAM_ID, false);
+    accounts[8] = AccountMeta::new_readonly(user, true);
+
+    let result = env.process(&raydium_buy_ix(&PROGRAM_ID, SOL, 1, accounts));
+    assert_eq!(result.unwrap_err(), MyError::SelfInvocation.into());
+    assert!(result.cpis_to(&PROGRAM_ID).is_empty());
+}
+
+#[test]
+fn fee_route_rejects_forwarding_to_the_router_itself() {
+    // 把本程序部署在 Pump 的地址上，使路由的目标程序与本程序相同，配置账户随之归该地址所有
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    env.add_program(PUMP_PROGRAM, process_instruction);
+    let config = env.config;
+    env.account_mut(&config).owner = PUMP_PROGRAM;
+
+    let ix = pump_buy_ix(&PUMP_PROGRAM, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let result = env.process(&ix);
+    assert_eq!(result.unwrap_err(), MyError::SelfInvocation.into());
+    assert!(result.cpis_to(&PUMP_PROGRAM).is_empty());
+}
+
+#[test]
+fn router_program_cannot_be_registered_as_a_route_target() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = add_route_program_ix(&PROGRAM_ID, &env.config, &env.admin, PUMP_SELECTOR, &PROGRAM_ID, 1);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::SelfInvocation.into());
+}
+
+#[test]
+fn registered_target_resolving_to_the_router_is_rejected() {
+    // 登记指令会拒绝本程序，这里直接写入指向本程序的登记记录，验证按解析出的目标程序拒绝
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let record = RouteProgramState { selector: *PUMP_SELECTOR, program: PROGRAM_ID, version: 1 };
+    let registered = route_program_address(&PROGRAM_ID, PUMP_SELECTOR, &PROGRAM_ID).0;
+    env.set_account(registered, Account::rent_exempt(borsh::to_vec(&record).unwrap(), PROGRAM_ID));
+
+    let mut forwarded = curve.buy_accounts();
+    *forwarded.last_mut().unwrap() = AccountMeta::new_readonly(PROGRAM_ID, false);
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, forwarded);
+    let ix = with_route_program(&PROGRAM_ID, ix, PUMP_SELECTOR, &PROGRAM_ID);
+    let result = env.process(&ix);
+    assert_eq!(result.unwrap_err(), MyError::SelfInvocation.into());
+    assert!(result.cpis_to(&PROGRAM_ID).is_empty());
+    assert_eq!(env.lamports(&user), 10 * SOL);
+}
+
+#[test]
+fn truncated_route_data_fails_cleanly() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+
+    // 只有选择器、只有计费金额、以及连选择器都不完整的数据
+    for len in [8, 16, 5, 0] {
+        let mut truncated = ix.clone();
+        truncated.data.truncate(len);
+        let result = env.process(&truncated);
+        assert_eq!(result.unwrap_err(), ProgramError::InvalidInstructionData, "数据长度 {}", len);
+        assert!(result.cpis_to(&PUMP_PROGRAM).is_empty());
+    }
+    assert_eq!(env.lamports(&user), 10 * SOL);
+}
+
+#[test]
+fn every_route_rejects_one_account_too_few() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+
+    // 借用 pump_buy_ix 生成前 4 个账户，选择器换成被测路由
+    let route_ix = |env: &mut TestEnv, route: &FeeRoute, len: usize| {
+        let forwarded = route_accounts(route, len, &user);
+        // DAMM v2 在计数校验之前先确认池子归属
+        if route.program == DAMM_V2_PROGRAM {
+            env.set_account(forwarded[1].pubkey, Account::rent_exempt(vec![0; 64], DAMM_V2_PROGRAM));
+        }
+        let mut ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 0, forwarded);
+        ix.data = selector_data(route.selector, &[&u64_args(&[SOL, 0, 0, 0, 0, 0])]);
+        ix
+    };
+
+    for route in FEE_ROUTES {
+        let ix = route_ix(&mut env, route, route.min_accounts - 1);
+
+        // PumpAMM 买入缺少的正是末尾的交易量累计账户，由路由检查给出更具体的错误
+        let expected = if route.selector == PUMP_AMM_SELECTOR {
+            MyError::PumpVolumeAccumulatorMissing.into()
+        } else {
+            ProgramError::NotEnoughAccountKeys
+        };
+        let result = env.process(&ix);
+        assert_eq!(result.unwrap_err(), expected, "路由 {}", route.name);
+        assert!(result.cpis_to(&route.program).is_empty());
+
+        // 账户数量达到下限后不再因数量被拒绝
+        let ix = route_ix(&mut env, route, route.min_accounts);
+        if expected == ProgramError::NotEnoughAccountKeys {
+            assert_ne!(env.process(&ix).unwrap_err(), expected, "路由 {}", route.name);
+        }
+    }
+}
+
+#[test]
+fn non_executable_program_slot_is_rejected() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+
+    // 程序位置传入普通钱包
+    let mut forwarded = curve.buy_accounts();
+    forwarded[11] = AccountMeta::new_readonly(env.wallet(SOL), false);
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, forwarded);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::TargetProgramNotExecutable.into());
+
+    // 地址正确但账户不可执行
+    env.account_mut(&PUMP_PROGRAM).executable = false;
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::TargetProgramNotExecutable.into());
+    assert_eq!(env.lamports(&user), 10 * SOL);
+}
+
+#[test]
+fn oversized_inner_data_is_rejected_before_the_cpi() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let fee_accounts = env.fee_accounts(&user);
+    let buy = |padding: usize| {
+        let mut ix = pump_buy_ix(&PROGRAM_ID, &fee_accounts, SOL / 10, SOL, curve.buy_accounts());
+        ix.data.resize(ix.data.len() + padding, 0);
+        ix
+    };
+
+    // 默认上限：内层数据为 [buy 鉴别器][amount][max_sol_cost] 加填充
+    let limit = DEFAULT_MAX_INNER_DATA_LEN as usize;
+    env.process(&buy(limit - 24)).assert_ok();
+    let result = env.process(&buy(limit - 23));
+    assert_eq!(result.unwrap_err(), MyError::InnerDataTooLarge.into());
+    assert!(result.cpis_to(&PUMP_PROGRAM).is_empty());
+
+    let ix = set_max_inner_data_len_ix(&PROGRAM_ID, &env.config, &env.admin, 24);
+    env.process(&ix).assert_ok();
+    env.process(&buy(0)).assert_ok();
+    assert_eq!(env.process(&buy(1)).unwrap_err(), MyError::InnerDataTooLarge.into());
+}
@@ -0,0 +1,56 @@
+// 收费方向：fee_sides 为 Both 时买卖都收费，BuyOnly / SellOnly 时另一侧的交易照常执行但不收费
+mod common;
+
+use amm_proxy_contract::{
+    instructions::fee::SwapResult,
+    ix_builder::{pump_buy_ix, pump_sell_ix, set_fee_sides_ix},
+    state::FeeSides,
+};
+use borsh::BorshDeserialize;
+use common::{
+    mocks::{set_swap_behavior, SwapBehavior},
+    runtime::TxResult,
+    TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+
+const TOKENS: u64 = 5_000_000;
+const SOLD_FOR: u64 = SOL / 2;
+
+fn charged_fee(result: TxResult) -> u64 {
+    SwapResult::try_from_slice(&result.assert_ok().return_data.unwrap().1).unwrap().fee
+}
+
+// 按设置完成一笔 1 SOL 的买入和一笔卖出，返回两笔的手续费并核对协议钱包的收入
+fn round_trip(fee_sides: FeeSides) -> (u64, u64) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_fee_sides_ix(&PROGRAM_ID, &env.config, &env.admin, fee_sides as u8);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().fee_sides, fee_sides);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, TOKENS);
+    let treasury_before = env.lamports(&env.admin);
+
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let buy_fee = charged_fee(env.process(&ix));
+    set_swap_behavior(SwapBehavior { output: Some(SOLD_FOR), ..Default::default() });
+    let ix = pump_sell_ix(&PROGRAM_ID, &env.fee_accounts(&user), TOKENS, 1, curve.sell_accounts());
+    let sell_fee = charged_fee(env.process(&ix));
+
+    assert_eq!(env.lamports(&env.admin) - treasury_before, buy_fee + sell_fee);
+    (buy_fee, sell_fee)
+}
+
+#[test]
+fn both_sides_are_charged_by_default() {
+    assert_eq!(round_trip(FeeSides::Both), (SOL / 100, SOLD_FOR / 100));
+}
+
+#[test]
+fn buy_only_skips_the_sell_fee() {
+    assert_eq!(round_trip(FeeSides::BuyOnly), (SOL / 100, 0));
+}
+
+#[test]
+fn sell_only_skips_the_buy_fee() {
+    assert_eq!(round_trip(FeeSides::SellOnly), (0, SOLD_FOR / 100));
+}
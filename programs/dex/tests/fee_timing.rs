@@ -0,0 +1,81 @@
+// 卖出收费时机：兑换前从计费金额中扣除并压低内层输入，或兑换后按实际收到的 SOL 收取
+mod common;
+
+use amm_proxy_contract::{
+    instructions::{
+        fee::SwapResult,
+        meteora::{DAMM_V2_SELL_ROUTE, DAMM_V2_SELL_SELECTOR},
+    },
+    ix_builder::{damm_v2_sell_ix, set_fee_timing_ix},
+    state::FeeTiming,
+    token::TOKEN_PROGRAM_ID,
+};
+use borsh::BorshDeserialize;
+use common::{
+    mocks::{set_swap_behavior, SwapBehavior, DAMM_V2_PROGRAM},
+    route_accounts, runtime::TxResult, u64_args, Account, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{instruction::AccountMeta, pubkey::Pubkey};
+
+const AMOUNT_IN: u64 = 5_000_000;
+const RECEIVED: u64 = SOL / 2;
+
+// 卖出的代币来自用户的代币账户，所得 SOL 直接转入用户钱包（output_token_account 即用户），池子持有待转出的 SOL
+fn sell(env: &mut TestEnv, user: &Pubkey) -> TxResult {
+    let mint = env.add_mint(6, &TOKEN_PROGRAM_ID);
+    let input = env.add_ata(user, &mint, AMOUNT_IN, &TOKEN_PROGRAM_ID);
+    let mut forwarded = route_accounts(&DAMM_V2_SELL_ROUTE, 13, user);
+    forwarded[2] = AccountMeta::new(input, false);
+    forwarded[3] = AccountMeta::new(*user, false);
+    forwarded.push(AccountMeta::new_readonly(DAMM_V2_PROGRAM, false));
+    env.set_account(forwarded[1].pubkey, Account::rent_exempt(vec![0; 64], DAMM_V2_PROGRAM));
+    env.fund(&forwarded[1].pubkey, SOL);
+    set_swap_behavior(SwapBehavior { output: Some(RECEIVED), ..Default::default() });
+
+    let ix = damm_v2_sell_ix(&PROGRAM_ID, &env.fee_accounts(user), AMOUNT_IN, 1, forwarded);
+    env.process(&ix)
+}
+
+fn set_timing(env: &mut TestEnv, timing: FeeTiming) {
+    let ix = set_fee_timing_ix(&PROGRAM_ID, &env.config, &env.admin, timing as u8);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().fee_timing, timing);
+}
+
+#[test]
+fn pre_swap_sell_deducts_the_fee_from_the_input() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(SOL);
+    let treasury_before = env.lamports(&env.admin);
+    let result = sell(&mut env, &user).assert_ok();
+
+    // 按计费金额收费，内层 amount_in 为扣费后的数量
+    let fee = AMOUNT_IN / 100;
+    let cpis = result.cpis_to(&DAMM_V2_PROGRAM);
+    assert_eq!(cpis.len(), 1);
+    assert_eq!(cpis[0].data[8..], u64_args(&[AMOUNT_IN - fee, 1])[..]);
+    assert_eq!(env.lamports(&env.admin) - treasury_before, fee);
+    assert_eq!(env.lamports(&user), SOL + RECEIVED - fee);
+
+    let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!((swap.route_id, swap.fee, swap.remaining), (*DAMM_V2_SELL_SELECTOR, fee, AMOUNT_IN - fee));
+}
+
+#[test]
+fn post_swap_sell_skims_the_fee_from_the_sol_received() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    set_timing(&mut env, FeeTiming::PostSwap);
+    let user = env.wallet(SOL);
+    let treasury_before = env.lamports(&env.admin);
+    let result = sell(&mut env, &user).assert_ok();
+
+    // 完整数量转发给内层兑换，手续费按实际收到的 SOL 收取
+    let fee = RECEIVED / 100;
+    let cpis = result.cpis_to(&DAMM_V2_PROGRAM);
+    assert_eq!(cpis[0].data[8..], u64_args(&[AMOUNT_IN, 1])[..]);
+    assert_eq!(env.lamports(&env.admin) - treasury_before, fee);
+    assert_eq!(env.lamports(&user), SOL + RECEIVED - fee);
+
+    let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!((swap.amount_out, swap.fee, swap.remaining), (RECEIVED, fee, RECEIVED - fee));
+}
@@ -0,0 +1,54 @@
+// 累计手续费的读取与清零：rst_fees 以返回数据给出清零前的累计值（含推荐人与回购分成），同一指令中清零
+mod common;
+
+use amm_proxy_contract::ix_builder::{pump_buy_ix, read_and_reset_fees_ix, set_buyback_ix};
+use common::{PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{instruction::AccountMeta, pubkey::Pubkey};
+
+fn read_and_reset(env: &mut TestEnv, admin: &Pubkey) -> u64 {
+    let ix = read_and_reset_fees_ix(&PROGRAM_ID, &env.config, admin);
+    let result = env.process(&ix).assert_ok();
+    u64::from_le_bytes(result.return_data.unwrap().1.try_into().unwrap())
+}
+
+fn buy(env: &mut TestEnv, user: &Pubkey, curve: &PumpCurve, amount: u64, extra: &[AccountMeta]) {
+    let mut ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), amount, 2 * amount, curve.buy_accounts());
+    ix.accounts.extend_from_slice(extra);
+    env.process(&ix).assert_ok();
+}
+
+#[test]
+fn returns_the_period_total_and_resets_it() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let buyback = env.wallet(SOL);
+    let ix = set_buyback_ix(&PROGRAM_ID, &env.config, &env.admin, &buyback, 3_000);
+    env.process(&ix).assert_ok();
+
+    // 回购分成同样计入累计值
+    let buyback_account = [AccountMeta::new(buyback, false)];
+    buy(&mut env, &user, &curve, SOL, &buyback_account);
+    buy(&mut env, &user, &curve, SOL / 2, &buyback_account);
+    let admin = env.admin;
+    assert_eq!(read_and_reset(&mut env, &admin), SOL / 100 + SOL / 200);
+    assert_eq!(env.config_state().total_fees_collected, 0);
+
+    // 清零后再次读取为 0，下一周期重新累计
+    assert_eq!(read_and_reset(&mut env, &admin), 0);
+    buy(&mut env, &user, &curve, 2 * SOL, &buyback_account);
+    assert_eq!(read_and_reset(&mut env, &admin), 2 * SOL / 100);
+}
+
+#[test]
+fn reset_requires_the_admin() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    buy(&mut env, &user, &curve, SOL, &[]);
+
+    let stranger = env.wallet(SOL);
+    let ix = read_and_reset_fees_ix(&PROGRAM_ID, &env.config, &stranger);
+    assert!(env.process(&ix).result.is_err());
+    assert_eq!(env.config_state().total_fees_collected, SOL / 100);
+}
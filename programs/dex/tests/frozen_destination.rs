@@ -0,0 +1,71 @@
+// 冻结的目标代币账户：开启预检后，买入的输出代币账户被冻结时在收费与 CPI 前拒绝，未冻结时照常买入
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    ix_builder::{pump_buy_ix, set_balance_check_ix, set_frozen_destination_check_ix},
+    state::{PREFLIGHT_FROZEN_DESTINATION, PREFLIGHT_PAYER_BALANCE},
+    token::ACCOUNT_STATE_FROZEN,
+};
+use common::{
+    mocks::{pack_token_account, PUMP_PROGRAM, TOKEN_ACCOUNT_FROZEN},
+    PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+fn setup(enabled: bool) -> (TestEnv, Pubkey, PumpCurve) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_frozen_destination_check_ix(&PROGRAM_ID, &env.config, &env.admin, enabled);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().preflight(PREFLIGHT_FROZEN_DESTINATION), enabled);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    (env, user, curve)
+}
+
+fn freeze(env: &mut TestEnv, user: &Pubkey, curve: &PumpCurve) {
+    let amount = env.token_balance(&curve.associated_user);
+    env.account_mut(&curve.associated_user).data = pack_token_account(&curve.mint, user, amount, ACCOUNT_STATE_FROZEN);
+}
+
+#[test]
+fn frozen_destination_is_rejected_before_the_fee() {
+    let (mut env, user, curve) = setup(true);
+    let treasury_before = env.lamports(&env.admin);
+
+    // 未冻结时照常买入
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    env.process(&ix).assert_ok();
+    assert_eq!(env.lamports(&env.admin) - treasury_before, SOL / 100);
+
+    freeze(&mut env, &user, &curve);
+    let (user_before, treasury_before) = (env.lamports(&user), env.lamports(&env.admin));
+    let result = env.process(&ix);
+    assert!(result.cpis_to(&PUMP_PROGRAM).is_empty());
+    assert_eq!(result.unwrap_err(), MyError::DestinationFrozen.into());
+    assert_eq!((env.lamports(&user), env.lamports(&env.admin)), (user_before, treasury_before));
+}
+
+#[test]
+fn frozen_destination_is_not_checked_when_disabled() {
+    let (mut env, user, curve) = setup(false);
+    freeze(&mut env, &user, &curve);
+    // 不做预检时买入照常发起，直到代币程序转入冻结账户时才失败
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let result = env.process(&ix);
+    assert_eq!(result.cpis_to(&PUMP_PROGRAM).len(), 1);
+    assert_eq!(result.unwrap_err(), ProgramError::Custom(TOKEN_ACCOUNT_FROZEN));
+}
+
+#[test]
+fn frozen_check_shares_the_flags_with_the_balance_check() {
+    let (mut env, ..) = setup(true);
+    let ix = set_balance_check_ix(&PROGRAM_ID, &env.config, &env.admin, true);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().preflight_checks, PREFLIGHT_PAYER_BALANCE | PREFLIGHT_FROZEN_DESTINATION);
+
+    // 关闭其中一项不影响另一项
+    let ix = set_frozen_destination_check_ix(&PROGRAM_ID, &env.config, &env.admin, false);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().preflight_checks, PREFLIGHT_PAYER_BALANCE);
+}
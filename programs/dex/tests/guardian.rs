@@ -0,0 +1,84 @@
+// 守护者：可以全局或按路由暂停，不能恢复暂停、修改手续费配置或提取托管收入
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    events::{ProtocolPauseChanged, PROTOCOL_PAUSE_EVENT},
+    instructions::{escrow::escrow_address, pump::PUMP_SELECTOR},
+    ix_builder::{
+        init_escrow_ix, pause_route_ix, pump_buy_ix, set_guardian_ix, set_paused_ix, set_rebate_ix,
+        withdraw_escrow_ix, FeeAccounts,
+    },
+};
+use borsh::BorshDeserialize;
+use common::{TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{epoch_schedule::EpochSchedule, program_error::ProgramError, pubkey::Pubkey};
+
+const REASON: u16 = 3;
+
+fn setup() -> (TestEnv, Pubkey) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let guardian = env.wallet(SOL);
+    let ix = set_guardian_ix(&PROGRAM_ID, &env.config, &env.admin, &guardian);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().guardian, guardian);
+    (env, guardian)
+}
+
+#[test]
+fn guardian_pauses_but_cannot_unpause() {
+    let (mut env, guardian) = setup();
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+
+    let ix = set_paused_ix(&PROGRAM_ID, &env.config, &guardian, true, REASON);
+    let result = env.process(&ix).assert_ok();
+    let event = ProtocolPauseChanged::try_from_slice(&result.events(PROTOCOL_PAUSE_EVENT)[0]).unwrap();
+    assert_eq!((event.paused, event.reason, event.authority), (true, REASON, guardian));
+    let buy = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    assert_eq!(env.process(&buy).unwrap_err(), MyError::ProtocolPaused.into());
+
+    // 恢复需要管理员
+    let ix = set_paused_ix(&PROGRAM_ID, &env.config, &guardian, false, 0);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::IllegalOwner);
+    assert!(env.config_state().paused);
+    let ix = set_paused_ix(&PROGRAM_ID, &env.config, &env.admin, false, 0);
+    env.process(&ix).assert_ok();
+    env.process(&buy).assert_ok();
+
+    // 单路由暂停同样如此
+    let ix = pause_route_ix(&PROGRAM_ID, &env.config, &guardian, PUMP_SELECTOR, true, REASON);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.process(&buy).unwrap_err(), MyError::RoutePaused.into());
+    let ix = pause_route_ix(&PROGRAM_ID, &env.config, &guardian, PUMP_SELECTOR, false, 0);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::IllegalOwner);
+}
+
+#[test]
+fn guardian_cannot_withdraw_or_change_fees() {
+    let (mut env, guardian) = setup();
+    let ix = init_escrow_ix(&PROGRAM_ID, &env.config, &env.admin, SOL);
+    env.process(&ix).assert_ok();
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let accounts = FeeAccounts { fee_receiver: escrow_address(&PROGRAM_ID).0, ..env.fee_accounts(&user) };
+    let ix = pump_buy_ix(&PROGRAM_ID, &accounts, SOL, 2 * SOL, curve.buy_accounts());
+    env.process(&ix).assert_ok();
+    env.warp_to_slot(EpochSchedule::without_warmup().slots_per_epoch);
+
+    let escrow_before = env.lamports(&escrow_address(&PROGRAM_ID).0);
+    let ix = withdraw_escrow_ix(&PROGRAM_ID, &env.config, &guardian, 0);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::IllegalOwner);
+    assert_eq!(env.lamports(&escrow_address(&PROGRAM_ID).0), escrow_before);
+    assert_eq!(env.lamports(&guardian), SOL);
+
+    let ix = set_rebate_ix(&PROGRAM_ID, &env.config, &guardian, 10_000);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::IllegalOwner);
+    let ix = set_guardian_ix(&PROGRAM_ID, &env.config, &guardian, &Pubkey::new_unique());
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::IllegalOwner);
+    assert_eq!((env.config_state().rebate_bps, env.config_state().guardian), (0, guardian));
+
+    // 管理员仍可提取
+    let ix = withdraw_escrow_ix(&PROGRAM_ID, &env.config, &env.admin, 0);
+    env.process(&ix).assert_ok();
+}
@@ -0,0 +1,84 @@
+// 客户端指令构造工具的输出经处理器执行后与预期一致
+mod common;
+
+use amm_proxy_contract::{
+    instructions::{fee::RoutePlan, pump::PUMP_SELECTOR},
+    ix_builder::{
+        explain_ix, get_config_ix, pump_buy_ix, read_and_reset_fees_ix, set_fee_wallet_ix, set_free_trades_ix,
+        set_trade_cooldown_ix, update_config_ix,
+    },
+    state::{ConfigUpdate, TradeFeeState},
+};
+use borsh::BorshDeserialize;
+use common::{mocks::PUMP_PROGRAM, u64_args, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+#[test]
+fn admin_builders_round_trip_through_the_processor() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let (config, admin) = (env.config, env.admin);
+    let new_wallet = env.wallet(SOL);
+
+    env.process(&set_free_trades_ix(&PROGRAM_ID, &config, &admin, 3)).assert_ok();
+    env.process(&set_trade_cooldown_ix(&PROGRAM_ID, &config, &admin, 25)).assert_ok();
+    let update = ConfigUpdate {
+        referral_share_bps: Some(1_500),
+        max_inner_data_len: Some(512),
+        ..Default::default()
+    };
+    env.process(&update_config_ix(&PROGRAM_ID, &config, &admin, &update)).assert_ok();
+    // 管理员即协议费钱包，更换后原管理员失去权限
+    env.process(&set_fee_wallet_ix(&PROGRAM_ID, &config, &admin, &new_wallet)).assert_ok();
+    let ix = set_free_trades_ix(&PROGRAM_ID, &config, &admin, 0);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::IllegalOwner);
+
+    let state = env.config_state();
+    assert_eq!(state.fee_wallet, new_wallet);
+    assert_eq!((state.free_trades, state.trade_cooldown_slots), (3, 25));
+    assert_eq!((state.referral_share_bps, state.max_inner_data_len), (1_500, 512));
+    // 未给出的字段保持不变
+    assert_eq!(state.fee_rate_pips, DEFAULT_FEE_RATE_PIPS);
+}
+
+#[test]
+fn get_config_returns_the_stored_state() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = get_config_ix(&PROGRAM_ID, &env.config);
+    let result = env.process(&ix).assert_ok();
+    let state = TradeFeeState::unpack(&result.return_data.unwrap().1).unwrap();
+    assert_eq!((state.fee_rate_pips, state.fee_wallet), (DEFAULT_FEE_RATE_PIPS, env.admin));
+}
+
+#[test]
+fn read_and_reset_fees_returns_the_collected_total() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let buy = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    env.process(&buy).assert_ok();
+
+    let ix = read_and_reset_fees_ix(&PROGRAM_ID, &env.config, &env.admin);
+    let result = env.process(&ix).assert_ok();
+    assert_eq!(result.return_data.unwrap().1, (SOL / 100).to_le_bytes());
+    assert_eq!(env.config_state().total_fees_collected, 0);
+}
+
+#[test]
+fn explain_describes_the_built_route_without_executing_it() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let buy = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+
+    let result = env.process(&explain_ix(&buy)).assert_ok();
+    assert!(result.cpis_to(&PUMP_PROGRAM).is_empty());
+    assert_eq!(env.lamports(&user), 10 * SOL);
+
+    let plan = RoutePlan::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    let fee = SOL / 100;
+    assert_eq!((plan.route_id, plan.program, plan.is_buy), (*PUMP_SELECTOR, PUMP_PROGRAM, true));
+    assert_eq!((plan.amount, plan.fee, plan.inner_amount), (SOL, fee, SOL - fee));
+    assert_eq!(plan.inner_data[8..], u64_args(&[SOL - fee, 2 * SOL])[..]);
+    let forwarded: Vec<Pubkey> = curve.buy_accounts().iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(plan.accounts, forwarded);
+}
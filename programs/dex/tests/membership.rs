@@ -0,0 +1,66 @@
+// 会员折扣：持有会员代币的交易者按配置比例减免手续费，未持有或余额为 0 时按全额收取
+mod common;
+
+use amm_proxy_contract::{
+    instructions::fee::SwapResult,
+    ix_builder::{pump_buy_ix, set_membership_ix, with_membership_account},
+    token::TOKEN_PROGRAM_ID,
+};
+use borsh::BorshDeserialize;
+use common::{runtime::TxResult, PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::pubkey::Pubkey;
+
+const DISCOUNT_BPS: u16 = 5_000;
+
+fn setup() -> (TestEnv, Pubkey, PumpCurve, Pubkey) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let membership_mint = env.add_mint(0, &TOKEN_PROGRAM_ID);
+    let ix = set_membership_ix(&PROGRAM_ID, &env.config, &env.admin, &membership_mint, DISCOUNT_BPS);
+    env.process(&ix).assert_ok();
+    let config = env.config_state();
+    assert_eq!((config.membership_mint, config.membership_discount_bps), (membership_mint, DISCOUNT_BPS));
+
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    (env, user, curve, membership_mint)
+}
+
+fn member_buy(env: &mut TestEnv, user: &Pubkey, curve: &PumpCurve, membership_mint: &Pubkey) -> TxResult {
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, curve.buy_accounts());
+    let ix = with_membership_account(ix, user, membership_mint, &TOKEN_PROGRAM_ID);
+    env.process(&ix)
+}
+
+fn charged_fee(result: TxResult) -> u64 {
+    SwapResult::try_from_slice(&result.assert_ok().return_data.unwrap().1).unwrap().fee
+}
+
+#[test]
+fn holder_gets_the_discount() {
+    let (mut env, user, curve, membership_mint) = setup();
+    env.add_ata(&user, &membership_mint, 1, &TOKEN_PROGRAM_ID);
+    let treasury_before = env.lamports(&env.admin);
+
+    let fee = charged_fee(member_buy(&mut env, &user, &curve, &membership_mint));
+    assert_eq!(fee, SOL / 100 / 2);
+    assert_eq!(env.lamports(&env.admin) - treasury_before, fee);
+}
+
+#[test]
+fn non_holder_pays_the_full_rate() {
+    let (mut env, user, curve, membership_mint) = setup();
+
+    // 未持有会员代币账户
+    assert_eq!(charged_fee(member_buy(&mut env, &user, &curve, &membership_mint)), SOL / 100);
+
+    // 账户存在但余额为 0
+    env.add_ata(&user, &membership_mint, 0, &TOKEN_PROGRAM_ID);
+    assert_eq!(charged_fee(member_buy(&mut env, &user, &curve, &membership_mint)), SOL / 100);
+
+    // 持有的是其他人的会员代币账户
+    let other = env.wallet(SOL);
+    env.add_ata(&other, &membership_mint, 1, &TOKEN_PROGRAM_ID);
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let ix = with_membership_account(ix, &other, &membership_mint, &TOKEN_PROGRAM_ID);
+    assert_eq!(charged_fee(env.process(&ix)), SOL / 100);
+}
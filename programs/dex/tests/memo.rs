@@ -0,0 +1,71 @@
+// 合规 Memo：开启 require_memo 后，交易中没有 Memo 指令的兑换被拒绝
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    ix_builder::{pump_buy_ix, set_require_memo_ix, with_instructions_sysvar},
+};
+use common::{PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, MEMO_PROGRAM_ID, PROGRAM_ID, SOL};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+fn setup() -> (TestEnv, Pubkey, PumpCurve) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_require_memo_ix(&PROGRAM_ID, &env.config, &env.admin, true);
+    env.process(&ix).assert_ok();
+    assert!(env.config_state().require_memo);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    (env, user, curve)
+}
+
+fn buy(env: &TestEnv, user: &Pubkey, curve: &PumpCurve) -> Instruction {
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, curve.buy_accounts());
+    with_instructions_sysvar(ix)
+}
+
+fn memo(signer: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(MEMO_PROGRAM_ID, b"partner-order-42", vec![AccountMeta::new_readonly(*signer, true)])
+}
+
+#[test]
+fn swap_with_a_memo_is_accepted() {
+    let (mut env, user, curve) = setup();
+    let treasury_before = env.lamports(&env.admin);
+
+    let transaction = [memo(&user), buy(&env, &user, &curve)];
+    env.process_transaction(&transaction).assert_ok();
+    assert_eq!(env.lamports(&env.admin) - treasury_before, SOL / 100);
+}
+
+#[test]
+fn swap_without_a_memo_is_rejected() {
+    let (mut env, user, curve) = setup();
+
+    let ix = buy(&env, &user, &curve);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::MemoRequired.into());
+    assert_eq!(env.lamports(&user), 10 * SOL);
+
+    // 未附带 Instructions sysvar 时无法检查
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let result = env.process_transaction(&[memo(&user), ix]);
+    assert_eq!(result.unwrap_err(), ProgramError::NotEnoughAccountKeys);
+
+    // 关闭后无需 Memo
+    let ix = set_require_memo_ix(&PROGRAM_ID, &env.config, &env.admin, false);
+    env.process(&ix).assert_ok();
+    let ix = buy(&env, &user, &curve);
+    env.process(&ix).assert_ok();
+}
+
+#[test]
+fn toggling_requires_the_admin() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let stranger = env.wallet(SOL);
+    let ix = set_require_memo_ix(&PROGRAM_ID, &env.config, &stranger, true);
+    assert!(env.process(&ix).result.is_err());
+    assert!(!env.config_state().require_memo);
+}
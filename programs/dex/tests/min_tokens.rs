@@ -0,0 +1,66 @@
+// 最小到账校验：按客户端指定的目标代币账户兑换前后的余额增量判断，低于 min_tokens 时整笔回滚
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    ix_builder::{pump_buy_ix, with_min_tokens},
+    token::TOKEN_PROGRAM_ID,
+};
+use common::{
+    mocks::{set_swap_behavior, SwapBehavior},
+    PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+
+const RECEIVED: u64 = 1_000_000;
+
+fn setup() -> (TestEnv, Pubkey, PumpCurve) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    set_swap_behavior(SwapBehavior { output: Some(RECEIVED), ..Default::default() });
+    (env, user, curve)
+}
+
+fn buy(env: &TestEnv, user: &Pubkey, curve: &PumpCurve, destination: &Pubkey, min_tokens: u64) -> Instruction {
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, curve.buy_accounts());
+    with_min_tokens(ix, destination, min_tokens)
+}
+
+#[test]
+fn increase_on_the_destination_must_reach_min_tokens() {
+    let (mut env, user, curve) = setup();
+
+    let ix = buy(&env, &user, &curve, &curve.associated_user, RECEIVED);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.token_balance(&curve.associated_user), RECEIVED);
+
+    // 只比较本笔的增量，不受已有余额影响
+    let (user_before, treasury_before) = (env.lamports(&user), env.lamports(&env.admin));
+    let ix = buy(&env, &user, &curve, &curve.associated_user, RECEIVED + 1);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::MinTokensNotReceived.into());
+    assert_eq!((env.lamports(&user), env.lamports(&env.admin)), (user_before, treasury_before));
+    assert_eq!(env.token_balance(&curve.associated_user), RECEIVED);
+}
+
+#[test]
+fn destination_outside_the_swap_sees_no_increase() {
+    let (mut env, user, curve) = setup();
+    // 交易者自己的另一个代币账户，不在转发账户中，追加在指令末尾
+    let mint = env.add_mint(6, &TOKEN_PROGRAM_ID);
+    let other = env.add_ata(&user, &mint, 0, &TOKEN_PROGRAM_ID);
+
+    let ix = buy(&env, &user, &curve, &other, 1);
+    assert_eq!(ix.accounts.last().unwrap().pubkey, other);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::MinTokensNotReceived.into());
+}
+
+#[test]
+fn destination_must_belong_to_the_trader() {
+    let (mut env, user, curve) = setup();
+    let stranger_ata = env.add_ata(&Pubkey::new_unique(), &curve.mint, 0, &TOKEN_PROGRAM_ID);
+
+    let ix = buy(&env, &user, &curve, &stranger_ata, 1);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidAccountData);
+    assert_eq!(env.lamports(&user), 10 * SOL);
+}
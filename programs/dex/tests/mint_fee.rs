@@ -0,0 +1,52 @@
+// 代币费率覆盖：交易代币登记了费率 PDA 时按其费率收费，未登记或清除后回到全局费率
+mod common;
+
+use amm_proxy_contract::{
+    instructions::{fee::SwapResult, mint_fee::mint_fee_address},
+    ix_builder::{clear_mint_fee_ix, pump_buy_ix, set_mint_fee_ix, with_mint_fee},
+    state::MintFeeState,
+};
+use borsh::BorshDeserialize;
+use common::{PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::pubkey::Pubkey;
+
+const PROMO_RATE_PIPS: u32 = 5_000;
+
+fn buy_fee(env: &mut TestEnv, user: &Pubkey, curve: &PumpCurve) -> u64 {
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, curve.buy_accounts());
+    let ix = with_mint_fee(&PROGRAM_ID, ix, &curve.mint);
+    let result = env.process(&ix).assert_ok();
+    SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap().fee
+}
+
+#[test]
+fn overridden_mint_uses_its_rate_and_others_the_default() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let (promoted, regular) = (env.pump_curve(&user, 0), env.pump_curve(&user, 0));
+
+    let ix = set_mint_fee_ix(&PROGRAM_ID, &env.config, &env.admin, &promoted.mint, PROMO_RATE_PIPS);
+    env.process(&ix).assert_ok();
+    let pda = mint_fee_address(&PROGRAM_ID, &promoted.mint).0;
+    let state = MintFeeState::try_from_slice(env.data(&pda)).unwrap();
+    assert_eq!((state.mint, state.rate_pips), (promoted.mint, PROMO_RATE_PIPS));
+
+    assert_eq!(buy_fee(&mut env, &user, &promoted), SOL / 200);
+    assert_eq!(buy_fee(&mut env, &user, &regular), SOL / 100);
+
+    // 清除后关闭费率 PDA，回到全局费率
+    let ix = clear_mint_fee_ix(&PROGRAM_ID, &env.config, &env.admin, &promoted.mint);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.lamports(&pda), 0);
+    assert_eq!(buy_fee(&mut env, &user, &promoted), SOL / 100);
+}
+
+#[test]
+fn only_the_admin_sets_mint_rates() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let stranger = env.wallet(SOL);
+    let mint = Pubkey::new_unique();
+    let ix = set_mint_fee_ix(&PROGRAM_ID, &env.config, &stranger, &mint, PROMO_RATE_PIPS);
+    assert!(env.process(&ix).result.is_err());
+    assert!(env.account(&mint_fee_address(&PROGRAM_ID, &mint).0).is_none());
+}
@@ -0,0 +1,106 @@
+// 多签管理员：开启门限后高风险管理指令，以及把路由、代币的费率降为 0 或全额返还等免收手续费的修改，
+// 需要 M 个不同的多签管理员签名
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::pump::PUMP_SELECTOR,
+    ix_builder::{
+        set_fee_rate_ix, set_mint_fee_ix, set_multisig_ix, set_rebate_ix, set_route_fees_ix, with_multisig_signers,
+    },
+    state::MAX_MULTISIG_ADMINS,
+};
+use common::{TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+const NEW_RATE_PIPS: u32 = 20_000;
+
+// 2-of-3 多签
+fn setup() -> (TestEnv, [Pubkey; 3]) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let signers = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+    let ix = set_multisig_ix(&PROGRAM_ID, &env.config, &env.admin, 2, &signers);
+    env.process(&ix).assert_ok();
+    let config = env.config_state();
+    assert_eq!(config.multisig_threshold, 2);
+    assert_eq!(config.multisig_admins[..3], signers);
+    (env, signers)
+}
+
+#[test]
+fn high_risk_change_needs_the_threshold() {
+    let (mut env, signers) = setup();
+    let fee_rate = |env: &TestEnv, approvers: &[Pubkey]| {
+        with_multisig_signers(set_fee_rate_ix(&PROGRAM_ID, &env.config, &env.admin, NEW_RATE_PIPS), approvers)
+    };
+
+    // 只有管理员、只有 1 个多签管理员，或同一管理员重复签名都不满足门限
+    for approvers in [&[][..], &signers[..1], &[signers[0], signers[0]][..]] {
+        let ix = fee_rate(&env, approvers);
+        assert_eq!(env.process(&ix).unwrap_err(), MyError::MultisigThresholdNotMet.into());
+    }
+    assert_eq!(env.config_state().fee_rate_pips, DEFAULT_FEE_RATE_PIPS);
+
+    let ix = fee_rate(&env, &signers[1..]);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().fee_rate_pips, NEW_RATE_PIPS);
+}
+
+#[test]
+fn changing_the_admin_set_also_needs_the_threshold() {
+    let (mut env, signers) = setup();
+    let replacement = [Pubkey::new_unique()];
+
+    let ix = with_multisig_signers(set_multisig_ix(&PROGRAM_ID, &env.config, &env.admin, 1, &replacement), &signers[..1]);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::MultisigThresholdNotMet.into());
+
+    let ix = with_multisig_signers(set_multisig_ix(&PROGRAM_ID, &env.config, &env.admin, 1, &replacement), &signers[..2]);
+    env.process(&ix).assert_ok();
+    let config = env.config_state();
+    assert_eq!((config.multisig_threshold, config.multisig_admins[0]), (1, replacement[0]));
+    assert_eq!(config.multisig_admins[1], Pubkey::default());
+}
+
+#[test]
+fn zero_rate_updates_need_the_threshold() {
+    let (mut env, signers) = setup();
+    let mint = Pubkey::new_unique();
+    let zeroing = [
+        set_route_fees_ix(&PROGRAM_ID, &env.config, &env.admin, &[(*PUMP_SELECTOR, 0)]),
+        set_mint_fee_ix(&PROGRAM_ID, &env.config, &env.admin, &mint, 0),
+        set_rebate_ix(&PROGRAM_ID, &env.config, &env.admin, 10_000),
+    ];
+
+    // 单个管理员不能免收手续费，非零的修改不受门限限制
+    for ix in &zeroing {
+        assert_eq!(env.process(ix).unwrap_err(), MyError::MultisigThresholdNotMet.into());
+    }
+    let config = env.config_state();
+    assert_eq!((config.fee_rate_for(PUMP_SELECTOR), config.rebate_bps), (DEFAULT_FEE_RATE_PIPS, 0));
+    for ix in [
+        set_route_fees_ix(&PROGRAM_ID, &env.config, &env.admin, &[(*PUMP_SELECTOR, 5_000)]),
+        set_mint_fee_ix(&PROGRAM_ID, &env.config, &env.admin, &mint, 5_000),
+        set_rebate_ix(&PROGRAM_ID, &env.config, &env.admin, 9_999),
+    ] {
+        env.process(&ix).assert_ok();
+    }
+
+    // 满足门限后可以降为 0
+    for ix in zeroing {
+        env.process(&with_multisig_signers(ix, &signers[..2])).assert_ok();
+    }
+    let config = env.config_state();
+    assert_eq!((config.fee_rate_for(PUMP_SELECTOR), config.rebate_bps), (0, 10_000));
+}
+
+#[test]
+fn invalid_admin_sets_are_rejected() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let admin = Pubkey::new_unique();
+    let too_many = vec![Pubkey::new_unique(); MAX_MULTISIG_ADMINS + 1];
+    for (threshold, admins) in [(2, vec![admin]), (2, vec![admin, admin]), (1, too_many)] {
+        let ix = set_multisig_ix(&PROGRAM_ID, &env.config, &env.admin, threshold, &admins);
+        assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidInstructionData);
+    }
+    assert_eq!(env.config_state().multisig_threshold, 0);
+}
@@ -0,0 +1,73 @@
+// nonce 防重放：窗口内重复使用的 nonce 被拒绝，超出 replay_window_slots 后可再次使用
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::nonce::{nonce_address, MAX_NONCE_RING_SIZE},
+    ix_builder::{pump_buy_ix, set_replay_window_ix, with_nonce},
+};
+use common::{PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+
+const WINDOW_SLOTS: u64 = 100;
+
+fn setup(ring_size: u16) -> (TestEnv, Pubkey, PumpCurve) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_replay_window_ix(&PROGRAM_ID, &env.config, &env.admin, WINDOW_SLOTS, ring_size);
+    env.process(&ix).assert_ok();
+    let config = env.config_state();
+    assert_eq!((config.replay_window_slots, config.nonce_ring_size), (WINDOW_SLOTS, ring_size));
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    (env, user, curve)
+}
+
+fn nonce_buy(env: &TestEnv, user: &Pubkey, curve: &PumpCurve, nonce: u64) -> Instruction {
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL / 10, SOL, curve.buy_accounts());
+    with_nonce(&PROGRAM_ID, ix, user, nonce)
+}
+
+#[test]
+fn nonce_is_reusable_only_after_the_window() {
+    let (mut env, user, curve) = setup(4);
+    env.warp_to_slot(10);
+    let ix = nonce_buy(&env, &user, &curve, 1);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.data(&nonce_address(&PROGRAM_ID, &user).0).len(), 4 * 16);
+
+    // 窗口边界上仍视为重放
+    env.warp_to_slot(10 + WINDOW_SLOTS);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::NonceReused.into());
+
+    env.warp_to_slot(11 + WINDOW_SLOTS);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::NonceReused.into());
+}
+
+#[test]
+fn full_ring_rejects_new_nonces_until_entries_expire() {
+    let (mut env, user, curve) = setup(2);
+    env.warp_to_slot(10);
+    for nonce in [1, 2] {
+        let ix = nonce_buy(&env, &user, &curve, nonce);
+        env.process(&ix).assert_ok();
+    }
+    let ix = nonce_buy(&env, &user, &curve, 3);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::NonceRingFull.into());
+
+    // 过期的记录被淘汰，腾出的位置可写入新 nonce
+    env.warp_to_slot(11 + WINDOW_SLOTS);
+    env.process(&ix).assert_ok();
+}
+
+#[test]
+fn replay_window_must_be_configured() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let ix = nonce_buy(&env, &user, &curve, 1);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::ReplayWindowNotSet.into());
+
+    let ix = set_replay_window_ix(&PROGRAM_ID, &env.config, &env.admin, WINDOW_SLOTS, MAX_NONCE_RING_SIZE + 1);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidInstructionData);
+}
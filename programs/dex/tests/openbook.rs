@@ -0,0 +1,81 @@
+// OpenBook v2：带手续费的 IOC 买单按 quote_lot_size 换算扣费后的数量，并把 place_take_order 转发给 OpenBook 程序
+mod common;
+
+use amm_proxy_contract::{
+    instructions::fee::SwapResult,
+    instructions::openbook::{OPENBOOK_BUY_ROUTE, OPENBOOK_BUY_SELECTOR, OPENBOOK_V2_PROGRAM},
+    ix_builder::{openbook_buy_ix, OpenBookOrder},
+};
+use borsh::BorshDeserialize;
+use common::{route_accounts, Account, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{instruction::AccountMeta, program_error::ProgramError, pubkey::Pubkey};
+
+const QUOTE_LOT_SIZE: u64 = 1_000;
+// Market 账户中 quote_lot_size 的偏移，其后为 base_lot_size
+const QUOTE_LOT_SIZE_OFFSET: usize = 736;
+
+const ORDER: OpenBookOrder = OpenBookOrder { price_lots: 5_000, max_base_lots: 100, order_type: 3, limit: 10 };
+
+fn market_account(quote_lot_size: i64) -> Account {
+    let mut data = vec![0u8; QUOTE_LOT_SIZE_OFFSET + 16];
+    data[QUOTE_LOT_SIZE_OFFSET..QUOTE_LOT_SIZE_OFFSET + 8].copy_from_slice(&quote_lot_size.to_le_bytes());
+    data[QUOTE_LOT_SIZE_OFFSET + 8..].copy_from_slice(&1i64.to_le_bytes());
+    Account::rent_exempt(data, OPENBOOK_V2_PROGRAM)
+}
+
+// place_take_order 的 16 个账户，末尾为 OpenBook v2 程序
+fn take_order_accounts(env: &mut TestEnv, user: &Pubkey, market: Account) -> Vec<AccountMeta> {
+    let mut accounts = route_accounts(&OPENBOOK_BUY_ROUTE, 16, user);
+    env.set_account(accounts[2].pubkey, market);
+    accounts.push(AccountMeta::new_readonly(OPENBOOK_V2_PROGRAM, false));
+    accounts
+}
+
+#[test]
+fn buy_places_take_order_with_quote_lots_after_fee() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let treasury_before = env.lamports(&env.admin);
+    let forwarded = take_order_accounts(&mut env, &user, market_account(QUOTE_LOT_SIZE as i64));
+
+    let ix = openbook_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL + 999, &ORDER, forwarded.clone());
+    let result = env.process(&ix).assert_ok();
+
+    let fee = (SOL + 999) / 100;
+    let cpis = result.cpis_to(&OPENBOOK_V2_PROGRAM);
+    assert_eq!(cpis.len(), 1);
+    // 内层数据为 [place_take_order 鉴别器][side][price_lots][max_base_lots][max_quote_lots][order_type][limit]，
+    // max_quote_lots 为扣费后的 quote 数量按 lot 向下取整
+    let quote_lots = (SOL + 999 - fee) / QUOTE_LOT_SIZE;
+    let mut expected = vec![3, 44, 71, 3, 26, 199, 203, 85, 0];
+    expected.extend_from_slice(&ORDER.price_lots.to_le_bytes());
+    expected.extend_from_slice(&ORDER.max_base_lots.to_le_bytes());
+    expected.extend_from_slice(&quote_lots.to_le_bytes());
+    expected.extend_from_slice(&[ORDER.order_type, ORDER.limit]);
+    assert_eq!(cpis[0].data, expected);
+    // 16 个 place_take_order 账户加上转发的目标程序账户
+    let keys = |metas: &[AccountMeta]| metas.iter().map(|meta| meta.pubkey).collect::<Vec<_>>();
+    assert_eq!(cpis[0].accounts.len(), 17);
+    assert_eq!(keys(&cpis[0].accounts), keys(&forwarded));
+
+    assert_eq!(env.lamports(&env.admin) - treasury_before, fee);
+    let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!((swap.route_id, swap.fee), (*OPENBOOK_BUY_SELECTOR, fee));
+}
+
+#[test]
+fn market_must_belong_to_openbook() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+
+    let mut forged = market_account(QUOTE_LOT_SIZE as i64);
+    forged.owner = Pubkey::new_unique();
+    let forwarded = take_order_accounts(&mut env, &user, forged);
+    let ix = openbook_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, &ORDER, forwarded);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::IllegalOwner);
+
+    let forwarded = take_order_accounts(&mut env, &user, market_account(0));
+    let ix = openbook_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, &ORDER, forwarded);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidAccountData);
+    assert_eq!(env.lamports(&user), 10 * SOL);
+}
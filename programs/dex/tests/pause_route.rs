@@ -0,0 +1,77 @@
+// 单路由暂停：暂停 Raydium 带手续费路由时 Pump 路由照常交易，恢复后 Raydium 重新可用，变更带原因码事件
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    events::{RoutePauseChanged, ROUTE_PAUSE_EVENT},
+    instructions::raydium::{RAYDIUM_BUY_BASE_OUT_ROUTE, RAYDIUM_BUY_BASE_OUT_SELECTOR},
+    ix_builder::{pause_route_ix, pump_buy_ix, raydium_buy_base_out_ix},
+};
+use borsh::BorshDeserialize;
+use common::{
+    mocks::RAYDIUM_AMM_V4_PROGRAM, route_accounts, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+const REASON: u16 = 42;
+
+fn raydium_buy(env: &TestEnv, user: &Pubkey) -> Instruction {
+    let mut forwarded = route_accounts(&RAYDIUM_BUY_BASE_OUT_ROUTE, 17, user);
+    forwarded.push(AccountMeta::new_readonly(RAYDIUM_AMM_V4_PROGRAM, false));
+    raydium_buy_base_out_ix(&PROGRAM_ID, &env.fee_accounts(user), 2 * SOL, 123_456, forwarded)
+}
+
+fn set_route_paused(env: &mut TestEnv, paused: bool) -> RoutePauseChanged {
+    let ix = pause_route_ix(&PROGRAM_ID, &env.config, &env.admin, RAYDIUM_BUY_BASE_OUT_SELECTOR, paused, REASON);
+    let result = env.process(&ix).assert_ok();
+    let events = result.events(ROUTE_PAUSE_EVENT);
+    assert_eq!(events.len(), 1);
+    RoutePauseChanged::try_from_slice(&events[0]).unwrap()
+}
+
+#[test]
+fn paused_raydium_route_leaves_pump_live() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+
+    let event = set_route_paused(&mut env, true);
+    assert_eq!(
+        (event.selector, event.paused, event.reason, event.authority),
+        (*RAYDIUM_BUY_BASE_OUT_SELECTOR, true, REASON, env.admin)
+    );
+    assert!(env.config_state().paused_routes.contains(RAYDIUM_BUY_BASE_OUT_SELECTOR));
+
+    // Raydium 被拒绝且不收费，Pump 照常收费
+    let (user_before, treasury_before) = (env.lamports(&user), env.lamports(&env.admin));
+    let ix = raydium_buy(&env, &user);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::RoutePaused.into());
+    assert_eq!((env.lamports(&user), env.lamports(&env.admin)), (user_before, treasury_before));
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    env.process(&ix).assert_ok();
+    assert_eq!(env.lamports(&env.admin) - treasury_before, SOL / 100);
+
+    // 恢复后 Raydium 重新可用，重复暂停不占用多个槽位
+    set_route_paused(&mut env, true);
+    let slots = env.config_state().paused_routes;
+    assert_eq!(slots.iter().filter(|s| *s == RAYDIUM_BUY_BASE_OUT_SELECTOR).count(), 1);
+    assert!(!set_route_paused(&mut env, false).paused);
+    assert!(!env.config_state().paused_routes.contains(RAYDIUM_BUY_BASE_OUT_SELECTOR));
+    let ix = raydium_buy(&env, &user);
+    env.process(&ix).assert_ok();
+}
+
+#[test]
+fn only_fee_routes_can_be_paused() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = pause_route_ix(&PROGRAM_ID, &env.config, &env.admin, b"zzzzzzzz", true, REASON);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::UnsupportedRoute.into());
+
+    let stranger = env.wallet(SOL);
+    let ix = pause_route_ix(&PROGRAM_ID, &env.config, &stranger, RAYDIUM_BUY_BASE_OUT_SELECTOR, true, REASON);
+    assert!(env.process(&ix).result.is_err());
+    assert!(!env.config_state().paused_routes.contains(RAYDIUM_BUY_BASE_OUT_SELECTOR));
+}
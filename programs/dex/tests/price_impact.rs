@@ -0,0 +1,60 @@
+// 价格影响上限：买入后按代币账户的实际余额变化与 expected_out 比较，恰好等于上限时放行，超过一个单位即回滚
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::{fee::PriceImpactGuard, impact::check_price_impact},
+    ix_builder::{pump_buy_ix, pump_sell_ix, with_price_impact},
+};
+use common::{
+    mocks::{set_swap_behavior, SwapBehavior},
+    TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+
+const EXPECTED_OUT: u64 = 1_000_000;
+const MAX_IMPACT_BPS: u16 = 100;
+
+#[test]
+fn buy_passes_at_the_impact_boundary_and_fails_beyond_it() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let buy = |env: &TestEnv| {
+        let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+        with_price_impact(ix, EXPECTED_OUT, MAX_IMPACT_BPS)
+    };
+
+    // 少收 1%（恰好为上限）
+    set_swap_behavior(SwapBehavior { output: Some(EXPECTED_OUT * 99 / 100), ..Default::default() });
+    let ix = buy(&env);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.token_balance(&curve.associated_user), EXPECTED_OUT * 99 / 100);
+
+    // 再少一个单位即超过上限，整笔回滚，手续费不收取
+    let (user_before, treasury_before) = (env.lamports(&user), env.lamports(&env.admin));
+    set_swap_behavior(SwapBehavior { output: Some(EXPECTED_OUT * 99 / 100 - 1), ..Default::default() });
+    let ix = buy(&env);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::PriceImpactExceeded.into());
+    assert_eq!((env.lamports(&user), env.lamports(&env.admin)), (user_before, treasury_before));
+    assert_eq!(env.token_balance(&curve.associated_user), EXPECTED_OUT * 99 / 100);
+}
+
+#[test]
+fn impact_guard_only_wraps_buys() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(SOL);
+    let curve = env.pump_curve(&user, EXPECTED_OUT);
+    let ix = pump_sell_ix(&PROGRAM_ID, &env.fee_accounts(&user), EXPECTED_OUT, 1, curve.sell_accounts());
+    let ix = with_price_impact(ix, EXPECTED_OUT, MAX_IMPACT_BPS);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::UnsupportedRoute.into());
+}
+
+#[test]
+fn impact_is_compared_without_rounding() {
+    let guard = PriceImpactGuard { expected_out: 3, max_impact_bps: 3_333 };
+    // 少收 1/3 即 3_333.33 基点，大于上限
+    assert_eq!(check_price_impact(&guard, 2), Err(MyError::PriceImpactExceeded.into()));
+    assert_eq!(check_price_impact(&guard, 3), Ok(()));
+    // 多收时不受限制
+    assert_eq!(check_price_impact(&guard, 10), Ok(()));
+}
@@ -0,0 +1,63 @@
+// 推广区间免手续费：当前 slot 位于 [promo_start_slot, promo_end_slot) 内时不收费，区间外照常收费
+mod common;
+
+use amm_proxy_contract::{
+    instructions::fee::SwapResult,
+    ix_builder::{pump_buy_ix, set_promo_window_ix},
+};
+use borsh::BorshDeserialize;
+use common::{TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+const START: u64 = 2_000;
+const END: u64 = 3_000;
+
+// 买入 1 SOL，返回国库收到的手续费，并核对 SwapResult 中的记录
+fn charged(env: &mut TestEnv, user: &Pubkey) -> u64 {
+    let curve = env.pump_curve(user, 0);
+    let treasury_before = env.lamports(&env.admin);
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, curve.buy_accounts());
+    let result = env.process(&ix).assert_ok();
+    let fee = env.lamports(&env.admin) - treasury_before;
+    assert_eq!(SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap().fee, fee);
+    fee
+}
+
+#[test]
+fn fee_is_waived_only_inside_the_window() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_promo_window_ix(&PROGRAM_ID, &env.config, &env.admin, START, END);
+    env.process(&ix).assert_ok();
+    let config = env.config_state();
+    assert_eq!((config.promo_start_slot, config.promo_end_slot), (START, END));
+    let user = env.wallet(10 * SOL);
+
+    // 区间前、开始 slot、结束前一个 slot、结束 slot（不含）
+    for (slot, fee) in [(START - 1, SOL / 100), (START, 0), (END - 1, 0), (END, SOL / 100)] {
+        env.warp_to_slot(slot);
+        assert_eq!(charged(&mut env, &user), fee, "slot {slot}");
+    }
+
+    // 更换区间后按新区间判断，结束 slot 为 0 表示关闭
+    env.warp_to_slot(END + 1);
+    let ix = set_promo_window_ix(&PROGRAM_ID, &env.config, &env.admin, END, 2 * END);
+    env.process(&ix).assert_ok();
+    assert_eq!(charged(&mut env, &user), 0);
+    let ix = set_promo_window_ix(&PROGRAM_ID, &env.config, &env.admin, END, 0);
+    env.process(&ix).assert_ok();
+    assert_eq!(charged(&mut env, &user), SOL / 100);
+}
+
+#[test]
+fn setter_rejects_an_empty_window_and_requires_the_admin() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    for (start, end) in [(END, END), (END, START)] {
+        let ix = set_promo_window_ix(&PROGRAM_ID, &env.config, &env.admin, start, end);
+        assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidInstructionData);
+    }
+
+    let stranger = env.wallet(SOL);
+    let ix = set_promo_window_ix(&PROGRAM_ID, &env.config, &stranger, START, END);
+    assert!(env.process(&ix).result.is_err());
+    assert_eq!(env.config_state().promo_end_slot, 0);
+}
@@ -0,0 +1,249 @@
+// Pump 内盘买入与卖出的端到端流程：创建配置、资金账户，经代理合约收费后 CPI 到模拟的 Pump 程序
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::fee::SwapResult,
+    instructions::pump::{detect_pump_market, PumpMarket, PUMP_SELECTOR, PUMP_SELL_SELECTOR},
+    ix_builder::{pump_amm_buy_ix, pump_buy_ix, pump_sell_ix, set_fee_timing_ix},
+    state::FeeTiming,
+};
+use borsh::BorshDeserialize;
+use common::{
+    mocks::{
+        set_swap_behavior, SwapBehavior, PUMP_AMM_PROGRAM, PUMP_BUY_DISCRIMINATOR, PUMP_PROGRAM,
+        PUMP_SELL_DISCRIMINATOR, PUMP_TOO_MUCH_SOL_REQUIRED,
+    },
+    selector_data, u64_args, without_signer, Account, PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{instruction::AccountMeta, program_error::ProgramError, pubkey::Pubkey};
+
+#[test]
+fn pump_buy_collects_fee_and_forwards_remaining_amount() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let (treasury, treasury_before) = (env.admin, env.lamports(&env.admin));
+
+    let ix = pump_buy_ix(
+        &PROGRAM_ID,
+        &env.fee_accounts(&user),
+        SOL,
+        2 * SOL,
+        curve.buy_accounts(),
+    );
+    assert_eq!(
+        ix.data,
+        selector_data(PUMP_SELECTOR, &[&u64_args(&[SOL, SOL, 2 * SOL])])
+    );
+    let result = env.process(&ix).assert_ok();
+
+    // 1% 手续费兑换前扣除，Pump 收到的金额为扣费后的数量，max_sol_cost 原样转发
+    let fee = SOL / 100;
+    let cpis = result.cpis_to(&PUMP_PROGRAM);
+    assert_eq!(cpis.len(), 1);
+    assert_eq!(
+        cpis[0].data,
+        selector_data(&PUMP_BUY_DISCRIMINATOR, &[&u64_args(&[SOL - fee, 2 * SOL])])
+    );
+    assert_eq!(cpis[0].accounts, curve.buy_accounts());
+
+    assert_eq!(env.lamports(&treasury) - treasury_before, fee);
+    assert_eq!(env.lamports(&user), 10 * SOL - SOL);
+    assert_eq!(env.token_balance(&curve.associated_user), SOL - fee);
+    assert_eq!(env.config_state().total_fees_collected, fee);
+
+    let (program, data) = result.return_data.unwrap();
+    assert_eq!(program, PROGRAM_ID);
+    let swap = SwapResult::try_from_slice(&data).unwrap();
+    assert_eq!(
+        (swap.route_id, swap.fee, swap.remaining),
+        (*PUMP_SELECTOR, fee, SOL - fee)
+    );
+    assert_eq!(swap.amount_out, SOL - fee);
+}
+
+#[test]
+fn pump_sell_forwards_token_amount_and_charges_sol_output() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(SOL);
+    let curve = env.pump_curve(&user, 5_000_000);
+    let treasury_before = env.lamports(&env.admin);
+    set_swap_behavior(SwapBehavior {
+        output: Some(SOL / 2),
+        ..Default::default()
+    });
+
+    let ix = pump_sell_ix(
+        &PROGRAM_ID,
+        &env.fee_accounts(&user),
+        5_000_000,
+        1,
+        curve.sell_accounts(),
+    );
+    assert_eq!(
+        ix.data,
+        selector_data(PUMP_SELL_SELECTOR, &[&u64_args(&[5_000_000, 5_000_000, 1])])
+    );
+    let result = env.process(&ix).assert_ok();
+
+    // 代币数量原样转发，手续费按收到的 SOL 收取
+    let cpis = result.cpis_to(&PUMP_PROGRAM);
+    assert_eq!(cpis.len(), 1);
+    assert_eq!(
+        cpis[0].data,
+        selector_data(&PUMP_SELL_DISCRIMINATOR, &[&u64_args(&[5_000_000, 1])])
+    );
+    assert_eq!(cpis[0].accounts, curve.sell_accounts());
+
+    let fee = SOL / 2 / 100;
+    assert_eq!(env.token_balance(&curve.associated_user), 0);
+    assert_eq!(env.lamports(&env.admin) - treasury_before, fee);
+    assert_eq!(env.lamports(&user), SOL + SOL / 2 - fee);
+
+    let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!(
+        (swap.amount_in, swap.amount_out, swap.fee),
+        (5_000_000, SOL / 2, fee)
+    );
+}
+
+#[test]
+fn failed_pump_buy_rolls_back_the_fee() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let treasury_before = env.lamports(&env.admin);
+
+    // 扣费后的金额仍超过 max_sol_cost，内层程序失败，整笔交易回滚
+    let ix = pump_buy_ix(
+        &PROGRAM_ID,
+        &env.fee_accounts(&user),
+        SOL,
+        SOL / 2,
+        curve.buy_accounts(),
+    );
+    let result = env.process(&ix);
+    assert_eq!(
+        result.unwrap_err(),
+        ProgramError::Custom(PUMP_TOO_MUCH_SOL_REQUIRED)
+    );
+    assert_eq!(env.lamports(&env.admin), treasury_before);
+    assert_eq!(env.lamports(&user), 10 * SOL);
+    assert_eq!(env.config_state().seq, 0);
+}
+
+#[test]
+fn pump_buy_requires_the_payer_signature() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+
+    let ix = without_signer(
+        pump_buy_ix(
+            &PROGRAM_ID,
+            &env.fee_accounts(&user),
+            SOL,
+            2 * SOL,
+            curve.buy_accounts(),
+        ),
+        &user,
+    );
+    assert_eq!(
+        env.process(&ix).unwrap_err(),
+        ProgramError::MissingRequiredSignature
+    );
+    assert!(env.process(&ix).cpis_to(&PUMP_PROGRAM).is_empty());
+}
+
+// 外盘池子账户集：只需第一个转发账户（pool）归 PumpAMM 所有
+fn amm_pool_accounts(env: &mut TestEnv, user: &Pubkey) -> Vec<AccountMeta> {
+    let pool = Pubkey::new_unique();
+    env.set_account(pool, Account::rent_exempt(vec![0; 300], PUMP_AMM_PROGRAM));
+    let mut accounts = vec![AccountMeta::new_readonly(pool, false), AccountMeta::new(*user, true)];
+    accounts.extend((2..16).map(|_| AccountMeta::new(Pubkey::new_unique(), false)));
+    accounts.push(AccountMeta::new_readonly(PUMP_AMM_PROGRAM, false));
+    accounts
+}
+
+fn complete_curve(env: &mut TestEnv, curve: &PumpCurve) {
+    env.account_mut(&curve.bonding_curve).data[48] = 1;
+}
+
+#[test]
+fn detect_pump_market_reads_the_forwarded_accounts() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(SOL);
+    let curve = env.pump_curve(&user, 0);
+    let curve_keys: Vec<Pubkey> = curve.buy_accounts().iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(env.with_account_infos(&curve_keys, detect_pump_market), Some(PumpMarket::BondingCurve));
+
+    complete_curve(&mut env, &curve);
+    assert_eq!(env.with_account_infos(&curve_keys, detect_pump_market), Some(PumpMarket::Migrated));
+
+    let amm_keys: Vec<Pubkey> = amm_pool_accounts(&mut env, &user).iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(env.with_account_infos(&amm_keys, detect_pump_market), Some(PumpMarket::Amm));
+
+    // 既不是 PumpAMM 池子也不是 Pump 的 bonding curve
+    let unknown: Vec<Pubkey> = (0..12).map(|_| Pubkey::new_unique()).collect();
+    assert_eq!(env.with_account_infos(&unknown, detect_pump_market), None);
+}
+
+#[test]
+fn curve_selector_on_migrated_token_suggests_the_amm_selector() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    complete_curve(&mut env, &curve);
+
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::PumpTokenMigrated.into());
+
+    // 直接转发外盘池子账户同样按已迁移处理
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, amm_pool_accounts(&mut env, &user));
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::PumpTokenMigrated.into());
+}
+
+#[test]
+fn amm_selector_on_curve_token_suggests_the_curve_selector() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+
+    let ix = pump_amm_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::PumpTokenOnCurve.into());
+    assert_eq!(env.lamports(&user), 10 * SOL);
+}
+
+#[test]
+fn pump_sell_never_patches_the_token_amount() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(SOL);
+    let curve = env.pump_curve(&user, 10_000_000);
+
+    // 无论配置的卖出收费时机如何，内盘卖出都从收到的 SOL 中收费，代币数量与 min_sol_output 原样转发
+    for (timing, received) in [(FeeTiming::PreSwap, SOL / 4), (FeeTiming::PostSwap, SOL / 8)] {
+        let ix = set_fee_timing_ix(&PROGRAM_ID, &env.config, &env.admin, timing as u8);
+        env.process(&ix).assert_ok();
+        set_swap_behavior(SwapBehavior {
+            output: Some(received),
+            ..Default::default()
+        });
+        let (user_before, treasury_before) = (env.lamports(&user), env.lamports(&env.admin));
+
+        let ix = pump_sell_ix(&PROGRAM_ID, &env.fee_accounts(&user), 5_000_000, received / 2, curve.sell_accounts());
+        let result = env.process(&ix).assert_ok();
+        let cpis = result.cpis_to(&PUMP_PROGRAM);
+        assert_eq!(
+            cpis[0].data,
+            selector_data(&PUMP_SELL_DISCRIMINATOR, &[&u64_args(&[5_000_000, received / 2])])
+        );
+
+        let fee = received / 100;
+        assert_eq!(env.lamports(&env.admin) - treasury_before, fee);
+        assert_eq!(env.lamports(&user) - user_before, received - fee);
+        let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+        assert_eq!((swap.amount_in, swap.amount_out, swap.remaining), (5_000_000, received, received - fee));
+    }
+    assert_eq!(env.token_balance(&curve.associated_user), 0);
+}
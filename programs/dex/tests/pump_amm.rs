@@ -0,0 +1,74 @@
+// PumpAMM 外盘买入的交易量累计账户：转发账户第 20、21 个须为全局与用户交易量累计 PDA，缺少或地址不符时在收费与 CPI 前拒绝
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::pump::PUMP_AMM_BUY_ROUTE,
+    ix_builder::pump_amm_buy_ix,
+};
+use common::{mocks::PUMP_AMM_PROGRAM, route_accounts, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+const GLOBAL_INDEX: usize = 19;
+const USER_INDEX: usize = 20;
+
+fn accumulator(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &PUMP_AMM_PROGRAM).0
+}
+
+// 完整的 21 个转发账户
+fn forwarded(user: &Pubkey) -> Vec<AccountMeta> {
+    let mut accounts = route_accounts(&PUMP_AMM_BUY_ROUTE, 21, user);
+    accounts[16] = AccountMeta::new_readonly(PUMP_AMM_PROGRAM, false);
+    accounts[GLOBAL_INDEX] = AccountMeta::new_readonly(accumulator(&[b"global_volume_accumulator"]), false);
+    accounts[USER_INDEX] = AccountMeta::new(accumulator(&[b"user_volume_accumulator", user.as_ref()]), false);
+    accounts
+}
+
+fn buy(env: &TestEnv, user: &Pubkey, forwarded: Vec<AccountMeta>) -> Instruction {
+    pump_amm_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, forwarded)
+}
+
+#[test]
+fn complete_account_set_is_forwarded() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let treasury_before = env.lamports(&env.admin);
+
+    let accounts = forwarded(&user);
+    let ix = buy(&env, &user, accounts.clone());
+    let result = env.process(&ix).assert_ok();
+    let cpis = result.cpis_to(&PUMP_AMM_PROGRAM);
+    assert_eq!(cpis.len(), 1);
+    let keys: Vec<Pubkey> = cpis[0].accounts.iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(keys[GLOBAL_INDEX..], [accounts[GLOBAL_INDEX].pubkey, accounts[USER_INDEX].pubkey]);
+    assert_eq!(env.lamports(&env.admin) - treasury_before, SOL / 100);
+}
+
+#[test]
+fn incomplete_account_set_is_rejected_before_the_cpi() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let other = Pubkey::new_unique();
+
+    // 缺少用户交易量累计账户、两者都缺少、两者位置互换、用户累计账户属于其他用户
+    let mut missing_user = forwarded(&user);
+    missing_user.truncate(USER_INDEX);
+    let mut missing_both = forwarded(&user);
+    missing_both.truncate(GLOBAL_INDEX);
+    let mut swapped = forwarded(&user);
+    swapped.swap(GLOBAL_INDEX, USER_INDEX);
+    let mut other_user = forwarded(&user);
+    other_user[USER_INDEX] = AccountMeta::new(accumulator(&[b"user_volume_accumulator", other.as_ref()]), false);
+
+    for accounts in [missing_user, missing_both, swapped, other_user] {
+        let ix = buy(&env, &user, accounts);
+        let result = env.process(&ix);
+        assert!(result.cpis_to(&PUMP_AMM_PROGRAM).is_empty());
+        assert_eq!(result.unwrap_err(), MyError::PumpVolumeAccumulatorMissing.into());
+        assert_eq!(env.lamports(&user), 10 * SOL);
+    }
+}
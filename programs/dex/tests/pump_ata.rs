@@ -0,0 +1,75 @@
+// 首次买入：同一指令中先幂等创建用户的关联代币账户再执行 Pump 内盘买入，ATA 已存在时只执行买入，买入失败时一并回滚
+mod common;
+
+use amm_proxy_contract::{ix_builder::pump_buy_with_ata_ix, token::TOKEN_PROGRAM_ID};
+use common::{
+    mocks::{PUMP_PROGRAM, PUMP_TOO_MUCH_SOL_REQUIRED, TOKEN_ACCOUNT_LEN},
+    PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+
+// 新用户尚无该代币的 ATA
+fn setup() -> (TestEnv, Pubkey, PumpCurve) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    env.remove_account(&curve.associated_user);
+    (env, user, curve)
+}
+
+fn buy(env: &TestEnv, user: &Pubkey, curve: &PumpCurve, max_sol_cost: u64) -> Instruction {
+    pump_buy_with_ata_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, max_sol_cost, curve.buy_accounts())
+}
+
+#[test]
+fn first_buy_creates_the_ata_and_repeat_buy_reuses_it() {
+    let (mut env, user, curve) = setup();
+    let (user_before, treasury_before) = (env.lamports(&user), env.lamports(&env.admin));
+
+    let ix = buy(&env, &user, &curve, 2 * SOL);
+    let result = env.process(&ix).assert_ok();
+    assert_eq!(result.cpis_to(&PUMP_PROGRAM).len(), 1);
+    let ata = env.account(&curve.associated_user).unwrap();
+    assert_eq!((ata.owner, ata.data.len()), (TOKEN_PROGRAM_ID, TOKEN_ACCOUNT_LEN));
+    assert_eq!(env.token_balance(&curve.associated_user), SOL - SOL / 100);
+
+    // 用户支付租金、兑换金额与 1% 手续费
+    let rent = Rent::default().minimum_balance(TOKEN_ACCOUNT_LEN);
+    assert_eq!(user_before - env.lamports(&user), rent + SOL);
+    assert_eq!(env.lamports(&env.admin) - treasury_before, SOL / 100);
+
+    // ATA 已存在时跳过创建，不再支付租金
+    let user_before = env.lamports(&user);
+    let ix = buy(&env, &user, &curve, 2 * SOL);
+    env.process(&ix).assert_ok();
+    assert_eq!(user_before - env.lamports(&user), SOL);
+    assert_eq!(env.token_balance(&curve.associated_user), 2 * (SOL - SOL / 100));
+}
+
+#[test]
+fn failed_buy_rolls_back_the_ata() {
+    let (mut env, user, curve) = setup();
+    let user_before = env.lamports(&user);
+
+    let ix = buy(&env, &user, &curve, SOL / 2);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::Custom(PUMP_TOO_MUCH_SOL_REQUIRED));
+    assert!(env.account(&curve.associated_user).is_none());
+    assert_eq!(env.lamports(&user), user_before);
+}
+
+#[test]
+fn destination_must_be_the_users_ata() {
+    let (mut env, user, curve) = setup();
+    let mut ix = buy(&env, &user, &curve, 2 * SOL);
+    let other = Pubkey::new_unique();
+    let forwarded = ix.accounts.iter_mut().find(|meta| meta.pubkey == curve.associated_user).unwrap();
+    *forwarded = AccountMeta::new(other, false);
+
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidSeeds);
+    assert!(env.account(&other).is_none());
+}
@@ -0,0 +1,84 @@
+// Pump 内盘按滑点买入：max_sol_cost 由合约按 bonding curve 的虚拟储备（恒定乘积，向上取整）、
+// Global 中的协议与创建者费率和 slippage_bps 计算，写入内层买入指令的 max_sol_cost
+mod common;
+
+use amm_proxy_contract::ix_builder::pump_buy_with_slippage_ix;
+use common::{
+    mocks::{PUMP_PROGRAM, PUMP_TOO_MUCH_SOL_REQUIRED},
+    PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+// 买入的代币数量（同时作为计费金额）。模拟的 Pump 把扣费后的数量按 lamports 花费并与 max_sol_cost 比较，
+// 测试中的储备使预计成本不低于该花费
+const AMOUNT: u64 = SOL;
+
+fn setup() -> (TestEnv, Pubkey, PumpCurve) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    (env, user, curve)
+}
+
+fn set_reserves(env: &mut TestEnv, curve: &PumpCurve, virtual_tokens: u64, virtual_sol: u64) {
+    let data = &mut env.account_mut(&curve.bonding_curve).data;
+    data[8..16].copy_from_slice(&virtual_tokens.to_le_bytes());
+    data[16..24].copy_from_slice(&virtual_sol.to_le_bytes());
+}
+
+// 返回转发给 Pump 的 max_sol_cost
+fn max_sol_cost(env: &mut TestEnv, user: &Pubkey, curve: &PumpCurve, slippage_bps: u16) -> u64 {
+    let ix = pump_buy_with_slippage_ix(&PROGRAM_ID, &env.fee_accounts(user), AMOUNT, slippage_bps, curve.buy_accounts());
+    let result = env.process(&ix).assert_ok();
+    let cpis = result.cpis_to(&PUMP_PROGRAM);
+    assert_eq!(cpis.len(), 1);
+    assert_eq!(cpis[0].data[8..16], (AMOUNT - AMOUNT / 100).to_le_bytes());
+    u64::from_le_bytes(cpis[0].data[16..24].try_into().unwrap())
+}
+
+#[test]
+fn max_sol_cost_follows_the_curve_reserves() {
+    let (mut env, user, curve) = setup();
+    // 2 SOL 代币 / 1 SOL：成本 1 SOL，加 100 bps 费率 1.01 SOL，加 5% 滑点
+    set_reserves(&mut env, &curve, 2 * SOL, SOL);
+    assert_eq!(max_sol_cost(&mut env, &user, &curve, 0), 1_010_000_000);
+    assert_eq!(max_sol_cost(&mut env, &user, &curve, 500), 1_060_500_000);
+
+    // 成本 999_999_997.000… 向上取整为 999_999_998，加费率后向下取整，100% 滑点时翻倍
+    set_reserves(&mut env, &curve, 3 * SOL + 7, 2 * SOL + 1);
+    assert_eq!(max_sol_cost(&mut env, &user, &curve, 10_000), 2_019_999_994);
+
+    // Global 中没有创建者费率字段时只计协议费率 95 bps
+    env.account_mut(&curve.global).data.truncate(113);
+    assert_eq!(max_sol_cost(&mut env, &user, &curve, 0), 1_009_499_997);
+}
+
+#[test]
+fn max_sol_cost_bounds_the_inner_buy() {
+    let (mut env, user, curve) = setup();
+    // 默认储备（1_073_000_000_000_000 代币 / 30 SOL）下预计成本 27_960，加费率与 5% 滑点为 29_650，
+    // 远低于模拟 Pump 的花费，内层买入按 max_sol_cost 失败并整笔回滚
+    let ix = pump_buy_with_slippage_ix(&PROGRAM_ID, &env.fee_accounts(&user), AMOUNT, 500, curve.buy_accounts());
+    let result = env.process(&ix);
+    assert_eq!(result.cpis_to(&PUMP_PROGRAM)[0].data[16..24], 29_650u64.to_le_bytes());
+    assert_eq!(result.unwrap_err(), ProgramError::Custom(PUMP_TOO_MUCH_SOL_REQUIRED));
+    assert_eq!(env.lamports(&user), 10 * SOL);
+}
+
+#[test]
+fn invalid_inputs_are_rejected_before_the_cpi() {
+    let (mut env, user, curve) = setup();
+    let fee_accounts = env.fee_accounts(&user);
+
+    // 滑点超过 100%
+    let ix = pump_buy_with_slippage_ix(&PROGRAM_ID, &fee_accounts, AMOUNT, 10_001, curve.buy_accounts());
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidInstructionData);
+
+    // 买入数量不小于虚拟代币储备
+    set_reserves(&mut env, &curve, AMOUNT, 30 * SOL);
+    let ix = pump_buy_with_slippage_ix(&PROGRAM_ID, &fee_accounts, AMOUNT, 0, curve.buy_accounts());
+    let result = env.process(&ix);
+    assert!(result.cpis_to(&PUMP_PROGRAM).is_empty());
+    assert_eq!(result.unwrap_err(), ProgramError::InvalidInstructionData);
+    assert_eq!(env.lamports(&user), 10 * SOL);
+}
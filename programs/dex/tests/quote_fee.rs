@@ -0,0 +1,129 @@
+// quote 代币收费：PumpAMM 的 token/USDC 交易对按配置的 quote mint 收取 USDC 手续费，
+// 买入从支付的 USDC 中先行扣除，卖出从收到的 USDC 中收取，均转入协议钱包的 USDC 关联代币账户
+mod common;
+
+use amm_proxy_contract::{
+    instructions::{
+        fee::{FeeRoute, SwapResult},
+        pump::{PUMP_AMM_BUY_ROUTE, PUMP_AMM_SELL_ROUTE},
+    },
+    ix_builder::{pump_amm_buy_ix, pump_amm_sell_ix, set_quote_fee_mint_ix, with_token_fee_accounts},
+    token::{associated_token_address, ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID},
+};
+use borsh::BorshDeserialize;
+use common::{
+    mocks::{pump_amm_pool_address, set_swap_behavior, SwapBehavior, PUMP_AMM_PROGRAM},
+    route_accounts, u64_args, Account, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{instruction::AccountMeta, pubkey::Pubkey, system_program};
+
+const USDC: u64 = 1_000_000;
+const TOKENS: u64 = 5_000_000;
+
+struct Pair {
+    env: TestEnv,
+    user: Pubkey,
+    usdc: Pubkey,
+    user_quote: Pubkey,
+    treasury_quote: Pubkey,
+    keys: Vec<Pubkey>,
+}
+
+// 用户持有 TOKENS 个代币与 100 USDC，池子两侧各持有充足余额
+fn setup() -> Pair {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let usdc = env.add_mint(6, &TOKEN_PROGRAM_ID);
+    let base = env.add_mint(6, &TOKEN_PROGRAM_ID);
+    let ix = set_quote_fee_mint_ix(&PROGRAM_ID, &env.config, &env.admin, &usdc);
+    env.process(&ix).assert_ok();
+
+    let pool = pump_amm_pool_address(&base).0;
+    env.set_account(pool, Account::rent_exempt(vec![0; 64], PUMP_AMM_PROGRAM));
+    let user_base = env.add_ata(&user, &base, TOKENS, &TOKEN_PROGRAM_ID);
+    let user_quote = env.add_ata(&user, &usdc, 100 * USDC, &TOKEN_PROGRAM_ID);
+    let pool_base = env.add_ata(&pool, &base, 1_000 * TOKENS, &TOKEN_PROGRAM_ID);
+    let pool_quote = env.add_ata(&pool, &usdc, 10_000 * USDC, &TOKEN_PROGRAM_ID);
+    let accumulator = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &PUMP_AMM_PROGRAM).0;
+
+    // 外盘买卖转发账户的前 17 个相同，买入另外追加创建者金库与交易量累计账户
+    let mut keys: Vec<Pubkey> = (0..21).map(|_| Pubkey::new_unique()).collect();
+    for (index, key) in [
+        (0, pool),
+        (1, user),
+        (3, base),
+        (4, usdc),
+        (5, user_base),
+        (6, user_quote),
+        (7, pool_base),
+        (8, pool_quote),
+        (11, TOKEN_PROGRAM_ID),
+        (12, TOKEN_PROGRAM_ID),
+        (13, system_program::id()),
+        (14, ASSOCIATED_TOKEN_PROGRAM_ID),
+        (16, PUMP_AMM_PROGRAM),
+        (19, accumulator(&[b"global_volume_accumulator"])),
+        (20, accumulator(&[b"user_volume_accumulator", user.as_ref()])),
+    ] {
+        keys[index] = key;
+    }
+    let treasury_quote = associated_token_address(&env.admin, &usdc, &TOKEN_PROGRAM_ID);
+    Pair { env, user, usdc, user_quote, treasury_quote, keys }
+}
+
+impl Pair {
+    fn forwarded(&self, route: &FeeRoute, len: usize) -> Vec<AccountMeta> {
+        route_accounts(route, len, &self.user)
+            .into_iter()
+            .zip(&self.keys)
+            .map(|(meta, key)| AccountMeta { pubkey: *key, ..meta })
+            .collect()
+    }
+}
+
+#[test]
+fn buy_deducts_the_fee_from_the_quote_input() {
+    let mut pair = setup();
+    let (sol_before, treasury_before) = (pair.env.lamports(&pair.user), pair.env.lamports(&pair.env.admin));
+
+    let forwarded = pair.forwarded(&PUMP_AMM_BUY_ROUTE, 21);
+    let ix = pump_amm_buy_ix(&PROGRAM_ID, &pair.env.fee_accounts(&pair.user), 10 * USDC, 20 * USDC, forwarded);
+    let ix = with_token_fee_accounts(ix, &pair.env.admin, &pair.usdc, &TOKEN_PROGRAM_ID);
+    let result = pair.env.process(&ix).assert_ok();
+
+    // 1% 的 USDC 转入协议钱包新建的 USDC 关联代币账户，内层数量扣除手续费
+    let fee = 10 * USDC / 100;
+    assert_eq!(pair.env.token_balance(&pair.treasury_quote), fee);
+    assert_eq!(pair.env.token_balance(&pair.user_quote), 100 * USDC - fee);
+    let cpis = result.cpis_to(&PUMP_AMM_PROGRAM);
+    assert_eq!(cpis[0].data[8..], u64_args(&[10 * USDC - fee, 20 * USDC])[..]);
+    let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!((swap.fee, swap.remaining, swap.breakdown.treasury), (fee, 10 * USDC - fee, fee));
+
+    // 不收取 SOL 手续费，用户只支付 USDC 关联代币账户的租金
+    assert_eq!(pair.env.lamports(&pair.env.admin), treasury_before);
+    assert_eq!(sol_before - pair.env.lamports(&pair.user), pair.env.lamports(&pair.treasury_quote));
+}
+
+#[test]
+fn sell_takes_the_fee_from_the_quote_received() {
+    let mut pair = setup();
+    let received = 50 * USDC;
+    set_swap_behavior(SwapBehavior { output: Some(received), ..Default::default() });
+    let treasury_before = pair.env.lamports(&pair.env.admin);
+
+    let forwarded = pair.forwarded(&PUMP_AMM_SELL_ROUTE, 17);
+    let ix = pump_amm_sell_ix(&PROGRAM_ID, &pair.env.fee_accounts(&pair.user), TOKENS, 1, forwarded);
+    let ix = with_token_fee_accounts(ix, &pair.env.admin, &pair.usdc, &TOKEN_PROGRAM_ID);
+    let result = pair.env.process(&ix).assert_ok();
+
+    // 代币数量原样转发，手续费按实际收到的 USDC 计算
+    let fee = received / 100;
+    let cpis = result.cpis_to(&PUMP_AMM_PROGRAM);
+    assert_eq!(cpis[0].data[8..], u64_args(&[TOKENS, 1])[..]);
+    assert_eq!(pair.env.token_balance(&pair.treasury_quote), fee);
+    assert_eq!(pair.env.token_balance(&pair.user_quote), 100 * USDC + received - fee);
+    assert_eq!(pair.env.lamports(&pair.env.admin), treasury_before);
+    let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!((swap.amount_out, swap.fee, swap.remaining), (received, fee, received - fee));
+}
@@ -0,0 +1,103 @@
+// Raydium AMM v4：带手续费的 swapBaseOut 买入按 max_amount_in 收费并改写内层 max_amount_in，
+// 不收费的 swapBaseIn 路由原样转发，SwapResult 的 amount_out 为目标账户的实际余额变化
+mod common;
+
+use amm_proxy_contract::{
+    instructions::fee::SwapResult,
+    instructions::raydium::{RAYDIUM_BUY_BASE_OUT_ROUTE, RAYDIUM_BUY_BASE_OUT_SELECTOR, RAYDIUM_SELL_SELECTOR},
+    ix_builder::{raydium_buy_base_out_ix, raydium_buy_ix, raydium_sell_ix},
+};
+use borsh::BorshDeserialize;
+use common::{
+    mocks::{set_swap_behavior, SwapBehavior, RAYDIUM_AMM_V4_PROGRAM, RAYDIUM_SWAP_BASE_IN, RAYDIUM_SWAP_BASE_OUT},
+    route_accounts, selector_data, u64_args, Account, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{instruction::AccountMeta, pubkey::Pubkey};
+
+// 17 个 swap 账户，末尾为 Raydium AMM v4 程序
+fn swap_accounts(user: &Pubkey) -> Vec<AccountMeta> {
+    let mut accounts = route_accounts(&RAYDIUM_BUY_BASE_OUT_ROUTE, 17, user);
+    accounts.push(AccountMeta::new_readonly(RAYDIUM_AMM_V4_PROGRAM, false));
+    accounts
+}
+
+#[test]
+fn base_out_buy_patches_max_amount_in() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let treasury_before = env.lamports(&env.admin);
+    let (max_amount_in, amount_out) = (2 * SOL, 123_456);
+
+    let forwarded = swap_accounts(&user);
+    let ix = raydium_buy_base_out_ix(&PROGRAM_ID, &env.fee_accounts(&user), max_amount_in, amount_out, forwarded.clone());
+    assert_eq!(
+        ix.data,
+        selector_data(RAYDIUM_BUY_BASE_OUT_SELECTOR, &[&u64_args(&[max_amount_in, max_amount_in, amount_out])])
+    );
+    let result = env.process(&ix).assert_ok();
+
+    // 内层数据为 [swapBaseOut 指令号][max_amount_in][amount_out]，只改写 max_amount_in
+    let fee = max_amount_in / 100;
+    let cpis = result.cpis_to(&RAYDIUM_AMM_V4_PROGRAM);
+    assert_eq!(cpis.len(), 1);
+    assert_eq!(cpis[0].data[0], RAYDIUM_SWAP_BASE_OUT);
+    assert_eq!(cpis[0].data[1..], u64_args(&[max_amount_in - fee, amount_out])[..]);
+    // 目标程序账户不转发给内层指令
+    let keys = |metas: &[AccountMeta]| metas.iter().map(|meta| meta.pubkey).collect::<Vec<_>>();
+    assert_eq!(keys(&cpis[0].accounts), keys(&forwarded[..17]));
+
+    assert_eq!(env.lamports(&env.admin) - treasury_before, fee);
+    let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!((swap.route_id, swap.fee, swap.remaining), (*RAYDIUM_BUY_BASE_OUT_SELECTOR, fee, max_amount_in - fee));
+}
+
+#[test]
+fn base_in_buy_forwards_swap_base_in_without_fee() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let treasury_before = env.lamports(&env.admin);
+
+    let mut accounts = vec![AccountMeta::new_readonly(RAYDIUM_AMM_V4_PROGRAM, false)];
+    accounts.extend((0..7).map(|_| AccountMeta::new(Pubkey::new_unique(), false)));
+    accounts.push(AccountMeta::new_readonly(user, true));
+    let ix = raydium_buy_ix(&PROGRAM_ID, SOL, 1, accounts);
+    let result = env.process(&ix).assert_ok();
+
+    let cpis = result.cpis_to(&RAYDIUM_AMM_V4_PROGRAM);
+    assert_eq!(cpis.len(), 1);
+    assert_eq!(cpis[0].data[0], RAYDIUM_SWAP_BASE_IN);
+    assert_eq!(cpis[0].data[1..], u64_args(&[SOL, 1])[..]);
+    assert_eq!(cpis[0].accounts.len(), 17);
+    assert_eq!(env.lamports(&env.admin), treasury_before);
+}
+
+#[test]
+fn legacy_sell_reports_the_measured_output() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(SOL);
+    // amm 账户归 Raydium 所有并持有待转出的 SOL，卖出所得转入用户钱包
+    let amm = Pubkey::new_unique();
+    env.set_account(amm, Account::rent_exempt(vec![0; 64], RAYDIUM_AMM_V4_PROGRAM));
+    env.fund(&amm, SOL);
+    set_swap_behavior(SwapBehavior { output: Some(SOL / 4), ..Default::default() });
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(RAYDIUM_AMM_V4_PROGRAM, false),
+        AccountMeta::new_readonly(Pubkey::new_unique(), false),
+        AccountMeta::new(amm, false),
+    ];
+    accounts.extend((0..4).map(|_| AccountMeta::new(Pubkey::new_unique(), false)));
+    accounts.push(AccountMeta::new(user, false));
+    accounts.push(AccountMeta::new_readonly(user, true));
+    let ix = raydium_sell_ix(&PROGRAM_ID, 5_000_000, 1, accounts);
+    let result = env.process(&ix).assert_ok();
+
+    let cpis = result.cpis_to(&RAYDIUM_AMM_V4_PROGRAM);
+    assert_eq!(cpis[0].data[1..], u64_args(&[5_000_000, 1])[..]);
+    assert_eq!(env.lamports(&user), SOL + SOL / 4);
+    let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!(
+        (swap.route_id, swap.amount_in, swap.amount_out, swap.fee, swap.remaining),
+        (*RAYDIUM_SELL_SELECTOR, 5_000_000, SOL / 4, 0, 5_000_000)
+    );
+}
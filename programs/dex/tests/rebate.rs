@@ -0,0 +1,84 @@
+// 全局返还：实际收取的手续费为 fee * (10_000 - rebate_bps) / 10_000，返还部分留在用户的兑换金额中；
+// 大额返还：金额大于 whale_threshold 的交易再按 whale_rebate_bps 减免
+mod common;
+
+use amm_proxy_contract::{
+    instructions::fee::SwapResult,
+    ix_builder::{pump_buy_ix, set_rebate_ix, set_whale_rebate_ix},
+};
+use borsh::BorshDeserialize;
+use common::{TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::program_error::ProgramError;
+
+const REBATE_BPS: u16 = 2_500;
+
+#[test]
+fn rebate_nets_down_the_charged_fee() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_rebate_ix(&PROGRAM_ID, &env.config, &env.admin, REBATE_BPS);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().rebate_bps, REBATE_BPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+
+    let treasury_before = env.lamports(&env.admin);
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let result = env.process(&ix).assert_ok();
+
+    let fee = SOL / 100;
+    let net_fee = fee * (10_000 - REBATE_BPS as u64) / 10_000;
+    assert_eq!(env.lamports(&env.admin) - treasury_before, net_fee);
+    let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!((swap.fee, swap.remaining), (net_fee, SOL - net_fee));
+    assert_eq!(10 * SOL - env.lamports(&user), SOL);
+}
+
+#[test]
+fn rebate_is_capped_at_the_whole_fee() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_rebate_ix(&PROGRAM_ID, &env.config, &env.admin, 10_001);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidInstructionData);
+    assert_eq!(env.config_state().rebate_bps, 0);
+
+    let mut config = env.config_state();
+    config.rebate_bps = 3_333;
+    assert_eq!(config.fee_after_rebate(10_000), 6_667);
+    config.rebate_bps = 10_000;
+    assert_eq!(config.fee_after_rebate(10_000), 0);
+}
+
+#[test]
+fn whale_rebate_applies_above_the_threshold() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_whale_rebate_ix(&PROGRAM_ID, &env.config, &env.admin, SOL, 5_000);
+    env.process(&ix).assert_ok();
+    let config = env.config_state();
+    assert_eq!((config.whale_threshold, config.whale_rebate_bps), (SOL, 5_000));
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+
+    // 恰好等于阈值时按原费率收取，超过阈值时费率减半
+    for (amount, fee) in [(SOL, SOL / 100), (SOL + 1, (SOL + 1) / 200), (2 * SOL, 2 * SOL / 200)] {
+        let treasury_before = env.lamports(&env.admin);
+        let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), amount, 2 * amount, curve.buy_accounts());
+        let result = env.process(&ix).assert_ok();
+        let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+        assert_eq!(swap.fee, fee, "{amount}");
+        assert_eq!(env.lamports(&env.admin) - treasury_before, fee);
+    }
+}
+
+#[test]
+fn whale_rebate_is_capped_at_the_whole_fee() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_whale_rebate_ix(&PROGRAM_ID, &env.config, &env.admin, SOL, 10_001);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidInstructionData);
+
+    // 阈值为 0 表示关闭
+    let mut config = env.config_state();
+    config.whale_rebate_bps = 5_000;
+    assert_eq!(config.fee_after_whale_rebate(100 * SOL, 1_000), 1_000);
+    config.whale_threshold = SOL;
+    assert_eq!(config.fee_after_whale_rebate(SOL, 1_000), 1_000);
+    assert_eq!(config.fee_after_whale_rebate(SOL + 1, 1_000), 500);
+}
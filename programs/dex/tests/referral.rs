@@ -0,0 +1,183 @@
+// 推荐码：登记、带推荐码交易分成、撤销后拒绝使用、更新后恢复、未登记推荐码回退到协议，
+// 以及推荐人、回购与协议三方分成的明细
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    events::{ReferralFallback, REFERRAL_FALLBACK_EVENT},
+    instructions::fee::SwapResult,
+    instructions::referral::referral_address,
+    ix_builder::{
+        pump_buy_ix, register_referral_ix, revoke_referral_ix, set_buyback_ix, update_referral_ix, with_referral,
+    },
+    state::ReferralState,
+};
+use borsh::BorshDeserialize;
+use common::{runtime::TxResult, PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+const CODE: u32 = 7;
+
+fn referral_buy(env: &TestEnv, user: &Pubkey, curve: &PumpCurve, referrer: &Pubkey) -> Instruction {
+    referral_buy_amount(env, user, curve, referrer, SOL)
+}
+
+fn referral_buy_amount(env: &TestEnv, user: &Pubkey, curve: &PumpCurve, referrer: &Pubkey, amount: u64) -> Instruction {
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), amount, 2 * amount, curve.buy_accounts());
+    with_referral(&PROGRAM_ID, ix, CODE, referrer)
+}
+
+fn fallback_events(result: &TxResult) -> Vec<ReferralFallback> {
+    result
+        .events(REFERRAL_FALLBACK_EVENT)
+        .iter()
+        .map(|data| ReferralFallback::try_from_slice(data).unwrap())
+        .collect()
+}
+
+#[test]
+fn registered_code_splits_the_fee_until_revoked() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let referrer = env.wallet(SOL);
+
+    let ix = register_referral_ix(&PROGRAM_ID, &env.config, &env.admin, CODE, &referrer, 2_000);
+    env.process(&ix).assert_ok();
+    let referral = ReferralState::try_from_slice(env.data(&referral_address(&PROGRAM_ID, CODE).0)).unwrap();
+    assert_eq!((referral.wallet, referral.share_bps, referral.revoked), (referrer, 2_000, false));
+
+    // 1% 手续费中 20% 归推荐人，其余归协议
+    let treasury_before = env.lamports(&env.admin);
+    let ix = referral_buy(&env, &user, &curve, &referrer);
+    let result = env.process(&ix).assert_ok();
+    let (fee, referrer_fee) = (SOL / 100, SOL / 100 * 2_000 / 10_000);
+    assert_eq!(env.lamports(&referrer) - SOL, referrer_fee);
+    assert_eq!(env.lamports(&env.admin) - treasury_before, fee - referrer_fee);
+    let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!((swap.fee, swap.breakdown.referrer, swap.breakdown.treasury), (fee, referrer_fee, fee - referrer_fee));
+
+    // 撤销后同一推荐码的交易被拒绝，余额不变
+    let ix = revoke_referral_ix(&PROGRAM_ID, &env.config, &env.admin, CODE);
+    env.process(&ix).assert_ok();
+    let (user_before, referrer_before) = (env.lamports(&user), env.lamports(&referrer));
+    let ix = referral_buy(&env, &user, &curve, &referrer);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::ReferralRevoked.into());
+    assert_eq!((env.lamports(&user), env.lamports(&referrer)), (user_before, referrer_before));
+
+    // 更新推荐码同时恢复使用
+    let ix = update_referral_ix(&PROGRAM_ID, &env.config, &env.admin, CODE, &referrer, 1_000);
+    env.process(&ix).assert_ok();
+    let ix = referral_buy(&env, &user, &curve, &referrer);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.lamports(&referrer) - referrer_before, fee * 1_000 / 10_000);
+}
+
+#[test]
+fn registering_requires_the_admin() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let stranger = env.wallet(SOL);
+    let referrer = env.wallet(SOL);
+
+    let ix = register_referral_ix(&PROGRAM_ID, &env.config, &stranger, CODE, &referrer, 2_000);
+    assert!(env.process(&ix).result.is_err());
+    assert!(env.account(&referral_address(&PROGRAM_ID, CODE).0).is_none());
+}
+
+#[test]
+fn closed_referrer_account_falls_back_to_the_treasury() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    // 推荐人钱包已关闭（0 lamports），5% 分成不足免租金额，无法转入
+    let referrer = Pubkey::new_unique();
+    let ix = register_referral_ix(&PROGRAM_ID, &env.config, &env.admin, CODE, &referrer, 500);
+    env.process(&ix).assert_ok();
+
+    let treasury_before = env.lamports(&env.admin);
+    let ix = referral_buy_amount(&env, &user, &curve, &referrer, SOL / 10);
+    let result = env.process(&ix).assert_ok();
+
+    let fee = SOL / 10 / 100;
+    assert_eq!(env.lamports(&referrer), 0);
+    assert_eq!(env.lamports(&env.admin) - treasury_before, fee);
+    let events = fallback_events(&result);
+    assert_eq!(events.len(), 1);
+    assert_eq!((events[0].code, events[0].referrer, events[0].fee), (CODE, referrer, fee));
+    let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!((swap.breakdown.referrer, swap.breakdown.treasury), (0, fee));
+}
+
+#[test]
+fn unusable_referrer_account_does_not_abort_the_trade() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let referrer = env.wallet(SOL);
+    let ix = register_referral_ix(&PROGRAM_ID, &env.config, &env.admin, CODE, &referrer, 2_000);
+    env.process(&ix).assert_ok();
+
+    // 推荐人账户以只读传入，或传入的不是登记的推荐人钱包
+    let mut readonly = referral_buy(&env, &user, &curve, &referrer);
+    readonly.accounts.last_mut().unwrap().is_writable = false;
+    let wrong_wallet = referral_buy(&env, &user, &curve, &Pubkey::new_unique());
+
+    for ix in [readonly, wrong_wallet] {
+        let treasury_before = env.lamports(&env.admin);
+        let result = env.process(&ix).assert_ok();
+        assert_eq!(fallback_events(&result).len(), 1);
+        assert_eq!(env.lamports(&env.admin) - treasury_before, SOL / 100);
+        assert_eq!(env.lamports(&referrer), SOL);
+    }
+}
+
+#[test]
+fn unknown_code_falls_back_to_the_treasury() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let referrer = env.wallet(SOL);
+
+    // 推荐码 PDA 未创建，交易照常完成，手续费全部归协议
+    let treasury_before = env.lamports(&env.admin);
+    let ix = referral_buy(&env, &user, &curve, &referrer);
+    let result = env.process(&ix).assert_ok();
+    let fee = SOL / 100;
+    assert_eq!(env.lamports(&env.admin) - treasury_before, fee);
+    assert_eq!(env.lamports(&referrer), SOL);
+    let events = fallback_events(&result);
+    assert_eq!(events.len(), 1);
+    assert_eq!((events[0].code, events[0].referrer, events[0].fee), (CODE, Pubkey::default(), fee));
+    let swap = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap();
+    assert_eq!((swap.breakdown.referrer, swap.breakdown.treasury), (0, fee));
+}
+
+#[test]
+fn three_way_split_is_returned_in_the_breakdown() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let (referrer, buyback) = (env.wallet(SOL), env.wallet(SOL));
+    let ix = register_referral_ix(&PROGRAM_ID, &env.config, &env.admin, CODE, &referrer, 2_000);
+    env.process(&ix).assert_ok();
+    let ix = set_buyback_ix(&PROGRAM_ID, &env.config, &env.admin, &buyback, 3_000);
+    env.process(&ix).assert_ok();
+
+    let treasury_before = env.lamports(&env.admin);
+    let mut ix = referral_buy(&env, &user, &curve, &referrer);
+    ix.accounts.push(AccountMeta::new(buyback, false));
+    let result = env.process(&ix).assert_ok();
+
+    // 1% 手续费中 20% 归推荐人、30% 归回购钱包，其余归协议
+    let fee = SOL / 100;
+    let (referrer_fee, buyback_fee) = (fee * 2_000 / 10_000, fee * 3_000 / 10_000);
+    let treasury_fee = fee - referrer_fee - buyback_fee;
+    let breakdown = SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap().breakdown;
+    assert_eq!((breakdown.treasury, breakdown.referrer, breakdown.buyback), (treasury_fee, referrer_fee, buyback_fee));
+    assert_eq!(env.lamports(&env.admin) - treasury_before, treasury_fee);
+    assert_eq!(env.lamports(&referrer) - SOL, referrer_fee);
+    assert_eq!(env.lamports(&buyback) - SOL, buyback_fee);
+}
@@ -0,0 +1,111 @@
+// 中继交易：中继者支付手续费，用户通过同一交易中的 ed25519 验签指令授权本笔路由
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::nonce::nonce_address,
+    ix_builder::{pump_buy_ix, relay_message, set_replay_window_ix, with_relayed_user},
+};
+use common::{mocks::ED25519_INVALID_SIGNATURE, PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+use solana_ed25519_program::new_ed25519_instruction_with_signature;
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+
+fn keypair(seed: u8) -> Keypair {
+    let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+    let public = PublicKey::from(&secret);
+    Keypair { secret, public }
+}
+
+fn ed25519_ix(signer: &Keypair, message: &[u8]) -> Instruction {
+    new_ed25519_instruction_with_signature(message, &signer.sign(message).to_bytes(), &signer.public.to_bytes())
+}
+
+struct Relay {
+    env: TestEnv,
+    relayer: Pubkey,
+    user: Keypair,
+    user_key: Pubkey,
+    curve: PumpCurve,
+}
+
+fn setup() -> Relay {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_replay_window_ix(&PROGRAM_ID, &env.config, &env.admin, 1_000, 8);
+    env.process(&ix).assert_ok();
+    let relayer = env.wallet(10 * SOL);
+    let user = keypair(7);
+    let user_key = Pubkey::new_from_array(user.public.to_bytes());
+    env.fund(&user_key, 10 * SOL);
+    let curve = env.pump_curve(&user_key, 0);
+    Relay { env, relayer, user, user_key, curve }
+}
+
+impl Relay {
+    // 中继者为手续费支付者的内盘买入（Pump 自身仍要求用户签名）
+    fn route_ix(&self) -> Instruction {
+        pump_buy_ix(&PROGRAM_ID, &self.env.fee_accounts(&self.relayer), SOL, 2 * SOL, self.curve.buy_accounts())
+    }
+
+    fn relayed(&self, nonce: u64) -> (Instruction, Vec<u8>) {
+        let route_ix = self.route_ix();
+        let message = relay_message(&PROGRAM_ID, &route_ix, nonce);
+        (with_relayed_user(&PROGRAM_ID, route_ix, &self.user_key, nonce), message)
+    }
+}
+
+#[test]
+fn user_signature_authorizes_a_relayed_swap() {
+    let mut relay = setup();
+    let treasury_before = relay.env.lamports(&relay.env.admin);
+    let (ix, message) = relay.relayed(1);
+
+    let result = relay.env.process_transaction(&[ed25519_ix(&relay.user, &message), ix]);
+    result.assert_ok();
+
+    // 手续费与用户 nonce PDA 的租金由中继者支付，用户只支付扣费后的兑换金额
+    let fee = SOL / 100;
+    let nonce_rent = relay.env.lamports(&nonce_address(&PROGRAM_ID, &relay.user_key).0);
+    assert_eq!(relay.env.lamports(&relay.env.admin) - treasury_before, fee);
+    assert_eq!(relay.env.lamports(&relay.user_key), 10 * SOL - (SOL - fee));
+    assert_eq!(10 * SOL - relay.env.lamports(&relay.relayer), fee + nonce_rent);
+}
+
+#[test]
+fn invalid_user_signature_fails_the_transaction() {
+    let mut relay = setup();
+    let (ix, message) = relay.relayed(1);
+
+    // 其他密钥对同一消息的签名，冒充用户公钥
+    let mut forged = ed25519_ix(&keypair(8), &message);
+    forged.data[16..48].copy_from_slice(&relay.user.public.to_bytes());
+    let result = relay.env.process_transaction(&[forged, ix]);
+    assert_eq!(result.unwrap_err(), ProgramError::Custom(ED25519_INVALID_SIGNATURE));
+    assert_eq!(relay.env.lamports(&relay.relayer), 10 * SOL);
+}
+
+#[test]
+fn signature_over_a_different_message_is_not_an_authorization() {
+    let mut relay = setup();
+    let (ix, _) = relay.relayed(1);
+
+    // 用户签的是另一个 nonce 的消息
+    let other = relay_message(&PROGRAM_ID, &relay.route_ix(), 2);
+    let result = relay.env.process_transaction(&[ed25519_ix(&relay.user, &other), ix.clone()]);
+    assert_eq!(result.unwrap_err(), MyError::MissingUserSignature.into());
+
+    // 交易中没有验签指令
+    assert_eq!(relay.env.process(&ix).unwrap_err(), MyError::MissingUserSignature.into());
+    assert_eq!(relay.env.lamports(&relay.relayer), 10 * SOL);
+}
+
+#[test]
+fn authorization_cannot_be_replayed() {
+    let mut relay = setup();
+    let (ix, message) = relay.relayed(1);
+    let transaction = [ed25519_ix(&relay.user, &message), ix];
+
+    relay.env.process_transaction(&transaction).assert_ok();
+    let result = relay.env.process_transaction(&transaction);
+    assert_eq!(result.unwrap_err(), MyError::NonceReused.into());
+}
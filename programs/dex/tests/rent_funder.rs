@@ -0,0 +1,101 @@
+// 交易中按需创建的 PDA 的租金：默认由手续费支付者支付；附带已开启且余额充足的共享租金账户时由其垫付，
+// 垫付的租金在交易结束前以协议所得的手续费归还，共享租金账户余额不变
+mod common;
+
+use amm_proxy_contract::{
+    instructions::{rent_funder::rent_funder_address, wallet::wallet_address},
+    ix_builder::{
+        close_rent_funder_ix, fund_rent_funder_ix, open_rent_funder_ix, pump_buy_ix, set_trade_cooldown_ix,
+        with_rent_funder, with_wallet_counter,
+    },
+    state::WalletState,
+};
+use common::{TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{instruction::Instruction, pubkey::Pubkey, rent::Rent};
+
+const FEE: u64 = SOL / 100;
+
+fn wallet_rent() -> u64 {
+    Rent::default().minimum_balance(WalletState::LEN)
+}
+
+// 开启交易冷却后，首次带钱包账户的买入会创建钱包 PDA，且照常收费
+fn counted_buy(env: &mut TestEnv, user: &Pubkey, rent_funder: bool) -> Instruction {
+    let curve = env.pump_curve(user, 0);
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, curve.buy_accounts());
+    let ix = with_wallet_counter(&PROGRAM_ID, ix, user);
+    match rent_funder {
+        true => with_rent_funder(&PROGRAM_ID, ix),
+        false => ix,
+    }
+}
+
+// 开启交易冷却，按需开启共享租金账户并充值 lamports
+fn setup(funded: Option<u64>) -> (TestEnv, Pubkey) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let (config, admin) = (env.config, env.admin);
+    env.process(&set_trade_cooldown_ix(&PROGRAM_ID, &config, &admin, 1)).assert_ok();
+    if let Some(lamports) = funded {
+        env.process(&open_rent_funder_ix(&PROGRAM_ID, &config, &admin)).assert_ok();
+        env.process(&fund_rent_funder_ix(&PROGRAM_ID, &admin, lamports)).assert_ok();
+    }
+    let user = env.wallet(10 * SOL);
+    (env, user)
+}
+
+// 返回交易者花费、协议收到的 lamports，并确认钱包 PDA 已按免租金额创建
+fn trade(env: &mut TestEnv, user: &Pubkey, rent_funder: bool) -> (u64, u64) {
+    let (user_before, treasury_before) = (env.lamports(user), env.lamports(&env.admin));
+    let ix = counted_buy(env, user, rent_funder);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.lamports(&wallet_address(&PROGRAM_ID, user).0), wallet_rent());
+    (user_before - env.lamports(user), env.lamports(&env.admin) - treasury_before)
+}
+
+#[test]
+fn fee_payer_funds_the_pda_by_default() {
+    let (mut env, user) = setup(None);
+    assert_eq!(trade(&mut env, &user, false), (SOL + wallet_rent(), FEE));
+}
+
+#[test]
+fn shared_funder_fronts_the_rent_and_is_repaid_from_the_fee() {
+    let (mut env, user) = setup(Some(SOL));
+    let funder = rent_funder_address(&PROGRAM_ID).0;
+    let funder_before = env.lamports(&funder);
+    assert_eq!(trade(&mut env, &user, true), (SOL, FEE - wallet_rent()));
+    assert_eq!(env.lamports(&funder), funder_before);
+
+    // 已开启但未附带时仍由支付者支付
+    let other = env.wallet(10 * SOL);
+    assert_eq!(trade(&mut env, &other, false), (SOL + wallet_rent(), FEE));
+}
+
+#[test]
+fn short_funder_falls_back_to_the_fee_payer() {
+    let (mut env, user) = setup(Some(wallet_rent() - 1));
+    let funder = rent_funder_address(&PROGRAM_ID).0;
+    let funder_before = env.lamports(&funder);
+    assert_eq!(trade(&mut env, &user, true), (SOL + wallet_rent(), FEE));
+    assert_eq!(env.lamports(&funder), funder_before);
+}
+
+#[test]
+fn only_the_admin_opens_and_closes_the_funder() {
+    let (mut env, _) = setup(None);
+    let (config, admin) = (env.config, env.admin);
+    let funder = rent_funder_address(&PROGRAM_ID).0;
+    let stranger = env.wallet(SOL);
+    assert!(env.process(&open_rent_funder_ix(&PROGRAM_ID, &config, &stranger)).result.is_err());
+    assert!(env.account(&funder).is_none());
+
+    env.process(&open_rent_funder_ix(&PROGRAM_ID, &config, &admin)).assert_ok();
+    env.process(&fund_rent_funder_ix(&PROGRAM_ID, &stranger, SOL / 2)).assert_ok();
+    assert!(env.process(&close_rent_funder_ix(&PROGRAM_ID, &config, &stranger)).result.is_err());
+
+    // 关闭时剩余 lamports 全部退还给管理员
+    let (admin_before, refund) = (env.lamports(&env.admin), env.lamports(&funder));
+    env.process(&close_rent_funder_ix(&PROGRAM_ID, &config, &admin)).assert_ok();
+    assert!(env.account(&funder).is_none());
+    assert_eq!(env.lamports(&env.admin) - admin_before, refund);
+}
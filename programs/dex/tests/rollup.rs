@@ -0,0 +1,62 @@
+// 按 epoch 汇总手续费：每个 epoch 的首笔交易创建汇总 PDA，跨 epoch 的交易分别累计
+mod common;
+
+use amm_proxy_contract::{
+    instructions::rollup::rollup_address,
+    ix_builder::{pump_buy_ix, set_epoch_rollup_ix, with_epoch_rollup},
+    state::FeeRollupState,
+};
+use borsh::BorshDeserialize;
+use common::{PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{epoch_schedule::EpochSchedule, instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+
+fn setup() -> (TestEnv, Pubkey, PumpCurve) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_epoch_rollup_ix(&PROGRAM_ID, &env.config, &env.admin, true);
+    env.process(&ix).assert_ok();
+    assert!(env.config_state().epoch_rollup);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    (env, user, curve)
+}
+
+fn buy(env: &TestEnv, user: &Pubkey, curve: &PumpCurve, amount: u64, epoch: u64) -> Instruction {
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), amount, 2 * amount, curve.buy_accounts());
+    with_epoch_rollup(&PROGRAM_ID, ix, epoch)
+}
+
+fn rollup(env: &TestEnv, epoch: u64) -> FeeRollupState {
+    FeeRollupState::try_from_slice(env.data(&rollup_address(&PROGRAM_ID, epoch).0)).unwrap()
+}
+
+#[test]
+fn fees_accrue_separately_per_epoch() {
+    let (mut env, user, curve) = setup();
+    assert!(env.account(&rollup_address(&PROGRAM_ID, 0).0).is_none());
+
+    for amount in [SOL, SOL / 2] {
+        let ix = buy(&env, &user, &curve, amount, 0);
+        env.process(&ix).assert_ok();
+    }
+    let first = rollup(&env, 0);
+    assert_eq!((first.epoch, first.total_fees, first.trade_count), (0, SOL / 100 + SOL / 200, 2));
+
+    // 下一个 epoch 的首笔交易创建新的汇总账户，上一 epoch 的汇总不变
+    env.warp_to_slot(EpochSchedule::without_warmup().slots_per_epoch);
+    let ix = buy(&env, &user, &curve, 2 * SOL, 1);
+    env.process(&ix).assert_ok();
+    let second = rollup(&env, 1);
+    assert_eq!((second.epoch, second.total_fees, second.trade_count), (1, 2 * SOL / 100, 1));
+    assert_eq!(rollup(&env, 0).total_fees, first.total_fees);
+}
+
+#[test]
+fn rollup_account_must_match_the_current_epoch() {
+    let (mut env, user, curve) = setup();
+    env.warp_to_slot(EpochSchedule::without_warmup().slots_per_epoch);
+
+    // 附带的是上一 epoch 的汇总账户
+    let ix = buy(&env, &user, &curve, SOL, 0);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::NotEnoughAccountKeys);
+    assert!(env.account(&rollup_address(&PROGRAM_ID, 0).0).is_none());
+}
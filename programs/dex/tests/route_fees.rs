@@ -0,0 +1,71 @@
+// 按路由覆盖费率：命中覆盖的路由按覆盖费率收费，其余路由使用全局费率
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::{
+        fee::SwapResult,
+        pump::PUMP_SELECTOR,
+        raydium::{RAYDIUM_BUY_BASE_OUT_ROUTE, RAYDIUM_BUY_BASE_OUT_SELECTOR},
+    },
+    ix_builder::{pump_buy_ix, raydium_buy_base_out_ix, set_route_fees_ix},
+    state::MAX_ROUTE_FEE_OVERRIDES,
+};
+use borsh::BorshDeserialize;
+use common::{
+    mocks::RAYDIUM_AMM_V4_PROGRAM, route_accounts, runtime::TxResult, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID,
+    SOL,
+};
+use solana_program::{instruction::AccountMeta, program_error::ProgramError};
+
+// Pump 50 bps、Raydium 80 bps
+const PUMP_RATE_PIPS: u32 = 5_000;
+const RAYDIUM_RATE_PIPS: u32 = 8_000;
+
+fn charged_fee(result: TxResult) -> u64 {
+    SwapResult::try_from_slice(&result.assert_ok().return_data.unwrap().1).unwrap().fee
+}
+
+#[test]
+fn each_route_uses_its_override_or_the_global_rate() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let overrides = [(*PUMP_SELECTOR, PUMP_RATE_PIPS), (*RAYDIUM_BUY_BASE_OUT_SELECTOR, RAYDIUM_RATE_PIPS)];
+    let ix = set_route_fees_ix(&PROGRAM_ID, &env.config, &env.admin, &overrides);
+    env.process(&ix).assert_ok();
+    let config = env.config_state();
+    assert_eq!(config.fee_rate_for(PUMP_SELECTOR), PUMP_RATE_PIPS);
+    assert_eq!(config.fee_rate_for(RAYDIUM_BUY_BASE_OUT_SELECTOR), RAYDIUM_RATE_PIPS);
+
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    assert_eq!(charged_fee(env.process(&ix)), SOL / 200);
+
+    let mut forwarded = route_accounts(&RAYDIUM_BUY_BASE_OUT_ROUTE, 17, &user);
+    forwarded.push(AccountMeta::new_readonly(RAYDIUM_AMM_V4_PROGRAM, false));
+    let ix = raydium_buy_base_out_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 1, forwarded);
+    assert_eq!(charged_fee(env.process(&ix)), SOL * 8 / 1_000);
+
+    // 清空覆盖后回到全局费率
+    let ix = set_route_fees_ix(&PROGRAM_ID, &env.config, &env.admin, &[]);
+    env.process(&ix).assert_ok();
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    assert_eq!(charged_fee(env.process(&ix)), SOL / 100);
+}
+
+#[test]
+fn invalid_overrides_are_rejected() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+
+    let ix = set_route_fees_ix(&PROGRAM_ID, &env.config, &env.admin, &[(*b"zzzzzzzz", PUMP_RATE_PIPS)]);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::UnsupportedRoute.into());
+
+    let overrides = vec![(*PUMP_SELECTOR, PUMP_RATE_PIPS); MAX_ROUTE_FEE_OVERRIDES + 1];
+    let ix = set_route_fees_ix(&PROGRAM_ID, &env.config, &env.admin, &overrides);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidInstructionData);
+
+    let stranger = env.wallet(SOL);
+    let ix = set_route_fees_ix(&PROGRAM_ID, &env.config, &stranger, &[(*PUMP_SELECTOR, PUMP_RATE_PIPS)]);
+    assert!(env.process(&ix).result.is_err());
+    assert_eq!(env.config_state().fee_rate_for(PUMP_SELECTOR), DEFAULT_FEE_RATE_PIPS);
+}
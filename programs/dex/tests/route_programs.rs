@@ -0,0 +1,114 @@
+// 路由的备选目标程序：登记后可转发到旧地址（需附带登记 PDA），未登记或已移除的地址在 CPI 前拒绝；
+// 转发账户中只有其他路由的目标程序时按选择器与程序不匹配拒绝
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::{
+        pump::{PUMP_SELECTOR, PUMP_SELL_SELECTOR},
+        raydium::RAYDIUM_BUY_BASE_OUT_ROUTE,
+        route_program::route_program_address,
+    },
+    ix_builder::{add_route_program_ix, pump_buy_ix, raydium_buy_base_out_ix, remove_route_program_ix, with_route_program},
+    state::RouteProgramState,
+};
+use borsh::BorshDeserialize;
+use common::{
+    mocks::{dex_processor, PUMP_PROGRAM, RAYDIUM_AMM_V4_PROGRAM},
+    route_accounts, PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+// 转发账户末尾的目标程序换成 program
+fn buy_via(env: &TestEnv, user: &Pubkey, curve: &PumpCurve, program: &Pubkey) -> Instruction {
+    let mut forwarded = curve.buy_accounts();
+    *forwarded.last_mut().unwrap() = AccountMeta::new_readonly(*program, false);
+    pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, forwarded)
+}
+
+fn setup() -> (TestEnv, Pubkey, PumpCurve, Pubkey) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    // 旧部署地址上的 Pump 程序
+    let legacy = Pubkey::new_unique();
+    env.add_program(legacy, dex_processor);
+    (env, user, curve, legacy)
+}
+
+#[test]
+fn registered_legacy_program_is_accepted() {
+    let (mut env, user, curve, legacy) = setup();
+    let ix = add_route_program_ix(&PROGRAM_ID, &env.config, &env.admin, PUMP_SELECTOR, &legacy, 1);
+    env.process(&ix).assert_ok();
+    let registered = route_program_address(&PROGRAM_ID, PUMP_SELECTOR, &legacy).0;
+    let state = RouteProgramState::try_from_slice(env.data(&registered)).unwrap();
+    assert_eq!((state.selector, state.program, state.version), (*PUMP_SELECTOR, legacy, 1));
+
+    let treasury_before = env.lamports(&env.admin);
+    let ix = with_route_program(&PROGRAM_ID, buy_via(&env, &user, &curve, &legacy), PUMP_SELECTOR, &legacy);
+    let result = env.process(&ix).assert_ok();
+    assert_eq!(result.cpis_to(&legacy).len(), 1);
+    assert_eq!(env.lamports(&env.admin) - treasury_before, SOL / 100);
+
+    // 移除后同一地址不再被接受，登记 PDA 关闭
+    let ix = remove_route_program_ix(&PROGRAM_ID, &env.config, &env.admin, PUMP_SELECTOR, &legacy);
+    env.process(&ix).assert_ok();
+    assert!(env.account(&registered).is_none());
+    let ix = with_route_program(&PROGRAM_ID, buy_via(&env, &user, &curve, &legacy), PUMP_SELECTOR, &legacy);
+    let result = env.process(&ix);
+    assert!(result.cpis_to(&legacy).is_empty());
+    assert!(result.result.is_err());
+}
+
+#[test]
+fn unlisted_program_is_rejected() {
+    let (mut env, user, curve, legacy) = setup();
+    let treasury_before = env.lamports(&env.admin);
+
+    let ix = buy_via(&env, &user, &curve, &legacy);
+    let result = env.process(&ix);
+    assert!(result.cpis_to(&legacy).is_empty());
+    assert_eq!(result.unwrap_err(), MyError::TargetProgramNotExecutable.into());
+    assert_eq!((env.lamports(&user), env.lamports(&env.admin)), (10 * SOL, treasury_before));
+
+    // 只登记在卖出路由下的程序不能用于买入
+    let ix = add_route_program_ix(&PROGRAM_ID, &env.config, &env.admin, PUMP_SELL_SELECTOR, &legacy, 1);
+    env.process(&ix).assert_ok();
+    let ix = with_route_program(&PROGRAM_ID, buy_via(&env, &user, &curve, &legacy), PUMP_SELL_SELECTOR, &legacy);
+    let result = env.process(&ix);
+    assert!(result.cpis_to(&legacy).is_empty());
+    assert_eq!(result.unwrap_err(), MyError::TargetProgramNotExecutable.into());
+    assert_eq!(env.lamports(&user), 10 * SOL);
+}
+
+#[test]
+fn registration_requires_the_upgrade_authority() {
+    let (mut env, _, _, legacy) = setup();
+    let stranger = env.wallet(SOL);
+    let ix = add_route_program_ix(&PROGRAM_ID, &env.config, &stranger, PUMP_SELECTOR, &legacy, 1);
+    assert!(env.process(&ix).result.is_err());
+    assert!(env.account(&route_program_address(&PROGRAM_ID, PUMP_SELECTOR, &legacy).0).is_none());
+}
+
+#[test]
+fn selector_and_forwarded_program_from_different_dexes_are_rejected() {
+    let (mut env, user, curve, _) = setup();
+    // Pump 选择器转发 Raydium 程序
+    let ix = buy_via(&env, &user, &curve, &RAYDIUM_AMM_V4_PROGRAM);
+    let result = env.process(&ix);
+    assert!(result.cpis_to(&RAYDIUM_AMM_V4_PROGRAM).is_empty());
+    assert_eq!(result.unwrap_err(), MyError::RouteProgramMismatch.into());
+
+    // Raydium 选择器转发 Pump 程序
+    let mut forwarded = route_accounts(&RAYDIUM_BUY_BASE_OUT_ROUTE, 17, &user);
+    forwarded.push(AccountMeta::new_readonly(PUMP_PROGRAM, false));
+    let ix = raydium_buy_base_out_ix(&PROGRAM_ID, &env.fee_accounts(&user), 2 * SOL, 123_456, forwarded);
+    let result = env.process(&ix);
+    assert!(result.cpis_to(&PUMP_PROGRAM).is_empty());
+    assert_eq!(result.unwrap_err(), MyError::RouteProgramMismatch.into());
+    assert_eq!(env.lamports(&user), 10 * SOL);
+}
@@ -0,0 +1,71 @@
+// 未知选择器：严格模式统一返回 InvalidInstructionData，宽松模式下同系列的未知选择器返回 UnsupportedRoute
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::raydium::RAYDIUM_BUY_SELECTOR,
+    ix_builder::set_lenient_selectors_ix,
+};
+use common::{TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+};
+
+// 与 ref_swap 等文本选择器同系列
+const TEXT_FAMILY: &[u8; 8] = b"ref_zzzz";
+const UNRELATED: &[u8; 8] = b"zzzzzzzz";
+
+// 与 Raydium 数字选择器仅首字节不同
+fn numeric_family() -> [u8; 8] {
+    let mut selector = *RAYDIUM_BUY_SELECTOR;
+    selector[0] = 250;
+    selector
+}
+
+fn unknown_ix(env: &TestEnv, selector: &[u8; 8]) -> Instruction {
+    let mut data = selector.to_vec();
+    data.extend_from_slice(&[0; 16]);
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: vec![AccountMeta::new_readonly(env.config, false)],
+        data,
+    }
+}
+
+fn set_lenient(env: &mut TestEnv, lenient: bool) {
+    let ix = set_lenient_selectors_ix(&PROGRAM_ID, &env.config, &env.admin, lenient);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.config_state().lenient_selectors, lenient);
+}
+
+#[test]
+fn strict_mode_rejects_every_unknown_selector_the_same_way() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    for selector in [TEXT_FAMILY, &numeric_family(), UNRELATED] {
+        let ix = unknown_ix(&env, selector);
+        assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidInstructionData);
+    }
+}
+
+#[test]
+fn lenient_mode_flags_unknown_selectors_of_a_known_family() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    set_lenient(&mut env, true);
+
+    for selector in [TEXT_FAMILY, &numeric_family()] {
+        let ix = unknown_ix(&env, selector);
+        assert_eq!(env.process(&ix).unwrap_err(), MyError::UnsupportedRoute.into());
+    }
+    let ix = unknown_ix(&env, UNRELATED);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidInstructionData);
+
+    // 未传入配置账户时按严格模式处理
+    let mut ix = unknown_ix(&env, TEXT_FAMILY);
+    ix.accounts.clear();
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidInstructionData);
+
+    set_lenient(&mut env, false);
+    let ix = unknown_ix(&env, TEXT_FAMILY);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidInstructionData);
+}
@@ -0,0 +1,83 @@
+// 卖出权限：交易发起人本人卖出，或以代币委托额度代替所有者卖出
+mod common;
+
+use amm_proxy_contract::{error::MyError, ix_builder::pump_sell_ix};
+use common::{
+    mocks::{set_swap_behavior, SwapBehavior},
+    PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+
+const TOKENS: u64 = 5_000_000;
+
+// 代币账户的委托字段：[COption 标记 72..76][delegate 76..108]，委托额度位于 121..129
+fn approve(env: &mut TestEnv, token_account: &Pubkey, delegate: &Pubkey, amount: u64) {
+    let data = &mut env.account_mut(token_account).data;
+    data[72] = 1;
+    data[76..108].copy_from_slice(delegate.as_ref());
+    data[121..129].copy_from_slice(&amount.to_le_bytes());
+}
+
+// 由 seller 签名并支付手续费，卖出 curve 中所有者的代币
+fn sell_as(env: &TestEnv, curve: &PumpCurve, seller: &Pubkey) -> Instruction {
+    let curve = PumpCurve { user: *seller, ..*curve };
+    pump_sell_ix(&PROGRAM_ID, &env.fee_accounts(seller), TOKENS, 1, curve.sell_accounts())
+}
+
+fn setup() -> (TestEnv, Pubkey, PumpCurve) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let owner = env.wallet(SOL);
+    let curve = env.pump_curve(&owner, TOKENS);
+    set_swap_behavior(SwapBehavior {
+        output: Some(SOL / 2),
+        ..Default::default()
+    });
+    (env, owner, curve)
+}
+
+#[test]
+fn owner_sells_its_own_tokens() {
+    let (mut env, owner, curve) = setup();
+
+    let ix = sell_as(&env, &curve, &owner);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.token_balance(&curve.associated_user), 0);
+    assert_eq!(env.lamports(&owner), SOL + SOL / 2 - SOL / 2 / 100);
+}
+
+#[test]
+fn delegate_sells_within_the_delegated_amount() {
+    let (mut env, owner, curve) = setup();
+    let delegate = env.wallet(SOL);
+    approve(&mut env, &curve.associated_user, &delegate, TOKENS);
+
+    let ix = sell_as(&env, &curve, &delegate);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.token_balance(&curve.associated_user), 0);
+    // SOL 手续费由委托人支付，所有者余额不变
+    assert_eq!(env.lamports(&owner), SOL);
+    assert_eq!(env.lamports(&delegate), SOL + SOL / 2 - SOL / 2 / 100);
+}
+
+#[test]
+fn delegate_cannot_sell_beyond_the_delegated_amount() {
+    let (mut env, _, curve) = setup();
+    let delegate = env.wallet(SOL);
+    approve(&mut env, &curve.associated_user, &delegate, TOKENS - 1);
+
+    let ix = sell_as(&env, &curve, &delegate);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::FeePayerNotAuthorized.into());
+    assert_eq!(env.token_balance(&curve.associated_user), TOKENS);
+}
+
+#[test]
+fn unrelated_signer_cannot_sell_someone_elses_tokens() {
+    let (mut env, _, curve) = setup();
+    let stranger = env.wallet(SOL);
+    let treasury_before = env.lamports(&env.admin);
+
+    let ix = sell_as(&env, &curve, &stranger);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::FeePayerNotAuthorized.into());
+    assert_eq!(env.lamports(&env.admin), treasury_before);
+    assert_eq!(env.lamports(&stranger), SOL);
+}
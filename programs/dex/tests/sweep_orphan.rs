@@ -0,0 +1,68 @@
+// SWEEP_ORPHAN 回收遗留的本程序账户
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError, instructions::wallet::wallet_address, ix_builder::sweep_orphan_ix, utils::program_data_address,
+};
+use common::{program_data_account, without_signer, Account, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+// 以密钥对地址创建、归本程序所有的遗留账户
+fn seed_orphan(env: &mut TestEnv, key: Pubkey) -> u64 {
+    let mut account = Account::rent_exempt(vec![7; 100], PROGRAM_ID);
+    account.lamports += SOL;
+    let lamports = account.lamports;
+    env.set_account(key, account);
+    lamports
+}
+
+#[test]
+fn seeded_orphan_is_swept_to_the_fee_wallet() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let orphan = Pubkey::new_unique();
+    let lamports = seed_orphan(&mut env, orphan);
+    let admin_before = env.lamports(&env.admin);
+
+    let ix = sweep_orphan_ix(&PROGRAM_ID, &env.config, &env.admin, &orphan);
+    env.process(&ix).assert_ok();
+
+    assert_eq!(env.lamports(&env.admin) - admin_before, lamports);
+    assert!(env.account(&orphan).is_none());
+}
+
+#[test]
+fn live_config_cannot_be_swept() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let config_lamports = env.lamports(&env.config);
+
+    let ix = sweep_orphan_ix(&PROGRAM_ID, &env.config, &env.admin, &env.config);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidArgument);
+    assert_eq!(env.lamports(&env.config), config_lamports);
+    assert_eq!(env.config_state().fee_rate_pips, DEFAULT_FEE_RATE_PIPS);
+}
+
+#[test]
+fn unsigned_pda_cannot_be_swept() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    // 在用的钱包计数 PDA 无法签名
+    let pda = wallet_address(&PROGRAM_ID, &Pubkey::new_unique()).0;
+    let lamports = seed_orphan(&mut env, pda);
+
+    let ix = without_signer(sweep_orphan_ix(&PROGRAM_ID, &env.config, &env.admin, &pda), &pda);
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::MissingRequiredSignature);
+    assert_eq!(env.lamports(&pda), lamports);
+}
+
+#[test]
+fn sweep_requires_the_upgrade_authority() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let orphan = Pubkey::new_unique();
+    let lamports = seed_orphan(&mut env, orphan);
+    // 协议费钱包与升级权限不一致时拒绝
+    let upgrade_authority = Pubkey::new_unique();
+    env.set_account(program_data_address(&PROGRAM_ID), program_data_account(Some(&upgrade_authority)));
+
+    let ix = sweep_orphan_ix(&PROGRAM_ID, &env.config, &env.admin, &orphan);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::NotUpgradeAuthority.into());
+    assert_eq!(env.lamports(&orphan), lamports);
+}
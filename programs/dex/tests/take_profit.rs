@@ -0,0 +1,111 @@
+// 买入加止盈：先按买入路由收费并兑换，再把本次买到的全部 base 代币以 price_lots 挂出 OpenBook v2 限价卖单
+mod common;
+
+use amm_proxy_contract::{
+    instructions::openbook::OPENBOOK_V2_PROGRAM,
+    ix_builder::{buy_take_profit_ix, pump_buy_ix, TakeProfitOrder},
+    token::TOKEN_PROGRAM_ID,
+};
+use common::{
+    mocks::{set_swap_behavior, SwapBehavior, PUMP_PROGRAM},
+    Account, PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+};
+
+// Market 账户中 base_lot_size 的偏移，紧跟在 quote_lot_size 之后
+const BASE_LOT_SIZE_OFFSET: usize = 744;
+const BASE_LOT_SIZE: u64 = 1_000;
+const BOUGHT: u64 = 1_234_567;
+const ORDER: TakeProfitOrder = TakeProfitOrder { price_lots: 7_500, client_order_id: 42, expiry_timestamp: 1_700_000_000 };
+
+fn setup() -> (TestEnv, Pubkey, PumpCurve) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    set_swap_behavior(SwapBehavior { output: Some(BOUGHT), ..Default::default() });
+    (env, user, curve)
+}
+
+// place_order 的 12 个账户，代币账户为买入的输出账户，market 为 OpenBook 所有的市场账户
+fn order_accounts(env: &mut TestEnv, user: &Pubkey, token_account: &Pubkey) -> Vec<AccountMeta> {
+    let mut accounts: Vec<AccountMeta> = (0..12).map(|_| AccountMeta::new(Pubkey::new_unique(), false)).collect();
+    accounts[0] = AccountMeta::new(*user, true);
+    accounts[2] = AccountMeta::new_readonly(OPENBOOK_V2_PROGRAM, false);
+    accounts[3] = AccountMeta::new(*token_account, false);
+    accounts[11] = AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false);
+    let mut market = vec![0u8; BASE_LOT_SIZE_OFFSET + 8];
+    market[BASE_LOT_SIZE_OFFSET - 8..BASE_LOT_SIZE_OFFSET].copy_from_slice(&1i64.to_le_bytes());
+    market[BASE_LOT_SIZE_OFFSET..].copy_from_slice(&(BASE_LOT_SIZE as i64).to_le_bytes());
+    env.set_account(accounts[4].pubkey, Account::rent_exempt(market, OPENBOOK_V2_PROGRAM));
+    accounts
+}
+
+fn buy_tp(env: &mut TestEnv, user: &Pubkey, curve: &PumpCurve, token_account: &Pubkey) -> (Instruction, Vec<AccountMeta>) {
+    let buy = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, curve.buy_accounts());
+    let order = order_accounts(env, user, token_account);
+    (buy_take_profit_ix(buy, &ORDER, order.clone()), order)
+}
+
+#[test]
+fn buy_is_followed_by_a_limit_sell_of_everything_bought() {
+    let (mut env, user, curve) = setup();
+    let treasury_before = env.lamports(&env.admin);
+    let (ix, order) = buy_tp(&mut env, &user, &curve, &curve.associated_user);
+    let result = env.process(&ix).assert_ok();
+    assert_eq!(env.lamports(&env.admin) - treasury_before, SOL / 100);
+    assert_eq!(env.token_balance(&curve.associated_user), BOUGHT);
+
+    // 除收费转账外，先买入，再挂单
+    let targets: Vec<Pubkey> = result
+        .invocations
+        .iter()
+        .filter(|ix| ix.stack_height == 2 && ix.program_id != system_program::id())
+        .map(|ix| ix.program_id)
+        .collect();
+    assert_eq!(targets, [PUMP_PROGRAM, OPENBOOK_V2_PROGRAM]);
+    assert_eq!(result.cpis_to(&PUMP_PROGRAM)[0].data[8..16], (SOL - SOL / 100).to_le_bytes());
+
+    // [place_order 鉴别器][side][price_lots][max_base_lots][max_quote_lots][client_order_id][order_type][expiry][self_trade][limit]
+    let mut expected = vec![51, 194, 155, 175, 109, 130, 96, 106, 1];
+    expected.extend_from_slice(&ORDER.price_lots.to_le_bytes());
+    expected.extend_from_slice(&((BOUGHT / BASE_LOT_SIZE) as i64).to_le_bytes());
+    expected.extend_from_slice(&i64::MAX.to_le_bytes());
+    expected.extend_from_slice(&ORDER.client_order_id.to_le_bytes());
+    expected.push(0);
+    expected.extend_from_slice(&ORDER.expiry_timestamp.to_le_bytes());
+    expected.extend_from_slice(&[0, 10]);
+    let cpis = result.cpis_to(&OPENBOOK_V2_PROGRAM);
+    assert_eq!(cpis.len(), 1);
+    assert_eq!(cpis[0].data, expected);
+    let keys = |metas: &[AccountMeta]| metas.iter().map(|meta| meta.pubkey).collect::<Vec<_>>();
+    assert_eq!(keys(&cpis[0].accounts), keys(&order));
+    assert!(cpis[0].accounts[0].is_signer);
+}
+
+#[test]
+fn order_must_sell_from_the_buy_output() {
+    let (mut env, user, curve) = setup();
+    let mint = env.add_mint(6, &TOKEN_PROGRAM_ID);
+    let other = env.add_ata(&user, &mint, 0, &TOKEN_PROGRAM_ID);
+    let (ix, _) = buy_tp(&mut env, &user, &curve, &other);
+    let result = env.process(&ix);
+    assert!(result.cpis_to(&PUMP_PROGRAM).is_empty());
+    assert_eq!(result.unwrap_err(), ProgramError::InvalidAccountData);
+    assert_eq!(env.lamports(&user), 10 * SOL);
+}
+
+#[test]
+fn buy_under_one_lot_is_rolled_back() {
+    let (mut env, user, curve) = setup();
+    set_swap_behavior(SwapBehavior { output: Some(BASE_LOT_SIZE - 1), ..Default::default() });
+    let (ix, _) = buy_tp(&mut env, &user, &curve, &curve.associated_user);
+    let result = env.process(&ix);
+    assert!(result.cpis_to(&OPENBOOK_V2_PROGRAM).is_empty());
+    assert_eq!(result.unwrap_err(), ProgramError::InvalidInstructionData);
+    assert_eq!((env.lamports(&user), env.token_balance(&curve.associated_user)), (10 * SOL, 0));
+}
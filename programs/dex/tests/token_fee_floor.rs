@@ -0,0 +1,141 @@
+// 代币手续费下限：以百万分之一个代币配置，按交易代币 mint 的 decimals 换算为最小单位并向上取整；按费率算出的
+// 代币手续费低于下限时按下限收取，下限不超过 MAX_FEE_BPS 上限。开启后以代币收费的交易须附带下限 PDA
+mod common;
+
+use amm_proxy_contract::{
+    instructions::token_fee_floor::token_fee_floor_address,
+    ix_builder::{pump_buy_ix, set_buy_fee_timing_ix, set_token_fee_floor_ix, with_token_fee_accounts, with_token_fee_floor},
+    state::{FeeTiming, TokenFeeFloorState, PREFLIGHT_TOKEN_FEE_FLOOR},
+    token::{associated_token_address, TOKEN_PROGRAM_ID},
+};
+use borsh::BorshDeserialize;
+use common::{
+    mocks::{set_swap_behavior, SwapBehavior},
+    PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+
+// 0.5 个代币
+const HALF_TOKEN_MICROS: u64 = 500_000;
+
+struct Setup {
+    env: TestEnv,
+    user: Pubkey,
+    curve: PumpCurve,
+    treasury_ata: Pubkey,
+}
+
+// 买入按输出代币收费，交易代币的精度为 decimals
+fn setup(decimals: u8, min_fee_micros: u64) -> Setup {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_buy_fee_timing_ix(&PROGRAM_ID, &env.config, &env.admin, FeeTiming::PostSwap as u8);
+    env.process(&ix).assert_ok();
+    let ix = set_token_fee_floor_ix(&PROGRAM_ID, &env.config, &env.admin, min_fee_micros);
+    env.process(&ix).assert_ok();
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    env.account_mut(&curve.mint).data[44] = decimals;
+    let treasury_ata = associated_token_address(&env.admin, &curve.mint, &TOKEN_PROGRAM_ID);
+    Setup { env, user, curve, treasury_ata }
+}
+
+fn buy(setup: &Setup) -> Instruction {
+    let ix = pump_buy_ix(&PROGRAM_ID, &setup.env.fee_accounts(&setup.user), SOL, 2 * SOL, setup.curve.buy_accounts());
+    with_token_fee_floor(&PROGRAM_ID, with_token_fee_accounts(ix, &setup.env.admin, &setup.curve.mint, &TOKEN_PROGRAM_ID))
+}
+
+// 兑换得到 received 个最小单位时收取的代币手续费
+fn token_fee(setup: &mut Setup, received: u64) -> u64 {
+    set_swap_behavior(SwapBehavior { output: Some(received), ..Default::default() });
+    let before = setup.env.account(&setup.treasury_ata).map_or(0, |_| setup.env.token_balance(&setup.treasury_ata));
+    let ix = buy(setup);
+    setup.env.process(&ix).assert_ok();
+    setup.env.token_balance(&setup.treasury_ata) - before
+}
+
+#[test]
+fn floor_is_scaled_by_the_mint_decimals() {
+    // 6 位精度：0.5 个代币 = 500_000；10 个代币按 1% 只有 100_000
+    let mut six = setup(6, HALF_TOKEN_MICROS);
+    let state = TokenFeeFloorState::try_from_slice(six.env.data(&token_fee_floor_address(&PROGRAM_ID).0)).unwrap();
+    assert_eq!(state.min_fee_micros, HALF_TOKEN_MICROS);
+    assert!(six.env.config_state().preflight(PREFLIGHT_TOKEN_FEE_FLOOR));
+    assert_eq!(token_fee(&mut six, 10_000_000), 500_000);
+    // 按费率计算高于下限时按费率收取
+    assert_eq!(token_fee(&mut six, 100_000_000), 1_000_000);
+
+    // 9 位精度：0.5 个代币 = 500_000_000；20 个代币按 1% 只有 200_000_000
+    let mut nine = setup(9, HALF_TOKEN_MICROS);
+    assert_eq!(token_fee(&mut nine, 20_000_000_000), 500_000_000);
+    assert_eq!(token_fee(&mut nine, 100_000_000_000), 1_000_000_000);
+}
+
+// 兑换得到 received 个最小单位时用户实际到账的数量
+fn kept(setup: &mut Setup, received: u64) -> u64 {
+    set_swap_behavior(SwapBehavior { output: Some(received), ..Default::default() });
+    let before = setup.env.token_balance(&setup.curve.associated_user);
+    let ix = buy(setup);
+    setup.env.process(&ix).assert_ok();
+    setup.env.token_balance(&setup.curve.associated_user) - before
+}
+
+#[test]
+fn zero_decimal_mint_pays_a_whole_unit_or_nothing() {
+    // 下限为百万分之一个代币，0 位精度时向上取整为 1 个最小单位
+    let mut zero = setup(0, 1);
+    // 100 个按 1% 恰为 1 个；50 个按费率舍入为 0，按下限收 1 个
+    assert_eq!(token_fee(&mut zero, 100), 1);
+    assert_eq!(token_fee(&mut zero, 50), 1);
+    // 不足 20 个时 5% 上限为 0，不收取，手续费不会超过兑换所得
+    assert_eq!(kept(&mut zero, 19), 19);
+    assert_eq!(kept(&mut zero, 1), 1);
+
+    // 未设置下限时小额交易按费率舍入为 0，直接跳过
+    let mut unfloored = setup(0, 0);
+    assert_eq!(kept(&mut unfloored, 50), 50);
+    assert_eq!(token_fee(&mut unfloored, 1_000), 10);
+}
+
+#[test]
+fn nine_decimal_mint_scales_a_tiny_floor() {
+    // 百万分之一个代币 = 1_000 个最小单位
+    let mut nine = setup(9, 1);
+    assert_eq!(token_fee(&mut nine, 50_000), 1_000);
+    assert_eq!(token_fee(&mut nine, 1_000_000_000), 10_000_000);
+    // 上限 5% 为 500，低于下限
+    assert_eq!(kept(&mut nine, 10_000), 10_000 - 500);
+}
+
+#[test]
+fn floor_never_exceeds_the_fee_cap() {
+    // 1 个代币的 5% 为 50_000，低于 0.5 个代币的下限
+    let mut six = setup(6, HALF_TOKEN_MICROS);
+    assert_eq!(token_fee(&mut six, 1_000_000), 50_000);
+}
+
+#[test]
+fn floor_account_is_required_once_enabled() {
+    let mut six = setup(6, HALF_TOKEN_MICROS);
+    set_swap_behavior(SwapBehavior { output: Some(10_000_000), ..Default::default() });
+    let ix = pump_buy_ix(&PROGRAM_ID, &six.env.fee_accounts(&six.user), SOL, 2 * SOL, six.curve.buy_accounts());
+    let ix = with_token_fee_accounts(ix, &six.env.admin, &six.curve.mint, &TOKEN_PROGRAM_ID);
+    assert_eq!(six.env.process(&ix).unwrap_err(), ProgramError::NotEnoughAccountKeys);
+    assert_eq!(six.env.lamports(&six.user), 10 * SOL);
+
+    // 设为 0 后关闭，不再需要下限 PDA，只按费率收取
+    let disable = set_token_fee_floor_ix(&PROGRAM_ID, &six.env.config, &six.env.admin, 0);
+    six.env.process(&disable).assert_ok();
+    assert!(!six.env.config_state().preflight(PREFLIGHT_TOKEN_FEE_FLOOR));
+    six.env.process(&ix).assert_ok();
+    assert_eq!(six.env.token_balance(&six.treasury_ata), 100_000);
+}
+
+#[test]
+fn floor_setter_requires_the_admin() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let stranger = env.wallet(SOL);
+    let ix = set_token_fee_floor_ix(&PROGRAM_ID, &env.config, &stranger, HALF_TOKEN_MICROS);
+    assert!(env.process(&ix).result.is_err());
+    assert!(env.account(&token_fee_floor_address(&PROGRAM_ID).0).is_none());
+    assert!(!env.config_state().preflight(PREFLIGHT_TOKEN_FEE_FLOOR));
+}
@@ -0,0 +1,59 @@
+// 交易发起人签名：转发账户中用户代币账户的 owner 未签名时，在收费与 CPI 之前拒绝。
+// 各路由的账户描述都把交易发起人标为签名账户，按账户描述的顺序校验先于发起人签名校验报错
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::raydium::RAYDIUM_BUY_BASE_OUT_ROUTE,
+    ix_builder::{pump_buy_ix, pump_sell_ix, raydium_buy_base_out_ix},
+};
+use common::{
+    mocks::{PUMP_PROGRAM, RAYDIUM_AMM_V4_PROGRAM},
+    route_accounts, runtime::TxResult, without_signer, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{instruction::AccountMeta, pubkey::Pubkey};
+
+fn assert_rejected_before_cpi(env: &TestEnv, result: TxResult, program: &Pubkey, treasury_before: u64) {
+    assert!(result.cpis_to(program).is_empty());
+    assert_eq!(result.unwrap_err(), MyError::AccountOrderMismatch.into());
+    assert_eq!(env.lamports(&env.admin), treasury_before);
+}
+
+#[test]
+fn pump_routes_require_the_user_signature() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let (owner, relayer) = (env.wallet(10 * SOL), env.wallet(10 * SOL));
+    let curve = env.pump_curve(&owner, 5_000_000);
+    let treasury_before = env.lamports(&env.admin);
+
+    // 他人支付手续费，代 owner 买入
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&relayer), SOL, 2 * SOL, curve.buy_accounts());
+    let result = env.process(&without_signer(ix, &owner));
+    assert_rejected_before_cpi(&env, result, &PUMP_PROGRAM, treasury_before);
+    assert_eq!(env.lamports(&owner), 10 * SOL);
+
+    // 他人支付手续费，代 owner 卖出
+    let ix = pump_sell_ix(&PROGRAM_ID, &env.fee_accounts(&relayer), 5_000_000, 1, curve.sell_accounts());
+    let result = env.process(&without_signer(ix, &owner));
+    assert_rejected_before_cpi(&env, result, &PUMP_PROGRAM, treasury_before);
+    assert_eq!(env.token_balance(&curve.associated_user), 5_000_000);
+}
+
+#[test]
+fn raydium_base_out_requires_the_user_owner_signature() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let (owner, relayer) = (env.wallet(10 * SOL), env.wallet(10 * SOL));
+    let treasury_before = env.lamports(&env.admin);
+
+    let mut forwarded = route_accounts(&RAYDIUM_BUY_BASE_OUT_ROUTE, 17, &owner);
+    forwarded.push(AccountMeta::new_readonly(RAYDIUM_AMM_V4_PROGRAM, false));
+    let ix = raydium_buy_base_out_ix(&PROGRAM_ID, &env.fee_accounts(&relayer), 2 * SOL, 123_456, forwarded.clone());
+    let result = env.process(&without_signer(ix, &owner));
+    assert_rejected_before_cpi(&env, result, &RAYDIUM_AMM_V4_PROGRAM, treasury_before);
+    assert_eq!(env.lamports(&relayer), 10 * SOL);
+
+    // owner 签名后他人可代付手续费
+    let ix = raydium_buy_base_out_ix(&PROGRAM_ID, &env.fee_accounts(&relayer), 2 * SOL, 123_456, forwarded);
+    env.process(&ix).assert_ok();
+    assert_eq!(env.lamports(&env.admin) - treasury_before, 2 * SOL / 100);
+}
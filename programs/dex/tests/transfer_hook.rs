@@ -0,0 +1,85 @@
+// 代币手续费与 Token-2022 transfer hook：TransferChecked 不附带 hook 的额外账户，交易代币挂载了 hook 程序时
+// 以该代币收费在转账前返回 TransferHookNotSupported 并整笔回滚；未挂载 hook 的 Token-2022 mint 照常收费
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    ix_builder::{pump_buy_ix, set_buy_fee_timing_ix, with_token_fee_accounts},
+    state::FeeTiming,
+    token::{associated_token_address, TOKEN_2022_PROGRAM_ID},
+};
+use common::{
+    mocks::{pack_mint, set_swap_behavior, SwapBehavior, TOKEN_ACCOUNT_LEN},
+    PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL,
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+const RECEIVED: u64 = 1_000_000;
+const EXTENSION_TRANSFER_HOOK: u16 = 14;
+
+// Token-2022 mint：[基础布局补齐到 165 字节][账户类型 1 = Mint][TLV 扩展]
+fn token_2022_mint(hook_program: Option<Pubkey>) -> Vec<u8> {
+    let mut data = pack_mint(6);
+    data.resize(TOKEN_ACCOUNT_LEN, 0);
+    data.push(1);
+    if let Some(program) = hook_program {
+        data.extend_from_slice(&EXTENSION_TRANSFER_HOOK.to_le_bytes());
+        data.extend_from_slice(&64u16.to_le_bytes());
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(program.as_ref());
+    }
+    data
+}
+
+// 买入按输出代币收费，交易代币及相关代币账户改由 Token-2022 管理
+fn setup(mint_data: Vec<u8>) -> (TestEnv, Pubkey, PumpCurve) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_buy_fee_timing_ix(&PROGRAM_ID, &env.config, &env.admin, FeeTiming::PostSwap as u8);
+    env.process(&ix).assert_ok();
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    let mint = env.account_mut(&curve.mint);
+    mint.owner = TOKEN_2022_PROGRAM_ID;
+    mint.data = mint_data;
+    for account in [curve.associated_bonding_curve, curve.associated_user] {
+        env.account_mut(&account).owner = TOKEN_2022_PROGRAM_ID;
+    }
+    set_swap_behavior(SwapBehavior { output: Some(RECEIVED), ..Default::default() });
+    (env, user, curve)
+}
+
+fn buy(env: &TestEnv, user: &Pubkey, curve: &PumpCurve) -> Instruction {
+    let mut forwarded = curve.buy_accounts();
+    forwarded[8] = AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false);
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, forwarded);
+    with_token_fee_accounts(ix, &env.admin, &curve.mint, &TOKEN_2022_PROGRAM_ID)
+}
+
+#[test]
+fn mint_with_a_transfer_hook_is_rejected() {
+    let (mut env, user, curve) = setup(token_2022_mint(Some(Pubkey::new_unique())));
+    let treasury_ata = associated_token_address(&env.admin, &curve.mint, &TOKEN_2022_PROGRAM_ID);
+
+    let ix = buy(&env, &user, &curve);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::TransferHookNotSupported.into());
+    assert_eq!(env.lamports(&user), 10 * SOL);
+    assert_eq!(env.token_balance(&curve.associated_user), 0);
+    assert!(env.account(&treasury_ata).is_none());
+}
+
+#[test]
+fn plain_token_2022_mint_is_charged() {
+    // 无扩展，以及 TransferHook 扩展未挂载 hook 程序
+    for mint_data in [token_2022_mint(None), token_2022_mint(Some(Pubkey::default()))] {
+        let (mut env, user, curve) = setup(mint_data);
+        let treasury_ata = associated_token_address(&env.admin, &curve.mint, &TOKEN_2022_PROGRAM_ID);
+
+        let ix = buy(&env, &user, &curve);
+        env.process(&ix).assert_ok();
+        assert_eq!(env.token_balance(&treasury_ata), RECEIVED / 100);
+        assert_eq!(env.token_balance(&curve.associated_user), RECEIVED - RECEIVED / 100);
+    }
+}
@@ -0,0 +1,82 @@
+// 高风险管理指令（暂停、提取）除管理员外还要求签名者是程序的升级权限
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::escrow::escrow_address,
+    instructions::pump::PUMP_SELECTOR,
+    ix_builder::{init_escrow_ix, pause_route_ix, set_paused_ix, withdraw_escrow_ix},
+    utils::{load_upgrade_authority, program_data_address},
+};
+use common::{program_data_account, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+fn set_upgrade_authority(env: &mut TestEnv, authority: Option<&Pubkey>) {
+    env.set_account(program_data_address(&PROGRAM_ID), program_data_account(authority));
+}
+
+#[test]
+fn load_upgrade_authority_reads_the_program_data() {
+    let mut env = TestEnv::new();
+    let program_data = program_data_address(&PROGRAM_ID);
+    let load = |env: &TestEnv| env.with_account_infos(&[program_data], |infos| load_upgrade_authority(&PROGRAM_ID, &infos[0]));
+    assert_eq!(load(&env), Ok(Some(env.admin)));
+
+    set_upgrade_authority(&mut env, None);
+    assert_eq!(load(&env), Ok(None));
+
+    // 不归升级加载器所有的账户
+    env.account_mut(&program_data).owner = Pubkey::new_unique();
+    assert_eq!(load(&env), Err(ProgramError::InvalidAccountData));
+}
+
+#[test]
+fn pause_requires_the_upgrade_authority() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_paused_ix(&PROGRAM_ID, &env.config, &env.admin, true, 1);
+    env.process(&ix).assert_ok();
+    assert!(env.config_state().paused);
+
+    // 升级权限已转给他人，管理员不能再恢复
+    set_upgrade_authority(&mut env, Some(&Pubkey::new_unique()));
+    let ix = set_paused_ix(&PROGRAM_ID, &env.config, &env.admin, false, 0);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::NotUpgradeAuthority.into());
+    let ix = pause_route_ix(&PROGRAM_ID, &env.config, &env.admin, PUMP_SELECTOR, true, 1);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::NotUpgradeAuthority.into());
+
+    // 程序已不可升级
+    set_upgrade_authority(&mut env, None);
+    let ix = set_paused_ix(&PROGRAM_ID, &env.config, &env.admin, false, 0);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::NotUpgradeAuthority.into());
+    assert!(env.config_state().paused);
+}
+
+#[test]
+fn forged_program_data_is_rejected() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let (forged, admin) = (Pubkey::new_unique(), env.admin);
+    env.set_account(forged, program_data_account(Some(&admin)));
+
+    let mut ix = set_paused_ix(&PROGRAM_ID, &env.config, &env.admin, true, 1);
+    ix.accounts[2].pubkey = forged;
+    assert_eq!(env.process(&ix).unwrap_err(), ProgramError::InvalidAccountData);
+    assert!(!env.config_state().paused);
+}
+
+#[test]
+fn escrow_withdraw_requires_the_upgrade_authority() {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = init_escrow_ix(&PROGRAM_ID, &env.config, &env.admin, SOL);
+    env.process(&ix).assert_ok();
+    let escrow_before = env.lamports(&escrow_address(&PROGRAM_ID).0);
+
+    set_upgrade_authority(&mut env, Some(&Pubkey::new_unique()));
+    let ix = withdraw_escrow_ix(&PROGRAM_ID, &env.config, &env.admin, 0);
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::NotUpgradeAuthority.into());
+    assert_eq!(env.lamports(&escrow_address(&PROGRAM_ID).0), escrow_before);
+
+    // 升级权限归还管理员后通过权限检查，因尚无可释放收入而失败
+    let admin = env.admin;
+    set_upgrade_authority(&mut env, Some(&admin));
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::NothingToRelease.into());
+}
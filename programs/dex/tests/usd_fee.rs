@@ -0,0 +1,116 @@
+// 美元定额手续费：按 Pyth SOL/USD 报价换算为 lamports，拒绝过期报价
+mod common;
+
+use amm_proxy_contract::{
+    error::MyError,
+    instructions::fee::SwapResult,
+    ix_builder::{pump_buy_ix, set_max_data_age_ix, set_usd_fee_ix},
+    oracle::{usd_micros_to_lamports, OraclePrice, PYTH_PROGRAM_ID},
+};
+use borsh::BorshDeserialize;
+use common::{runtime::TxResult, Account, PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::{instruction::AccountMeta, program_error::ProgramError, pubkey::Pubkey};
+
+// 1.5 美元
+const FEE_USD_MICROS: u64 = 1_500_000;
+const MAX_PRICE_AGE_SECS: u32 = 60;
+const NOW: i64 = 1_700_000_000;
+
+// Pyth legacy v2 价格账户：magic、expo、更新时间、聚合价格、状态与发布 slot
+fn price_account(price: i64, expo: i32, timestamp: i64, pub_slot: u64) -> Account {
+    let mut data = vec![0u8; 240];
+    data[0..4].copy_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+    data[20..24].copy_from_slice(&expo.to_le_bytes());
+    data[96..104].copy_from_slice(&timestamp.to_le_bytes());
+    data[208..216].copy_from_slice(&price.to_le_bytes());
+    data[224..228].copy_from_slice(&1u32.to_le_bytes());
+    data[232..240].copy_from_slice(&pub_slot.to_le_bytes());
+    Account::rent_exempt(data, PYTH_PROGRAM_ID)
+}
+
+fn setup() -> (TestEnv, Pubkey, PumpCurve, Pubkey) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    env.set_unix_timestamp(NOW);
+    let oracle = Pubkey::new_unique();
+    let ix = set_usd_fee_ix(&PROGRAM_ID, &env.config, &env.admin, FEE_USD_MICROS, &oracle, MAX_PRICE_AGE_SECS);
+    env.process(&ix).assert_ok();
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    (env, user, curve, oracle)
+}
+
+fn buy_with_oracle(env: &mut TestEnv, user: &Pubkey, curve: &PumpCurve, oracle: &Pubkey) -> TxResult {
+    let mut ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, curve.buy_accounts());
+    ix.accounts.push(AccountMeta::new_readonly(*oracle, false));
+    env.process(&ix)
+}
+
+fn charged_fee(result: TxResult) -> u64 {
+    SwapResult::try_from_slice(&result.assert_ok().return_data.unwrap().1).unwrap().fee
+}
+
+#[test]
+fn fee_follows_the_oracle_price() {
+    let (mut env, user, curve, oracle) = setup();
+
+    // $150/SOL 时 1.5 美元为 0.01 SOL，$100/SOL 时为 0.015 SOL
+    for (price, fee) in [(15_000_000_000, SOL / 100), (10_000_000_000, SOL * 15 / 1_000)] {
+        env.set_account(oracle, price_account(price, -8, NOW, 0));
+        let treasury_before = env.lamports(&env.admin);
+        assert_eq!(charged_fee(buy_with_oracle(&mut env, &user, &curve, &oracle)), fee);
+        assert_eq!(env.lamports(&env.admin) - treasury_before, fee);
+    }
+
+    // 换算结果仍不超过 5% 上限
+    env.set_account(oracle, price_account(1_000_000, -8, NOW, 0));
+    assert_eq!(charged_fee(buy_with_oracle(&mut env, &user, &curve, &oracle)), SOL / 20);
+}
+
+#[test]
+fn stale_price_is_rejected() {
+    let (mut env, user, curve, oracle) = setup();
+
+    env.set_account(oracle, price_account(15_000_000_000, -8, NOW - MAX_PRICE_AGE_SECS as i64, 0));
+    charged_fee(buy_with_oracle(&mut env, &user, &curve, &oracle));
+
+    env.set_account(oracle, price_account(15_000_000_000, -8, NOW - MAX_PRICE_AGE_SECS as i64 - 1, 0));
+    let result = buy_with_oracle(&mut env, &user, &curve, &oracle);
+    assert_eq!(result.unwrap_err(), MyError::StaleOraclePrice.into());
+
+    // 配置了最大发布时长（slot）时，发布 slot 过旧同样拒绝
+    let ix = set_max_data_age_ix(&PROGRAM_ID, &env.config, &env.admin, 10);
+    env.process(&ix).assert_ok();
+    env.warp_to_slot(100);
+    env.set_unix_timestamp(NOW);
+    env.set_account(oracle, price_account(15_000_000_000, -8, NOW, 89));
+    let result = buy_with_oracle(&mut env, &user, &curve, &oracle);
+    assert_eq!(result.unwrap_err(), MyError::StaleOraclePrice.into());
+    env.set_account(oracle, price_account(15_000_000_000, -8, NOW, 90));
+    charged_fee(buy_with_oracle(&mut env, &user, &curve, &oracle));
+}
+
+#[test]
+fn invalid_price_accounts_are_rejected() {
+    let (mut env, user, curve, oracle) = setup();
+
+    // 未追加价格账户
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    assert_eq!(env.process(&ix).unwrap_err(), MyError::InvalidOraclePrice.into());
+
+    let mut forged = price_account(15_000_000_000, -8, NOW, 0);
+    forged.owner = Pubkey::new_unique();
+    env.set_account(oracle, forged);
+    assert_eq!(buy_with_oracle(&mut env, &user, &curve, &oracle).unwrap_err(), ProgramError::IllegalOwner);
+
+    env.set_account(oracle, price_account(-1, -8, NOW, 0));
+    let result = buy_with_oracle(&mut env, &user, &curve, &oracle);
+    assert_eq!(result.unwrap_err(), MyError::InvalidOraclePrice.into());
+}
+
+#[test]
+fn usd_micros_convert_with_either_exponent_sign() {
+    let price = |price, expo| OraclePrice { price, expo, timestamp: 0, pub_slot: 0 };
+    assert_eq!(usd_micros_to_lamports(FEE_USD_MICROS, &price(150_000, -3)), Ok(SOL / 100));
+    assert_eq!(usd_micros_to_lamports(FEE_USD_MICROS, &price(15, 1)), Ok(SOL / 100));
+    assert_eq!(usd_micros_to_lamports(0, &price(150, 0)), Ok(0));
+}
@@ -0,0 +1,71 @@
+// 钱包交易计数 PDA：新钱包前 N 笔免手续费
+mod common;
+
+use amm_proxy_contract::{
+    instructions::fee::SwapResult,
+    instructions::wallet::wallet_address,
+    ix_builder::{pump_buy_ix, set_free_trades_ix, with_wallet_counter},
+    state::WalletState,
+};
+use borsh::BorshDeserialize;
+use common::{PumpCurve, TestEnv, DEFAULT_FEE_RATE_PIPS, PROGRAM_ID, SOL};
+use solana_program::pubkey::Pubkey;
+
+const FREE_TRADES: u32 = 2;
+
+fn setup(free_trades: u32) -> (TestEnv, Pubkey, PumpCurve) {
+    let mut env = TestEnv::with_config(DEFAULT_FEE_RATE_PIPS);
+    let ix = set_free_trades_ix(&PROGRAM_ID, &env.config, &env.admin, free_trades);
+    env.process(&ix).assert_ok();
+    let user = env.wallet(10 * SOL);
+    let curve = env.pump_curve(&user, 0);
+    (env, user, curve)
+}
+
+// 返回本笔收取的手续费
+fn counted_buy(env: &mut TestEnv, user: &Pubkey, curve: &PumpCurve) -> u64 {
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(user), SOL, 2 * SOL, curve.buy_accounts());
+    let ix = with_wallet_counter(&PROGRAM_ID, ix, user);
+    let result = env.process(&ix).assert_ok();
+    SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap().fee
+}
+
+fn trade_count(env: &TestEnv, user: &Pubkey) -> u64 {
+    WalletState::unpack(env.data(&wallet_address(&PROGRAM_ID, user).0)).unwrap().trade_count
+}
+
+#[test]
+fn first_trades_are_free_and_the_next_is_charged() {
+    let (mut env, user, curve) = setup(FREE_TRADES);
+    let treasury_before = env.lamports(&env.admin);
+    assert!(env.account(&wallet_address(&PROGRAM_ID, &user).0).is_none());
+
+    // 首笔交易时创建钱包 PDA
+    for count in 1..=FREE_TRADES as u64 {
+        assert_eq!(counted_buy(&mut env, &user, &curve), 0);
+        assert_eq!(trade_count(&env, &user), count);
+    }
+    assert_eq!(env.lamports(&env.admin), treasury_before);
+
+    assert_eq!(counted_buy(&mut env, &user, &curve), SOL / 100);
+    assert_eq!(trade_count(&env, &user), FREE_TRADES as u64 + 1);
+    assert_eq!(env.lamports(&env.admin) - treasury_before, SOL / 100);
+}
+
+#[test]
+fn trades_without_the_counter_are_charged() {
+    let (mut env, user, curve) = setup(FREE_TRADES);
+
+    let ix = pump_buy_ix(&PROGRAM_ID, &env.fee_accounts(&user), SOL, 2 * SOL, curve.buy_accounts());
+    let result = env.process(&ix).assert_ok();
+    assert_eq!(SwapResult::try_from_slice(&result.return_data.unwrap().1).unwrap().fee, SOL / 100);
+    assert!(env.account(&wallet_address(&PROGRAM_ID, &user).0).is_none());
+}
+
+#[test]
+fn counter_is_not_created_when_free_trades_are_off() {
+    let (mut env, user, curve) = setup(0);
+
+    assert_eq!(counted_buy(&mut env, &user, &curve), SOL / 100);
+    assert!(env.account(&wallet_address(&PROGRAM_ID, &user).0).is_none());
+}
@@ -0,0 +1,101 @@
+// 各选择器的指令数据构造，保证与合约端解析的布局一致
+use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey, system_program};
+
+// 带手续费路由的数据布局: [选择器 8][计费金额 u64][内层参数...]
+// 合约会丢弃计费金额，并用 "计费金额 - 手续费" 覆盖第一个内层参数
+pub fn fee_route_data(selector: &[u8; 8], amount: u64, inner_args: &[u64]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(16 + inner_args.len() * 8);
+    data.extend_from_slice(selector);
+    data.extend_from_slice(&amount.to_le_bytes());
+    for arg in inner_args {
+        data.extend_from_slice(&arg.to_le_bytes());
+    }
+    data
+}
+
+// 带手续费路由固定的前 4 个账户: 配置账户、系统程序、手续费支付者、手续费接收者
+pub fn fee_header_metas(config: Pubkey, payer: Pubkey, fee_wallet: Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(config, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(payer, true),
+        AccountMeta::new(fee_wallet, false),
+    ]
+}
+
+// Raydium 数据布局: [选择器 8][Raydium 指令号 1][amount_in u64][min_amount_out u64]
+pub fn raydium_swap_data(selector: &[u8; 8], discriminator: u8, amount_in: u64, min_amount_out: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(25);
+    data.extend_from_slice(selector);
+    data.push(discriminator);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&min_amount_out.to_le_bytes());
+    data
+}
+
+// Raydium CLMM 精确输出买入数据布局:
+// [选择器 8][max_amount_in u64][amount_out u64][max_amount_in 占位 u64][sqrt_price_limit_x64 u128][is_base_input u8 = 0]
+#[allow(dead_code)]
+pub fn raydium_clmm_exact_out_data(selector: &[u8; 8], max_amount_in: u64, amount_out: u64, sqrt_price_limit_x64: u128) -> Vec<u8> {
+    let mut data = fee_route_data(selector, max_amount_in, &[amount_out, max_amount_in]);
+    data.extend_from_slice(&sqrt_price_limit_x64.to_le_bytes());
+    data.push(0);
+    data
+}
+
+// ATA 数据布局: [选择器 8][ATA 程序指令号 1]，0 为 Create，1 为 CreateIdempotent
+pub fn create_ata_data(selector: &[u8; 8], idempotent: bool) -> Vec<u8> {
+    let mut data = Vec::with_capacity(9);
+    data.extend_from_slice(selector);
+    data.push(idempotent as u8);
+    data
+}
+
+// 过期槽检查数据布局: [选择器 8][过期槽 u64]
+#[allow(dead_code)]
+pub fn expired_slot_data(selector: &[u8; 8], expiry_slot: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(16);
+    data.extend_from_slice(selector);
+    data.extend_from_slice(&expiry_slot.to_le_bytes());
+    data
+}
+
+// 管理员设置手续费钱包: [b"set_fee\0"][新钱包 32]
+#[allow(dead_code)]
+pub fn set_fee_wallet_data(new_wallet: &Pubkey) -> Vec<u8> {
+    let mut data = Vec::with_capacity(40);
+    data.extend_from_slice(b"set_fee\0");
+    data.extend_from_slice(new_wallet.as_ref());
+    data
+}
+
+// 管理员设置费率: [b"set_rate"][费率 pips u32]
+#[allow(dead_code)]
+pub fn set_fee_rate_data(fee_rate_pips: u32) -> Vec<u8> {
+    let mut data = Vec::with_capacity(12);
+    data.extend_from_slice(b"set_rate");
+    data.extend_from_slice(&fee_rate_pips.to_le_bytes());
+    data
+}
+
+// 查询版本与费率上限: [b"version\0"]
+#[allow(dead_code)]
+pub fn version_data() -> Vec<u8> {
+    b"version\0".to_vec()
+}
+
+// 无状态手续费预估: [b"fee_prev"][amount u64][rate_bps u16]
+#[allow(dead_code)]
+pub fn fee_preview_data(amount: u64, rate_bps: u16) -> Vec<u8> {
+    let mut data = Vec::with_capacity(18);
+    data.extend_from_slice(b"fee_prev");
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&rate_bps.to_le_bytes());
+    data
+}
+
+// 读取完整配置（return data 为 borsh 编码的 TradeFeeState）: [b"get_cfg\0"]，账户仅为配置账户
+#[allow(dead_code)]
+pub fn get_config_data() -> Vec<u8> {
+    b"get_cfg\0".to_vec()
+}
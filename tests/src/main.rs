@@ -8,7 +8,7 @@ use solana_rpc_client_api::config::RpcSendTransactionConfig;
 use solana_sdk::{
     commitment_config::{CommitmentConfig, CommitmentLevel},
     instruction::{AccountMeta, Instruction},
-    pubkey::{self, Pubkey},
+    pubkey::Pubkey,
     rent::sysvar,
     signer::Signer,
     system_instruction::create_account_with_seed,
@@ -17,6 +17,10 @@ use solana_sdk::{
 };
 use spl_associated_token_account::get_associated_token_address;
 use spl_token::instruction::initialize_account;
+
+mod ix_data;
+use ix_data::{create_ata_data, fee_header_metas, fee_route_data, raydium_swap_data};
+
 const RENT_LAMPORTS: u64 = 3000000;
 
 #[tokio::main(flavor = "multi_thread")]
@@ -56,7 +60,17 @@ pub const RAYDIUM_SELL_SELECTOR: &[u8; 8] = &[183, 77, 232, 39, 117, 138, 183, 7
 
 const BONDING_CURVE_SEED: &[u8] = b"bonding-curve";
 
+// 手续费配置账户与协议费钱包从环境变量读取
+fn config_account() -> Pubkey {
+    env::var("CONFIG_ACCOUNT").unwrap().parse().unwrap()
+}
+
+fn fee_wallet() -> Pubkey {
+    env::var("FEE_WALLET").unwrap().parse().unwrap()
+}
+
 // 生成判别符
+#[allow(dead_code)]
 fn generate_discriminant() -> [u8; 8] {
     let mut hasher = Sha256::new();
     hasher.update(b"global:pump_buy");
@@ -79,10 +93,7 @@ async fn pump_buy() -> Result<(), Error> {
 
     let token_amount = 351100_u64;
     let max_sol_cost = 11000000_u64;
-    let mut data = Vec::with_capacity(24);
-    data.extend_from_slice(PUMP_SELECTOR);
-    data.extend_from_slice(&token_amount.to_le_bytes());
-    data.extend_from_slice(&max_sol_cost.to_le_bytes());
+    let data = fee_route_data(PUMP_SELECTOR, token_amount, &[token_amount, max_sol_cost]);
 
     let signer = solana_sdk::signature::Keypair::from_base58_string(&private_key);
 
@@ -96,24 +107,22 @@ async fn pump_buy() -> Result<(), Error> {
     let associated_bonding_curve =
         get_associated_token_address(&bonding_curve_address.0, &token_mint);
 
-    let instruction = Instruction::new_with_bytes(
-        PROXY_PROGRAM,
-        &data,
-        vec![
-            AccountMeta::new_readonly(GLOBAL_ACCOUNT, false),
-            AccountMeta::new(FEE_RECIPIENT, false),
-            AccountMeta::new_readonly(token_mint, false),
-            AccountMeta::new(bonding_curve_address.0, false),
-            AccountMeta::new(associated_bonding_curve, false),
-            AccountMeta::new(associated_user, false),
-            AccountMeta::new(signer.pubkey(), true),
-            AccountMeta::new_readonly(system_program::id(), false),
-            AccountMeta::new_readonly(spl_token::id(), false),
-            AccountMeta::new_readonly(sysvar::id(), false),
-            AccountMeta::new_readonly(EVENT_AUTHORITY, false),
-            AccountMeta::new_readonly(PUMP_PROGRAM_ID, false),
-        ],
-    );
+    let mut accounts = fee_header_metas(config_account(), signer.pubkey(), fee_wallet());
+    accounts.extend_from_slice(&[
+        AccountMeta::new_readonly(GLOBAL_ACCOUNT, false),
+        AccountMeta::new(FEE_RECIPIENT, false),
+        AccountMeta::new_readonly(token_mint, false),
+        AccountMeta::new(bonding_curve_address.0, false),
+        AccountMeta::new(associated_bonding_curve, false),
+        AccountMeta::new(associated_user, false),
+        AccountMeta::new(signer.pubkey(), true),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(sysvar::id(), false),
+        AccountMeta::new_readonly(EVENT_AUTHORITY, false),
+        AccountMeta::new_readonly(PUMP_PROGRAM_ID, false),
+    ]);
+    let instruction = Instruction::new_with_bytes(PROXY_PROGRAM, &data, accounts);
     let blockhash = rpc_client
         .get_latest_blockhash_with_commitment(CommitmentConfig {
             commitment: CommitmentLevel::Confirmed,
@@ -121,11 +130,7 @@ async fn pump_buy() -> Result<(), Error> {
         .await
         .unwrap();
 
-    let mut ata_data = Vec::with_capacity(9);
-
-    ata_data.extend_from_slice(ATA_SELECTOR);
-
-    ata_data.extend_from_slice(&[0]);
+    let ata_data = create_ata_data(ATA_SELECTOR, false);
 
     let ata_instruction = Instruction::new_with_bytes(
         PROXY_PROGRAM,
@@ -173,16 +178,10 @@ async fn raydium_buy() -> Result<(), Error> {
     let private_key = env::var("PRIVATE_KEY").unwrap();
     let rpc_client =
         RpcClient::new_with_commitment("".to_string(), CommitmentConfig::confirmed());
-    let signer = solana_sdk::signature::Keypair::from_base58_string(&private_key);
 
     let token_amount = 351100_u64;
     let max_sol_cost = 11000000_u64;
-    let mut data = Vec::with_capacity(25);
-
-    data.extend_from_slice(RAYDIUM_BUY_SELECTOR);
-    data.extend_from_slice(&[9]);
-    data.extend_from_slice(&token_amount.to_le_bytes());
-    data.extend_from_slice(&max_sol_cost.to_le_bytes());
+    let data = raydium_swap_data(RAYDIUM_BUY_SELECTOR, 9, token_amount, max_sol_cost);
 
     let output_mint: Pubkey = solana_sdk::pubkey!("DYUjm68jHoQFHMHzuRqomrhRcog9mc4TNrCWHpufpump");
 
@@ -275,10 +274,7 @@ async fn pump_sell() -> Result<(), Error> {
 
     let token_amount = 351100_u64;
     let min_sol_receive = 10000000_u64;
-    let mut data = Vec::with_capacity(24);
-    data.extend_from_slice(PUMP_SELL_SELECTOR);
-    data.extend_from_slice(&token_amount.to_le_bytes());
-    data.extend_from_slice(&min_sol_receive.to_le_bytes());
+    let data = fee_route_data(PUMP_SELL_SELECTOR, token_amount, &[token_amount, min_sol_receive]);
 
     let signer = solana_sdk::signature::Keypair::from_base58_string(&private_key);
 
@@ -292,24 +288,22 @@ async fn pump_sell() -> Result<(), Error> {
     let associated_bonding_curve =
         get_associated_token_address(&bonding_curve_address.0, &token_mint);
 
-    let instruction = Instruction::new_with_bytes(
-        PROXY_PROGRAM,
-        &data,
-        vec![
-            AccountMeta::new_readonly(GLOBAL_ACCOUNT, false),
-            AccountMeta::new(FEE_RECIPIENT, false),
-            AccountMeta::new_readonly(token_mint, false),
-            AccountMeta::new(bonding_curve_address.0, false),
-            AccountMeta::new(associated_bonding_curve, false),
-            AccountMeta::new(associated_user, false),
-            AccountMeta::new(signer.pubkey(), true),
-            AccountMeta::new_readonly(system_program::id(), false),
-            AccountMeta::new_readonly(spl_token::id(), false),
-            AccountMeta::new_readonly(sysvar::id(), false),
-            AccountMeta::new_readonly(EVENT_AUTHORITY, false),
-            AccountMeta::new_readonly(PUMP_PROGRAM_ID, false),
-        ],
-    );
+    let mut accounts = fee_header_metas(config_account(), signer.pubkey(), fee_wallet());
+    accounts.extend_from_slice(&[
+        AccountMeta::new_readonly(GLOBAL_ACCOUNT, false),
+        AccountMeta::new(FEE_RECIPIENT, false),
+        AccountMeta::new_readonly(token_mint, false),
+        AccountMeta::new(bonding_curve_address.0, false),
+        AccountMeta::new(associated_bonding_curve, false),
+        AccountMeta::new(associated_user, false),
+        AccountMeta::new(signer.pubkey(), true),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(sysvar::id(), false),
+        AccountMeta::new_readonly(EVENT_AUTHORITY, false),
+        AccountMeta::new_readonly(PUMP_PROGRAM_ID, false),
+    ]);
+    let instruction = Instruction::new_with_bytes(PROXY_PROGRAM, &data, accounts);
 
     let blockhash = rpc_client
         .get_latest_blockhash_with_commitment(CommitmentConfig {
@@ -349,16 +343,10 @@ async fn raydium_sell() -> Result<(), Error> {
     let private_key = env::var("PRIVATE_KEY").unwrap();
     let rpc_client =
         RpcClient::new_with_commitment("".to_string(), CommitmentConfig::confirmed());
-    let signer = solana_sdk::signature::Keypair::from_base58_string(&private_key);
 
     let token_amount = 351100_u64;
     let min_sol_receive = 10000000_u64;
-    let mut data = Vec::with_capacity(25);
-
-    data.extend_from_slice(RAYDIUM_SELL_SELECTOR);
-    data.extend_from_slice(&[9]);
-    data.extend_from_slice(&token_amount.to_le_bytes());
-    data.extend_from_slice(&min_sol_receive.to_le_bytes());
+    let data = raydium_swap_data(RAYDIUM_SELL_SELECTOR, 9, token_amount, min_sol_receive);
 
     let input_mint: Pubkey = solana_sdk::pubkey!("DYUjm68jHoQFHMHzuRqomrhRcog9mc4TNrCWHpufpump");
 